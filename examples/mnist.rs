@@ -1,13 +1,74 @@
-// use ducky_learn::layers::*;
+use ducky_learn::activations::*;
+use ducky_learn::conv::*;
+use ducky_learn::layers::*;
+use ducky_learn::preprocessing::MinMaxScaler;
 use ducky_learn::util::one_hot_encoding_vec;
 use mnist::*;
 use ndarray::prelude::*;
 
 fn main() {
-    let (_train_data, _train_labels, _test_data, _test_labels, _val_data, _val_labels) =
+    let (train_data, train_labels, test_data, test_labels, _val_data, _val_labels) =
         create_mnist_dataset(50_000, 10_000, 10_000);
+
+    // Small CNN: 8 3x3 kernels keeping the 28x28 size, pooled down to 14x14
+    let window = Window2d::new((3, 3), (1, 1), (1, 1), (1, 1)).unwrap();
+    let features: Vec<Box<dyn Layer4d>> = vec![
+        Box::new(Conv2d::new(1, 8, window, Activation::Relu)),
+        Box::new(MaxPool2d::new((2, 2), (2, 2)).unwrap()),
+    ];
+    let head = vec![Dense1d::from(
+        |x| x,
+        |x| x.map(|_| 1f64),
+        Array2::zeros((10, 8 * 14 * 14)),
+        Array1::zeros(10),
+    )];
+    let model = ConvNet::new(features, head);
+
+    // Train on a slice of the images to keep each epoch short on a CPU
+    let train_images = images(&train_data.slice(s![..5_000, ..]).to_owned());
+    let train_labels = train_labels.slice(s![..5_000, ..]).to_owned();
+    let costs = model
+        .train(&train_images, &train_labels, 3, 32, 0.01)
+        .expect("Error training the model");
+    println!("Training cost per epoch: {:?}", costs);
+
+    let outputs = model
+        .forward(
+            &images(&test_data.slice(s![..1_000, ..]).to_owned()),
+            Mode::Eval,
+        )
+        .expect("Error predicting the test images");
+    let correct = outputs
+        .rows()
+        .into_iter()
+        .zip(test_labels.rows())
+        .filter(|(output, label)| argmax(output) == argmax(label))
+        .count();
+    println!("Test accuracy: {}", correct as f64 / outputs.nrows() as f64);
+}
+
+/// Reshapes rows of 784 pixels into images of shape( samples, 1, 28, 28 ).
+fn images(rows: &Array2<f64>) -> Array4<f64> {
+    rows.to_shape((rows.nrows(), 1, 28, 28))
+        .expect("Error reshaping rows into images")
+        .into_owned()
+}
+
+fn argmax(values: &ArrayView1<f64>) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold(
+            (0, f64::NEG_INFINITY),
+            |(max_index, max), (index, value)| match *value > max {
+                true => (index, *value),
+                false => (max_index, max),
+            },
+        )
+        .0
 }
 
+#[allow(clippy::type_complexity)]
 fn create_mnist_dataset(
     trn_len: u32,
     tst_len: u32,
@@ -35,25 +96,36 @@ fn create_mnist_dataset(
 
     let train_data = Array2::from_shape_vec((50_000, 784), trn_img)
         .expect("Error converting images to Array2 struct")
-        .map(|x| *x as f64 / 256.);
+        .map(|x| *x as f64);
 
     let trn_lbl: Vec<usize> = trn_lbl.iter().map(|x| *x as usize).collect();
     let train_labels: Array2<f64> = one_hot_encoding_vec(&trn_lbl).unwrap();
 
     let test_data = Array2::from_shape_vec((10_000, 784), tst_img)
         .expect("Error converting images to Array2 struct")
-        .map(|x| *x as f64 / 256.);
+        .map(|x| *x as f64);
 
     let tst_lbl: Vec<usize> = tst_lbl.iter().map(|x| *x as usize).collect();
     let test_labels: Array2<f64> = one_hot_encoding_vec(&tst_lbl).unwrap();
 
     let val_data = Array2::from_shape_vec((10_000, 784), val_img)
         .expect("Error converting images to Array2 struct")
-        .map(|x| *x as f64 / 256.);
+        .map(|x| *x as f64);
 
     let val_lbl: Vec<usize> = val_lbl.iter().map(|x| *x as usize).collect();
     let val_labels: Array2<f64> = one_hot_encoding_vec(&val_lbl).unwrap();
 
+    // Scale pixels into [0, 1] using only what was seen in the training images
+    let (scaler, train_data) = MinMaxScaler::default()
+        .fit_transform(&train_data)
+        .expect("Error scaling training images");
+    let test_data = scaler
+        .transform(&test_data)
+        .expect("Error scaling test images");
+    let val_data = scaler
+        .transform(&val_data)
+        .expect("Error scaling validation images");
+
     (
         train_data,
         train_labels,
@@ -1,6 +1,12 @@
 extern crate ndarray;
 
+use super::autograd::{Tape, Tensor};
+use super::error::DuckyError;
 use ndarray::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// The activations are computed on a `Tape` so their derivatives come from the same
+// gradients that train the layers built on `autograd::Tensor`.
 
 /// Relu activation function for 1d array
 ///
@@ -22,10 +28,10 @@ use ndarray::prelude::*;
 /// assert_eq!(relu_1d(input_array), arr1(&[0., 0., 0.]));
 /// ```
 pub fn relu_1d(input_array: Array1<f64>) -> Array1<f64> {
-    input_array.map(|value| value.max(0.))
+    Tape::new().variable(input_array).relu().value_1d()
 }
 
-/// Derivative of Relu function
+/// Derivative of Relu function, taken from the gradient of `relu_1d`
 ///
 /// # Arguments
 ///
@@ -43,11 +49,19 @@ pub fn relu_1d(input_array: Array1<f64>) -> Array1<f64> {
 /// assert_eq!(deriv_relu_1d(input_array), arr1(&[1., 0., 1.]));
 /// ```
 pub fn deriv_relu_1d(input_array: Array1<f64>) -> Array1<f64> {
-    input_array.map(|value| (*value > 0f64) as i32 as f64)
+    let input = Tape::new().variable(input_array);
+
+    input
+        .relu()
+        .backward()
+        .wrt(&input)
+        .into_dimensionality::<Ix1>()
+        .unwrap()
 }
 
-/// Softmax activation function for 1d array. Note that you can run into NaN issue if values are
-/// < -1000 or > 1000 (https://users.rust-lang.org/t/watch-out-for-nans/70016)
+/// Softmax activation function for 1d array. It is computed by `Tensor::softmax`, which shifts
+/// the values by their maximum first, so values < -1000 or > 1000 do not overflow into NaN
+/// (https://users.rust-lang.org/t/watch-out-for-nans/70016)
 ///
 /// More info: https://deepai.org/machine-learning-glossary-and-terms/softmax-layer#:~:text=The%20softmax%20function%20is%20a,can%20be%20interpreted%20as%20probabilities.
 ///
@@ -65,12 +79,69 @@ pub fn deriv_relu_1d(input_array: Array1<f64>) -> Array1<f64> {
 ///
 /// let input_array = arr1(&[0., 1., -1., 0.01, -0.1]);
 /// assert_eq!(softmax_1d(input_array),
-///            arr1(&[0.16663753690463112, 0.4529677885070323, 0.0613025239546613, 0.16831227199301688, 0.15077987864065834]));
+///            arr1(&[0.16663753690463112, 0.4529677885070323, 0.06130252395466129, 0.16831227199301685, 0.15077987864065834]));
 /// ```
 pub fn softmax_1d(input_array: Array1<f64>) -> Array1<f64> {
-    let sum_exp_input_array = input_array.map(|value| value.exp()).sum();
+    // A 1d array always has axis 0
+    Tape::new()
+        .variable(input_array)
+        .softmax(0)
+        .unwrap()
+        .value_1d()
+}
+
+/// Activation function known by name, so a layer using one can be saved with its model where
+/// a function pointer can not. Its derivative comes from the tape.
+///
+/// * `Identity` - Leaves the values as they are
+/// * `Relu` - `max(0, x)` of every value
+/// * `Sigmoid` - `1 / (1 + exp(-x))` of every value
+/// * `Tanh` - Hyperbolic tangent of every value
+/// * `Softmax` - Softmax along the last axis, with its full Jacobian on the tape
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::activations::Activation;
+/// use ducky_learn::autograd::Tape;
+/// use ndarray::arr1;
+///
+/// let z = Tape::new().variable(arr1(&[-1., 0., 2.]));
+///
+/// let a = Activation::Relu.apply(&z).unwrap();
+///
+/// assert_eq!(a.value(), &arr1(&[0., 0., 2.]).into_dyn());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Activation {
+    Identity,
+    Relu,
+    Sigmoid,
+    Tanh,
+    Softmax,
+}
 
-    input_array.map(|value| value.exp() / sum_exp_input_array)
+impl Activation {
+    /// Applies the activation to a tensor.
+    ///
+    /// # Arguments
+    ///
+    /// * `z`: Weighted input of a layer
+    ///
+    /// returns: `Result<Tensor, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `Softmax` is applied to a scalar, which has no axis
+    pub fn apply(&self, z: &Tensor) -> Result<Tensor, DuckyError> {
+        Ok(match self {
+            Activation::Identity => z.clone(),
+            Activation::Relu => z.relu(),
+            Activation::Sigmoid => z.sigmoid(),
+            Activation::Tanh => z.tanh(),
+            Activation::Softmax => z.softmax(z.shape().len().max(1) - 1)?,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -126,8 +197,8 @@ mod activations_tests {
             arr1(&[
                 0.16663753690463112,
                 0.4529677885070323,
-                0.0613025239546613,
-                0.16831227199301688,
+                0.06130252395466129,
+                0.16831227199301685,
                 0.15077987864065834
             ])
         );
@@ -146,7 +217,37 @@ mod activations_tests {
 
         assert_eq!(
             softmax_1d(input_array),
-            arr1(&[1.2404210269803915e-101, 1.0, 0.0])
+            arr1(&[1.2404210269803829e-101, 1.0, 0.0])
+        );
+    }
+
+    #[test]
+    fn softmax_1d_large_values() {
+        assert_eq!(softmax_1d(arr1(&[1000., 0.])), arr1(&[1., 0.]));
+        assert_eq!(softmax_1d(arr1(&[-1000., -1000.])), arr1(&[0.5, 0.5]));
+    }
+
+    #[test]
+    fn activation_apply() {
+        let tape = Tape::new();
+        let z = tape.variable(arr1(&[-1., 0., 2.]));
+
+        assert_eq!(
+            Activation::Identity.apply(&z).unwrap().value_1d(),
+            z.value_1d()
+        );
+        assert_eq!(
+            Activation::Sigmoid.apply(&z).unwrap().value_1d(),
+            z.value_1d().mapv(|value| 1. / (1. + (-value).exp()))
+        );
+        assert_eq!(
+            Activation::Tanh.apply(&z).unwrap().value_1d(),
+            z.value_1d().mapv(f64::tanh)
         );
+        assert!((Activation::Softmax.apply(&z).unwrap().sum().item() - 1.).abs() < 1e-12);
+        assert!(matches!(
+            Activation::Softmax.apply(&tape.scalar(1.)),
+            Err(DuckyError::InvalidParameter(_))
+        ));
     }
 }
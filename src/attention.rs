@@ -0,0 +1,1314 @@
+use super::activations::Activation;
+use super::autograd::{concatenate, stack, Gradients, Tape, Tensor};
+use super::error::DuckyError;
+use super::layers::{Dense1d, Layer1d, LayerNorm, Mode, SavedLayer};
+use super::recurrent::{check_sequences, check_shape, output_gradient, sequence};
+use super::util::seeded_rng;
+use ndarray::prelude::*;
+use ndarray_rand::rand_distr::Uniform;
+use ndarray_rand::RandomExt;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+use std::sync::RwLock;
+
+/// Affine map applied to every step of a sequence, x W + b.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Projection {
+    /// Weights of shape( input size, output size )
+    pub weights: Array2<f64>,
+    /// Bias of length output size
+    pub bias: Array1<f64>,
+}
+
+impl Projection {
+    fn new(input_size: usize, output_size: usize, rng: &mut StdRng) -> Self {
+        let bound = 1. / (input_size.max(1) as f64).sqrt();
+
+        Self {
+            weights: Array2::random_using(
+                (input_size, output_size),
+                Uniform::new_inclusive(-bound, bound),
+                rng,
+            ),
+            bias: Array1::zeros(output_size),
+        }
+    }
+
+    /// Records the projection on `tape`.
+    fn record(&self, tape: &Rc<Tape>) -> ProjectionTensors {
+        ProjectionTensors {
+            weights: tape.variable(self.weights.clone()),
+            bias: tape.variable(self.bias.clone()),
+        }
+    }
+}
+
+/// `Projection` recorded on an `autograd::Tape`.
+struct ProjectionTensors {
+    weights: Tensor,
+    bias: Tensor,
+}
+
+impl ProjectionTensors {
+    fn apply(&self, x: &Tensor) -> Result<Tensor, DuckyError> {
+        x.matmul(&self.weights)?.try_add(&self.bias)
+    }
+
+    /// Takes one gradient descent step on `projection` with the gradients of the tensors.
+    fn step(&self, projection: &mut Projection, gradients: &Gradients, learning_rate: f64) {
+        projection.weights.scaled_add(
+            -learning_rate,
+            &gradients
+                .wrt(&self.weights)
+                .into_dimensionality::<Ix2>()
+                .unwrap(),
+        );
+        projection.bias.scaled_add(
+            -learning_rate,
+            &gradients
+                .wrt(&self.bias)
+                .into_dimensionality::<Ix1>()
+                .unwrap(),
+        );
+    }
+}
+
+/// Projections of a `MultiHeadAttention` layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttentionWeights {
+    pub query: Projection,
+    pub key: Projection,
+    pub value: Projection,
+    pub output: Projection,
+}
+
+impl AttentionWeights {
+    /// Records the projections on `tape`.
+    fn record(&self, tape: &Rc<Tape>) -> AttentionTensors {
+        AttentionTensors {
+            query: self.query.record(tape),
+            key: self.key.record(tape),
+            value: self.value.record(tape),
+            output: self.output.record(tape),
+        }
+    }
+}
+
+/// `AttentionWeights` recorded on an `autograd::Tape`.
+struct AttentionTensors {
+    query: ProjectionTensors,
+    key: ProjectionTensors,
+    value: ProjectionTensors,
+    output: ProjectionTensors,
+}
+
+/// Softmax of every row of `scores` over the allowed columns, on the tape of `scores`. Rows
+/// without any allowed column are all zero.
+fn masked_softmax(scores: &Tensor, allowed: &Array2<bool>) -> Result<Tensor, DuckyError> {
+    let tape = scores.tape();
+    let any_allowed = allowed.map_axis(Axis(1), |row| row.iter().any(|&allowed| allowed));
+
+    // Rows without any allowed column are left whole for the softmax and zeroed afterwards
+    let offset = Array2::from_shape_fn(allowed.raw_dim(), |(row, column)| {
+        if allowed[[row, column]] || !any_allowed[row] {
+            0.
+        } else {
+            f64::NEG_INFINITY
+        }
+    });
+    let rows = any_allowed
+        .mapv(|any_allowed| any_allowed as i32 as f64)
+        .insert_axis(Axis(1));
+
+    scores
+        .try_add(&tape.variable(offset))?
+        .softmax(1)?
+        .try_mul(&tape.variable(rows))
+}
+
+/// Multi-head scaled dot-product self-attention over batches of sequences of shape( batch,
+/// time, d_model ).
+///
+/// Every head attends with its own `d_model / num_heads` slice of the query, key and value
+/// projections, and the heads are joined by the output projection. Padded steps, marked
+/// `false` in the mask, are never attended to, and a causal layer only lets each step attend
+/// to itself and the steps before it. The outputs at padded steps are still computed and
+/// should be ignored.
+///
+/// As a `Layer1d` it takes one unmasked sequence at a time, flattened step by step into an
+/// array of length `time * d_model`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::attention::MultiHeadAttention;
+/// use ndarray::{arr2, Array3};
+///
+/// let attention = MultiHeadAttention::new(8, 2, false, Some(42)).unwrap();
+/// let mask = arr2(&[[true, true, true], [true, true, false]]);
+///
+/// let output = attention.pass_batch(&Array3::ones((2, 3, 8)), Some(&mask)).unwrap();
+/// assert_eq!(output.shape(), &[2, 3, 8]);
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultiHeadAttention {
+    num_heads: usize,
+    causal: bool,
+    weights: RwLock<AttentionWeights>,
+}
+
+impl MultiHeadAttention {
+    /// Create MultiHeadAttention layer with weights drawn uniformly from ±1 / sqrt( d_model )
+    /// and bias's set to zero
+    ///
+    /// # Arguments
+    ///
+    /// * `d_model`: Number of features of every step
+    /// * `num_heads`: Number of heads, which has to divide `d_model`
+    /// * `causal`: Whether steps can only attend to themselves and the steps before them
+    /// * `seed`: Seed for drawing the weights, or `None` to seed it from the operating system
+    ///
+    /// returns: `Result<MultiHeadAttention, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `num_heads` is zero or does not divide `d_model`
+    pub fn new(
+        d_model: usize,
+        num_heads: usize,
+        causal: bool,
+        seed: Option<u64>,
+    ) -> Result<Self, DuckyError> {
+        let mut rng = seeded_rng(seed);
+
+        Self::from(
+            AttentionWeights {
+                query: Projection::new(d_model, d_model, &mut rng),
+                key: Projection::new(d_model, d_model, &mut rng),
+                value: Projection::new(d_model, d_model, &mut rng),
+                output: Projection::new(d_model, d_model, &mut rng),
+            },
+            num_heads,
+            causal,
+        )
+    }
+
+    /// Create MultiHeadAttention layer with full control over the weights
+    ///
+    /// # Arguments
+    ///
+    /// * `weights`: Projections of the layer, each with weights of shape( d_model, d_model )
+    ///   and a bias of length d_model
+    /// * `num_heads`: Number of heads, which has to divide `d_model`
+    /// * `causal`: Whether steps can only attend to themselves and the steps before them
+    ///
+    /// returns: `Result<MultiHeadAttention, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `num_heads` is zero or does not divide `d_model`
+    /// * `DuckyError::ShapeMismatch`: a projection is not of shape( d_model, d_model )
+    pub fn from(
+        weights: AttentionWeights,
+        num_heads: usize,
+        causal: bool,
+    ) -> Result<Self, DuckyError> {
+        let d_model = weights.query.weights.nrows();
+
+        if num_heads == 0 || !d_model.is_multiple_of(num_heads) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "num_heads must divide d_model {}, got {}",
+                d_model, num_heads
+            )));
+        }
+        for projection in [
+            &weights.query,
+            &weights.key,
+            &weights.value,
+            &weights.output,
+        ] {
+            for found in [projection.weights.shape(), projection.bias.shape()] {
+                let expected = &[d_model, d_model][..found.len()];
+                if found != expected {
+                    return Err(DuckyError::ShapeMismatch {
+                        expected: expected.to_vec(),
+                        found: found.to_vec(),
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            num_heads,
+            causal,
+            weights: RwLock::new(weights),
+        })
+    }
+
+    /// Number of features of every step.
+    pub fn d_model(&self) -> usize {
+        self.weights.read().unwrap().query.weights.nrows()
+    }
+
+    /// Number of heads.
+    pub fn num_heads(&self) -> usize {
+        self.num_heads
+    }
+
+    /// Whether steps can only attend to themselves and the steps before them.
+    pub fn causal(&self) -> bool {
+        self.causal
+    }
+
+    /// Projections of the layer.
+    pub fn weights(&self) -> AttentionWeights {
+        self.weights.read().unwrap().clone()
+    }
+
+    /// Which keys every query of one sequence can attend to, of shape( time, time ).
+    fn allowed(&self, mask: Option<ArrayView1<bool>>, time: usize) -> Array2<bool> {
+        Array2::from_shape_fn((time, time), |(query, key)| {
+            let unmasked = match &mask {
+                Some(mask) => mask[key],
+                None => true,
+            };
+            (!self.causal || key <= query) && unmasked
+        })
+    }
+
+    /// Records the layer attending over one sequence `x` of shape( time, d_model ).
+    fn sample_pass(
+        &self,
+        weights: &AttentionTensors,
+        x: &Tensor,
+        allowed: &Array2<bool>,
+    ) -> Result<Tensor, DuckyError> {
+        let head_size = weights.query.weights.shape()[1] / self.num_heads;
+        let scale = (head_size as f64).sqrt();
+        let query = weights.query.apply(x)?;
+        let key = weights.key.apply(x)?;
+        let value = weights.value.apply(x)?;
+
+        let heads = (0..self.num_heads)
+            .map(|head| {
+                let columns = head * head_size..(head + 1) * head_size;
+                let scores = query
+                    .slice_axis(1, columns.clone())?
+                    .matmul(&key.slice_axis(1, columns.clone())?.t()?)?
+                    / scale;
+
+                masked_softmax(&scores, allowed)?.matmul(&value.slice_axis(1, columns)?)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        weights.output.apply(&concatenate(1, &heads)?)
+    }
+
+    /// Records the layer attending over every sequence of `input_array` on the tape of
+    /// `weights`.
+    ///
+    /// returns: the input as a tensor, and the output of shape( batch, time, d_model )
+    fn run(
+        &self,
+        weights: &AttentionTensors,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<(Tensor, Tensor), DuckyError> {
+        let input = weights.query.weights.tape().variable(input_array.clone());
+
+        let outputs = (0..input_array.shape()[0])
+            .map(|sample| {
+                let allowed =
+                    self.allowed(mask.map(|mask| mask.row(sample)), input_array.shape()[1]);
+                self.sample_pass(weights, &input.index_axis(0, sample)?, &allowed)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((input, stack(0, &outputs)?))
+    }
+
+    /// Feeds forward a batch of sequences through the layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of sequences of shape( batch, time, d_model )
+    /// * `mask`: Which steps of each sequence are real, of shape( batch, time )
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` of shape( batch, time, d_model )
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `input_array` or `mask` do not line up with the layer
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let weights = self.weights.read().unwrap();
+        check_sequences(weights.query.weights.nrows(), input_array, mask)?;
+
+        let (_, output) = self.run(&weights.record(&Tape::new()), input_array, mask)?;
+        Ok(output.value_as::<Ix3>())
+    }
+
+    /// Backpropagates the gradient of the output of `pass_batch`, taking one gradient descent
+    /// step on the weights with their gradients summed over the batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The batch the layer was passed during the forward pass
+    /// * `mask`: The mask the layer was passed during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of `pass_batch`
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to `input_array`, computed before the weights are updated
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn back_pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+        output_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let mut weights = self.weights.write().unwrap();
+        check_sequences(weights.query.weights.nrows(), input_array, mask)?;
+        check_shape(input_array.shape(), output_gradient)?;
+
+        let variables = weights.record(&Tape::new());
+        let (input, output) = self.run(&variables, input_array, mask)?;
+        let gradients = output.backward_with(output_gradient)?;
+
+        variables
+            .query
+            .step(&mut weights.query, &gradients, learning_rate);
+        variables
+            .key
+            .step(&mut weights.key, &gradients, learning_rate);
+        variables
+            .value
+            .step(&mut weights.value, &gradients, learning_rate);
+        variables
+            .output
+            .step(&mut weights.output, &gradients, learning_rate);
+
+        Ok(gradients.wrt(&input).into_dimensionality().unwrap())
+    }
+}
+
+impl Clone for MultiHeadAttention {
+    fn clone(&self) -> Self {
+        Self {
+            num_heads: self.num_heads,
+            causal: self.causal,
+            weights: RwLock::new(self.weights()),
+        }
+    }
+}
+
+impl Layer1d for MultiHeadAttention {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        _mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let input_array = sequence(self.d_model(), &input_array)?;
+        let output: Array1<f64> = self.pass_batch(&input_array, None)?.into_iter().collect();
+        Ok((output.clone(), output))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        _weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let input_array = sequence(self.d_model(), input_array)?;
+        let shape = [1, input_array.shape()[1], self.d_model()];
+
+        let input_gradient = self.back_pass_batch(
+            &input_array,
+            None,
+            &self::output_gradient(shape, output_gradient)?,
+            learning_rate,
+        )?;
+        Ok(input_gradient.into_iter().collect())
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::MultiHeadAttention(self.clone()))
+    }
+}
+
+/// Adds the fixed sine and cosine encoding of "Attention Is All You Need" to every step, so
+/// attention layers can tell the steps apart. Feature `2i` of step `t` gets
+/// sin( t / 10000^(2i / d_model) ) and feature `2i + 1` the cosine of the same angle.
+///
+/// It has no weights, so the gradient of its input is the gradient of its output.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::attention::SinusoidalEncoding;
+/// use ndarray::Array3;
+///
+/// let encoding = SinusoidalEncoding::new(4).unwrap();
+/// let output = encoding.pass_batch(&Array3::zeros((1, 2, 4))).unwrap();
+///
+/// assert_eq!(output[[0, 0, 1]], 1.);
+/// assert_eq!(output[[0, 1, 0]], 1f64.sin());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SinusoidalEncoding {
+    d_model: usize,
+}
+
+impl SinusoidalEncoding {
+    /// Create SinusoidalEncoding layer
+    ///
+    /// # Arguments
+    ///
+    /// * `d_model`: Number of features of every step
+    ///
+    /// returns: `Result<SinusoidalEncoding, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `d_model` is zero
+    pub fn new(d_model: usize) -> Result<Self, DuckyError> {
+        if d_model == 0 {
+            return Err(DuckyError::InvalidParameter(
+                "d_model must be positive".to_string(),
+            ));
+        }
+
+        Ok(Self { d_model })
+    }
+
+    /// Number of features of every step.
+    pub fn d_model(&self) -> usize {
+        self.d_model
+    }
+
+    /// Encoding of the first `length` steps, of shape( length, d_model ).
+    pub fn table(&self, length: usize) -> Array2<f64> {
+        Array2::from_shape_fn((length, self.d_model), |(step, feature)| {
+            let angle =
+                step as f64 / 10000f64.powf((feature - feature % 2) as f64 / self.d_model as f64);
+            if feature % 2 == 0 {
+                angle.sin()
+            } else {
+                angle.cos()
+            }
+        })
+    }
+
+    /// Adds the encoding to a batch of sequences of shape( batch, time, d_model ).
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the steps do not have `d_model` features
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn pass_batch(&self, input_array: &Array3<f64>) -> Result<Array3<f64>, DuckyError> {
+        check_sequences(self.d_model, input_array, None)?;
+        Ok(input_array + &self.table(input_array.shape()[1]))
+    }
+}
+
+impl Layer1d for SinusoidalEncoding {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        _mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let output: Array1<f64> = self
+            .pass_batch(&sequence(self.d_model, &input_array)?)?
+            .into_iter()
+            .collect();
+        Ok((output.clone(), output))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        _weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        _learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        if output_gradient.len() != input_array.len() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: input_array.shape().to_vec(),
+                found: output_gradient.shape().to_vec(),
+            });
+        }
+
+        Ok(output_gradient.clone())
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::SinusoidalEncoding(*self))
+    }
+}
+
+/// Adds a learned vector to every step, one per position up to `max_length`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::attention::LearnedEncoding;
+/// use ndarray::Array3;
+///
+/// let encoding = LearnedEncoding::new(16, 4, Some(42));
+/// let output = encoding.pass_batch(&Array3::zeros((2, 3, 4))).unwrap();
+///
+/// assert_eq!(output.slice(ndarray::s![0, .., ..]), encoding.weights().slice(ndarray::s![..3, ..]));
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LearnedEncoding {
+    weights: RwLock<Array2<f64>>,
+}
+
+impl LearnedEncoding {
+    /// Create LearnedEncoding layer with weights drawn uniformly from ±0.05
+    ///
+    /// # Arguments
+    ///
+    /// * `max_length`: Number of steps sequences can have at most
+    /// * `d_model`: Number of features of every step
+    /// * `seed`: Seed for drawing the weights, or `None` to seed it from the operating system
+    ///
+    /// returns: `LearnedEncoding`
+    pub fn new(max_length: usize, d_model: usize, seed: Option<u64>) -> Self {
+        Self {
+            weights: RwLock::new(Array2::random_using(
+                (max_length, d_model),
+                Uniform::new_inclusive(-0.05, 0.05),
+                &mut seeded_rng(seed),
+            )),
+        }
+    }
+
+    /// Number of steps sequences can have at most.
+    pub fn max_length(&self) -> usize {
+        self.weights.read().unwrap().nrows()
+    }
+
+    /// Number of features of every step.
+    pub fn d_model(&self) -> usize {
+        self.weights.read().unwrap().ncols()
+    }
+
+    /// Vector of every position, of shape( max_length, d_model ).
+    pub fn weights(&self) -> Array2<f64> {
+        self.weights.read().unwrap().clone()
+    }
+
+    fn check_length(&self, input_array: &Array3<f64>) -> Result<(), DuckyError> {
+        check_sequences(self.d_model(), input_array, None)?;
+
+        if input_array.shape()[1] > self.max_length() {
+            return Err(DuckyError::InvalidParameter(format!(
+                "sequences of {} steps are longer than max_length {}",
+                input_array.shape()[1],
+                self.max_length()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Adds the encoding to a batch of sequences of shape( batch, time, d_model ).
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the steps do not have `d_model` features
+    /// * `DuckyError::InvalidParameter`: the sequences are longer than `max_length`
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn pass_batch(&self, input_array: &Array3<f64>) -> Result<Array3<f64>, DuckyError> {
+        self.check_length(input_array)?;
+
+        let time = input_array.shape()[1];
+        Ok(input_array + &self.weights.read().unwrap().slice(s![..time, ..]))
+    }
+
+    /// Takes one gradient descent step on the vectors of the positions in the batch, with
+    /// their gradients summed over the batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The batch the layer was passed during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of `pass_batch`
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to `input_array`, which is `output_gradient`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    /// * `DuckyError::InvalidParameter`: the sequences are longer than `max_length`
+    pub fn back_pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        output_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError> {
+        self.check_length(input_array)?;
+        check_shape(input_array.shape(), output_gradient)?;
+
+        let time = input_array.shape()[1];
+        self.weights
+            .write()
+            .unwrap()
+            .slice_mut(s![..time, ..])
+            .scaled_add(-learning_rate, &output_gradient.sum_axis(Axis(0)));
+
+        Ok(output_gradient.clone())
+    }
+}
+
+impl Clone for LearnedEncoding {
+    fn clone(&self) -> Self {
+        Self {
+            weights: RwLock::new(self.weights()),
+        }
+    }
+}
+
+impl Layer1d for LearnedEncoding {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        _mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let output: Array1<f64> = self
+            .pass_batch(&sequence(self.d_model(), &input_array)?)?
+            .into_iter()
+            .collect();
+        Ok((output.clone(), output))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        _weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let input_array = sequence(self.d_model(), input_array)?;
+        let input_gradient = self.back_pass_batch(
+            &input_array,
+            &self::output_gradient([1, input_array.shape()[1], self.d_model()], output_gradient)?,
+            learning_rate,
+        )?;
+
+        Ok(input_gradient.into_iter().collect())
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::LearnedEncoding(self.clone()))
+    }
+}
+
+/// What the forward pass of one step of the feed-forward block keeps for the backward pass.
+struct FeedForwardCache {
+    attention_sum: Array1<f64>,
+    attention_x_hat: Array1<f64>,
+    attention_norm: Array1<f64>,
+    hidden_z: Array1<f64>,
+    hidden: Array1<f64>,
+    output_z: Array1<f64>,
+    output_sum: Array1<f64>,
+    output_x_hat: Array1<f64>,
+    output_norm: Array1<f64>,
+}
+
+/// Transformer encoder block: self-attention followed by a feed-forward network applied to
+/// every step, each wrapped in a residual connection and a `LayerNorm`,
+///
+/// h = LayerNorm( x + MultiHeadAttention( x ) ),
+/// output = LayerNorm( h + Dense1d( relu( Dense1d( h ) ) ) ).
+///
+/// The attention layer takes one step per batch. Like `train::train` does sample by sample,
+/// the feed-forward and norm layers take a step for every step of every sequence.
+///
+/// As a `Layer1d` it takes one unmasked sequence at a time, flattened step by step into an
+/// array of length `time * d_model`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::attention::TransformerEncoderLayer;
+/// use ndarray::{arr2, Array3};
+///
+/// let encoder = TransformerEncoderLayer::new(8, 2, 16, false, Some(42)).unwrap();
+/// let mask = arr2(&[[true, true, true], [true, false, false]]);
+///
+/// let output = encoder.pass_batch(&Array3::ones((2, 3, 8)), Some(&mask)).unwrap();
+/// assert_eq!(output.shape(), &[2, 3, 8]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformerEncoderLayer {
+    pub attention: MultiHeadAttention,
+    pub attention_norm: LayerNorm,
+    pub hidden: Dense1d,
+    pub output: Dense1d,
+    pub output_norm: LayerNorm,
+}
+
+impl TransformerEncoderLayer {
+    /// Create TransformerEncoderLayer with randomly set weights, drawn uniformly from
+    /// ±1 / sqrt( fan in ), and norms that start as the identity
+    ///
+    /// # Arguments
+    ///
+    /// * `d_model`: Number of features of every step
+    /// * `num_heads`: Number of attention heads, which has to divide `d_model`
+    /// * `feed_forward_size`: Number of nodes in the hidden layer of the feed-forward network
+    /// * `causal`: Whether steps can only attend to themselves and the steps before them
+    /// * `seed`: Seed for drawing the weights, or `None` to seed it from the operating system
+    ///
+    /// returns: `Result<TransformerEncoderLayer, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `num_heads` is zero or does not divide `d_model`, or
+    ///   `d_model` is zero
+    pub fn new(
+        d_model: usize,
+        num_heads: usize,
+        feed_forward_size: usize,
+        causal: bool,
+        seed: Option<u64>,
+    ) -> Result<Self, DuckyError> {
+        let mut rng = seeded_rng(seed);
+        let mut dense = |input_size: usize, output_size: usize, activation| {
+            let projection = Projection::new(input_size, output_size, &mut rng);
+            Dense1d::from_named(
+                activation,
+                projection.weights.reversed_axes(),
+                projection.bias,
+            )
+        };
+        let hidden = dense(d_model, feed_forward_size, Activation::Relu);
+        let output = dense(feed_forward_size, d_model, Activation::Identity);
+
+        Ok(Self {
+            attention: MultiHeadAttention::new(
+                d_model,
+                num_heads,
+                causal,
+                seed.map(|seed| seed.wrapping_add(1)),
+            )?,
+            attention_norm: LayerNorm::new(d_model, 1e-5)?,
+            hidden,
+            output,
+            output_norm: LayerNorm::new(d_model, 1e-5)?,
+        })
+    }
+
+    /// Number of features of every step.
+    pub fn d_model(&self) -> usize {
+        self.attention.d_model()
+    }
+
+    fn step_pass(&self, attention_sum: Array1<f64>) -> Result<FeedForwardCache, DuckyError> {
+        let (attention_x_hat, attention_norm) = self
+            .attention_norm
+            .pass(attention_sum.clone(), Mode::Train)?;
+        let (hidden_z, hidden) = self.hidden.pass(attention_norm.clone(), Mode::Train)?;
+        let (output_z, output) = self.output.pass(hidden.clone(), Mode::Train)?;
+        let output_sum = &attention_norm + &output;
+        let (output_x_hat, output_norm) = self.output_norm.pass(output_sum.clone(), Mode::Train)?;
+
+        Ok(FeedForwardCache {
+            attention_sum,
+            attention_x_hat,
+            attention_norm,
+            hidden_z,
+            hidden,
+            output_z,
+            output_sum,
+            output_x_hat,
+            output_norm,
+        })
+    }
+
+    /// Runs every step of a batch through the feed-forward block after the attention layer.
+    fn run(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<Vec<FeedForwardCache>, DuckyError> {
+        let attention_sum = input_array + &self.attention.pass_batch(input_array, mask)?;
+        let d_model = self.d_model();
+
+        attention_sum
+            .into_shape((input_array.len() / d_model, d_model))
+            .unwrap()
+            .outer_iter()
+            .map(|step| self.step_pass(step.to_owned()))
+            .collect()
+    }
+
+    /// Feeds forward a batch of sequences through the block.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of sequences of shape( batch, time, d_model )
+    /// * `mask`: Which steps of each sequence are real, of shape( batch, time )
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` of shape( batch, time, d_model )
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `input_array` or `mask` do not line up with the layer
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let steps: Vec<_> = self
+            .run(input_array, mask)?
+            .into_iter()
+            .flat_map(|cache| cache.output_norm)
+            .collect();
+
+        Ok(Array3::from_shape_vec(input_array.raw_dim(), steps).unwrap())
+    }
+
+    /// Backpropagates the gradient of the output of `pass_batch` through the block, taking
+    /// gradient descent steps on every layer along the way.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The batch the layer was passed during the forward pass
+    /// * `mask`: The mask the layer was passed during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of `pass_batch`
+    /// * `learning_rate`: Size of the gradient descent steps
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to `input_array`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn back_pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+        output_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let caches = self.run(input_array, mask)?;
+        check_shape(input_array.shape(), output_gradient)?;
+
+        let mut attention_sum_gradient = Vec::with_capacity(input_array.len());
+        for (cache, output_gradient) in caches.iter().zip(
+            output_gradient
+                .to_shape((caches.len(), self.d_model()))
+                .unwrap()
+                .outer_iter(),
+        ) {
+            let output_sum_gradient = self.output_norm.back_pass(
+                &cache.output_sum,
+                &cache.output_x_hat,
+                &output_gradient.to_owned(),
+                learning_rate,
+            )?;
+            let hidden_gradient = self.output.back_pass(
+                &cache.hidden,
+                &cache.output_z,
+                &output_sum_gradient,
+                learning_rate,
+            )?;
+            let attention_norm_gradient = output_sum_gradient
+                + self.hidden.back_pass(
+                    &cache.attention_norm,
+                    &cache.hidden_z,
+                    &hidden_gradient,
+                    learning_rate,
+                )?;
+
+            attention_sum_gradient.extend(self.attention_norm.back_pass(
+                &cache.attention_sum,
+                &cache.attention_x_hat,
+                &attention_norm_gradient,
+                learning_rate,
+            )?);
+        }
+
+        let attention_sum_gradient =
+            Array3::from_shape_vec(input_array.raw_dim(), attention_sum_gradient).unwrap();
+        let input_gradient = self.attention.back_pass_batch(
+            input_array,
+            mask,
+            &attention_sum_gradient,
+            learning_rate,
+        )?;
+
+        Ok(input_gradient + attention_sum_gradient)
+    }
+}
+
+impl Layer1d for TransformerEncoderLayer {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        _mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let input_array = sequence(self.d_model(), &input_array)?;
+        let output: Array1<f64> = self.pass_batch(&input_array, None)?.into_iter().collect();
+        Ok((output.clone(), output))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        _weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let input_array = sequence(self.d_model(), input_array)?;
+        let shape = [1, input_array.shape()[1], self.d_model()];
+
+        let input_gradient = self.back_pass_batch(
+            &input_array,
+            None,
+            &self::output_gradient(shape, output_gradient)?,
+            learning_rate,
+        )?;
+        Ok(input_gradient.into_iter().collect())
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::TransformerEncoderLayer(Box::new(self.clone())))
+    }
+}
+
+#[cfg(test)]
+mod attention_tests {
+    use super::*;
+    use crate::test_util::{assert_close, numerical_gradient, uneven};
+    use crate::train::train;
+    use crate::traits::Persist;
+    use ndarray::{arr1, arr2};
+
+    fn mask() -> Array2<bool> {
+        arr2(&[[true, true, true], [true, true, false]])
+    }
+
+    fn check_attention_gradients(causal: bool, mask: Option<&Array2<bool>>) {
+        let layer = MultiHeadAttention::new(4, 2, causal, Some(7)).unwrap();
+        let x = uneven((2, 3, 4));
+        let output_gradient = uneven((2, 3, 4)).mapv(|value| value.cos());
+
+        let expected =
+            numerical_gradient(&x, &output_gradient, |x| layer.pass_batch(x, mask).unwrap());
+        let updated = layer.clone();
+        let found = updated
+            .back_pass_batch(&x, mask, &output_gradient, 1.)
+            .unwrap();
+        assert_close(&found, &expected);
+
+        let before = layer.weights();
+        let after = updated.weights();
+        let with = |weights: AttentionWeights| {
+            MultiHeadAttention::from(weights, 2, causal)
+                .unwrap()
+                .pass_batch(&x, mask)
+                .unwrap()
+        };
+        let projections = |weights: &AttentionWeights| {
+            [
+                weights.query.clone(),
+                weights.key.clone(),
+                weights.value.clone(),
+                weights.output.clone(),
+            ]
+        };
+        let replace = |index: usize, projection: Projection| {
+            let mut weights = before.clone();
+            *[
+                &mut weights.query,
+                &mut weights.key,
+                &mut weights.value,
+                &mut weights.output,
+            ][index] = projection;
+            weights
+        };
+
+        for (index, (before, after)) in projections(&before)
+            .into_iter()
+            .zip(projections(&after))
+            .enumerate()
+        {
+            let weights = numerical_gradient(&before.weights, &output_gradient, |weights| {
+                with(replace(
+                    index,
+                    Projection {
+                        weights: weights.clone(),
+                        bias: before.bias.clone(),
+                    },
+                ))
+            });
+            let bias = numerical_gradient(&before.bias, &output_gradient, |bias| {
+                with(replace(
+                    index,
+                    Projection {
+                        weights: before.weights.clone(),
+                        bias: bias.clone(),
+                    },
+                ))
+            });
+            assert_close(&(&before.weights - &after.weights), &weights);
+            assert_close(&(&before.bias - &after.bias), &bias);
+        }
+    }
+
+    #[test]
+    fn attention_gradients() {
+        check_attention_gradients(false, None);
+        check_attention_gradients(false, Some(&mask()));
+        check_attention_gradients(true, Some(&mask()));
+    }
+
+    #[test]
+    fn attention_masks() {
+        let x = uneven((2, 3, 4));
+        let mut changed = x.clone();
+        changed.slice_mut(s![1, 2, ..]).fill(5.);
+
+        let padded = MultiHeadAttention::new(4, 2, false, Some(1)).unwrap();
+        let mask = mask();
+        assert_close(
+            &padded
+                .pass_batch(&x, Some(&mask))
+                .unwrap()
+                .slice(s![.., ..2, ..])
+                .to_owned(),
+            &padded
+                .pass_batch(&changed, Some(&mask))
+                .unwrap()
+                .slice(s![.., ..2, ..])
+                .to_owned(),
+        );
+
+        let causal = MultiHeadAttention::new(4, 1, true, Some(1)).unwrap();
+        let output = causal.pass_batch(&x, None).unwrap();
+        let changed_output = causal.pass_batch(&changed, None).unwrap();
+        assert_close(
+            &output.slice(s![1, ..2, ..]).to_owned(),
+            &changed_output.slice(s![1, ..2, ..]).to_owned(),
+        );
+        assert!(output[[1, 2, 0]] != changed_output[[1, 2, 0]]);
+    }
+
+    #[test]
+    fn masked_softmax_rows() {
+        let scores = arr2(&[[1., 2., 3.], [0., 0., 0.]]);
+        let allowed = arr2(&[[true, true, false], [false, false, false]]);
+        let attention = masked_softmax(&Tape::new().variable(scores), &allowed)
+            .unwrap()
+            .value_as::<Ix2>();
+
+        let e = 1f64.exp();
+        assert_close(
+            &attention,
+            &arr2(&[[1. / (1. + e), e / (1. + e), 0.], [0., 0., 0.]]),
+        );
+    }
+
+    #[test]
+    fn sinusoidal_encoding() {
+        let encoding = SinusoidalEncoding::new(4).unwrap();
+        let table = encoding.table(3);
+
+        assert_eq!(table.row(0), arr1(&[0., 1., 0., 1.]));
+        assert_close(
+            &table.row(2).to_owned(),
+            &arr1(&[2f64.sin(), 2f64.cos(), 0.02f64.sin(), 0.02f64.cos()]),
+        );
+        assert_eq!(
+            encoding
+                .back_pass(&arr1(&[0.; 8]), &arr1(&[0.; 8]), &arr1(&[1.; 8]), 0.1)
+                .unwrap(),
+            arr1(&[1.; 8])
+        );
+        assert!(matches!(
+            SinusoidalEncoding::new(0),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn learned_encoding() {
+        let encoding = LearnedEncoding::new(3, 2, Some(2));
+        let before = encoding.weights();
+        let output_gradient = uneven((2, 2, 2));
+
+        let input_gradient = encoding
+            .back_pass_batch(&Array3::zeros((2, 2, 2)), &output_gradient, 0.5)
+            .unwrap();
+
+        assert_eq!(input_gradient, output_gradient);
+        assert_close(
+            &(&before.slice(s![..2, ..]) - &encoding.weights().slice(s![..2, ..])),
+            &(output_gradient.sum_axis(Axis(0)) * 0.5),
+        );
+        assert_eq!(encoding.weights().row(2), before.row(2));
+        assert!(matches!(
+            encoding.pass_batch(&Array3::zeros((1, 4, 2))),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn encoder_gradients() {
+        let encoder = TransformerEncoderLayer::new(4, 2, 6, false, Some(3)).unwrap();
+        let x = uneven((2, 3, 4));
+        let mask = mask();
+        let output_gradient = uneven((2, 3, 4)).mapv(|value| value.cos());
+
+        let expected = numerical_gradient(&x, &output_gradient, |x| {
+            encoder.pass_batch(x, Some(&mask)).unwrap()
+        });
+        let found = encoder
+            .back_pass_batch(&x, Some(&mask), &output_gradient, 0.)
+            .unwrap();
+        assert_close(&found, &expected);
+    }
+
+    #[test]
+    fn attention_errors() {
+        let layer = MultiHeadAttention::new(4, 2, false, Some(4)).unwrap();
+
+        assert!(matches!(
+            MultiHeadAttention::new(4, 3, false, None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            MultiHeadAttention::new(4, 0, false, None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            MultiHeadAttention::from(
+                AttentionWeights {
+                    output: Projection {
+                        weights: Array2::zeros((4, 3)),
+                        bias: Array1::zeros(3),
+                    },
+                    ..layer.weights()
+                },
+                2,
+                false
+            ),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert_eq!(
+            layer.pass_batch(&Array3::zeros((1, 2, 3)), None),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1, 2, 4],
+                found: vec![1, 2, 3],
+            })
+        );
+        assert_eq!(
+            layer.back_pass_batch(
+                &Array3::zeros((1, 2, 4)),
+                None,
+                &Array3::zeros((1, 3, 4)),
+                0.1
+            ),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1, 2, 4],
+                found: vec![1, 3, 4],
+            })
+        );
+        assert!(matches!(
+            TransformerEncoderLayer::new(4, 3, 8, false, None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn attention_write_read() {
+        let layer = MultiHeadAttention::new(4, 2, true, Some(5)).unwrap();
+        let mut buffer = Vec::new();
+        layer.write_to(&mut buffer).unwrap();
+
+        let loaded = MultiHeadAttention::read_from(buffer.as_slice()).unwrap();
+        assert_eq!(loaded.weights(), layer.weights());
+        assert_eq!((loaded.num_heads(), loaded.causal()), (2, true));
+    }
+
+    #[test]
+    fn encoder_write_read() {
+        let model: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(SinusoidalEncoding::new(4).unwrap()),
+            Box::new(TransformerEncoderLayer::new(4, 2, 8, true, Some(7)).unwrap()),
+        ];
+        let mut buffer = Vec::new();
+        model.write_to(&mut buffer).unwrap();
+
+        let loaded = Vec::<Box<dyn Layer1d>>::read_from(buffer.as_slice()).unwrap();
+        let input_array = uneven((1, 3, 4)).into_shape(12).unwrap();
+        for (loaded, layer) in loaded.iter().zip(&model) {
+            // Loaded weights are in standard layout, so products may round differently
+            assert_close(
+                &loaded.pass(input_array.clone(), Mode::Eval).unwrap().1,
+                &layer.pass(input_array.clone(), Mode::Eval).unwrap().1,
+            );
+        }
+    }
+
+    #[test]
+    fn encoder_train() {
+        // The target is the first value of the sequence times the last
+        let model: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(SinusoidalEncoding::new(2).unwrap()),
+            Box::new(TransformerEncoderLayer::new(2, 1, 8, false, Some(6)).unwrap()),
+            Box::new(Dense1d::from(
+                |x| x,
+                |x| x.map(|_| 1f64),
+                Array2::from_elem((1, 6), 0.1),
+                arr1(&[0.]),
+            )),
+        ];
+        let data = arr2(&[
+            [1., 0., 0., 0., 1., 0.],
+            [1., 0., 0., 0., -1., 0.],
+            [-1., 0., 0., 0., 1., 0.],
+            [0.5, 0., 0., 0., 0.5, 0.],
+        ]);
+        let labels = arr2(&[[1.], [-1.], [-1.], [0.25]]);
+
+        let costs = train(
+            &model,
+            data.clone(),
+            labels.clone(),
+            data,
+            labels,
+            100,
+            0.02,
+        )
+        .unwrap();
+
+        assert!(costs.iter().all(|cost| cost.is_finite()));
+        assert!(costs[99] < costs[0]);
+    }
+}
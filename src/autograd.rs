@@ -0,0 +1,1271 @@
+use super::error::DuckyError;
+use ndarray::prelude::*;
+use ndarray::Slice;
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Range, Sub};
+use std::rc::Rc;
+
+/// Maps the gradient of a node's value to the gradient of one of its parents.
+type Backward = Box<dyn Fn(&ArrayD<f64>) -> ArrayD<f64>>;
+
+struct Node {
+    parents: Vec<(usize, Backward)>,
+}
+
+/// Records every operation on its tensors in the order they happen, so `Tensor::backward`
+/// can walk the record in reverse and apply the chain rule.
+///
+/// Every tensor of a computation has to come from the same tape. Create a new tape for every
+/// forward pass, as the tape keeps growing for as long as it is used.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::autograd::Tape;
+/// use ndarray::{arr1, arr2};
+///
+/// let tape = Tape::new();
+/// let w = tape.variable(arr2(&[[1., 2.], [3., 4.]]));
+/// let x = tape.variable(arr1(&[1., -1.]));
+///
+/// let loss = w.matmul(&x).unwrap().relu().sum();
+/// let gradients = loss.backward();
+///
+/// assert_eq!(loss.item(), 0.);
+/// assert_eq!(gradients.wrt(&x), arr1(&[0., 0.]).into_dyn());
+/// ```
+#[derive(Default)]
+pub struct Tape {
+    nodes: RefCell<Vec<Node>>,
+}
+
+impl Tape {
+    /// Creates an empty tape.
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self::default())
+    }
+
+    /// Number of tensors recorded on the tape.
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    /// Whether no tensor has been recorded on the tape.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records a tensor that gradients can be taken with respect to.
+    ///
+    /// # Arguments
+    ///
+    /// * `value`: Value of the tensor, of any dimension
+    ///
+    /// returns: `Tensor`
+    pub fn variable<D: Dimension>(self: &Rc<Self>, value: Array<f64, D>) -> Tensor {
+        self.push(value.into_dyn(), Vec::new())
+    }
+
+    /// Records a tensor holding a single value.
+    pub fn scalar(self: &Rc<Self>, value: f64) -> Tensor {
+        self.variable(arr0(value))
+    }
+
+    fn push(self: &Rc<Self>, value: ArrayD<f64>, parents: Vec<(usize, Backward)>) -> Tensor {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(Node { parents });
+
+        Tensor {
+            tape: Rc::clone(self),
+            index: nodes.len() - 1,
+            value: Rc::new(value),
+        }
+    }
+}
+
+/// Gradient of a tensor with respect to every tensor recorded before it, from
+/// `Tensor::backward`.
+#[derive(Debug, Clone)]
+pub struct Gradients {
+    gradients: Vec<Option<ArrayD<f64>>>,
+}
+
+impl Gradients {
+    /// Gradient with respect to `tensor`, or `None` if the output does not depend on it.
+    pub fn get(&self, tensor: &Tensor) -> Option<&ArrayD<f64>> {
+        self.gradients.get(tensor.index).and_then(Option::as_ref)
+    }
+
+    /// Gradient with respect to `tensor`, which is all zeros if the output does not depend
+    /// on it.
+    pub fn wrt(&self, tensor: &Tensor) -> ArrayD<f64> {
+        self.get(tensor)
+            .cloned()
+            .unwrap_or_else(|| ArrayD::zeros(tensor.value.raw_dim()))
+    }
+}
+
+/// Array of `f64` whose operations are recorded on a `Tape`, so the gradient of any result
+/// with respect to the tensors it was computed from comes from `backward` instead of
+/// hand-written derivatives.
+///
+/// The arithmetic operators work between tensors and between tensors and `f64`, and
+/// broadcast like ndarray does. Like ndarray's, the operators between two tensors panic if
+/// their shapes do not broadcast or they come from different tapes; `try_add`, `try_sub`,
+/// `try_mul` and `try_div` return a `DuckyError` instead. Every other operation that depends
+/// on the shape of its tensors returns a `DuckyError` when they do not fit.
+///
+/// Cloning a tensor is cheap and refers to the same recorded value.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::autograd::Tape;
+/// use ndarray::{arr1, arr2};
+///
+/// let tape = Tape::new();
+/// let x = tape.variable(arr2(&[[1., 2.], [3., 4.]]));
+/// let bias = tape.variable(arr1(&[1., -1.]));
+///
+/// // The bias is broadcast over the rows, so its gradient is summed over them
+/// let y = (&x * &x + &bias).sum();
+/// let gradients = y.backward();
+///
+/// assert_eq!(y.item(), 30.);
+/// assert_eq!(gradients.wrt(&x), arr2(&[[2., 4.], [6., 8.]]).into_dyn());
+/// assert_eq!(gradients.wrt(&bias), arr1(&[2., 2.]).into_dyn());
+/// ```
+#[derive(Clone)]
+pub struct Tensor {
+    tape: Rc<Tape>,
+    index: usize,
+    value: Rc<ArrayD<f64>>,
+}
+
+impl fmt::Debug for Tensor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tensor")
+            .field("index", &self.index)
+            .field("value", &*self.value)
+            .finish()
+    }
+}
+
+/// Checks that values of shapes `left` and `right` can be broadcast against each other.
+fn check_broadcast(left: &[usize], right: &[usize]) -> Result<(), DuckyError> {
+    let fits = left
+        .iter()
+        .rev()
+        .zip(right.iter().rev())
+        .all(|(&left, &right)| left == right || left == 1 || right == 1);
+    if !fits {
+        return Err(DuckyError::ShapeMismatch {
+            expected: left.to_vec(),
+            found: right.to_vec(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks that `axis` is one of the `ndim` axes of a tensor.
+fn check_axis(axis: usize, ndim: usize) -> Result<(), DuckyError> {
+    if axis >= ndim {
+        return Err(DuckyError::InvalidParameter(format!(
+            "axis {} is out of bounds for a tensor with {} axes",
+            axis, ndim
+        )));
+    }
+
+    Ok(())
+}
+
+/// Sums `gradient` down to `shape`, undoing the broadcasting of a value of that shape.
+fn unbroadcast(mut gradient: ArrayD<f64>, shape: &[usize]) -> ArrayD<f64> {
+    while gradient.ndim() > shape.len() {
+        gradient = gradient.sum_axis(Axis(0));
+    }
+    for (axis, &size) in shape.iter().enumerate() {
+        if size == 1 && gradient.shape()[axis] != 1 {
+            gradient = gradient.sum_axis(Axis(axis)).insert_axis(Axis(axis));
+        }
+    }
+
+    gradient
+}
+
+impl Tensor {
+    /// Value of the tensor.
+    pub fn value(&self) -> &ArrayD<f64> {
+        &self.value
+    }
+
+    /// Value of a tensor known to be 1d, such as one recorded from an `Array1`.
+    pub(crate) fn value_1d(&self) -> Array1<f64> {
+        self.value_as::<Ix1>()
+    }
+
+    /// Value of a tensor known to have the dimension `D`.
+    pub(crate) fn value_as<D: Dimension>(&self) -> Array<f64, D> {
+        self.value
+            .view()
+            .into_dimensionality::<D>()
+            .unwrap()
+            .to_owned()
+    }
+
+    /// Shape of the value of the tensor.
+    pub fn shape(&self) -> &[usize] {
+        self.value.shape()
+    }
+
+    /// Sum of the values of the tensor, which is its only value for a scalar.
+    pub fn item(&self) -> f64 {
+        self.value.sum()
+    }
+
+    /// Tape the tensor is recorded on.
+    pub fn tape(&self) -> &Rc<Tape> {
+        &self.tape
+    }
+
+    fn check_tape(&self, other: &Tensor) -> Result<(), DuckyError> {
+        if !Rc::ptr_eq(&self.tape, &other.tape) {
+            return Err(DuckyError::InvalidParameter(
+                "tensors are recorded on different tapes".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `other` can be combined with the tensor element by element.
+    fn check_operand(&self, other: &Tensor) -> Result<(), DuckyError> {
+        self.check_tape(other)?;
+        check_broadcast(self.shape(), other.shape())
+    }
+
+    fn unary(
+        &self,
+        value: ArrayD<f64>,
+        backward: impl Fn(&ArrayD<f64>) -> ArrayD<f64> + 'static,
+    ) -> Tensor {
+        self.tape
+            .push(value, vec![(self.index, Box::new(backward) as Backward)])
+    }
+
+    fn binary(
+        &self,
+        other: &Tensor,
+        value: ArrayD<f64>,
+        backward_self: impl Fn(&ArrayD<f64>) -> ArrayD<f64> + 'static,
+        backward_other: impl Fn(&ArrayD<f64>) -> ArrayD<f64> + 'static,
+    ) -> Tensor {
+        self.tape.push(
+            value,
+            vec![
+                (self.index, Box::new(backward_self) as Backward),
+                (other.index, Box::new(backward_other) as Backward),
+            ],
+        )
+    }
+
+    /// Gradient of the sum of the values of the tensor with respect to every tensor recorded
+    /// before it. For a scalar this is simply its gradient.
+    pub fn backward(&self) -> Gradients {
+        self.backward_from(ArrayD::ones(self.value.raw_dim()))
+    }
+
+    /// Gradient of the tensor with respect to every tensor recorded before it, given the
+    /// gradient of some cost with respect to the tensor.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_gradient`: Gradient of the cost with respect to this tensor
+    ///
+    /// returns: `Result<Gradients, DuckyError>` holding the gradient of the cost
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `output_gradient` has a different shape to the tensor
+    pub fn backward_with<D: Dimension>(
+        &self,
+        output_gradient: &Array<f64, D>,
+    ) -> Result<Gradients, DuckyError> {
+        if output_gradient.shape() != self.shape() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: self.shape().to_vec(),
+                found: output_gradient.shape().to_vec(),
+            });
+        }
+
+        Ok(self.backward_from(output_gradient.view().into_dyn().to_owned()))
+    }
+
+    fn backward_from(&self, output_gradient: ArrayD<f64>) -> Gradients {
+        let nodes = self.tape.nodes.borrow();
+        let mut gradients: Vec<Option<ArrayD<f64>>> = vec![None; self.index + 1];
+        gradients[self.index] = Some(output_gradient);
+
+        // Every node is recorded after its parents, so walking backwards visits a node only
+        // once all of the gradient flowing into it has been added up
+        for index in (0..=self.index).rev() {
+            let gradient = match &gradients[index] {
+                Some(gradient) => gradient.clone(),
+                None => continue,
+            };
+
+            for (parent, backward) in &nodes[index].parents {
+                let parent_gradient = backward(&gradient);
+                match &mut gradients[*parent] {
+                    Some(sum) => *sum += &parent_gradient,
+                    empty => *empty = Some(parent_gradient),
+                }
+            }
+        }
+
+        Gradients { gradients }
+    }
+
+    /// Records a copy of the tensor that gradients do not flow through.
+    pub fn detach(&self) -> Tensor {
+        self.tape.push((*self.value).clone(), Vec::new())
+    }
+
+    /// Matrix product with `other`, for a matrix times a matrix or a vector.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: the tensor is not a matrix, `other` is not a matrix
+    ///   or vector, or they are recorded on different tapes
+    /// * `DuckyError::ShapeMismatch`: the inner dimensions differ
+    pub fn matmul(&self, other: &Tensor) -> Result<Tensor, DuckyError> {
+        self.check_tape(other)?;
+        if self.value.ndim() != 2 || !(1..=2).contains(&other.value.ndim()) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "matmul needs a matrix on the left and a matrix or vector on the right, found \
+                 shapes {:?} and {:?}",
+                self.shape(),
+                other.shape()
+            )));
+        }
+        if self.shape()[1] != other.shape()[0] {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![self.shape()[1]],
+                found: vec![other.shape()[0]],
+            });
+        }
+
+        let a = self
+            .value
+            .view()
+            .into_dimensionality::<Ix2>()
+            .unwrap()
+            .to_owned();
+
+        Ok(match other.value.ndim() {
+            1 => {
+                let b = other
+                    .value
+                    .view()
+                    .into_dimensionality::<Ix1>()
+                    .unwrap()
+                    .to_owned();
+                let value = a.dot(&b).into_dyn();
+                let (a_copy, b_copy) = (a.clone(), b.clone());
+
+                self.binary(
+                    other,
+                    value,
+                    move |gradient| {
+                        let gradient = gradient.view().into_dimensionality::<Ix1>().unwrap();
+                        gradient
+                            .insert_axis(Axis(1))
+                            .dot(&b_copy.view().insert_axis(Axis(0)))
+                            .into_dyn()
+                    },
+                    move |gradient| {
+                        let gradient = gradient.view().into_dimensionality::<Ix1>().unwrap();
+                        a_copy.t().dot(&gradient).into_dyn()
+                    },
+                )
+            }
+            _ => {
+                let b = other
+                    .value
+                    .view()
+                    .into_dimensionality::<Ix2>()
+                    .unwrap()
+                    .to_owned();
+                let value = a.dot(&b).into_dyn();
+                let (a_copy, b_copy) = (a.clone(), b.clone());
+
+                self.binary(
+                    other,
+                    value,
+                    move |gradient| {
+                        let gradient = gradient.view().into_dimensionality::<Ix2>().unwrap();
+                        gradient.dot(&b_copy.t()).into_dyn()
+                    },
+                    move |gradient| {
+                        let gradient = gradient.view().into_dimensionality::<Ix2>().unwrap();
+                        a_copy.t().dot(&gradient).into_dyn()
+                    },
+                )
+            }
+        })
+    }
+
+    /// Transpose of a matrix.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: the tensor is not a matrix
+    pub fn t(&self) -> Result<Tensor, DuckyError> {
+        if self.value.ndim() != 2 {
+            return Err(DuckyError::InvalidParameter(format!(
+                "only matrices can be transposed, found shape {:?}",
+                self.shape()
+            )));
+        }
+
+        Ok(self.unary(self.value.t().to_owned(), |gradient| {
+            gradient.t().to_owned()
+        }))
+    }
+
+    /// Tensor with the same values in a different shape, in row-major order.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `shape` does not hold as many values as the tensor
+    pub fn reshape(&self, shape: &[usize]) -> Result<Tensor, DuckyError> {
+        if shape.iter().product::<usize>() != self.value.len() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: self.shape().to_vec(),
+                found: shape.to_vec(),
+            });
+        }
+
+        let original = self.shape().to_vec();
+        let value = self.value.to_shape(shape).unwrap().to_owned();
+
+        Ok(self.unary(value, move |gradient| {
+            gradient.to_shape(original.as_slice()).unwrap().to_owned()
+        }))
+    }
+
+    /// Values from `range.start` up to `range.end` along `axis`.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `axis` is out of bounds or `range` does not fit in it
+    pub fn slice_axis(&self, axis: usize, range: Range<usize>) -> Result<Tensor, DuckyError> {
+        check_axis(axis, self.value.ndim())?;
+        if range.start > range.end || range.end > self.shape()[axis] {
+            return Err(DuckyError::InvalidParameter(format!(
+                "range {:?} is out of bounds for an axis of length {}",
+                range,
+                self.shape()[axis]
+            )));
+        }
+
+        let shape = self.value.raw_dim();
+        let slice = Slice::from(range);
+        Ok(self.unary(
+            self.value.slice_axis(Axis(axis), slice).to_owned(),
+            move |gradient| {
+                let mut input_gradient = ArrayD::zeros(shape.clone());
+                input_gradient
+                    .slice_axis_mut(Axis(axis), slice)
+                    .assign(gradient);
+                input_gradient
+            },
+        ))
+    }
+
+    /// Values at `index` along `axis`, which is removed from the shape.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `axis` or `index` is out of bounds
+    pub fn index_axis(&self, axis: usize, index: usize) -> Result<Tensor, DuckyError> {
+        let slice = self.slice_axis(axis, index..index + 1)?;
+
+        let mut shape = self.shape().to_vec();
+        shape.remove(axis);
+        slice.reshape(&shape)
+    }
+
+    /// Applies `function` to every value, with `derivative` giving the derivative of every
+    /// value from the input and output value.
+    fn elementwise(
+        &self,
+        function: impl Fn(f64) -> f64,
+        derivative: impl Fn(f64, f64) -> f64 + 'static,
+    ) -> Tensor {
+        let value = self.value.mapv(function);
+        let input = Rc::clone(&self.value);
+        let output = value.clone();
+
+        self.unary(value, move |gradient| {
+            let mut input_gradient = gradient.clone();
+            ndarray::Zip::from(&mut input_gradient)
+                .and(&*input)
+                .and(&output)
+                .for_each(|gradient, &input, &output| *gradient *= derivative(input, output));
+            input_gradient
+        })
+    }
+
+    /// Exponential of every value.
+    pub fn exp(&self) -> Tensor {
+        self.elementwise(f64::exp, |_, output| output)
+    }
+
+    /// Natural logarithm of every value.
+    pub fn ln(&self) -> Tensor {
+        self.elementwise(f64::ln, |input, _| 1. / input)
+    }
+
+    /// Square root of every value.
+    pub fn sqrt(&self) -> Tensor {
+        self.elementwise(f64::sqrt, |_, output| 0.5 / output)
+    }
+
+    /// Every value to the power `n`.
+    pub fn powi(&self, n: i32) -> Tensor {
+        self.elementwise(
+            move |value| value.powi(n),
+            move |input, _| n as f64 * input.powi(n - 1),
+        )
+    }
+
+    /// Relu activation of every value.
+    pub fn relu(&self) -> Tensor {
+        self.elementwise(|value| value.max(0.), |input, _| (input > 0.) as i32 as f64)
+    }
+
+    /// Sigmoid activation of every value.
+    pub fn sigmoid(&self) -> Tensor {
+        self.elementwise(
+            |value| 1. / (1. + (-value).exp()),
+            |_, output| output * (1. - output),
+        )
+    }
+
+    /// Tanh activation of every value.
+    pub fn tanh(&self) -> Tensor {
+        self.elementwise(f64::tanh, |_, output| 1. - output * output)
+    }
+
+    /// Sum of every value, as a scalar.
+    pub fn sum(&self) -> Tensor {
+        let shape = self.value.raw_dim();
+        self.unary(arr0(self.value.sum()).into_dyn(), move |gradient| {
+            ArrayD::from_elem(shape.clone(), gradient.sum())
+        })
+    }
+
+    /// Mean of every value, as a scalar.
+    pub fn mean(&self) -> Tensor {
+        self.sum() / self.value.len().max(1) as f64
+    }
+
+    /// Sum along `axis`, which is removed from the shape.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `axis` is out of bounds
+    pub fn sum_axis(&self, axis: usize) -> Result<Tensor, DuckyError> {
+        check_axis(axis, self.value.ndim())?;
+
+        let shape = self.value.raw_dim();
+        Ok(
+            self.unary(self.value.sum_axis(Axis(axis)), move |gradient| {
+                gradient
+                    .view()
+                    .insert_axis(Axis(axis))
+                    .broadcast(shape.clone())
+                    .unwrap()
+                    .to_owned()
+            }),
+        )
+    }
+
+    /// Mean along `axis`, which is removed from the shape.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `axis` is out of bounds
+    pub fn mean_axis(&self, axis: usize) -> Result<Tensor, DuckyError> {
+        Ok(self.sum_axis(axis)? / self.shape()[axis].max(1) as f64)
+    }
+
+    /// Tensor with a new axis of length one at `axis`, for broadcasting against it.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `axis` is greater than the number of axes
+    pub fn insert_axis(&self, axis: usize) -> Result<Tensor, DuckyError> {
+        check_axis(axis, self.value.ndim() + 1)?;
+
+        let mut shape = self.shape().to_vec();
+        shape.insert(axis, 1);
+        self.reshape(&shape)
+    }
+
+    /// Log of the softmax along `axis`, computed without overflowing for large values.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `axis` is out of bounds
+    pub fn log_softmax(&self, axis: usize) -> Result<Tensor, DuckyError> {
+        check_axis(axis, self.value.ndim())?;
+
+        let max = self
+            .value
+            .fold_axis(Axis(axis), f64::NEG_INFINITY, |&max, &value| max.max(value))
+            .insert_axis(Axis(axis));
+        let shifted = self - &self.tape.variable(max);
+
+        Ok(&shifted - &shifted.exp().sum_axis(axis)?.ln().insert_axis(axis)?)
+    }
+
+    /// Softmax activation along `axis`.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `axis` is out of bounds
+    pub fn softmax(&self, axis: usize) -> Result<Tensor, DuckyError> {
+        Ok(self.log_softmax(axis)?.exp())
+    }
+
+    /// Sum of the tensor and `other`, broadcast against each other.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the shapes do not broadcast
+    /// * `DuckyError::InvalidParameter`: the tensors are recorded on different tapes
+    pub fn try_add(&self, other: &Tensor) -> Result<Tensor, DuckyError> {
+        self.check_operand(other)?;
+
+        let (left, right) = (self.shape().to_vec(), other.shape().to_vec());
+        Ok(self.binary(
+            other,
+            &*self.value + &*other.value,
+            move |gradient| unbroadcast(gradient.clone(), &left),
+            move |gradient| unbroadcast(gradient.clone(), &right),
+        ))
+    }
+
+    /// Difference of the tensor and `other`, broadcast against each other.
+    ///
+    /// # Errors
+    ///
+    /// The same as `try_add`.
+    pub fn try_sub(&self, other: &Tensor) -> Result<Tensor, DuckyError> {
+        self.check_operand(other)?;
+
+        let (left, right) = (self.shape().to_vec(), other.shape().to_vec());
+        Ok(self.binary(
+            other,
+            &*self.value - &*other.value,
+            move |gradient| unbroadcast(gradient.clone(), &left),
+            move |gradient| unbroadcast(-gradient, &right),
+        ))
+    }
+
+    /// Product of the tensor and `other` element by element, broadcast against each other.
+    ///
+    /// # Errors
+    ///
+    /// The same as `try_add`.
+    pub fn try_mul(&self, other: &Tensor) -> Result<Tensor, DuckyError> {
+        self.check_operand(other)?;
+
+        let (left, right) = (Rc::clone(&self.value), Rc::clone(&other.value));
+        let (left_copy, right_copy) = (Rc::clone(&left), Rc::clone(&right));
+        Ok(self.binary(
+            other,
+            &*left * &*right,
+            move |gradient| unbroadcast(gradient * &*right_copy, left_copy.shape()),
+            move |gradient| unbroadcast(gradient * &*left, right.shape()),
+        ))
+    }
+
+    /// Quotient of the tensor and `other` element by element, broadcast against each other.
+    ///
+    /// # Errors
+    ///
+    /// The same as `try_add`.
+    pub fn try_div(&self, other: &Tensor) -> Result<Tensor, DuckyError> {
+        self.check_operand(other)?;
+
+        let (left, right) = (Rc::clone(&self.value), Rc::clone(&other.value));
+        let (left_copy, right_copy) = (Rc::clone(&left), Rc::clone(&right));
+        Ok(self.binary(
+            other,
+            &*left / &*right,
+            move |gradient| unbroadcast(gradient / &*right_copy, left_copy.shape()),
+            move |gradient| {
+                unbroadcast(
+                    -(gradient * &*left) / &right.mapv(|value| value * value),
+                    right.shape(),
+                )
+            },
+        ))
+    }
+}
+
+// The operators between tensors panic with the error of their `try_` method, like ndarray's
+// operators panic on shapes that do not broadcast
+macro_rules! tensor_operators {
+    ($($trait:ident $method:ident $try_method:ident),*) => {$(
+        impl $trait for &Tensor {
+            type Output = Tensor;
+
+            fn $method(self, other: &Tensor) -> Tensor {
+                self.$try_method(other)
+                    .unwrap_or_else(|error| panic!("{}", error))
+            }
+        }
+    )*};
+}
+
+tensor_operators!(Add add try_add, Sub sub try_sub, Mul mul try_mul, Div div try_div);
+
+impl Neg for &Tensor {
+    type Output = Tensor;
+
+    fn neg(self) -> Tensor {
+        self.unary(-&*self.value, |gradient| -gradient)
+    }
+}
+
+impl Add<f64> for &Tensor {
+    type Output = Tensor;
+
+    fn add(self, other: f64) -> Tensor {
+        self.unary(&*self.value + other, |gradient| gradient.clone())
+    }
+}
+
+impl Sub<f64> for &Tensor {
+    type Output = Tensor;
+
+    fn sub(self, other: f64) -> Tensor {
+        self + -other
+    }
+}
+
+impl Mul<f64> for &Tensor {
+    type Output = Tensor;
+
+    fn mul(self, other: f64) -> Tensor {
+        self.unary(&*self.value * other, move |gradient| gradient * other)
+    }
+}
+
+impl Div<f64> for &Tensor {
+    type Output = Tensor;
+
+    fn div(self, other: f64) -> Tensor {
+        self * (1. / other)
+    }
+}
+
+// The owned operators borrow their operands and hand them to the operators above
+macro_rules! owned_operators {
+    ($($trait:ident $method:ident),*) => {$(
+        impl $trait for Tensor {
+            type Output = Tensor;
+
+            fn $method(self, other: Tensor) -> Tensor {
+                (&self).$method(&other)
+            }
+        }
+
+        impl $trait<&Tensor> for Tensor {
+            type Output = Tensor;
+
+            fn $method(self, other: &Tensor) -> Tensor {
+                (&self).$method(other)
+            }
+        }
+
+        impl $trait<Tensor> for &Tensor {
+            type Output = Tensor;
+
+            fn $method(self, other: Tensor) -> Tensor {
+                self.$method(&other)
+            }
+        }
+
+        impl $trait<f64> for Tensor {
+            type Output = Tensor;
+
+            fn $method(self, other: f64) -> Tensor {
+                (&self).$method(other)
+            }
+        }
+    )*};
+}
+
+owned_operators!(Add add, Sub sub, Mul mul, Div div);
+
+impl Neg for Tensor {
+    type Output = Tensor;
+
+    fn neg(self) -> Tensor {
+        -&self
+    }
+}
+
+/// Joins tensors along an existing `axis`, like `ndarray::concatenate`.
+///
+/// # Arguments
+///
+/// * `axis`: Axis to join the tensors along
+/// * `tensors`: Tensors with the same shape apart from along `axis`
+///
+/// returns: `Result<Tensor, DuckyError>`
+///
+/// # Errors
+///
+/// * `DuckyError::EmptyInput`: `tensors` is empty
+/// * `DuckyError::ShapeMismatch`: the shapes differ apart from along `axis`
+/// * `DuckyError::InvalidParameter`: `axis` is out of bounds or the tensors are recorded on
+///   different tapes
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::autograd::{concatenate, Tape};
+/// use ndarray::{arr1, arr2};
+///
+/// let tape = Tape::new();
+/// let x = tape.variable(arr2(&[[1., 2.]]));
+/// let y = tape.variable(arr2(&[[3., 4.], [5., 6.]]));
+///
+/// let joined = concatenate(0, &[x.clone(), y]).unwrap();
+///
+/// assert_eq!(joined.shape(), &[3, 2]);
+/// assert_eq!(joined.sum_axis(1).unwrap().backward().wrt(&x), arr2(&[[1., 1.]]).into_dyn());
+/// ```
+pub fn concatenate(axis: usize, tensors: &[Tensor]) -> Result<Tensor, DuckyError> {
+    let first = tensors.first().ok_or(DuckyError::EmptyInput)?;
+    check_axis(axis, first.value.ndim())?;
+    for tensor in tensors {
+        first.check_tape(tensor)?;
+        let fits = tensor.value.ndim() == first.value.ndim()
+            && (0..first.value.ndim())
+                .all(|other| other == axis || tensor.shape()[other] == first.shape()[other]);
+        if !fits {
+            return Err(DuckyError::ShapeMismatch {
+                expected: first.shape().to_vec(),
+                found: tensor.shape().to_vec(),
+            });
+        }
+    }
+
+    let views: Vec<_> = tensors.iter().map(|tensor| tensor.value.view()).collect();
+    let value = ndarray::concatenate(Axis(axis), &views).unwrap();
+
+    let mut start = 0;
+    let parents = tensors
+        .iter()
+        .map(|tensor| {
+            let slice = Slice::from(start..start + tensor.shape()[axis]);
+            start += tensor.shape()[axis];
+            let backward =
+                move |gradient: &ArrayD<f64>| gradient.slice_axis(Axis(axis), slice).to_owned();
+            (tensor.index, Box::new(backward) as Backward)
+        })
+        .collect();
+
+    Ok(first.tape.push(value, parents))
+}
+
+/// Joins tensors of the same shape along a new `axis`, like `ndarray::stack`.
+///
+/// # Arguments
+///
+/// * `axis`: Position of the new axis in the shape of the result
+/// * `tensors`: Tensors of the same shape
+///
+/// returns: `Result<Tensor, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `concatenate`.
+pub fn stack(axis: usize, tensors: &[Tensor]) -> Result<Tensor, DuckyError> {
+    let expanded = tensors
+        .iter()
+        .map(|tensor| tensor.insert_axis(axis))
+        .collect::<Result<Vec<_>, DuckyError>>()?;
+
+    concatenate(axis, &expanded)
+}
+
+/// Squared error of every element of a prediction, in the same order and with the same
+/// reduction as `cost::mean_squared_error`: nothing is summed, so take `mean` or `sum` of it
+/// for a scalar to call `backward` on.
+///
+/// # Arguments
+///
+/// * `observed`: Target values
+/// * `predicted`: Predicted values, of the same shape as `observed`
+///
+/// returns: `Result<Tensor, DuckyError>` holding `(observed - predicted)²` for every element
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: the tensors have different shapes
+/// * `DuckyError::InvalidParameter`: the tensors are recorded on different tapes
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::autograd::{mean_squared_error, Tape};
+/// use ndarray::arr1;
+///
+/// let tape = Tape::new();
+/// let observed = tape.variable(arr1(&[0., 0.]));
+/// let predicted = tape.variable(arr1(&[1., 2.]));
+///
+/// let loss = mean_squared_error(&observed, &predicted).unwrap().mean();
+///
+/// assert_eq!(loss.item(), 2.5);
+/// assert_eq!(loss.backward().wrt(&predicted), arr1(&[1., 2.]).into_dyn());
+/// ```
+pub fn mean_squared_error(observed: &Tensor, predicted: &Tensor) -> Result<Tensor, DuckyError> {
+    check_same_shape(observed, predicted)?;
+    Ok(observed.try_sub(predicted)?.powi(2))
+}
+
+/// Cross-entropy of every element between the target probabilities and the softmax of
+/// `logits` along their last axis. It takes the same order and reduction as
+/// `cost::cross_entropy`, which takes probabilities instead of logits. Nothing is summed, so
+/// take `sum` of it for a scalar to call `backward` on.
+///
+/// # Arguments
+///
+/// * `observed`: Target probabilities, e.g. one-hot encoded labels
+/// * `logits`: Unnormalised log probabilities with the classes along the last axis, of the
+///   same shape as `observed`
+///
+/// returns: `Result<Tensor, DuckyError>` holding `-observed * log_softmax(logits)` for every
+/// element
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: the tensors have different shapes
+/// * `DuckyError::InvalidParameter`: `logits` is a scalar or the tensors are recorded on
+///   different tapes
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::autograd::{cross_entropy_with_logits, Tape};
+/// use ndarray::arr2;
+///
+/// let tape = Tape::new();
+/// let observed = tape.variable(arr2(&[[1., 0.], [0., 1.]]));
+/// let logits = tape.variable(arr2(&[[0., 0.], [0., 0.]]));
+///
+/// let loss = cross_entropy_with_logits(&observed, &logits).unwrap();
+///
+/// assert!((loss.sum().item() - 2. * 2f64.ln()).abs() < 1e-12);
+/// ```
+pub fn cross_entropy_with_logits(observed: &Tensor, logits: &Tensor) -> Result<Tensor, DuckyError> {
+    check_same_shape(observed, logits)?;
+    let classes_axis = logits.shape().len().max(1) - 1;
+
+    Ok(-observed.try_mul(&logits.log_softmax(classes_axis)?)?)
+}
+
+/// Checks that a prediction has the same shape as its target.
+fn check_same_shape(observed: &Tensor, predicted: &Tensor) -> Result<(), DuckyError> {
+    if predicted.shape() != observed.shape() {
+        return Err(DuckyError::ShapeMismatch {
+            expected: observed.shape().to_vec(),
+            found: predicted.shape().to_vec(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod autograd_tests {
+    use super::*;
+    use crate::test_util::{assert_close, numerical_gradient};
+    use ndarray::{arr1, arr2};
+
+    fn check_gradient(x: ArrayD<f64>, f: impl Fn(&Rc<Tape>, Tensor) -> Tensor) {
+        let tape = Tape::new();
+        let variable = tape.variable(x.clone());
+        let found = f(&tape, variable.clone()).backward().wrt(&variable);
+        let expected = numerical_gradient(&x, &arr0(1.).into_dyn(), |x| {
+            let tape = Tape::new();
+            arr0(f(&tape, tape.variable(x.clone())).item()).into_dyn()
+        });
+
+        assert_close(&found, &expected);
+    }
+
+    fn matrix() -> ArrayD<f64> {
+        arr2(&[[0.5, -1.2, 0.3], [2.0, 0.1, -0.7]]).into_dyn()
+    }
+
+    #[test]
+    fn elementwise_gradients() {
+        check_gradient(matrix(), |_, x| x.exp().sum());
+        check_gradient(matrix().mapv(f64::abs), |_, x| x.ln().sum());
+        check_gradient(matrix().mapv(f64::abs), |_, x| x.sqrt().sum());
+        check_gradient(matrix(), |_, x| x.powi(3).sum());
+        check_gradient(matrix(), |_, x| x.relu().sum());
+        check_gradient(matrix(), |_, x| x.sigmoid().sum());
+        check_gradient(matrix(), |_, x| (x.tanh() * 2.).sum());
+        check_gradient(matrix(), |_, x| ((&x * &x) / (&x.exp() + 1.) - 3.).sum());
+        check_gradient(matrix(), |_, x| (-x).mean());
+    }
+
+    #[test]
+    fn broadcasting_gradients() {
+        let rows = arr2(&[[1., 2., 3.], [4., 5., 6.]]);
+        let row = arr1(&[0.5, -1., 2.]).into_dyn();
+        let column = arr2(&[[0.3], [-0.4]]).into_dyn();
+
+        check_gradient(row.clone(), move |tape, x| {
+            (&tape.variable(rows.clone()) * &x).powi(2).sum()
+        });
+        check_gradient(column, |tape, x| (&tape.variable(matrix()) / &x).sum());
+        check_gradient(row, |tape, x| (&tape.variable(matrix()) - &x).exp().sum());
+        check_gradient(arr0(1.5).into_dyn(), |tape, x| {
+            (&tape.variable(matrix()) + &x).powi(2).sum()
+        });
+    }
+
+    #[test]
+    fn matmul_gradients() {
+        let other = arr2(&[[1., 0.5], [-1., 2.], [0.3, 0.7]]);
+        let vector = arr1(&[1., -2., 0.5]);
+
+        let right = other.clone();
+        check_gradient(matrix(), move |tape, x| {
+            x.matmul(&tape.variable(right.clone()))
+                .unwrap()
+                .tanh()
+                .sum()
+        });
+        check_gradient(other.into_dyn(), |tape, x| {
+            tape.variable(matrix()).matmul(&x).unwrap().powi(2).sum()
+        });
+        let right = vector.clone();
+        check_gradient(matrix(), move |tape, x| {
+            x.matmul(&tape.variable(right.clone()))
+                .unwrap()
+                .sigmoid()
+                .sum()
+        });
+        check_gradient(vector.into_dyn(), |tape, x| {
+            tape.variable(matrix()).matmul(&x).unwrap().exp().sum()
+        });
+        check_gradient(matrix(), |_, x| x.t().unwrap().matmul(&x).unwrap().sum());
+    }
+
+    #[test]
+    fn reduction_gradients() {
+        let weights = arr1(&[1., -2., 3.]);
+        check_gradient(matrix(), move |tape, x| {
+            (&x.sum_axis(0).unwrap() * &tape.variable(weights.clone())).sum()
+        });
+        check_gradient(matrix(), |_, x| x.mean_axis(1).unwrap().powi(2).sum());
+        check_gradient(matrix(), |_, x| {
+            x.reshape(&[3, 2]).unwrap().matmul(&x).unwrap().sum()
+        });
+        check_gradient(matrix(), |_, x| {
+            (&x.softmax(1).unwrap() * &x.insert_axis(0).unwrap().sum_axis(0).unwrap()).sum()
+        });
+    }
+
+    #[test]
+    fn slice_and_join_gradients() {
+        check_gradient(matrix(), |_, x| {
+            x.slice_axis(1, 1..3).unwrap().powi(2).sum()
+        });
+        check_gradient(matrix(), |_, x| x.index_axis(0, 1).unwrap().exp().sum());
+        check_gradient(matrix(), |_, x| {
+            let joined = concatenate(1, &[x.slice_axis(1, 2..3).unwrap(), x.clone()]).unwrap();
+            (&joined * &joined.sum_axis(0).unwrap()).sum()
+        });
+        check_gradient(matrix(), |_, x| {
+            let rows = [x.index_axis(0, 1).unwrap(), x.index_axis(0, 0).unwrap()];
+            stack(1, &rows).unwrap().tanh().matmul(&x).unwrap().sum()
+        });
+    }
+
+    #[test]
+    fn shared_tensor_gradient() {
+        let tape = Tape::new();
+        let x = tape.scalar(3.);
+        let y = &(&x * &x) + &(&x * 2.);
+
+        assert_eq!(y.item(), 15.);
+        assert_eq!(y.backward().wrt(&x), arr0(8.).into_dyn());
+    }
+
+    #[test]
+    fn unused_and_detached_tensors() {
+        let tape = Tape::new();
+        let x = tape.variable(arr1(&[1., 2.]));
+        let unused = tape.variable(arr1(&[3.]));
+        let y = (&x * &x.detach()).sum();
+        let gradients = y.backward();
+
+        assert_eq!(gradients.wrt(&x), arr1(&[1., 2.]).into_dyn());
+        assert!(gradients.get(&unused).is_none());
+        assert_eq!(gradients.wrt(&unused), arr1(&[0.]).into_dyn());
+        assert_eq!(tape.len(), 5);
+    }
+
+    #[test]
+    fn backward_with_gradient() {
+        let tape = Tape::new();
+        let x = tape.variable(arr1(&[1., 2.]));
+        let y = x.powi(2);
+
+        assert_eq!(
+            y.backward_with(&arr1(&[1., -1.])).unwrap().wrt(&x),
+            arr1(&[2., -4.]).into_dyn()
+        );
+        assert_eq!(
+            y.backward_with(&arr1(&[1.])).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![1],
+            }
+        );
+    }
+
+    #[test]
+    fn loss_gradients() {
+        let observed = arr2(&[[0., 1., 0.], [1., 0., 0.]]);
+        let target = observed.clone();
+        check_gradient(matrix(), move |tape, x| {
+            cross_entropy_with_logits(&tape.variable(target.clone()), &x)
+                .unwrap()
+                .sum()
+        });
+        check_gradient(matrix(), move |tape, x| {
+            mean_squared_error(&tape.variable(observed.clone()), &x)
+                .unwrap()
+                .mean()
+        });
+
+        let tape = Tape::new();
+        let huge = tape.variable(arr2(&[[1000., 0.]]));
+        let loss = cross_entropy_with_logits(&tape.variable(arr2(&[[0., 1.]])), &huge).unwrap();
+        assert!((loss.sum().item() - 1000.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn losses_match_cost() {
+        // The predictions sum to one, so their logarithms are logits with them as softmax
+        let observed = arr1(&[0.2, 0.5, 0.3]);
+        let predicted = arr1(&[0.3, 0.6, 0.1]);
+        let tape = Tape::new();
+
+        let squared_error = mean_squared_error(
+            &tape.variable(observed.clone()),
+            &tape.variable(predicted.clone()),
+        )
+        .unwrap();
+        let cross_entropy = cross_entropy_with_logits(
+            &tape.variable(observed.clone()),
+            &tape.variable(predicted.mapv(f64::ln)),
+        )
+        .unwrap();
+
+        assert_eq!(
+            squared_error.value(),
+            crate::cost::mean_squared_error(observed.clone(), predicted.clone()).into_dyn()
+        );
+        assert!((cross_entropy.value()
+            - crate::cost::cross_entropy(observed, predicted).into_dyn())
+        .iter()
+        .all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    #[should_panic(expected = "different tapes")]
+    fn different_tapes() {
+        let _ = &Tape::new().scalar(1.) + &Tape::new().scalar(2.);
+    }
+
+    #[test]
+    fn shape_errors() {
+        let tape = Tape::new();
+        let x = tape.variable(matrix());
+        let vector = tape.variable(arr1(&[1., 2.]));
+
+        assert!(matches!(
+            x.try_add(&tape.variable(arr1(&[1., 2.]))),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            x.try_mul(&Tape::new().variable(matrix())),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            x.matmul(&vector),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            vector.matmul(&x),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(vector.t(), Err(DuckyError::InvalidParameter(_))));
+        assert!(matches!(
+            x.reshape(&[4]),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            x.sum_axis(2),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            x.insert_axis(3),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(x.softmax(2), Err(DuckyError::InvalidParameter(_))));
+        assert!(matches!(
+            x.slice_axis(1, 2..4),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            x.index_axis(2, 0),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(concatenate(0, &[]), Err(DuckyError::EmptyInput)));
+        assert!(matches!(
+            concatenate(0, &[x.clone(), x.t().unwrap()]),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            stack(0, &[x.clone(), vector.clone()]),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            mean_squared_error(&x, &x.t().unwrap()),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            cross_entropy_with_logits(&vector, &x),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+}
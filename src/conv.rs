@@ -0,0 +1,2530 @@
+use super::activations::Activation;
+use super::autograd::Tape;
+use super::cost::mean_squared_error;
+use super::error::{check_finite, check_targets, DuckyError};
+use super::layers::{Layer1d, Mode, SavedLayer};
+use super::train::{back_propagation_batch, forward_pass, forward_pass_batch};
+use ndarray::prelude::*;
+use ndarray_rand::rand_distr::Uniform;
+use ndarray_rand::RandomExt;
+use serde::{Deserialize, Serialize};
+use std::iter::zip;
+use std::sync::RwLock;
+
+/// Layer working on batches of images, stored as `Array4` of shape
+/// ( batch, channels, height, width ).
+pub trait Layer4d {
+    /// Feeds forward a batch of images through the layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of images of shape( batch, channels, height, width )
+    /// * `mode`: Whether the layer is being trained or used for inference
+    ///
+    /// returns: `Result<(Array4<f64>, Array4<f64>), DuckyError>` holding the weighted input
+    /// of the layer and the output of its activation function
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `input_array` does not line up with the layer
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::conv::*;
+    /// use ducky_learn::layers::Mode;
+    /// use ndarray::Array4;
+    ///
+    /// let layer = MaxPool2d::new((2, 2), (2, 2)).unwrap();
+    /// let images = Array4::from_shape_vec((1, 1, 2, 2), vec![1., 4., 3., 2.]).unwrap();
+    ///
+    /// let (_, a) = layer.pass(images, Mode::Eval).unwrap();
+    ///
+    /// assert_eq!(a.into_raw_vec(), vec![4.]);
+    /// ```
+    fn pass(
+        &self,
+        input_array: Array4<f64>,
+        mode: Mode,
+    ) -> Result<(Array4<f64>, Array4<f64>), DuckyError>;
+
+    /// Passes the gradient of the cost backwards through the layer, taking one gradient
+    /// descent step on the layer's parameters along the way. Parameter gradients are summed
+    /// over the batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The input the layer was passed during the forward pass
+    /// * `weighted_input`: The weighted input the layer returned during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of the layer
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array4<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to the input of the layer, computed before the parameters are updated
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    fn back_pass(
+        &self,
+        input_array: &Array4<f64>,
+        weighted_input: &Array4<f64>,
+        output_gradient: &Array4<f64>,
+        learning_rate: f64,
+    ) -> Result<Array4<f64>, DuckyError>;
+}
+
+impl<L: Layer4d + ?Sized> Layer4d for Box<L> {
+    fn pass(
+        &self,
+        input_array: Array4<f64>,
+        mode: Mode,
+    ) -> Result<(Array4<f64>, Array4<f64>), DuckyError> {
+        (**self).pass(input_array, mode)
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array4<f64>,
+        weighted_input: &Array4<f64>,
+        output_gradient: &Array4<f64>,
+        learning_rate: f64,
+    ) -> Result<Array4<f64>, DuckyError> {
+        (**self).back_pass(input_array, weighted_input, output_gradient, learning_rate)
+    }
+}
+
+/// Window slid over the height and width of a batch of images by `Conv2d` and the pooling
+/// layers.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::conv::Window2d;
+///
+/// // 3x3 kernel that keeps the size of the image
+/// let window = Window2d::new((3, 3), (1, 1), (1, 1), (1, 1)).unwrap();
+///
+/// assert_eq!(window.output_size((28, 28)), Some((28, 28)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Window2d {
+    kernel_size: (usize, usize),
+    stride: (usize, usize),
+    padding: (usize, usize),
+    dilation: (usize, usize),
+}
+
+impl Window2d {
+    /// Create Window2d
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel_size`: ( height, width ) of the window
+    /// * `stride`: Step between windows along ( height, width )
+    /// * `padding`: Zeros added to both sides of ( height, width )
+    /// * `dilation`: Spacing between the values the window covers along ( height, width )
+    ///
+    /// returns: `Result<Window2d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `kernel_size`, `stride` or `dilation` contains a zero
+    pub fn new(
+        kernel_size: (usize, usize),
+        stride: (usize, usize),
+        padding: (usize, usize),
+        dilation: (usize, usize),
+    ) -> Result<Self, DuckyError> {
+        for (name, (height, width)) in [
+            ("kernel_size", kernel_size),
+            ("stride", stride),
+            ("dilation", dilation),
+        ] {
+            if height == 0 || width == 0 {
+                return Err(DuckyError::InvalidParameter(format!(
+                    "{} has to be positive, found ({}, {})",
+                    name, height, width
+                )));
+            }
+        }
+
+        Ok(Self {
+            kernel_size,
+            stride,
+            padding,
+            dilation,
+        })
+    }
+
+    /// ( height, width ) of the window.
+    pub fn kernel_size(&self) -> (usize, usize) {
+        self.kernel_size
+    }
+
+    /// Step between windows along ( height, width ).
+    pub fn stride(&self) -> (usize, usize) {
+        self.stride
+    }
+
+    /// Zeros added to both sides of ( height, width ).
+    pub fn padding(&self) -> (usize, usize) {
+        self.padding
+    }
+
+    /// Spacing between the values the window covers along ( height, width ).
+    pub fn dilation(&self) -> (usize, usize) {
+        self.dilation
+    }
+
+    /// ( height, width ) of the output for an image of ( height, width ) `input_size`, or
+    /// `None` if the window does not fit in the padded image.
+    pub fn output_size(&self, input_size: (usize, usize)) -> Option<(usize, usize)> {
+        let axis = |input: usize, kernel: usize, stride: usize, padding: usize, dilation| {
+            (input + 2 * padding)
+                .checked_sub(dilation * (kernel - 1) + 1)
+                .map(|room| room / stride + 1)
+        };
+
+        Some((
+            axis(
+                input_size.0,
+                self.kernel_size.0,
+                self.stride.0,
+                self.padding.0,
+                self.dilation.0,
+            )?,
+            axis(
+                input_size.1,
+                self.kernel_size.1,
+                self.stride.1,
+                self.padding.1,
+                self.dilation.1,
+            )?,
+        ))
+    }
+
+    /// Output size for `input_array`, or a `ShapeMismatch` holding the smallest image the
+    /// window fits in.
+    fn checked_output_size(&self, input_array: &Array4<f64>) -> Result<(usize, usize), DuckyError> {
+        let (batch, channels, height, width) = input_array.dim();
+
+        self.output_size((height, width))
+            .ok_or_else(|| DuckyError::ShapeMismatch {
+                expected: vec![
+                    batch,
+                    channels,
+                    (self.dilation.0 * (self.kernel_size.0 - 1) + 1)
+                        .saturating_sub(2 * self.padding.0)
+                        .max(1),
+                    (self.dilation.1 * (self.kernel_size.1 - 1) + 1)
+                        .saturating_sub(2 * self.padding.1)
+                        .max(1),
+                ],
+                found: input_array.shape().to_vec(),
+            })
+    }
+
+    /// Position in the unpadded image covered by kernel index `kernel` of output index
+    /// `output` along one axis, or `None` if it falls in the padding.
+    fn source(
+        output: usize,
+        kernel: usize,
+        stride: usize,
+        padding: usize,
+        dilation: usize,
+    ) -> Option<usize> {
+        (output * stride + kernel * dilation).checked_sub(padding)
+    }
+
+    /// Unrolls every window into a column, so a convolution becomes one matrix product.
+    ///
+    /// returns: `Array2<f64>` of shape( channels * kernel height * kernel width,
+    /// batch * output height * output width )
+    fn im2col(&self, input_array: &Array4<f64>, output_size: (usize, usize)) -> Array2<f64> {
+        let (batch, channels, ..) = input_array.dim();
+        let (kernel_height, kernel_width) = self.kernel_size;
+        let (output_height, output_width) = output_size;
+        let mut cols = Array2::zeros((
+            channels * kernel_height * kernel_width,
+            batch * output_height * output_width,
+        ));
+
+        self.for_each_position(
+            input_array.dim(),
+            output_size,
+            |row, col, y, x, image, channel| {
+                cols[[row, col]] = input_array[[image, channel, y, x]];
+            },
+        );
+
+        cols
+    }
+
+    /// Adds every column back onto the window it was unrolled from, undoing `im2col` for
+    /// gradients.
+    fn col2im(
+        &self,
+        cols: &Array2<f64>,
+        input_shape: (usize, usize, usize, usize),
+        output_size: (usize, usize),
+    ) -> Array4<f64> {
+        let mut images = Array4::zeros(input_shape);
+
+        self.for_each_position(
+            input_shape,
+            output_size,
+            |row, col, y, x, image, channel| {
+                images[[image, channel, y, x]] += cols[[row, col]];
+            },
+        );
+
+        images
+    }
+
+    /// Calls `f(row, col, y, x, image, channel)` for every value of the unrolled windows that
+    /// falls inside the image.
+    fn for_each_position<F>(
+        &self,
+        input_shape: (usize, usize, usize, usize),
+        output_size: (usize, usize),
+        mut f: F,
+    ) where
+        F: FnMut(usize, usize, usize, usize, usize, usize),
+    {
+        let (batch, channels, height, width) = input_shape;
+        let (kernel_height, kernel_width) = self.kernel_size;
+        let (output_height, output_width) = output_size;
+
+        for channel in 0..channels {
+            for kernel_y in 0..kernel_height {
+                for kernel_x in 0..kernel_width {
+                    let row = (channel * kernel_height + kernel_y) * kernel_width + kernel_x;
+
+                    for image in 0..batch {
+                        for output_y in 0..output_height {
+                            let y = match Self::source(
+                                output_y,
+                                kernel_y,
+                                self.stride.0,
+                                self.padding.0,
+                                self.dilation.0,
+                            ) {
+                                Some(y) if y < height => y,
+                                _ => continue,
+                            };
+
+                            for output_x in 0..output_width {
+                                let x = match Self::source(
+                                    output_x,
+                                    kernel_x,
+                                    self.stride.1,
+                                    self.padding.1,
+                                    self.dilation.1,
+                                ) {
+                                    Some(x) if x < width => x,
+                                    _ => continue,
+                                };
+                                let col =
+                                    (image * output_height + output_y) * output_width + output_x;
+
+                                f(row, col, y, x, image, channel);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Moves the channels of a batch of images to the last axis, where `Activation` works, so
+/// an activation across channels such as `Activation::Softmax` sees every pixel on its own.
+fn channels_last(array: &Array4<f64>) -> Array4<f64> {
+    array
+        .view()
+        .permuted_axes([0, 2, 3, 1])
+        .as_standard_layout()
+        .into_owned()
+}
+
+/// Checks that `array` has the shape `expected`.
+fn check_shape<D: Dimension>(expected: &[usize], array: &Array<f64, D>) -> Result<(), DuckyError> {
+    if array.shape() != expected {
+        return Err(DuckyError::ShapeMismatch {
+            expected: expected.to_vec(),
+            found: array.shape().to_vec(),
+        });
+    }
+
+    Ok(())
+}
+
+/// 2d convolution layer. Every output channel slides a kernel over all of the input
+/// channels, adds a bias and passes the result through the activation function.
+///
+/// The activation is an `activations::Activation`, applied to the channels of every pixel
+/// and differentiated on an `autograd::Tape`, so `Activation::Softmax` gives every pixel a
+/// distribution over the output channels.
+///
+/// The convolution is computed with im2col, which unrolls every window of the batch into a
+/// column so the whole layer is a single matrix product.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::activations::*;
+/// use ducky_learn::conv::*;
+/// use ducky_learn::layers::Mode;
+/// use ndarray::Array4;
+///
+/// // 1 input channel, 8 output channels, 3x3 kernels that keep the size of the image
+/// let window = Window2d::new((3, 3), (1, 1), (1, 1), (1, 1)).unwrap();
+/// let layer = Conv2d::new(1, 8, window, Activation::Relu);
+///
+/// let (_, a) = layer.pass(Array4::ones((4, 1, 28, 28)), Mode::Eval).unwrap();
+///
+/// assert_eq!(a.shape(), &[4, 8, 28, 28]);
+/// ```
+#[derive(Debug)]
+pub struct Conv2d {
+    activation: Activation,
+    weights: RwLock<Array4<f64>>,
+    bias: RwLock<Array1<f64>>,
+    window: Window2d,
+}
+
+impl Conv2d {
+    /// Create Conv2d layer with full control over every part of the layer
+    ///
+    /// # Arguments
+    ///
+    /// * `activation`: Activation function, applied to the channels of every position
+    /// * `weights`: 4d array of kernels that has to be of shape( output channels,
+    ///   input channels, kernel height, kernel width )
+    /// * `bias`: 1d array of bias's that has to be the size of the output channels
+    /// * `window`: Stride, padding and dilation of the kernels. Its kernel size has to match
+    ///   `weights`
+    ///
+    /// returns: `Result<Conv2d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `bias` or `window` do not line up with `weights`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::activations::Activation;
+    /// use ducky_learn::conv::*;
+    /// use ndarray::{arr1, Array4};
+    ///
+    /// let window = Window2d::new((2, 2), (1, 1), (0, 0), (1, 1)).unwrap();
+    /// let layer = Conv2d::from(
+    ///     Activation::Identity,
+    ///     Array4::ones((1, 1, 2, 2)),
+    ///     arr1(&[0.]),
+    ///     window,
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn from(
+        activation: Activation,
+        weights: Array4<f64>,
+        bias: Array1<f64>,
+        window: Window2d,
+    ) -> Result<Self, DuckyError> {
+        let (out_channels, in_channels, kernel_height, kernel_width) = weights.dim();
+
+        check_targets(out_channels, bias.len())?;
+        if window.kernel_size != (kernel_height, kernel_width) {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![out_channels, in_channels, kernel_height, kernel_width],
+                found: vec![
+                    out_channels,
+                    in_channels,
+                    window.kernel_size.0,
+                    window.kernel_size.1,
+                ],
+            });
+        }
+
+        Ok(Self {
+            activation,
+            weights: RwLock::new(weights),
+            bias: RwLock::new(bias),
+            window,
+        })
+    }
+
+    /// Create Conv2d layer with randomly set weights and bias's. Both are drawn uniformly
+    /// from ±1 / sqrt( input channels * kernel height * kernel width ), which keeps the
+    /// weighted inputs in a trainable range for large kernels.
+    ///
+    /// # Arguments
+    ///
+    /// * `in_channels`: Number of channels of the input images
+    /// * `out_channels`: Number of kernels, and so channels of the output images
+    /// * `window`: Kernel size, stride, padding and dilation of the kernels
+    /// * `activation`: Activation function, applied to the channels of every position
+    ///
+    /// returns: `Conv2d`
+    pub fn new(
+        in_channels: usize,
+        out_channels: usize,
+        window: Window2d,
+        activation: Activation,
+    ) -> Self {
+        let (kernel_height, kernel_width) = window.kernel_size;
+        let bound = 1.0 / ((in_channels * kernel_height * kernel_width).max(1) as f64).sqrt();
+
+        Self {
+            activation,
+            weights: RwLock::new(Array4::random(
+                (out_channels, in_channels, kernel_height, kernel_width),
+                Uniform::new_inclusive(-bound, bound),
+            )),
+            bias: RwLock::new(Array1::random(
+                out_channels,
+                Uniform::new_inclusive(-bound, bound),
+            )),
+            window,
+        }
+    }
+
+    /// Kernel size, stride, padding and dilation of the kernels.
+    pub fn window(&self) -> Window2d {
+        self.window
+    }
+
+    /// Kernels of shape( output channels, input channels, kernel height, kernel width ).
+    pub fn weights(&self) -> Array4<f64> {
+        self.weights.read().unwrap().clone()
+    }
+
+    /// Bias of every output channel.
+    pub fn bias(&self) -> Array1<f64> {
+        self.bias.read().unwrap().clone()
+    }
+
+    /// Checks `input_array` against the kernels and returns the size of the output.
+    fn output_size(
+        &self,
+        weights: &Array4<f64>,
+        input_array: &Array4<f64>,
+    ) -> Result<(usize, usize), DuckyError> {
+        let (batch, channels, height, width) = input_array.dim();
+
+        if channels != weights.shape()[1] {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![batch, weights.shape()[1], height, width],
+                found: input_array.shape().to_vec(),
+            });
+        }
+        self.window.checked_output_size(input_array)
+    }
+}
+
+impl Clone for Conv2d {
+    fn clone(&self) -> Self {
+        Self {
+            activation: self.activation,
+            weights: RwLock::new(self.weights()),
+            bias: RwLock::new(self.bias()),
+            window: self.window,
+        }
+    }
+}
+
+impl Layer4d for Conv2d {
+    fn pass(
+        &self,
+        input_array: Array4<f64>,
+        _mode: Mode,
+    ) -> Result<(Array4<f64>, Array4<f64>), DuckyError> {
+        let weights = self.weights.read().unwrap();
+        let bias = self.bias.read().unwrap();
+        let (out_channels, ..) = weights.dim();
+        let (output_height, output_width) = self.output_size(&weights, &input_array)?;
+        check_finite(&input_array)?;
+
+        let kernels = weights
+            .to_shape((out_channels, weights.len() / out_channels.max(1)))
+            .unwrap();
+        let cols = self
+            .window
+            .im2col(&input_array, (output_height, output_width));
+        let z = (kernels.dot(&cols) + bias.view().insert_axis(Axis(1)))
+            .into_shape((
+                out_channels,
+                input_array.shape()[0],
+                output_height,
+                output_width,
+            ))
+            .unwrap()
+            .permuted_axes([1, 0, 2, 3])
+            .as_standard_layout()
+            .into_owned();
+        let a = self
+            .activation
+            .apply(&Tape::new().variable(channels_last(&z)))?
+            .value_as::<Ix4>()
+            .permuted_axes([0, 3, 1, 2])
+            .as_standard_layout()
+            .into_owned();
+        Ok((z, a))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array4<f64>,
+        weighted_input: &Array4<f64>,
+        output_gradient: &Array4<f64>,
+        learning_rate: f64,
+    ) -> Result<Array4<f64>, DuckyError> {
+        let mut weights = self.weights.write().unwrap();
+        let mut bias = self.bias.write().unwrap();
+        let (out_channels, ..) = weights.dim();
+        let (output_height, output_width) = self.output_size(&weights, input_array)?;
+        let output_shape = [
+            input_array.shape()[0],
+            out_channels,
+            output_height,
+            output_width,
+        ];
+        check_shape(&output_shape, weighted_input)?;
+        check_shape(&output_shape, output_gradient)?;
+
+        // Gradient of the weighted input, with its channels last as the tape recorded them
+        let z = Tape::new().variable(channels_last(weighted_input));
+        let delta = self
+            .activation
+            .apply(&z)?
+            .backward_with(&channels_last(output_gradient))?
+            .wrt(&z)
+            .into_dimensionality::<Ix4>()
+            .unwrap()
+            .permuted_axes([3, 0, 1, 2])
+            .as_standard_layout()
+            .into_shape((out_channels, output_shape[0] * output_height * output_width))
+            .unwrap()
+            .into_owned();
+        let kernels = weights
+            .to_shape((out_channels, weights.len() / out_channels.max(1)))
+            .unwrap()
+            .into_owned();
+        let cols = self
+            .window
+            .im2col(input_array, (output_height, output_width));
+
+        let input_gradient = self.window.col2im(
+            &kernels.t().dot(&delta),
+            input_array.dim(),
+            (output_height, output_width),
+        );
+
+        let weight_gradient = delta.dot(&cols.t()).into_shape(weights.raw_dim()).unwrap();
+        weights.scaled_add(-learning_rate, &weight_gradient);
+        bias.scaled_add(-learning_rate, &delta.sum_axis(Axis(1)));
+
+        Ok(input_gradient)
+    }
+}
+
+/// Unrolls the windows of every channel of `input_array` separately, for pooling.
+///
+/// returns: `Array2<f64>` with one column per output value, in the order of an output of
+/// shape( batch, channels, output height, output width )
+fn pool_columns(
+    window: &Window2d,
+    input_array: &Array4<f64>,
+    output_size: (usize, usize),
+) -> Array2<f64> {
+    let (batch, channels, height, width) = input_array.dim();
+    let channel_images = input_array
+        .to_shape((batch * channels, 1, height, width))
+        .unwrap()
+        .into_owned();
+
+    window.im2col(&channel_images, output_size)
+}
+
+/// Adds the gradient of every unrolled window back onto its channel, undoing `pool_columns`.
+fn pool_gradient(
+    window: &Window2d,
+    cols: &Array2<f64>,
+    input_shape: (usize, usize, usize, usize),
+    output_size: (usize, usize),
+) -> Array4<f64> {
+    let (batch, channels, height, width) = input_shape;
+
+    window
+        .col2im(cols, (batch * channels, 1, height, width), output_size)
+        .into_shape(input_shape)
+        .unwrap()
+}
+
+/// Checks the gradient passed back to a pooling layer and returns it with one value per
+/// column of `pool_columns`.
+fn pool_output_gradient(
+    input_array: &Array4<f64>,
+    output_size: (usize, usize),
+    output_gradient: &Array4<f64>,
+) -> Result<Array1<f64>, DuckyError> {
+    let (batch, channels, ..) = input_array.dim();
+    check_shape(
+        &[batch, channels, output_size.0, output_size.1],
+        output_gradient,
+    )?;
+
+    Ok(output_gradient.iter().cloned().collect())
+}
+
+/// Max pooling layer. Every channel keeps the largest value of each window, and the
+/// gradient flows back only to that value.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::conv::*;
+/// use ducky_learn::layers::Mode;
+/// use ndarray::Array4;
+///
+/// let layer = MaxPool2d::new((2, 2), (2, 2)).unwrap();
+///
+/// let (_, a) = layer.pass(Array4::ones((4, 8, 28, 28)), Mode::Eval).unwrap();
+///
+/// assert_eq!(a.shape(), &[4, 8, 14, 14]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaxPool2d {
+    window: Window2d,
+}
+
+impl MaxPool2d {
+    /// Create MaxPool2d layer
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel_size`: ( height, width ) of the window
+    /// * `stride`: Step between windows along ( height, width )
+    ///
+    /// returns: `Result<MaxPool2d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `kernel_size` or `stride` contains a zero
+    pub fn new(kernel_size: (usize, usize), stride: (usize, usize)) -> Result<Self, DuckyError> {
+        Ok(Self {
+            window: Window2d::new(kernel_size, stride, (0, 0), (1, 1))?,
+        })
+    }
+
+    /// Kernel size and stride of the window.
+    pub fn window(&self) -> Window2d {
+        self.window
+    }
+}
+
+impl Layer4d for MaxPool2d {
+    fn pass(
+        &self,
+        input_array: Array4<f64>,
+        _mode: Mode,
+    ) -> Result<(Array4<f64>, Array4<f64>), DuckyError> {
+        let output_size = self.window.checked_output_size(&input_array)?;
+        check_finite(&input_array)?;
+        let (batch, channels, ..) = input_array.dim();
+
+        let a = pool_columns(&self.window, &input_array, output_size)
+            .map_axis(Axis(0), |window| {
+                window.fold(f64::NEG_INFINITY, |max, value| max.max(*value))
+            })
+            .into_shape((batch, channels, output_size.0, output_size.1))
+            .unwrap();
+        Ok((a.clone(), a))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array4<f64>,
+        _weighted_input: &Array4<f64>,
+        output_gradient: &Array4<f64>,
+        _learning_rate: f64,
+    ) -> Result<Array4<f64>, DuckyError> {
+        let output_size = self.window.checked_output_size(input_array)?;
+        let output_gradient = pool_output_gradient(input_array, output_size, output_gradient)?;
+
+        let cols = pool_columns(&self.window, input_array, output_size);
+        let mut cols_gradient = Array2::zeros(cols.raw_dim());
+        for ((window, mut window_gradient), gradient) in cols
+            .columns()
+            .into_iter()
+            .zip(cols_gradient.columns_mut())
+            .zip(output_gradient)
+        {
+            // Ties send the gradient to the first largest value
+            let (max_index, _) = window.iter().enumerate().fold(
+                (0, f64::NEG_INFINITY),
+                |(max_index, max), (index, value)| match *value > max {
+                    true => (index, *value),
+                    false => (max_index, max),
+                },
+            );
+            window_gradient[max_index] = gradient;
+        }
+
+        Ok(pool_gradient(
+            &self.window,
+            &cols_gradient,
+            input_array.dim(),
+            output_size,
+        ))
+    }
+}
+
+/// Average pooling layer. Every channel keeps the mean of each window, and the gradient is
+/// shared evenly over the window.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::conv::*;
+/// use ducky_learn::layers::Mode;
+/// use ndarray::Array4;
+///
+/// let layer = AvgPool2d::new((2, 2), (2, 2)).unwrap();
+/// let images = Array4::from_shape_vec((1, 1, 2, 2), vec![1., 4., 3., 2.]).unwrap();
+///
+/// let (_, a) = layer.pass(images, Mode::Eval).unwrap();
+///
+/// assert_eq!(a.into_raw_vec(), vec![2.5]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AvgPool2d {
+    window: Window2d,
+}
+
+impl AvgPool2d {
+    /// Create AvgPool2d layer
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel_size`: ( height, width ) of the window
+    /// * `stride`: Step between windows along ( height, width )
+    ///
+    /// returns: `Result<AvgPool2d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `kernel_size` or `stride` contains a zero
+    pub fn new(kernel_size: (usize, usize), stride: (usize, usize)) -> Result<Self, DuckyError> {
+        Ok(Self {
+            window: Window2d::new(kernel_size, stride, (0, 0), (1, 1))?,
+        })
+    }
+
+    /// Kernel size and stride of the window.
+    pub fn window(&self) -> Window2d {
+        self.window
+    }
+}
+
+impl Layer4d for AvgPool2d {
+    fn pass(
+        &self,
+        input_array: Array4<f64>,
+        _mode: Mode,
+    ) -> Result<(Array4<f64>, Array4<f64>), DuckyError> {
+        let output_size = self.window.checked_output_size(&input_array)?;
+        check_finite(&input_array)?;
+        let (batch, channels, ..) = input_array.dim();
+
+        let a = pool_columns(&self.window, &input_array, output_size)
+            .mean_axis(Axis(0))
+            .unwrap()
+            .into_shape((batch, channels, output_size.0, output_size.1))
+            .unwrap();
+        Ok((a.clone(), a))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array4<f64>,
+        _weighted_input: &Array4<f64>,
+        output_gradient: &Array4<f64>,
+        _learning_rate: f64,
+    ) -> Result<Array4<f64>, DuckyError> {
+        let output_size = self.window.checked_output_size(input_array)?;
+        let output_gradient = pool_output_gradient(input_array, output_size, output_gradient)?;
+        let (kernel_height, kernel_width) = self.window.kernel_size;
+
+        let window_size = kernel_height * kernel_width;
+
+        let cols_gradient = output_gradient
+            .broadcast((window_size, output_gradient.len()))
+            .unwrap()
+            .mapv(|gradient| gradient / window_size as f64);
+
+        Ok(pool_gradient(
+            &self.window,
+            &cols_gradient,
+            input_array.dim(),
+            output_size,
+        ))
+    }
+}
+
+/// Global average pooling layer. Every channel is averaged down to a single value, giving
+/// an output of shape( batch, channels, 1, 1 ) whatever the size of the images.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::conv::*;
+/// use ducky_learn::layers::Mode;
+/// use ndarray::Array4;
+///
+/// let (_, a) = GlobalAvgPool::new().pass(Array4::ones((4, 8, 7, 7)), Mode::Eval).unwrap();
+///
+/// assert_eq!(a.shape(), &[4, 8, 1, 1]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlobalAvgPool;
+
+impl GlobalAvgPool {
+    /// Create GlobalAvgPool layer
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Layer4d for GlobalAvgPool {
+    fn pass(
+        &self,
+        input_array: Array4<f64>,
+        _mode: Mode,
+    ) -> Result<(Array4<f64>, Array4<f64>), DuckyError> {
+        let (batch, channels, height, width) = input_array.dim();
+        if height == 0 || width == 0 {
+            return Err(DuckyError::EmptyInput);
+        }
+        check_finite(&input_array)?;
+
+        let a = input_array
+            .mean_axis(Axis(3))
+            .unwrap()
+            .mean_axis(Axis(2))
+            .unwrap()
+            .into_shape((batch, channels, 1, 1))
+            .unwrap();
+        Ok((a.clone(), a))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array4<f64>,
+        _weighted_input: &Array4<f64>,
+        output_gradient: &Array4<f64>,
+        _learning_rate: f64,
+    ) -> Result<Array4<f64>, DuckyError> {
+        let (batch, channels, height, width) = input_array.dim();
+        check_shape(&[batch, channels, 1, 1], output_gradient)?;
+
+        Ok(output_gradient
+            .broadcast(input_array.raw_dim())
+            .unwrap()
+            .mapv(|gradient| gradient / (height * width) as f64))
+    }
+}
+
+/// Flattens a batch of images of shape( batch, channels, height, width ) into rows of shape
+/// ( batch, channels * height * width ), so they can be fed into `Layer1d` layers such as
+/// `Dense1d`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::conv::Flatten;
+/// use ndarray::Array4;
+///
+/// let images = Array4::ones((4, 8, 7, 7));
+///
+/// let rows = Flatten::new().pass(&images);
+/// assert_eq!(rows.shape(), &[4, 392]);
+///
+/// let gradient = Flatten::new().back_pass(&images, &rows).unwrap();
+/// assert_eq!(gradient, images);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flatten;
+
+impl Flatten {
+    /// Create Flatten layer
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Flattens every image of the batch into a row.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of images of shape( batch, channels, height, width )
+    ///
+    /// returns: `Array2<f64>` of shape( batch, channels * height * width )
+    pub fn pass(&self, input_array: &Array4<f64>) -> Array2<f64> {
+        let (batch, channels, height, width) = input_array.dim();
+
+        input_array
+            .to_shape((batch, channels * height * width))
+            .unwrap()
+            .into_owned()
+    }
+
+    /// Reshapes the gradient of the flattened rows back into the shape of the images.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The images that were flattened
+    /// * `output_gradient`: Gradient of the cost with respect to the flattened rows
+    ///
+    /// returns: `Result<Array4<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `output_gradient` is not the shape of the flattened rows
+    pub fn back_pass(
+        &self,
+        input_array: &Array4<f64>,
+        output_gradient: &Array2<f64>,
+    ) -> Result<Array4<f64>, DuckyError> {
+        let (batch, channels, height, width) = input_array.dim();
+        if output_gradient.dim() != (batch, channels * height * width) {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![batch, channels * height * width],
+                found: output_gradient.shape().to_vec(),
+            });
+        }
+
+        Ok(output_gradient
+            .to_shape(input_array.raw_dim())
+            .unwrap()
+            .into_owned())
+    }
+}
+
+/// Window slid over the length of a batch of sequences by `Conv1d` and `MaxPool1d`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::conv::Window1d;
+///
+/// let window = Window1d::new(3, 1, 1, 1).unwrap();
+/// assert_eq!(window.output_size(100), Some(100));
+///
+/// // Causal windows only look at the current and earlier steps
+/// let window = Window1d::causal(3, 1, 2).unwrap();
+/// assert_eq!(window.padding(), (4, 0));
+/// assert_eq!(window.output_size(100), Some(100));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Window1d {
+    kernel_size: usize,
+    stride: usize,
+    padding: (usize, usize),
+    dilation: usize,
+}
+
+impl Window1d {
+    /// Create Window1d
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel_size`: Length of the window
+    /// * `stride`: Step between windows
+    /// * `padding`: Zeros added to both ends of the sequence
+    /// * `dilation`: Spacing between the values the window covers
+    ///
+    /// returns: `Result<Window1d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `kernel_size`, `stride` or `dilation` is zero
+    pub fn new(
+        kernel_size: usize,
+        stride: usize,
+        padding: usize,
+        dilation: usize,
+    ) -> Result<Self, DuckyError> {
+        Window2d::new((1, kernel_size), (1, stride), (0, 0), (1, dilation))?;
+
+        Ok(Self {
+            kernel_size,
+            stride,
+            padding: (padding, padding),
+            dilation,
+        })
+    }
+
+    /// Create causal Window1d, which pads only the start of the sequence so every output
+    /// step only depends on the same and earlier input steps.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel_size`: Length of the window
+    /// * `stride`: Step between windows
+    /// * `dilation`: Spacing between the values the window covers
+    ///
+    /// returns: `Result<Window1d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `kernel_size`, `stride` or `dilation` is zero
+    pub fn causal(kernel_size: usize, stride: usize, dilation: usize) -> Result<Self, DuckyError> {
+        let window = Self::new(kernel_size, stride, 0, dilation)?;
+
+        Ok(Self {
+            padding: (dilation * (kernel_size - 1), 0),
+            ..window
+        })
+    }
+
+    /// Length of the window.
+    pub fn kernel_size(&self) -> usize {
+        self.kernel_size
+    }
+
+    /// Step between windows.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Zeros added to the ( start, end ) of the sequence.
+    pub fn padding(&self) -> (usize, usize) {
+        self.padding
+    }
+
+    /// Spacing between the values the window covers.
+    pub fn dilation(&self) -> usize {
+        self.dilation
+    }
+
+    /// Length of the output for a sequence of length `input_size`, or `None` if the window
+    /// does not fit in the padded sequence.
+    pub fn output_size(&self, input_size: usize) -> Option<usize> {
+        (input_size + self.padding.0 + self.padding.1)
+            .checked_sub(self.dilation * (self.kernel_size - 1) + 1)
+            .map(|room| room / self.stride + 1)
+    }
+
+    /// The window over the padded sequence, seen as an image of height one.
+    fn window_2d(&self) -> Window2d {
+        Window2d {
+            kernel_size: (1, self.kernel_size),
+            stride: (1, self.stride),
+            padding: (0, 0),
+            dilation: (1, self.dilation),
+        }
+    }
+
+    /// Pads `input_array` and turns it into images of height one.
+    fn pad(&self, input_array: &Array3<f64>) -> Array4<f64> {
+        let (batch, channels, length) = input_array.dim();
+        let (start, end) = self.padding;
+        let mut images = Array4::zeros((batch, channels, 1, start + length + end));
+
+        images
+            .slice_mut(s![.., .., 0, start..start + length])
+            .assign(input_array);
+        images
+    }
+
+    /// Undoes `pad` for the gradient of the padded images.
+    fn unpad(&self, images: &Array4<f64>, length: usize) -> Array3<f64> {
+        let start = self.padding.0;
+
+        images
+            .slice(s![.., .., 0, start..start + length])
+            .to_owned()
+    }
+
+    /// Checks `input_array` has `channels` channels and fits the window, and returns the
+    /// output length.
+    fn checked_output_size(
+        &self,
+        channels: usize,
+        input_array: &Array3<f64>,
+    ) -> Result<usize, DuckyError> {
+        let (batch, found_channels, length) = input_array.dim();
+        let min_length = (self.dilation * (self.kernel_size - 1) + 1)
+            .saturating_sub(self.padding.0 + self.padding.1)
+            .max(1);
+
+        match self.output_size(length) {
+            Some(output_size) if found_channels == channels => Ok(output_size),
+            _ => Err(DuckyError::ShapeMismatch {
+                expected: vec![batch, channels, length.max(min_length)],
+                found: input_array.shape().to_vec(),
+            }),
+        }
+    }
+}
+
+/// Reshapes a flattened sample into a batch of one sequence with `channels` channels.
+fn sequence(channels: usize, input_array: &Array1<f64>) -> Result<Array3<f64>, DuckyError> {
+    let length = input_array.len() / channels.max(1);
+
+    if length == 0 || channels * length != input_array.len() {
+        return Err(DuckyError::ShapeMismatch {
+            expected: vec![channels * length.max(1)],
+            found: input_array.shape().to_vec(),
+        });
+    }
+
+    Ok(input_array
+        .to_shape((1, channels, length))
+        .unwrap()
+        .into_owned())
+}
+
+/// Flattens a batch of one sequence back into a sample.
+fn flat(sequence: Array3<f64>) -> Array1<f64> {
+    sequence.iter().cloned().collect()
+}
+
+/// 1d convolution layer for sequences of shape( batch, channels, length ). Every output
+/// channel slides a kernel over all of the input channels, adds a bias and passes the result
+/// through the activation function. Use `Window1d::causal` so outputs never look ahead.
+///
+/// The activation is applied to the channels of every position, as for `Conv2d`.
+///
+/// `pass_batch` and `back_pass_batch` work on whole batches. As a `Layer1d` it takes one
+/// sample at a time, flattened channel by channel into an array of length
+/// `channels * length`, so it can be stacked with `Dense1d` layers in `train::train` and
+/// `DenseClassifier`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::activations::*;
+/// use ducky_learn::conv::*;
+/// use ducky_learn::layers::*;
+/// use ndarray::{Array1, Array3};
+///
+/// // 3 sensor channels, 8 output channels, causal kernels of length 4
+/// let window = Window1d::causal(4, 1, 1).unwrap();
+/// let layer = Conv1d::new(3, 8, window, Activation::Relu);
+///
+/// let (_, a) = layer.pass_batch(&Array3::ones((16, 3, 50)), Mode::Eval).unwrap();
+/// assert_eq!(a.shape(), &[16, 8, 50]);
+///
+/// let (_, a) = layer.pass(Array1::ones(3 * 50), Mode::Eval).unwrap();
+/// assert_eq!(a.len(), 8 * 50);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Conv1d {
+    conv: Conv2d,
+    window: Window1d,
+}
+
+impl Conv1d {
+    /// Create Conv1d layer with full control over every part of the layer
+    ///
+    /// # Arguments
+    ///
+    /// * `activation`: Activation function, applied to the channels of every position
+    /// * `weights`: 3d array of kernels that has to be of shape( output channels,
+    ///   input channels, kernel size )
+    /// * `bias`: 1d array of bias's that has to be the size of the output channels
+    /// * `window`: Stride, padding and dilation of the kernels. Its kernel size has to match
+    ///   `weights`
+    ///
+    /// returns: `Result<Conv1d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `bias` or `window` do not line up with `weights`
+    pub fn from(
+        activation: Activation,
+        weights: Array3<f64>,
+        bias: Array1<f64>,
+        window: Window1d,
+    ) -> Result<Self, DuckyError> {
+        let conv = Conv2d::from(
+            activation,
+            weights.insert_axis(Axis(2)),
+            bias,
+            window.window_2d(),
+        )?;
+
+        Ok(Self { conv, window })
+    }
+
+    /// Create Conv1d layer with randomly set weights and bias's, drawn like `Conv2d::new`.
+    ///
+    /// # Arguments
+    ///
+    /// * `in_channels`: Number of channels of the input sequences
+    /// * `out_channels`: Number of kernels, and so channels of the output sequences
+    /// * `window`: Kernel size, stride, padding and dilation of the kernels
+    /// * `activation`: Activation function, applied to the channels of every position
+    ///
+    /// returns: `Conv1d`
+    pub fn new(
+        in_channels: usize,
+        out_channels: usize,
+        window: Window1d,
+        activation: Activation,
+    ) -> Self {
+        Self {
+            conv: Conv2d::new(in_channels, out_channels, window.window_2d(), activation),
+            window,
+        }
+    }
+
+    /// Kernel size, stride, padding and dilation of the kernels.
+    pub fn window(&self) -> Window1d {
+        self.window
+    }
+
+    /// Kernels of shape( output channels, input channels, kernel size ).
+    pub fn weights(&self) -> Array3<f64> {
+        self.conv.weights().remove_axis(Axis(2))
+    }
+
+    /// Bias of every output channel.
+    pub fn bias(&self) -> Array1<f64> {
+        self.conv.bias()
+    }
+
+    fn channels(&self) -> (usize, usize) {
+        let weights = self.conv.weights.read().unwrap();
+        (weights.shape()[0], weights.shape()[1])
+    }
+
+    /// Feeds forward a batch of sequences through the layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of sequences of shape( batch, channels, length )
+    /// * `mode`: Whether the layer is being trained or used for inference
+    ///
+    /// returns: `Result<(Array3<f64>, Array3<f64>), DuckyError>` holding the weighted input
+    /// of the layer and the output of its activation function
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `input_array` does not line up with the layer
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mode: Mode,
+    ) -> Result<(Array3<f64>, Array3<f64>), DuckyError> {
+        self.window
+            .checked_output_size(self.channels().1, input_array)?;
+
+        let (z, a) = self.conv.pass(self.window.pad(input_array), mode)?;
+        Ok((z.remove_axis(Axis(2)), a.remove_axis(Axis(2))))
+    }
+
+    /// Passes the gradient of the cost backwards through a batch of sequences, taking one
+    /// gradient descent step on the kernels and bias's along the way. Parameter gradients
+    /// are summed over the batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The batch the layer was passed during the forward pass
+    /// * `weighted_input`: The weighted input the layer returned during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of the layer
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to `input_array`, computed before the parameters are updated
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    pub fn back_pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        weighted_input: &Array3<f64>,
+        output_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let (out_channels, in_channels) = self.channels();
+        let output_size = self.window.checked_output_size(in_channels, input_array)?;
+        let output_shape = [input_array.shape()[0], out_channels, output_size];
+        check_shape(&output_shape, weighted_input)?;
+        check_shape(&output_shape, output_gradient)?;
+
+        let input_gradient = self.conv.back_pass(
+            &self.window.pad(input_array),
+            &weighted_input.clone().insert_axis(Axis(2)),
+            &output_gradient.clone().insert_axis(Axis(2)),
+            learning_rate,
+        )?;
+        Ok(self.window.unpad(&input_gradient, input_array.shape()[2]))
+    }
+}
+
+impl Layer1d for Conv1d {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let (z, a) = self.pass_batch(&sequence(self.channels().1, &input_array)?, mode)?;
+        Ok((flat(z), flat(a)))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let out_channels = self.channels().0;
+
+        Ok(flat(self.back_pass_batch(
+            &sequence(self.channels().1, input_array)?,
+            &sequence(out_channels, weighted_input)?,
+            &sequence(out_channels, output_gradient)?,
+            learning_rate,
+        )?))
+    }
+}
+
+/// Max pooling layer for sequences of shape( batch, channels, length ). Every channel keeps
+/// the largest value of each window, and the gradient flows back only to that value.
+///
+/// Like `Conv1d` it also works as a `Layer1d` on samples flattened channel by channel, which
+/// is why it needs to know the number of channels.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::conv::MaxPool1d;
+/// use ducky_learn::layers::*;
+/// use ndarray::{arr1, Array3};
+///
+/// let layer = MaxPool1d::new(2, 2, 2).unwrap();
+///
+/// let (_, a) = layer.pass_batch(&Array3::ones((16, 2, 50)), Mode::Eval).unwrap();
+/// assert_eq!(a.shape(), &[16, 2, 25]);
+///
+/// let (_, a) = layer.pass(arr1(&[1., 3., 2., 0., 5., 4., 6., 7.]), Mode::Eval).unwrap();
+/// assert_eq!(a, arr1(&[3., 2., 5., 7.]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaxPool1d {
+    channels: usize,
+    window: Window1d,
+}
+
+impl MaxPool1d {
+    /// Create MaxPool1d layer
+    ///
+    /// # Arguments
+    ///
+    /// * `channels`: Number of channels of the input sequences
+    /// * `kernel_size`: Length of the window
+    /// * `stride`: Step between windows
+    ///
+    /// returns: `Result<MaxPool1d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `channels`, `kernel_size` or `stride` is zero
+    pub fn new(channels: usize, kernel_size: usize, stride: usize) -> Result<Self, DuckyError> {
+        if channels == 0 {
+            return Err(DuckyError::InvalidParameter(
+                "channels has to be positive".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            channels,
+            window: Window1d::new(kernel_size, stride, 0, 1)?,
+        })
+    }
+
+    /// Number of channels of the input sequences.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Kernel size and stride of the window.
+    pub fn window(&self) -> Window1d {
+        self.window
+    }
+
+    fn pool(&self) -> MaxPool2d {
+        MaxPool2d {
+            window: self.window.window_2d(),
+        }
+    }
+
+    /// Feeds forward a batch of sequences through the layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of sequences of shape( batch, channels, length )
+    /// * `mode`: Whether the layer is being trained or used for inference
+    ///
+    /// returns: `Result<(Array3<f64>, Array3<f64>), DuckyError>` holding the pooled batch
+    /// twice, as the weighted input and the output of the layer
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `input_array` does not line up with the layer
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mode: Mode,
+    ) -> Result<(Array3<f64>, Array3<f64>), DuckyError> {
+        self.window
+            .checked_output_size(self.channels, input_array)?;
+
+        let (z, a) = self.pool().pass(self.window.pad(input_array), mode)?;
+        Ok((z.remove_axis(Axis(2)), a.remove_axis(Axis(2))))
+    }
+
+    /// Passes the gradient of the cost backwards through a batch of sequences.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The batch the layer was passed during the forward pass
+    /// * `weighted_input`: The weighted input the layer returned during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of the layer
+    /// * `learning_rate`: Unused, as the layer has no parameters
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to `input_array`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    pub fn back_pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        weighted_input: &Array3<f64>,
+        output_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let output_size = self
+            .window
+            .checked_output_size(self.channels, input_array)?;
+        let output_shape = [input_array.shape()[0], self.channels, output_size];
+        check_shape(&output_shape, weighted_input)?;
+        check_shape(&output_shape, output_gradient)?;
+
+        let input_gradient = self.pool().back_pass(
+            &self.window.pad(input_array),
+            &weighted_input.clone().insert_axis(Axis(2)),
+            &output_gradient.clone().insert_axis(Axis(2)),
+            learning_rate,
+        )?;
+        Ok(self.window.unpad(&input_gradient, input_array.shape()[2]))
+    }
+}
+
+impl Layer1d for MaxPool1d {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let (z, a) = self.pass_batch(&sequence(self.channels, &input_array)?, mode)?;
+        Ok((flat(z), flat(a)))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        Ok(flat(self.back_pass_batch(
+            &sequence(self.channels, input_array)?,
+            &sequence(self.channels, weighted_input)?,
+            &sequence(self.channels, output_gradient)?,
+            learning_rate,
+        )?))
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::MaxPool1d(*self))
+    }
+}
+
+/// Feeds forward a batch of images through every layer of the model.
+///
+/// # Arguments
+///
+/// * `model`: Layers of the model, from input to output
+/// * `data`: Batch of images of shape( batch, channels, height, width )
+/// * `mode`: Whether the model is being trained or used for inference
+///
+/// returns: `Result<(Vec<Array4<f64>>, Vec<Array4<f64>>), DuckyError>` holding the weighted
+/// inputs and the outputs of every layer
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: `data` or the output of a layer does not line up with the
+///   next layer
+/// * `DuckyError::NonFinite`: `data` contains a NaN or infinite value
+#[allow(clippy::type_complexity)]
+pub fn forward_pass_4d<L: Layer4d>(
+    model: &[L],
+    data: Array4<f64>,
+    mode: Mode,
+) -> Result<(Vec<Array4<f64>>, Vec<Array4<f64>>), DuckyError> {
+    let mut weighted_inputs = Vec::with_capacity(model.len());
+    let mut activations = Vec::with_capacity(model.len());
+
+    let mut activation = data;
+    for layer in model {
+        let (z, a) = layer.pass(activation, mode)?;
+        weighted_inputs.push(z);
+        activations.push(a.clone());
+        activation = a;
+    }
+
+    Ok((weighted_inputs, activations))
+}
+
+/// Propagates `output_gradient` back through the model, taking one gradient descent step on
+/// every layer.
+///
+/// # Arguments
+///
+/// * `model`: Layers of the model, from input to output
+/// * `data`: The batch of images the model was passed
+/// * `weighted_inputs`: Weighted inputs of each layer, as returned by `forward_pass_4d`
+/// * `activations`: Outputs of each layer, as returned by `forward_pass_4d`
+/// * `output_gradient`: Gradient of the cost with respect to the output of the model
+/// * `learning_rate`: Size of the gradient descent step
+///
+/// returns: `Result<Array4<f64>, DuckyError>` holding the gradient of the cost with respect to
+/// `data`
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: the forward pass or `output_gradient` do not line up with
+///   the model
+pub fn back_propagation_4d<L: Layer4d>(
+    model: &[L],
+    data: &Array4<f64>,
+    weighted_inputs: &[Array4<f64>],
+    activations: &[Array4<f64>],
+    output_gradient: Array4<f64>,
+    learning_rate: f64,
+) -> Result<Array4<f64>, DuckyError> {
+    check_targets(model.len(), weighted_inputs.len())?;
+    check_targets(model.len(), activations.len())?;
+
+    let mut gradient = output_gradient;
+    for (index, layer) in model.iter().enumerate().rev() {
+        let input_array = match index {
+            0 => data,
+            _ => &activations[index - 1],
+        };
+        gradient = layer.back_pass(
+            input_array,
+            &weighted_inputs[index],
+            &gradient,
+            learning_rate,
+        )?;
+    }
+
+    Ok(gradient)
+}
+
+/// Convolutional network made of `Layer4d` feature layers, whose output is flattened and fed
+/// into a head of `Layer1d` layers such as `Dense1d`. Trained with mini-batch gradient
+/// descent on the mean squared error.
+///
+/// Every layer takes one step per batch on the gradient averaged over the batch. The head is
+/// passed the flattened batch with `Layer1d::pass_batch`, as in `train::train_batches`, so a
+/// `BatchNorm1d` in it normalizes with the statistics of the batch.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::activations::*;
+/// use ducky_learn::conv::*;
+/// use ducky_learn::layers::*;
+/// use ndarray::{Array2, Array4};
+///
+/// let window = Window2d::new((3, 3), (1, 1), (0, 0), (1, 1)).unwrap();
+/// let features: Vec<Box<dyn Layer4d>> = vec![
+///     Box::new(Conv2d::new(1, 4, window, Activation::Relu)),
+///     Box::new(MaxPool2d::new((2, 2), (2, 2)).unwrap()),
+/// ];
+/// let head = vec![Dense1d::new(4 * 3 * 3, 10, |x| x, |x| x.map(|_| 1.))];
+/// let model = ConvNet::new(features, head);
+///
+/// let images = Array4::zeros((2, 1, 8, 8));
+/// let costs = model.train(&images, &Array2::zeros((2, 10)), 2, 2, 0.01).unwrap();
+///
+/// assert_eq!(costs.len(), 2);
+/// assert_eq!(model.forward(&images, Mode::Eval).unwrap().shape(), &[2, 10]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConvNet<C, D> {
+    pub features: Vec<C>,
+    pub head: Vec<D>,
+}
+
+impl<C: Layer4d, D: Layer1d> ConvNet<C, D> {
+    /// Create ConvNet
+    ///
+    /// # Arguments
+    ///
+    /// * `features`: `Layer4d` layers, from input to output
+    /// * `head`: `Layer1d` layers fed the flattened output of `features`
+    ///
+    /// returns: `ConvNet<C, D>`
+    pub fn new(features: Vec<C>, head: Vec<D>) -> Self {
+        Self { features, head }
+    }
+
+    /// Feeds forward a batch of images through the whole network.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: Batch of images of shape( batch, channels, height, width )
+    /// * `mode`: Whether the network is being trained or used for inference
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>` holding the output of the head for every
+    /// image
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `data` or a layer does not line up with the next layer
+    /// * `DuckyError::NonFinite`: `data` contains a NaN or infinite value
+    pub fn forward(&self, data: &Array4<f64>, mode: Mode) -> Result<Array2<f64>, DuckyError> {
+        let (_, activations) = forward_pass_4d(&self.features, data.clone(), mode)?;
+        let rows = Flatten.pass(activations.last().unwrap_or(data));
+
+        let outputs = rows
+            .rows()
+            .into_iter()
+            .map(|row| {
+                let (_, activation_vec) = forward_pass(&self.head, row.to_owned(), mode)?;
+                Ok(activation_vec
+                    .last()
+                    .cloned()
+                    .unwrap_or_else(|| row.to_owned()))
+            })
+            .collect::<Result<Vec<_>, DuckyError>>()?;
+        let views: Vec<_> = outputs.iter().map(|output| output.view()).collect();
+
+        match views.is_empty() {
+            true => Ok(Array2::zeros((0, 0))),
+            false => {
+                Ok(
+                    ndarray::stack(Axis(0), &views).map_err(|_| DuckyError::ShapeMismatch {
+                        expected: vec![views[0].len()],
+                        found: views.iter().map(|view| view.len()).collect(),
+                    })?,
+                )
+            }
+        }
+    }
+
+    /// Takes one gradient descent step on a batch of images.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: Batch of images of shape( batch, channels, height, width )
+    /// * `labels`: Target outputs of shape( batch, outputs )
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<f64, DuckyError>` holding the mean squared error of the batch before
+    /// the step
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the data and labels do not line up with each other or
+    ///   the network
+    /// * `DuckyError::NonFinite`: `data` contains a NaN or infinite value
+    pub fn train_batch(
+        &self,
+        data: &Array4<f64>,
+        labels: &Array2<f64>,
+        learning_rate: f64,
+    ) -> Result<f64, DuckyError> {
+        check_targets(data.shape()[0], labels.nrows())?;
+
+        let (weighted_inputs, activations) =
+            forward_pass_4d(&self.features, data.clone(), Mode::Train)?;
+        let features = activations.last().unwrap_or(data);
+        let rows = Flatten.pass(features);
+
+        let (weighted_batches, activation_batches) =
+            forward_pass_batch(&self.head, &rows, Mode::Train)?;
+        let output = activation_batches.last().unwrap_or(&rows);
+        check_targets(labels.ncols(), output.ncols())?;
+        let batch = data.shape()[0].max(1) as f64;
+        let cost: f64 = zip(labels.rows(), output.rows())
+            .map(|(target, row)| {
+                mean_squared_error(target.to_owned(), row.to_owned())
+                    .mean()
+                    .unwrap_or(0.0)
+            })
+            .sum();
+
+        let rows_gradient = back_propagation_batch(
+            &self.head,
+            &rows,
+            weighted_batches,
+            activation_batches,
+            labels,
+            learning_rate,
+        )?;
+        let gradient = Flatten.back_pass(features, &rows_gradient)?;
+        back_propagation_4d(
+            &self.features,
+            data,
+            &weighted_inputs,
+            &activations,
+            gradient,
+            learning_rate,
+        )?;
+
+        Ok(cost / batch)
+    }
+
+    /// Trains the network with mini-batch gradient descent.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: Training images of shape( samples, channels, height, width )
+    /// * `labels`: Target outputs of shape( samples, outputs )
+    /// * `epochs`: Number of passes over the training data
+    /// * `batch_size`: Number of images in each gradient descent step
+    /// * `learning_rate`: Size of each gradient descent step
+    ///
+    /// returns: `Result<Vec<f64>, DuckyError>` holding the mean squared error over the
+    /// training data during each epoch
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the data and labels do not line up with each other or
+    ///   the network
+    /// * `DuckyError::NonFinite`: `data` contains a NaN or infinite value
+    /// * `DuckyError::InvalidParameter`: `batch_size` is zero or `learning_rate` is not
+    ///   positive
+    pub fn train(
+        &self,
+        data: &Array4<f64>,
+        labels: &Array2<f64>,
+        epochs: usize,
+        batch_size: usize,
+        learning_rate: f64,
+    ) -> Result<Vec<f64>, DuckyError> {
+        check_targets(data.shape()[0], labels.nrows())?;
+        if batch_size == 0 {
+            return Err(DuckyError::InvalidParameter(
+                "batch_size has to be positive".to_string(),
+            ));
+        }
+        if !(learning_rate > 0.0 && learning_rate.is_finite()) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "learning_rate has to be positive, found {}",
+                learning_rate
+            )));
+        }
+
+        let mut costs = Vec::with_capacity(epochs);
+        for _ in 0..epochs {
+            let mut cost = 0.0;
+            for start in (0..data.shape()[0]).step_by(batch_size) {
+                let end = (start + batch_size).min(data.shape()[0]);
+                let batch = data.slice(s![start..end, .., .., ..]).to_owned();
+                let batch_labels = labels.slice(s![start..end, ..]).to_owned();
+
+                cost +=
+                    self.train_batch(&batch, &batch_labels, learning_rate)? * (end - start) as f64;
+            }
+            costs.push(cost / data.shape()[0].max(1) as f64);
+        }
+
+        Ok(costs)
+    }
+}
+
+#[cfg(test)]
+mod conv_tests {
+    use super::*;
+    use crate::activations::*;
+    use crate::layers::Dense1d;
+    use crate::test_util::{assert_close, numerical_gradient, uneven};
+    use crate::train::train;
+    use ndarray::{arr1, arr2, arr3, Array};
+
+    fn identity_conv(weights: Array4<f64>, bias: Array1<f64>, window: Window2d) -> Conv2d {
+        Conv2d::from(Activation::Identity, weights, bias, window).unwrap()
+    }
+
+    fn image(values: &[f64], shape: (usize, usize, usize, usize)) -> Array4<f64> {
+        Array4::from_shape_vec(shape, values.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn window_output_size() {
+        let window = Window2d::new((3, 3), (2, 1), (1, 0), (1, 2)).unwrap();
+
+        // Height: (7 + 2 - 3) / 2 + 1, width: (7 - 5) / 1 + 1
+        assert_eq!(window.output_size((7, 7)), Some((4, 3)));
+        assert_eq!(window.output_size((7, 4)), None);
+        assert!(matches!(
+            Window2d::new((3, 0), (1, 1), (0, 0), (1, 1)),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            Window2d::new((3, 3), (0, 1), (0, 0), (1, 1)),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn conv2d_pass() {
+        let window = Window2d::new((2, 2), (1, 1), (0, 0), (1, 1)).unwrap();
+        let layer = identity_conv(Array4::ones((1, 1, 2, 2)), arr1(&[1.]), window);
+        let input_array = image(&[1., 2., 3., 4., 5., 6., 7., 8., 9.], (1, 1, 3, 3));
+
+        let (_, a) = layer.pass(input_array, Mode::Eval).unwrap();
+
+        assert_eq!(a, image(&[13., 17., 25., 29.], (1, 1, 2, 2)));
+    }
+
+    #[test]
+    fn conv2d_pass_padding_stride_dilation() {
+        let input_array = image(&[1., 2., 3., 4., 5., 6., 7., 8., 9.], (1, 1, 3, 3));
+
+        let padded = Window2d::new((2, 2), (2, 2), (1, 1), (1, 1)).unwrap();
+        let layer = identity_conv(Array4::ones((1, 1, 2, 2)), arr1(&[0.]), padded);
+        let (_, a) = layer.pass(input_array.clone(), Mode::Eval).unwrap();
+        assert_eq!(a, image(&[1., 5., 11., 28.], (1, 1, 2, 2)));
+
+        let dilated = Window2d::new((2, 2), (1, 1), (0, 0), (2, 2)).unwrap();
+        let layer = identity_conv(Array4::ones((1, 1, 2, 2)), arr1(&[0.]), dilated);
+        let (_, a) = layer.pass(input_array, Mode::Eval).unwrap();
+        assert_eq!(a, image(&[20.], (1, 1, 1, 1)));
+    }
+
+    #[test]
+    fn conv2d_pass_channels() {
+        let window = Window2d::new((1, 1), (1, 1), (0, 0), (1, 1)).unwrap();
+        // Output channel 0 adds the input channels, channel 1 subtracts them
+        let weights = image(&[1., 1., 1., -1.], (2, 2, 1, 1));
+        let layer = identity_conv(weights, arr1(&[0., 10.]), window);
+        let input_array = image(&[1., 2., 3., 4., 5., 6., 7., 8.], (2, 2, 1, 2));
+
+        let (_, a) = layer.pass(input_array, Mode::Eval).unwrap();
+
+        assert_eq!(a, image(&[4., 6., 8., 8., 12., 14., 8., 8.], (2, 2, 1, 2)));
+    }
+
+    #[test]
+    fn conv2d_pass_wrong_shape() {
+        let window = Window2d::new((3, 3), (1, 1), (0, 0), (1, 1)).unwrap();
+        let layer = identity_conv(Array4::ones((1, 2, 3, 3)), arr1(&[0.]), window);
+
+        assert_eq!(
+            layer.pass(Array4::ones((1, 1, 3, 3)), Mode::Eval),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1, 2, 3, 3],
+                found: vec![1, 1, 3, 3]
+            })
+        );
+        assert_eq!(
+            layer.pass(Array4::ones((1, 2, 2, 3)), Mode::Eval),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1, 2, 3, 3],
+                found: vec![1, 2, 2, 3]
+            })
+        );
+        assert_eq!(
+            layer.pass(Array4::from_elem((1, 2, 3, 3), f64::NAN), Mode::Eval),
+            Err(DuckyError::NonFinite)
+        );
+        assert!(matches!(
+            Conv2d::from(
+                Activation::Identity,
+                Array4::ones((2, 1, 3, 3)),
+                arr1(&[0.]),
+                window
+            ),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn conv2d_back_pass() {
+        let window = Window2d::new((3, 2), (2, 1), (1, 1), (1, 2)).unwrap();
+        let weights = uneven((3, 2, 3, 2));
+        let bias = arr1(&[0.1, -0.2, 0.3]);
+        let layer = Conv2d::from(Activation::Tanh, weights.clone(), bias.clone(), window).unwrap();
+        let input_array = uneven((2, 2, 5, 4));
+
+        let (z, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let output_gradient = uneven(a.dim()).mapv(|value| value * 0.5);
+
+        let expected_input = numerical_gradient(&input_array, &output_gradient, |x| {
+            layer.pass(x.clone(), Mode::Eval).unwrap().1
+        });
+        let expected_weights = numerical_gradient(&weights, &output_gradient, |w| {
+            let layer = Conv2d::from(Activation::Tanh, w.clone(), bias.clone(), window).unwrap();
+            layer.pass(input_array.clone(), Mode::Eval).unwrap().1
+        });
+
+        let input_gradient = layer
+            .back_pass(&input_array, &z, &output_gradient, 0.1)
+            .unwrap();
+
+        assert_close(&input_gradient, &expected_input);
+        assert_close(&((&weights - &layer.weights()) / 0.1), &expected_weights);
+    }
+
+    #[test]
+    fn conv2d_softmax_channels() {
+        let window = Window2d::new((2, 2), (1, 1), (1, 0), (1, 1)).unwrap();
+        let weights = uneven((3, 2, 2, 2));
+        let bias = arr1(&[0.1, -0.2, 0.3]);
+        let layer = Conv2d::from(Activation::Softmax, weights, bias, window).unwrap();
+        let input_array = uneven((2, 2, 3, 3));
+
+        let (z, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let output_gradient = uneven(a.dim());
+
+        // Every pixel holds a distribution over the output channels
+        assert_close(&a.sum_axis(Axis(1)), &Array3::ones((2, 4, 2)));
+
+        let expected_input = numerical_gradient(&input_array, &output_gradient, |x| {
+            layer.pass(x.clone(), Mode::Eval).unwrap().1
+        });
+        let input_gradient = layer
+            .back_pass(&input_array, &z, &output_gradient, 0.)
+            .unwrap();
+
+        assert_close(&input_gradient, &expected_input);
+    }
+
+    #[test]
+    fn conv2d_back_pass_wrong_shape() {
+        let window = Window2d::new((2, 2), (1, 1), (0, 0), (1, 1)).unwrap();
+        let layer = identity_conv(Array4::ones((1, 1, 2, 2)), arr1(&[0.]), window);
+        let input_array = Array4::ones((1, 1, 3, 3));
+        let (z, _) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+
+        assert_eq!(
+            layer.back_pass(&input_array, &z, &Array4::ones((1, 1, 3, 3)), 0.1),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1, 1, 2, 2],
+                found: vec![1, 1, 3, 3]
+            })
+        );
+    }
+
+    #[test]
+    fn max_pool_2d() {
+        let layer = MaxPool2d::new((2, 2), (2, 2)).unwrap();
+        let input_array = image(
+            &[
+                1., 5., 2., 0., //
+                3., 4., 8., 1., //
+                0., 0., 1., 1., //
+                9., 0., 1., 1., //
+            ],
+            (1, 1, 4, 4),
+        );
+
+        let (z, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let input_gradient = layer
+            .back_pass(
+                &input_array,
+                &z,
+                &image(&[1., 2., 3., 4.], (1, 1, 2, 2)),
+                0.1,
+            )
+            .unwrap();
+
+        assert_eq!(a, image(&[5., 8., 9., 1.], (1, 1, 2, 2)));
+        assert_eq!(
+            input_gradient,
+            image(
+                &[
+                    0., 1., 0., 0., //
+                    0., 0., 2., 0., //
+                    0., 0., 4., 0., //
+                    3., 0., 0., 0., //
+                ],
+                (1, 1, 4, 4)
+            )
+        );
+    }
+
+    #[test]
+    fn max_pool_2d_overlapping() {
+        let layer = MaxPool2d::new((2, 2), (1, 1)).unwrap();
+        let input_array = uneven((2, 3, 4, 5));
+
+        let (z, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let output_gradient = uneven(a.dim());
+        let expected = numerical_gradient(&input_array, &output_gradient, |x| {
+            layer.pass(x.clone(), Mode::Eval).unwrap().1
+        });
+
+        assert_eq!(a.shape(), &[2, 3, 3, 4]);
+        assert_close(
+            &layer
+                .back_pass(&input_array, &z, &output_gradient, 0.1)
+                .unwrap(),
+            &expected,
+        );
+    }
+
+    #[test]
+    fn avg_pool_2d() {
+        let layer = AvgPool2d::new((2, 3), (1, 2)).unwrap();
+        let input_array = uneven((2, 2, 4, 7));
+
+        let (z, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let output_gradient = uneven(a.dim());
+        let expected = numerical_gradient(&input_array, &output_gradient, |x| {
+            layer.pass(x.clone(), Mode::Eval).unwrap().1
+        });
+
+        assert_eq!(a.shape(), &[2, 2, 3, 3]);
+        assert!(
+            (a[[0, 0, 0, 0]] - input_array.slice(s![0, 0, 0..2, 0..3]).mean().unwrap()).abs()
+                < 1e-12
+        );
+        assert_close(
+            &layer
+                .back_pass(&input_array, &z, &output_gradient, 0.1)
+                .unwrap(),
+            &expected,
+        );
+        assert!(matches!(
+            layer.pass(Array4::ones((1, 1, 1, 3)), Mode::Eval),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn global_avg_pool() {
+        let layer = GlobalAvgPool::new();
+        let input_array = uneven((2, 3, 4, 5));
+
+        let (z, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let output_gradient = uneven(a.dim());
+        let expected = numerical_gradient(&input_array, &output_gradient, |x| {
+            layer.pass(x.clone(), Mode::Eval).unwrap().1
+        });
+
+        assert_eq!(a.shape(), &[2, 3, 1, 1]);
+        assert!(
+            (a[[1, 2, 0, 0]] - input_array.slice(s![1, 2, .., ..]).mean().unwrap()).abs() < 1e-12
+        );
+        assert_close(
+            &layer
+                .back_pass(&input_array, &z, &output_gradient, 0.1)
+                .unwrap(),
+            &expected,
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        let input_array = uneven((2, 3, 2, 2));
+
+        let rows = Flatten::new().pass(&input_array);
+
+        assert_eq!(rows.shape(), &[2, 12]);
+        assert_eq!(rows[[1, 5]], input_array[[1, 1, 0, 1]]);
+        assert_eq!(
+            Flatten::new().back_pass(&input_array, &rows).unwrap(),
+            input_array
+        );
+        assert!(matches!(
+            Flatten::new().back_pass(&input_array, &rows.t().to_owned()),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn back_propagation_4d_chain() {
+        let window = Window2d::new((2, 2), (1, 1), (1, 1), (1, 1)).unwrap();
+        let conv = Conv2d::from(
+            Activation::Tanh,
+            uneven((2, 1, 2, 2)),
+            arr1(&[0., 0.1]),
+            window,
+        )
+        .unwrap();
+        let model: Vec<Box<dyn Layer4d>> = vec![
+            Box::new(conv.clone()),
+            Box::new(MaxPool2d::new((2, 2), (2, 2)).unwrap()),
+            Box::new(GlobalAvgPool::new()),
+        ];
+        let data = uneven((2, 1, 5, 5));
+
+        let (weighted_inputs, activations) =
+            forward_pass_4d(&model, data.clone(), Mode::Train).unwrap();
+        let output_gradient = uneven(activations[2].dim());
+        let expected = numerical_gradient(&data, &output_gradient, |x| {
+            let (_, activations) = forward_pass_4d(&model, x.clone(), Mode::Eval).unwrap();
+            activations[2].clone()
+        });
+
+        let input_gradient = back_propagation_4d(
+            &model,
+            &data,
+            &weighted_inputs,
+            &activations,
+            output_gradient,
+            0.,
+        )
+        .unwrap();
+
+        assert_eq!(activations[2].shape(), &[2, 2, 1, 1]);
+        assert_close(&input_gradient, &expected);
+    }
+
+    /// Images of a vertical or horizontal line, labelled one-hot as [vertical, horizontal].
+    fn lines() -> (Array4<f64>, Array2<f64>) {
+        let mut images = Array4::zeros((8, 1, 6, 6));
+        let mut labels = Array2::zeros((8, 2));
+
+        for index in 0..8 {
+            let position = index / 2 + 1;
+            match index % 2 {
+                0 => images.slice_mut(s![index, 0, .., position]).fill(1.),
+                _ => images.slice_mut(s![index, 0, position, ..]).fill(1.),
+            }
+            labels[[index, index % 2]] = 1.;
+        }
+
+        (images, labels)
+    }
+
+    #[test]
+    fn conv_net_train() {
+        let window = Window2d::new((3, 3), (1, 1), (1, 1), (1, 1)).unwrap();
+        let features: Vec<Box<dyn Layer4d>> = vec![
+            Box::new(
+                Conv2d::from(
+                    Activation::Relu,
+                    uneven((4, 1, 3, 3)).mapv(|value| value * 0.3),
+                    arr1(&[0.1, 0.1, 0.1, 0.1]),
+                    window,
+                )
+                .unwrap(),
+            ),
+            Box::new(MaxPool2d::new((2, 2), (2, 2)).unwrap()),
+        ];
+        let head = vec![Dense1d::from(
+            |x| x,
+            |x| x.map(|_| 1f64),
+            Array::from_shape_fn((2, 36), |(row, col)| {
+                ((row * 36 + col) as f64 * 0.7).sin() * 0.1
+            }),
+            arr1(&[0., 0.]),
+        )];
+        let model = ConvNet::new(features, head);
+        let (images, labels) = lines();
+
+        let costs = model.train(&images, &labels, 100, 4, 0.01).unwrap();
+        let outputs = model.forward(&images, Mode::Eval).unwrap();
+
+        assert!(costs[99] < costs[0]);
+        for (output, label) in outputs.rows().into_iter().zip(labels.rows()) {
+            assert_eq!(output[0] > output[1], label[0] > label[1]);
+        }
+    }
+
+    #[test]
+    fn conv_net_head_takes_one_batch_step() {
+        let weights = arr2(&[[0.1, -0.2, 0.3, 0.], [0.2, 0.1, -0.1, 0.4]]);
+        let features: Vec<Box<dyn Layer4d>> = Vec::new();
+        let head = vec![Dense1d::from(
+            |x| x,
+            |x| x.map(|_| 1f64),
+            weights.clone(),
+            arr1(&[0., 0.]),
+        )];
+        let model = ConvNet::new(features, head);
+        let images = uneven((3, 1, 2, 2));
+        let labels = arr2(&[[1., 0.], [0., 1.], [1., 1.]]);
+
+        model.train_batch(&images, &labels, 0.1).unwrap();
+
+        // One step on the mean squared error averaged over the batch
+        let rows = images.into_shape((3, 4)).unwrap();
+        let output_gradient = (rows.dot(&weights.t()) - &labels) * 2.;
+        let expected = &weights - &(output_gradient.t().dot(&rows) * (0.1 / 3.));
+        assert!((&model.head[0].weights() - &expected)
+            .iter()
+            .all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    fn conv_net_invalid() {
+        let window = Window2d::new((3, 3), (1, 1), (0, 0), (1, 1)).unwrap();
+        let model = ConvNet::new(
+            vec![Conv2d::new(1, 2, window, Activation::Relu)],
+            vec![Dense1d::new(2 * 4 * 4, 2, |x| x, |x| x)],
+        );
+        let (images, labels) = lines();
+
+        assert!(matches!(
+            model.train(&images, &labels, 1, 0, 0.1),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            model.train(&images, &labels, 1, 4, 0.),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            model.train(&images, &labels.slice(s![..3, ..]).to_owned(), 1, 4, 0.1),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            model.forward(&Array4::ones((1, 1, 5, 5)), Mode::Eval),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    fn sequences(values: &[f64], shape: (usize, usize, usize)) -> Array3<f64> {
+        Array3::from_shape_vec(shape, values.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn window_1d() {
+        let window = Window1d::new(3, 2, 1, 2).unwrap();
+        let causal = Window1d::causal(3, 1, 2).unwrap();
+
+        // (10 + 2 - 5) / 2 + 1
+        assert_eq!(window.output_size(10), Some(4));
+        assert_eq!(window.output_size(2), None);
+        assert_eq!(causal.padding(), (4, 0));
+        assert_eq!(causal.output_size(1), Some(1));
+        assert!(matches!(
+            Window1d::causal(0, 1, 1),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            Window1d::new(3, 1, 0, 0),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn conv1d_pass_batch() {
+        let input_array = sequences(&[1., 2., 3., 4., 0., 1., 0., 1.], (2, 1, 4));
+
+        let window = Window1d::new(2, 1, 0, 1).unwrap();
+        let layer = Conv1d::from(
+            Activation::Identity,
+            arr3(&[[[1., 1.]]]),
+            arr1(&[0.]),
+            window,
+        )
+        .unwrap();
+        let (_, a) = layer.pass_batch(&input_array, Mode::Eval).unwrap();
+        assert_eq!(a, sequences(&[3., 5., 7., 1., 1., 1.], (2, 1, 3)));
+
+        let window = Window1d::new(2, 2, 1, 1).unwrap();
+        let layer = Conv1d::from(
+            Activation::Identity,
+            arr3(&[[[1., 1.]]]),
+            arr1(&[0.]),
+            window,
+        )
+        .unwrap();
+        let (_, a) = layer.pass_batch(&input_array, Mode::Eval).unwrap();
+        assert_eq!(a, sequences(&[1., 5., 4., 0., 1., 1.], (2, 1, 3)));
+    }
+
+    #[test]
+    fn conv1d_causal() {
+        let window = Window1d::causal(2, 1, 2).unwrap();
+        // Output t is input t - 2 + 10 * input t
+        let layer = Conv1d::from(
+            Activation::Identity,
+            arr3(&[[[1., 10.]]]),
+            arr1(&[0.]),
+            window,
+        )
+        .unwrap();
+        let input_array = sequences(&[1., 2., 3., 4., 5.], (1, 1, 5));
+
+        let (_, a) = layer.pass_batch(&input_array, Mode::Eval).unwrap();
+        assert_eq!(a, sequences(&[10., 20., 31., 42., 53.], (1, 1, 5)));
+
+        // Changing the last step leaves every earlier output alone
+        let mut changed = input_array.clone();
+        changed[[0, 0, 4]] = -100.;
+        let (_, changed) = layer.pass_batch(&changed, Mode::Eval).unwrap();
+        assert_eq!(changed.slice(s![.., .., ..4]), a.slice(s![.., .., ..4]));
+    }
+
+    #[test]
+    fn conv1d_back_pass_batch() {
+        let window = Window1d::causal(3, 2, 2).unwrap();
+        let weights = uneven((3, 2, 3));
+        let bias = arr1(&[0.1, -0.2, 0.3]);
+        let layer = Conv1d::from(Activation::Tanh, weights.clone(), bias.clone(), window).unwrap();
+        let input_array = uneven((2, 2, 9));
+
+        let (z, a) = layer.pass_batch(&input_array, Mode::Train).unwrap();
+        let output_gradient = uneven(a.dim()).mapv(|value| value * 0.5);
+
+        let expected_input = numerical_gradient(&input_array, &output_gradient, |x| {
+            layer.pass_batch(x, Mode::Eval).unwrap().1
+        });
+        let expected_weights = numerical_gradient(&weights, &output_gradient, |w| {
+            let layer = Conv1d::from(Activation::Tanh, w.clone(), bias.clone(), window).unwrap();
+            layer.pass_batch(&input_array, Mode::Eval).unwrap().1
+        });
+
+        let input_gradient = layer
+            .back_pass_batch(&input_array, &z, &output_gradient, 0.1)
+            .unwrap();
+
+        assert_eq!(a.shape(), &[2, 3, 5]);
+        assert_close(&input_gradient, &expected_input);
+        assert_close(&((&weights - &layer.weights()) / 0.1), &expected_weights);
+    }
+
+    #[test]
+    fn conv1d_layer1d() {
+        let window = Window1d::new(3, 1, 1, 1).unwrap();
+        let layer = Conv1d::from(
+            Activation::Tanh,
+            uneven((2, 3, 3)),
+            arr1(&[0., 0.5]),
+            window,
+        )
+        .unwrap();
+        let input_array = uneven((1, 3, 4));
+        let flat_input: Array1<f64> = input_array.iter().cloned().collect();
+
+        let (z, a) = layer.pass(flat_input.clone(), Mode::Train).unwrap();
+        let (_, batch_a) = layer.pass_batch(&input_array, Mode::Train).unwrap();
+        let output_gradient = Array1::ones(a.len());
+        let expected = numerical_gradient(&flat_input, &output_gradient, |x| {
+            layer.pass(x.clone(), Mode::Eval).unwrap().1
+        });
+
+        assert_eq!(a, batch_a.iter().cloned().collect::<Array1<f64>>());
+        assert_close(
+            &layer
+                .back_pass(&flat_input, &z, &output_gradient, 0.)
+                .unwrap(),
+            &expected,
+        );
+        assert!(matches!(
+            layer.pass(Array1::ones(10), Mode::Eval),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert_eq!(
+            layer.pass_batch(&Array3::ones((1, 2, 4)), Mode::Eval),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1, 3, 4],
+                found: vec![1, 2, 4]
+            })
+        );
+    }
+
+    #[test]
+    fn max_pool_1d() {
+        let layer = MaxPool1d::new(2, 3, 2).unwrap();
+        let input_array = sequences(
+            &[1., 5., 2., 0., 3., 9., 1., 0., 4., 0., 2., 2., 0., 8.],
+            (1, 2, 7),
+        );
+
+        let (z, a) = layer.pass_batch(&input_array, Mode::Train).unwrap();
+        let input_gradient = layer
+            .back_pass_batch(
+                &input_array,
+                &z,
+                &sequences(&[1., 2., 3., 4., 5., 6.], (1, 2, 3)),
+                0.1,
+            )
+            .unwrap();
+
+        assert_eq!(a, sequences(&[5., 3., 9., 4., 2., 8.], (1, 2, 3)));
+        assert_eq!(
+            input_gradient,
+            sequences(
+                &[0., 1., 0., 0., 2., 3., 0., 0., 4., 0., 5., 0., 0., 6.],
+                (1, 2, 7)
+            )
+        );
+        assert!(matches!(
+            MaxPool1d::new(0, 2, 2),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            layer.pass(Array1::ones(7), Mode::Eval),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn conv1d_train() {
+        let window = Window1d::causal(2, 1, 1).unwrap();
+        let model: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(
+                Conv1d::from(
+                    Activation::Relu,
+                    uneven((2, 1, 2)).mapv(|value| value * 0.5),
+                    arr1(&[0.1, 0.1]),
+                    window,
+                )
+                .unwrap(),
+            ),
+            Box::new(MaxPool1d::new(2, 2, 2).unwrap()),
+            Box::new(Dense1d::from(
+                |x| x,
+                |x| x.map(|_| 1f64),
+                Array2::from_elem((1, 6), 0.1),
+                arr1(&[0.]),
+            )),
+        ];
+        // The target is the size of the largest jump between steps
+        let data = arr2(&[
+            [0., 0., 1., 1., 1., 1.],
+            [0., 0., 0., 0., 0., 2.],
+            [1., 1., 1., 1., 1., 1.],
+            [2., 0., 0., 0., 0., 0.],
+        ]);
+        let labels = arr2(&[[1.], [2.], [0.], [0.]]);
+
+        let costs = train(
+            &model,
+            data.clone(),
+            labels.clone(),
+            data,
+            labels,
+            200,
+            0.01,
+        )
+        .unwrap();
+
+        assert!(costs.iter().all(|cost| cost.is_finite()));
+        assert!(costs[199] < costs[0]);
+    }
+}
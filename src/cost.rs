@@ -1,11 +1,39 @@
-use ndarray::Array1;
+use super::autograd::{Tape, Tensor};
+use ndarray::{Array1, Ix1};
+use serde::{Deserialize, Serialize};
+
+// The costs are computed on a `Tape` so their derivatives are the gradients of the same
+// operations. Each one is returned per element; its gradient is that of their sum.
+
+fn squared_error(observed: &Tensor, predicted: &Tensor) -> Tensor {
+    (observed - predicted).powi(2)
+}
+
+fn clipped_cross_entropy(observed: &Tensor, predicted: &Tensor) -> Tensor {
+    -(observed * &predicted.ln())
+}
+
+/// Cost of every element and its derivative with respect to each predicted value.
+fn with_deriv(
+    observed_array: Array1<f64>,
+    predicted_array: Array1<f64>,
+    cost: fn(&Tensor, &Tensor) -> Tensor,
+) -> (Array1<f64>, Array1<f64>) {
+    let tape = Tape::new();
+    let predicted = tape.variable(predicted_array);
+    let cost = cost(&tape.variable(observed_array), &predicted);
+
+    let deriv = cost.backward().wrt(&predicted);
+
+    (cost.value_1d(), deriv.into_dimensionality::<Ix1>().unwrap())
+}
 
 // TODO: Documentation
 pub fn mean_squared_error(
     observed_array: Array1<f64>,
     predicted_array: Array1<f64>,
 ) -> Array1<f64> {
-    (&observed_array - &predicted_array).mapv(|value| value.powi(2))
+    with_deriv(observed_array, predicted_array, squared_error).0
 }
 
 // TODO: Documentation
@@ -13,7 +41,115 @@ pub fn deriv_mean_squared_error(
     observed_array: Array1<f64>,
     predicted_array: Array1<f64>,
 ) -> Array1<f64> {
-    -2f64 * (&observed_array - &predicted_array)
+    with_deriv(observed_array, predicted_array, squared_error).1
+}
+
+/// Cross-entropy of every element, for outputs holding class probabilities such as a softmax.
+/// Predictions are clipped to `[1e-15, 1 - 1e-15]` so a confident wrong one gives a large but
+/// finite cost.
+///
+/// # Arguments
+///
+/// * `observed_array`: Target probabilities, usually one-hot
+/// * `predicted_array`: Predicted probabilities
+///
+/// returns: `Array1<f64>` holding `-observed * ln(predicted)` for every element
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::cost::cross_entropy;
+/// use ndarray::arr1;
+///
+/// let cost = cross_entropy(arr1(&[0., 1.]), arr1(&[0.5, 0.5]));
+///
+/// assert_eq!(cost, arr1(&[0., 2f64.ln()]));
+/// ```
+pub fn cross_entropy(observed_array: Array1<f64>, predicted_array: Array1<f64>) -> Array1<f64> {
+    let predicted_array = predicted_array.mapv(clip_probability);
+    with_deriv(observed_array, predicted_array, clipped_cross_entropy).0
+}
+
+/// Derivative of `cross_entropy` with respect to each predicted probability.
+///
+/// # Arguments
+///
+/// * `observed_array`: Target probabilities, usually one-hot
+/// * `predicted_array`: Predicted probabilities
+///
+/// returns: `Array1<f64>` holding `-observed / predicted` for every element
+pub fn deriv_cross_entropy(
+    observed_array: Array1<f64>,
+    predicted_array: Array1<f64>,
+) -> Array1<f64> {
+    let predicted_array = predicted_array.mapv(clip_probability);
+    with_deriv(observed_array, predicted_array, clipped_cross_entropy).1
+}
+
+fn clip_probability(value: f64) -> f64 {
+    value.clamp(1e-15, 1. - 1e-15)
+}
+
+/// Cost a model output is trained to minimise. Both are summed over the elements of the
+/// output, so `deriv` is the gradient of `cost` and their weights in a model with several
+/// outputs mean the same thing.
+///
+/// * `MeanSquaredError` - `mean_squared_error`, for outputs holding values.
+/// * `CrossEntropy` - `cross_entropy`, for outputs holding class probabilities. The layer
+///   making them has to back propagate through the whole softmax Jacobian, as a
+///   `Dense1d::new_auto` layer activated by `Tensor::softmax` does.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::cost::Loss;
+/// use ndarray::arr1;
+///
+/// let observed = arr1(&[0., 1.]);
+/// let predicted = arr1(&[0.5, 0.5]);
+///
+/// assert_eq!(Loss::MeanSquaredError.cost(&observed, &predicted), 0.5);
+/// assert_eq!(Loss::CrossEntropy.cost(&observed, &predicted), 2f64.ln());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Loss {
+    MeanSquaredError,
+    CrossEntropy,
+}
+
+impl Loss {
+    /// Cost of a prediction, the squared error or the cross-entropy summed over the elements
+    /// of the output.
+    ///
+    /// # Arguments
+    ///
+    /// * `observed_array`: Target output
+    /// * `predicted_array`: Predicted output, the same length as `observed_array`
+    ///
+    /// returns: `f64`
+    pub fn cost(&self, observed_array: &Array1<f64>, predicted_array: &Array1<f64>) -> f64 {
+        let (observed_array, predicted_array) = (observed_array.clone(), predicted_array.clone());
+        match self {
+            Loss::MeanSquaredError => mean_squared_error(observed_array, predicted_array).sum(),
+            Loss::CrossEntropy => cross_entropy(observed_array, predicted_array).sum(),
+        }
+    }
+
+    /// Gradient of `cost` with respect to each element of the prediction, as used by back
+    /// propagation.
+    ///
+    /// # Arguments
+    ///
+    /// * `observed_array`: Target output
+    /// * `predicted_array`: Predicted output, the same length as `observed_array`
+    ///
+    /// returns: `Array1<f64>`
+    pub fn deriv(&self, observed_array: Array1<f64>, predicted_array: Array1<f64>) -> Array1<f64> {
+        match self {
+            Loss::MeanSquaredError => deriv_mean_squared_error(observed_array, predicted_array),
+            Loss::CrossEntropy => deriv_cross_entropy(observed_array, predicted_array),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -84,4 +220,52 @@ mod cost_tests {
 
         assert_eq!(deriv_mean_squared_error(observed, predicted), arr1(&[]));
     }
+
+    #[test]
+    fn cross_entropy_clips() {
+        let observed = arr1(&[1., 0.]);
+        let predicted = arr1(&[0., 1.]);
+
+        let cost = cross_entropy(observed.clone(), predicted.clone());
+        assert!((cost[0] - 1e-15f64.ln().abs()).abs() < 1e-9);
+        assert_eq!(cost[1], 0.);
+        assert!(deriv_cross_entropy(observed, predicted)
+            .iter()
+            .all(|value| value.is_finite()));
+    }
+
+    #[test]
+    fn deriv_cross_entropy_matches_numerical() {
+        let observed = arr1(&[0.2, 0.8]);
+        let predicted = arr1(&[0.3, 0.6]);
+        let h = 1e-6;
+
+        let deriv = deriv_cross_entropy(observed.clone(), predicted.clone());
+        for i in 0..2 {
+            let mut shifted = predicted.clone();
+            shifted[i] += h;
+            let numerical = (cross_entropy(observed.clone(), shifted).sum()
+                - cross_entropy(observed.clone(), predicted.clone()).sum())
+                / h;
+            assert!((deriv[i] - numerical).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn loss_deriv_matches_numerical_cost() {
+        let observed = arr1(&[0.2, 0.5, 0.3]);
+        let predicted = arr1(&[0.3, 0.6, 0.1]);
+        let h = 1e-6;
+
+        for loss in [Loss::MeanSquaredError, Loss::CrossEntropy] {
+            let deriv = loss.deriv(observed.clone(), predicted.clone());
+            for i in 0..3 {
+                let mut shifted = predicted.clone();
+                shifted[i] += h;
+                let numerical =
+                    (loss.cost(&observed, &shifted) - loss.cost(&observed, &predicted)) / h;
+                assert!((deriv[i] - numerical).abs() < 1e-4, "{:?} {}", loss, i);
+            }
+        }
+    }
 }
@@ -0,0 +1,575 @@
+use super::error::DuckyError;
+use super::feature_extraction::{CountVectorizer, PADDING_ID, UNKNOWN_ID};
+use super::layers::{Layer1d, Mode, SavedLayer};
+use super::util::seeded_rng;
+use ndarray::prelude::*;
+use ndarray_rand::rand_distr::Uniform;
+use ndarray_rand::RandomExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Lookup table turning token IDs into dense vectors, for feeding text to recurrent and
+/// dense layers. Row `i` of the weights is the vector of token ID `i`.
+///
+/// Only the rows of the tokens in a batch are updated during the backward pass, so the cost
+/// of a step does not grow with the size of the vocabulary. The row of the padding ID, if
+/// there is one, stays fixed.
+///
+/// As a `Layer1d` it takes one sequence of token IDs at a time, stored as `f64`, and outputs
+/// the vectors of the tokens one after another.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::embedding::Embedding;
+/// use ducky_learn::feature_extraction::{padding_mask, CountVectorizer, PADDING_ID};
+///
+/// let mut count_vector = CountVectorizer::new();
+/// let documents = vec!["ducks like bread".to_string(), "ducks swim".to_string()];
+/// count_vector.fit(&documents);
+///
+/// let sequences = count_vector.transform_sequences(&documents, 3);
+/// let embedding = Embedding::new(
+///     count_vector.sequence_vocabulary_size(),
+///     4,
+///     Some(PADDING_ID),
+///     Some(42),
+/// )
+/// .unwrap();
+///
+/// let vectors = embedding.pass_batch(&sequences).unwrap();
+/// assert_eq!(vectors.shape(), &[2, 3, 4]);
+/// assert_eq!(padding_mask(&sequences)[[1, 2]], false);
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Embedding {
+    weights: RwLock<Array2<f64>>,
+    padding_id: Option<usize>,
+}
+
+impl Embedding {
+    /// Create Embedding layer with weights drawn uniformly from ±0.05
+    ///
+    /// # Arguments
+    ///
+    /// * `vocab_size`: Number of token IDs
+    /// * `dim`: Length of the vector of every token
+    /// * `padding_id`: Token ID whose vector is kept at zero and never updated, if any
+    /// * `seed`: Seed for drawing the weights, or `None` to seed it from the operating system
+    ///
+    /// returns: `Result<Embedding, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `padding_id` is outside the vocabulary
+    pub fn new(
+        vocab_size: usize,
+        dim: usize,
+        padding_id: Option<usize>,
+        seed: Option<u64>,
+    ) -> Result<Self, DuckyError> {
+        let weights = Array2::random_using(
+            (vocab_size, dim),
+            Uniform::new_inclusive(-0.05, 0.05),
+            &mut seeded_rng(seed),
+        );
+
+        Self::from(weights, padding_id)
+    }
+
+    /// Create Embedding layer with full control over the weights
+    ///
+    /// # Arguments
+    ///
+    /// * `weights`: Vector of every token ID, of shape( vocab size, dim )
+    /// * `padding_id`: Token ID whose vector is kept at zero and never updated, if any
+    ///
+    /// returns: `Result<Embedding, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `padding_id` is outside the vocabulary
+    pub fn from(mut weights: Array2<f64>, padding_id: Option<usize>) -> Result<Self, DuckyError> {
+        if let Some(padding_id) = padding_id {
+            check_id(padding_id, weights.nrows())?;
+            weights.row_mut(padding_id).fill(0.);
+        }
+
+        Ok(Self {
+            weights: RwLock::new(weights),
+            padding_id,
+        })
+    }
+
+    /// Create Embedding layer from pre-trained vectors in the GloVe text format, where every
+    /// line holds a word followed by the values of its vector, separated by spaces.
+    ///
+    /// The rows line up with the token IDs of `CountVectorizer::transform_sequences`, and
+    /// `PADDING_ID` is the padding ID. Words of the vocabulary that are not in the file, as
+    /// well as unknown words, start with a vector of zeros. Words of the file that are not in
+    /// the vocabulary are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: GloVe text
+    /// * `vectorizer`: Vectorizer whose vocabulary the sequences are built from
+    ///
+    /// returns: `Result<Embedding, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::Persistence`: the text can not be read, a value is not a number or the
+    ///   lines hold vectors of different lengths
+    /// * `DuckyError::EmptyInput`: the text holds no vectors
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::embedding::Embedding;
+    /// use ducky_learn::feature_extraction::CountVectorizer;
+    /// use ndarray::arr1;
+    ///
+    /// let mut count_vector = CountVectorizer::new();
+    /// count_vector.fit(&vec!["ducks swim".to_string()]);
+    ///
+    /// let glove = "swim 0.5 -0.25\ngeese 1.0 1.0\n";
+    /// let embedding = Embedding::read_glove(glove.as_bytes(), &count_vector).unwrap();
+    ///
+    /// assert_eq!(embedding.weights().row(count_vector.token_id("swim")), arr1(&[0.5, -0.25]));
+    /// assert_eq!(embedding.weights().row(count_vector.token_id("ducks")), arr1(&[0., 0.]));
+    /// ```
+    pub fn read_glove<R: BufRead>(
+        reader: R,
+        vectorizer: &CountVectorizer,
+    ) -> Result<Self, DuckyError> {
+        let mut vectors: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+        let mut dim = None;
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line.map_err(|error| DuckyError::Persistence(error.to_string()))?;
+            let mut values = line.split_whitespace();
+            let word = match values.next() {
+                Some(word) => word,
+                None => continue,
+            };
+            let vector = values
+                .map(|value| value.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|error| {
+                    DuckyError::Persistence(format!("line {}: {}", number + 1, error))
+                })?;
+
+            let dim = *dim.get_or_insert(vector.len());
+            if vector.len() != dim {
+                return Err(DuckyError::Persistence(format!(
+                    "line {} holds {} values, expected {}",
+                    number + 1,
+                    vector.len(),
+                    dim
+                )));
+            }
+
+            let id = vectorizer.token_id(word);
+            if id != UNKNOWN_ID {
+                vectors.insert(id, vector);
+            }
+        }
+
+        let dim = dim.ok_or(DuckyError::EmptyInput)?;
+        let mut weights = Array2::zeros((vectorizer.sequence_vocabulary_size(), dim));
+        for (id, vector) in vectors {
+            weights.row_mut(id).assign(&Array1::from(vector));
+        }
+
+        Self::from(weights, Some(PADDING_ID))
+    }
+
+    /// Same as `read_glove` but reads the vectors from a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Path of the GloVe text file
+    /// * `vectorizer`: Vectorizer whose vocabulary the sequences are built from
+    ///
+    /// returns: `Result<Embedding, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::Persistence`: the file can not be opened, or see `read_glove`
+    /// * `DuckyError::EmptyInput`: the file holds no vectors
+    pub fn load_glove<P: AsRef<Path>>(
+        path: P,
+        vectorizer: &CountVectorizer,
+    ) -> Result<Self, DuckyError> {
+        let file = File::open(path).map_err(|error| DuckyError::Persistence(error.to_string()))?;
+        Self::read_glove(BufReader::new(file), vectorizer)
+    }
+
+    /// Number of token IDs.
+    pub fn vocab_size(&self) -> usize {
+        self.weights.read().unwrap().nrows()
+    }
+
+    /// Length of the vector of every token.
+    pub fn dim(&self) -> usize {
+        self.weights.read().unwrap().ncols()
+    }
+
+    /// Token ID whose vector is kept at zero, if any.
+    pub fn padding_id(&self) -> Option<usize> {
+        self.padding_id
+    }
+
+    /// Vector of every token ID, of shape( vocab size, dim ).
+    pub fn weights(&self) -> Array2<f64> {
+        self.weights.read().unwrap().clone()
+    }
+
+    /// Looks up the vectors of a batch of sequences of token IDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids`: Token IDs of shape( batch, time )
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` of shape( batch, time, dim )
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: a token ID is outside the vocabulary
+    pub fn pass_batch(&self, ids: &Array2<usize>) -> Result<Array3<f64>, DuckyError> {
+        let weights = self.weights.read().unwrap();
+        let (batch, time) = ids.dim();
+        let mut output = Array3::zeros((batch, time, weights.ncols()));
+
+        for ((sequence, step), &id) in ids.indexed_iter() {
+            check_id(id, weights.nrows())?;
+            output
+                .slice_mut(s![sequence, step, ..])
+                .assign(&weights.row(id));
+        }
+
+        Ok(output)
+    }
+
+    /// Takes one gradient descent step on the vectors of the tokens in the batch. The
+    /// gradient of a token seen several times is summed, and the padding ID is skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids`: The token IDs the layer was passed during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of `pass_batch`
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<(), DuckyError>`, as token IDs have no gradient
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `output_gradient` does not line up with `ids`
+    /// * `DuckyError::InvalidParameter`: a token ID is outside the vocabulary
+    pub fn back_pass_batch(
+        &self,
+        ids: &Array2<usize>,
+        output_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<(), DuckyError> {
+        let mut weights = self.weights.write().unwrap();
+        let (batch, time) = ids.dim();
+
+        if output_gradient.dim() != (batch, time, weights.ncols()) {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![batch, time, weights.ncols()],
+                found: output_gradient.shape().to_vec(),
+            });
+        }
+
+        let mut gradients: BTreeMap<usize, Array1<f64>> = BTreeMap::new();
+        for ((sequence, step), &id) in ids.indexed_iter() {
+            check_id(id, weights.nrows())?;
+            if Some(id) == self.padding_id {
+                continue;
+            }
+
+            let gradient = output_gradient.slice(s![sequence, step, ..]);
+            gradients
+                .entry(id)
+                .and_modify(|sum| *sum += &gradient)
+                .or_insert_with(|| gradient.to_owned());
+        }
+
+        for (id, gradient) in gradients {
+            weights.row_mut(id).scaled_add(-learning_rate, &gradient);
+        }
+
+        Ok(())
+    }
+}
+
+impl Clone for Embedding {
+    fn clone(&self) -> Self {
+        Self {
+            weights: RwLock::new(self.weights()),
+            padding_id: self.padding_id,
+        }
+    }
+}
+
+/// Checks that `id` is a row of a table of `vocab_size` vectors.
+fn check_id(id: usize, vocab_size: usize) -> Result<(), DuckyError> {
+    if id >= vocab_size {
+        return Err(DuckyError::InvalidParameter(format!(
+            "token ID {} is outside the vocabulary of {} tokens",
+            id, vocab_size
+        )));
+    }
+
+    Ok(())
+}
+
+/// Converts a sample of token IDs stored as `f64` into a batch of one sequence.
+fn token_ids(input_array: &Array1<f64>) -> Result<Array2<usize>, DuckyError> {
+    input_array
+        .iter()
+        .map(|&value| {
+            if value >= 0. && value.fract() == 0. && value.is_finite() {
+                Ok(value as usize)
+            } else {
+                Err(DuckyError::InvalidParameter(format!(
+                    "{} is not a token ID",
+                    value
+                )))
+            }
+        })
+        .collect::<Result<Array1<usize>, DuckyError>>()
+        .map(|ids| ids.insert_axis(Axis(0)))
+}
+
+impl Layer1d for Embedding {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        _mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let output: Array1<f64> = self
+            .pass_batch(&token_ids(&input_array)?)?
+            .into_iter()
+            .collect();
+        Ok((output.clone(), output))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        _weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let ids = token_ids(input_array)?;
+        let shape = (1, ids.ncols(), self.dim());
+        let output_gradient =
+            Array3::from_shape_vec(shape, output_gradient.to_vec()).map_err(|_| {
+                DuckyError::ShapeMismatch {
+                    expected: vec![shape.1 * shape.2],
+                    found: output_gradient.shape().to_vec(),
+                }
+            })?;
+
+        self.back_pass_batch(&ids, &output_gradient, learning_rate)?;
+        Ok(Array1::zeros(input_array.len()))
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::Embedding(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod embedding_tests {
+    use super::*;
+    use crate::feature_extraction::padding_mask;
+    use crate::layers::Dense1d;
+    use crate::recurrent::{ReturnMode, GRU};
+    use crate::train::train;
+    use crate::traits::Persist;
+    use ndarray::{arr1, arr2, arr3};
+
+    fn vectorizer() -> CountVectorizer {
+        let mut count_vector = CountVectorizer::new();
+        count_vector.fit(&["good duck".to_string(), "bad goose".to_string()]);
+        count_vector
+    }
+
+    #[test]
+    fn transform_sequences() {
+        let count_vector = vectorizer();
+        let sequences = count_vector.transform_sequences(
+            &[
+                "duck good good".to_string(),
+                "bad swan".to_string(),
+                "".to_string(),
+            ],
+            2,
+        );
+
+        assert_eq!(count_vector.sequence_vocabulary_size(), 6);
+        assert_eq!(sequences, arr2(&[[3, 2], [4, UNKNOWN_ID], [0, 0]]));
+        assert_eq!(
+            padding_mask(&sequences),
+            arr2(&[[true, true], [true, true], [false, false]])
+        );
+    }
+
+    #[test]
+    fn embedding_pass() {
+        let weights = arr2(&[[9., 9.], [1., 2.], [3., 4.]]);
+        let embedding = Embedding::from(weights, Some(0)).unwrap();
+
+        assert_eq!(embedding.weights().row(0), arr1(&[0., 0.]));
+        assert_eq!(
+            embedding.pass_batch(&arr2(&[[2, 1], [0, 2]])).unwrap(),
+            arr3(&[[[3., 4.], [1., 2.]], [[0., 0.], [3., 4.]]])
+        );
+        assert_eq!(
+            embedding.pass(arr1(&[1., 2.]), Mode::Eval).unwrap().1,
+            arr1(&[1., 2., 3., 4.])
+        );
+    }
+
+    #[test]
+    fn embedding_sparse_update() {
+        let weights = arr2(&[[0., 0.], [1., 2.], [3., 4.], [5., 6.]]);
+        let embedding = Embedding::from(weights, Some(0)).unwrap();
+        let ids = arr2(&[[1, 1], [0, 2]]);
+        let output_gradient = arr3(&[[[1., 0.], [1., 1.]], [[5., 5.], [0., 2.]]]);
+
+        embedding
+            .back_pass_batch(&ids, &output_gradient, 0.5)
+            .unwrap();
+
+        assert_eq!(
+            embedding.weights(),
+            arr2(&[[0., 0.], [0., 1.5], [3., 3.], [5., 6.]])
+        );
+    }
+
+    #[test]
+    fn embedding_errors() {
+        let embedding = Embedding::new(3, 2, None, Some(1)).unwrap();
+
+        assert!(matches!(
+            Embedding::new(3, 2, Some(3), None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            embedding.pass_batch(&arr2(&[[0, 3]])),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            embedding.pass(arr1(&[0.5]), Mode::Eval),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert_eq!(
+            embedding.back_pass_batch(&arr2(&[[0, 1]]), &Array3::zeros((1, 2, 3)), 0.1),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1, 2, 2],
+                found: vec![1, 2, 3],
+            })
+        );
+    }
+
+    #[test]
+    fn read_glove() {
+        let count_vector = vectorizer();
+        let glove = "duck 0.1 0.2 0.3\n\nswan 1 1 1\ngoose -1 0 1e-1\n";
+
+        let embedding = Embedding::read_glove(glove.as_bytes(), &count_vector).unwrap();
+
+        assert_eq!(embedding.padding_id(), Some(PADDING_ID));
+        assert_eq!(
+            embedding.weights(),
+            arr2(&[
+                [0., 0., 0.],
+                [0., 0., 0.],
+                [0., 0., 0.],
+                [0.1, 0.2, 0.3],
+                [0., 0., 0.],
+                [-1., 0., 0.1],
+            ])
+        );
+    }
+
+    #[test]
+    fn read_glove_errors() {
+        let count_vector = vectorizer();
+
+        assert!(matches!(
+            Embedding::read_glove("duck 0.1 0.2\ngoose 0.1\n".as_bytes(), &count_vector),
+            Err(DuckyError::Persistence(_))
+        ));
+        assert!(matches!(
+            Embedding::read_glove("duck 0.1 quack\n".as_bytes(), &count_vector),
+            Err(DuckyError::Persistence(_))
+        ));
+        assert_eq!(
+            Embedding::read_glove("".as_bytes(), &count_vector).unwrap_err(),
+            DuckyError::EmptyInput
+        );
+        assert!(matches!(
+            Embedding::load_glove("no/such/glove.txt", &count_vector),
+            Err(DuckyError::Persistence(_))
+        ));
+    }
+
+    #[test]
+    fn embedding_write_read() {
+        let embedding = Embedding::new(4, 3, Some(0), Some(2)).unwrap();
+        let mut buffer = Vec::new();
+        embedding.write_to(&mut buffer).unwrap();
+
+        let loaded = Embedding::read_from(buffer.as_slice()).unwrap();
+        assert_eq!(loaded.weights(), embedding.weights());
+        assert_eq!(loaded.padding_id(), Some(0));
+    }
+
+    #[test]
+    fn embedding_train() {
+        let count_vector = vectorizer();
+        let documents = [
+            "good duck".to_string(),
+            "bad goose".to_string(),
+            "duck good".to_string(),
+            "goose bad".to_string(),
+        ];
+        let data = count_vector
+            .transform_sequences(&documents, 2)
+            .mapv(|id| id as f64);
+        let labels = arr2(&[[1.], [-1.], [1.], [-1.]]);
+
+        let model: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(
+                Embedding::new(
+                    count_vector.sequence_vocabulary_size(),
+                    3,
+                    Some(PADDING_ID),
+                    Some(3),
+                )
+                .unwrap(),
+            ),
+            Box::new(GRU::new(3, 4, ReturnMode::Last, Some(4))),
+            Box::new(Dense1d::from(
+                |x| x,
+                |x| x.map(|_| 1f64),
+                Array2::from_elem((1, 4), 0.1),
+                arr1(&[0.]),
+            )),
+        ];
+
+        let costs = train(&model, data.clone(), labels.clone(), data, labels, 100, 0.1).unwrap();
+
+        assert!(costs.iter().all(|cost| cost.is_finite()));
+        assert!(costs[99] < costs[0] / 2.);
+    }
+}
@@ -0,0 +1,205 @@
+use std::error::Error;
+use std::fmt;
+
+/// Error returned when a model or layer is given input it can not work with.
+///
+/// # Variants
+/// - `ShapeMismatch`: The input has a different shape to the one expected, e.g. a different
+///   number of rows in `x` and `y` or a different number of features to what the model was fit on.
+/// - `EmptyInput`: The input has no rows or no features.
+/// - `NonFinite`: The input contains a NaN or infinite value.
+/// - `UnknownClass`: A class label was seen that the model does not know about.
+/// - `NotFitted`: The model has to be fit before it can be used.
+/// - `InvalidParameter`: A parameter of a model or layer is outside of its valid range.
+/// - `Persistence`: A model could not be saved or loaded.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::error::DuckyError;
+/// use ducky_learn::naive_bayes::StdNaiveBayes;
+///
+/// let x: Vec<Vec<f64>> = vec![vec![1.0, 2.0]];
+/// let y: Vec<String> = vec![];
+///
+/// let error = StdNaiveBayes::new(1.0).fit(&x, &y).unwrap_err();
+/// assert_eq!(error, DuckyError::ShapeMismatch { expected: vec![1], found: vec![0] });
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum DuckyError {
+    ShapeMismatch {
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+    EmptyInput,
+    NonFinite,
+    UnknownClass(String),
+    NotFitted,
+    InvalidParameter(String),
+    Persistence(String),
+}
+
+impl fmt::Display for DuckyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuckyError::ShapeMismatch { expected, found } => write!(
+                f,
+                "Shape mismatch, expected shape {:?} but was given shape {:?}",
+                expected, found
+            ),
+            DuckyError::EmptyInput => write!(f, "Empty input"),
+            DuckyError::NonFinite => write!(f, "Input contains NaN or infinite values"),
+            DuckyError::UnknownClass(class) => write!(f, "Unknown class {:?}", class),
+            DuckyError::NotFitted => write!(f, "Model has not been fit"),
+            DuckyError::InvalidParameter(message) => write!(f, "Invalid parameter: {}", message),
+            DuckyError::Persistence(message) => write!(f, "Could not save or load: {}", message),
+        }
+    }
+}
+
+impl Error for DuckyError {}
+
+/// Checks that `x` has at least one row and feature, that every row has `n_features`
+/// values and that all of the values are finite.
+///
+/// # Arguments
+///
+/// * `x`: Rows of data
+/// * `n_features`: Expected length of every row
+///
+/// returns: `Result<(), DuckyError>`
+pub(crate) fn check_rows(x: &[Vec<f64>], n_features: usize) -> Result<(), DuckyError> {
+    if x.is_empty() || n_features == 0 {
+        return Err(DuckyError::EmptyInput);
+    }
+
+    for row in x {
+        if row.len() != n_features {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![n_features],
+                found: vec![row.len()],
+            });
+        }
+        check_finite(row)?;
+    }
+
+    Ok(())
+}
+
+/// Checks that every value is finite.
+///
+/// # Arguments
+///
+/// * `values`: Values to check
+///
+/// returns: `Result<(), DuckyError>`
+pub(crate) fn check_finite<'a, I: IntoIterator<Item = &'a f64>>(
+    values: I,
+) -> Result<(), DuckyError> {
+    if values.into_iter().all(|value| value.is_finite()) {
+        Ok(())
+    } else {
+        Err(DuckyError::NonFinite)
+    }
+}
+
+/// Checks that there is one target per row of data.
+///
+/// # Arguments
+///
+/// * `n_rows`: Number of rows of data
+/// * `n_targets`: Number of targets
+///
+/// returns: `Result<(), DuckyError>`
+pub(crate) fn check_targets(n_rows: usize, n_targets: usize) -> Result<(), DuckyError> {
+    if n_rows != n_targets {
+        return Err(DuckyError::ShapeMismatch {
+            expected: vec![n_rows],
+            found: vec![n_targets],
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks that data has as many features as the model was fit on.
+///
+/// # Arguments
+///
+/// * `n_features`: Number of features the model was fit on
+/// * `found`: Number of features of the data
+///
+/// returns: `Result<(), DuckyError>`
+pub(crate) fn check_n_features(n_features: usize, found: usize) -> Result<(), DuckyError> {
+    if n_features != found {
+        return Err(DuckyError::ShapeMismatch {
+            expected: vec![n_features],
+            found: vec![found],
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn check_rows_ok() {
+        assert_eq!(check_rows(&[vec![1., 2.], vec![3., 4.]], 2), Ok(()));
+    }
+
+    #[test]
+    fn check_rows_empty() {
+        assert_eq!(check_rows(&[], 2), Err(DuckyError::EmptyInput));
+    }
+
+    #[test]
+    fn check_rows_ragged() {
+        assert_eq!(
+            check_rows(&[vec![1., 2.], vec![3.]], 2),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![1]
+            })
+        );
+    }
+
+    #[test]
+    fn check_rows_non_finite() {
+        assert_eq!(
+            check_rows(&[vec![1., f64::NAN]], 2),
+            Err(DuckyError::NonFinite)
+        );
+        assert_eq!(
+            check_rows(&[vec![f64::INFINITY, 1.]], 2),
+            Err(DuckyError::NonFinite)
+        );
+    }
+
+    #[test]
+    fn check_n_features_mismatch() {
+        assert_eq!(check_n_features(3, 3), Ok(()));
+        assert_eq!(
+            check_n_features(3, 2),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![3],
+                found: vec![2]
+            })
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(DuckyError::NotFitted.to_string(), "Model has not been fit");
+        assert_eq!(
+            DuckyError::ShapeMismatch {
+                expected: vec![3],
+                found: vec![2]
+            }
+            .to_string(),
+            "Shape mismatch, expected shape [3] but was given shape [2]"
+        );
+    }
+}
@@ -1,7 +1,14 @@
-use std::collections::HashSet;
+use super::error::DuckyError;
+use super::sparse::{CsrMatrix, FeatureMatrix};
+use super::traits;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 
 /// Struct for converting a collection of text documents to a matrix of token counts.
-/// This implementation produces a sparse representation of the counts using a Vector.
+/// `transform` produces a dense vector of vectors, while `transform_sparse` and the
+/// `traits::Transform` implementation produce a `CsrMatrix` that only stores the words
+/// present in each document, which is what large vocabularies need.
 ///
 /// # Fields
 /// `feature_names`: A vector storing the unique words found across all documents.
@@ -20,8 +27,17 @@ use std::collections::HashSet;
 /// count_vector.fit_transform(&document);
 /// assert_eq!(count_vector.feature_names, vec!["hello", "this", "is", "a", "test", "another"]);
 /// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CountVectorizer {
     pub feature_names: Vec<String>,
+    // Position of every word in `feature_names`, kept in step with it by `fit`
+    vocabulary: HashMap<String, usize>,
+}
+
+impl Default for CountVectorizer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CountVectorizer {
@@ -41,6 +57,38 @@ impl CountVectorizer {
     pub fn new() -> Self {
         Self {
             feature_names: Vec::new(),
+            vocabulary: HashMap::new(),
+        }
+    }
+
+    /// Learns the 'vocabulary' from the input data, i.e. all unique words across all
+    /// documents. Words are appended to `feature_names` in the order they are first seen.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::feature_extraction::CountVectorizer;
+    ///
+    /// let mut count_vector = CountVectorizer::new();
+    /// let document = vec![
+    ///     "hello this is a test".to_string(),
+    ///     "this is another test".to_string(),
+    /// ];
+    /// count_vector.fit(&document);
+    /// assert_eq!(count_vector.feature_names, vec!["hello", "this", "is", "a", "test", "another"]);
+    /// ```
+    pub fn fit(&mut self, input_document: &[String]) {
+        for sentence in input_document {
+            for word in sentence.split(' ') {
+                if !self.vocabulary.contains_key(word) {
+                    self.vocabulary
+                        .insert(word.to_string(), self.feature_names.len());
+                    self.feature_names.push(word.to_string());
+                }
+            }
         }
     }
 
@@ -74,25 +122,17 @@ impl CountVectorizer {
     ///     vec![0.0, 1.0, 1.0, 0.0, 1.0, 1.0],
     /// ]);
     /// ```
-    pub fn fit_transform(&mut self, input_document: &Vec<String>) -> Vec<Vec<f64>> {
-        // Adds words to the feature_names
-        for sentence in input_document {
-            for word in sentence.split(" ") {
-                let word = word.to_string();
-                if !self.feature_names.contains(&word) {
-                    self.feature_names.push(word);
-                }
-            }
-        }
-
+    pub fn fit_transform(&mut self, input_document: &[String]) -> Vec<Vec<f64>> {
+        self.fit(input_document);
         self.transform(input_document)
     }
 
     /// Transforms the data into a matrix of token counts using the learned vocabulary.
     ///
     /// This process involves representing each document as a vector of counts of the
-    /// words in the learned vocabulary. Note that this method does not learn the vocabulary
-    /// and assumes that `fit_transform` has already been called.
+    /// words in the learned vocabulary. Words that are not in the learned vocabulary are
+    /// ignored. Note that this method does not learn the vocabulary and assumes that
+    /// `fit_transform` has already been called.
     ///
     /// # Arguments
     /// * `input_document` - A vector of strings where each string represents a document.
@@ -120,19 +160,843 @@ impl CountVectorizer {
     ///     vec![0.0, 1.0, 0.0, 0.0, 1.0, 1.0],
     /// ]);
     /// ```
-    pub fn transform(&self, input_document: &Vec<String>) -> Vec<Vec<f64>> {
-        let mut count_vector: Vec<Vec<f64>> = Vec::with_capacity(input_document.len());
+    pub fn transform(&self, input_document: &[String]) -> Vec<Vec<f64>> {
+        self.transform_sparse(input_document).to_vec()
+    }
 
-        for (idx, sentence) in input_document.iter().enumerate() {
-            count_vector.push(zeros(self.feature_names.len()));
-            for word in sentence.split(" ") {
-                let word = word.to_string();
-                let position_of_word = self.feature_names.iter().position(|x| x == &word).unwrap();
-                count_vector[idx][position_of_word] += 1f64;
+    /// Same as `fit_transform` but returns the token counts as a `CsrMatrix`, so only
+    /// the words present in each document take up memory.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Returns
+    /// A `CsrMatrix` with a row per document and a column per word in the learned vocabulary.
+    pub fn fit_transform_sparse(&mut self, input_document: &[String]) -> CsrMatrix {
+        self.fit(input_document);
+        self.transform_sparse(input_document)
+    }
+
+    /// Same as `transform` but returns the token counts as a `CsrMatrix`, so only
+    /// the words present in each document take up memory. Words that are not in the
+    /// learned vocabulary are ignored.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Returns
+    /// A `CsrMatrix` with a row per document and a column per word in the learned vocabulary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::feature_extraction::CountVectorizer;
+    ///
+    /// let mut count_vector = CountVectorizer::new();
+    /// count_vector.fit(&vec!["hello this is a test".to_string()]);
+    /// let count_matrix = count_vector.transform_sparse(&vec!["test test a swan".to_string()]);
+    /// assert_eq!(count_matrix.row(0).indices, &[3, 4]);
+    /// assert_eq!(count_matrix.row(0).data, &[1.0, 2.0]);
+    /// ```
+    pub fn transform_sparse(&self, input_document: &[String]) -> CsrMatrix {
+        let mut count_matrix = CsrMatrix::new(self.feature_names.len());
+        for sentence in input_document {
+            count_matrix.push_row(
+                sentence
+                    .split(' ')
+                    .filter_map(|word| self.vocabulary.get(word).map(|&position| (position, 1f64))),
+            );
+        }
+
+        count_matrix
+    }
+
+    /// Number of token IDs `transform_sequences` can produce: one for every word in the
+    /// learned vocabulary plus `PADDING_ID` and `UNKNOWN_ID`. This is the vocabulary size
+    /// an `Embedding` fed from this vectorizer needs.
+    pub fn sequence_vocabulary_size(&self) -> usize {
+        self.feature_names.len() + FIRST_WORD_ID
+    }
+
+    /// Token ID of `word` in the sequences returned by `transform_sequences`, or
+    /// `UNKNOWN_ID` if the word is not in the learned vocabulary.
+    ///
+    /// # Arguments
+    /// * `word` - A single word.
+    pub fn token_id(&self, word: &str) -> usize {
+        self.vocabulary
+            .get(word)
+            .map_or(UNKNOWN_ID, |position| position + FIRST_WORD_ID)
+    }
+
+    /// Transforms the data into sequences of token IDs that keep the order of the words.
+    ///
+    /// The word at position `i` of `feature_names` gets the ID `i + 2`, words that are not in
+    /// the learned vocabulary get `UNKNOWN_ID`, and documents shorter than `max_length` are
+    /// padded at the end with `PADDING_ID`. Longer documents are cut off after `max_length`
+    /// words. Empty tokens, such as those between two spaces, are skipped, so an empty
+    /// document is all padding.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    /// * `max_length` - Number of IDs in every sequence.
+    ///
+    /// # Returns
+    /// An array of shape( documents, max_length ) holding a row of token IDs per document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::feature_extraction::CountVectorizer;
+    /// use ndarray::arr2;
+    ///
+    /// let mut count_vector = CountVectorizer::new();
+    /// count_vector.fit(&vec!["hello this is a test".to_string()]);
+    /// let sequences = count_vector.transform_sequences(
+    ///     &vec!["a  test".to_string(), "this is not a test".to_string()],
+    ///     4,
+    /// );
+    /// assert_eq!(sequences, arr2(&[[5, 6, 0, 0], [3, 4, 1, 5]]));
+    /// ```
+    pub fn transform_sequences(
+        &self,
+        input_document: &[String],
+        max_length: usize,
+    ) -> Array2<usize> {
+        let mut sequences = Array2::from_elem((input_document.len(), max_length), PADDING_ID);
+        for (mut row, sentence) in sequences.outer_iter_mut().zip(input_document) {
+            let words = sentence.split(' ').filter(|word| !word.is_empty());
+            for (id, word) in row.iter_mut().zip(words) {
+                *id = self.token_id(word);
             }
         }
 
-        count_vector
+        sequences
+    }
+}
+
+/// Token ID `CountVectorizer::transform_sequences` pads short documents with.
+pub const PADDING_ID: usize = 0;
+
+/// Token ID `CountVectorizer::transform_sequences` gives words outside the vocabulary.
+pub const UNKNOWN_ID: usize = 1;
+
+/// Token ID `CountVectorizer::transform_sequences` gives the first word of the vocabulary.
+pub const FIRST_WORD_ID: usize = 2;
+
+/// Mask of the real tokens of padded sequences, for recurrent layers.
+///
+/// # Arguments
+/// * `sequences` - Token IDs from `CountVectorizer::transform_sequences`.
+///
+/// # Returns
+/// An array of the same shape as `sequences` that is `false` wherever it holds `PADDING_ID`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::feature_extraction::padding_mask;
+/// use ndarray::arr2;
+///
+/// let mask = padding_mask(&arr2(&[[5, 6, 0, 0], [3, 4, 1, 5]]));
+/// assert_eq!(mask, arr2(&[[true, true, false, false], [true, true, true, true]]));
+/// ```
+pub fn padding_mask(sequences: &Array2<usize>) -> Array2<bool> {
+    sequences.mapv(|id| id != PADDING_ID)
+}
+
+impl traits::Fit<[String], ()> for CountVectorizer {
+    type Fitted = CountVectorizer;
+
+    fn fit(mut self, input_document: &[String], _: &()) -> Result<CountVectorizer, DuckyError> {
+        CountVectorizer::fit(&mut self, input_document);
+        Ok(self)
+    }
+}
+
+impl traits::Transform<[String]> for CountVectorizer {
+    type Output = CsrMatrix;
+
+    fn transform(&self, input_document: &[String]) -> Result<CsrMatrix, DuckyError> {
+        if self.feature_names.is_empty() {
+            return Err(DuckyError::NotFitted);
+        }
+        Ok(self.transform_sparse(input_document))
+    }
+}
+
+/// Norm used to scale each row of a transformed matrix.
+///
+/// * `L1` - The sum of the absolute values of each row will be 1.
+/// * `L2` - The sum of the squares of each row will be 1.
+/// * `Max` - The largest absolute value of each row will be 1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Norm {
+    L1,
+    L2,
+    Max,
+}
+
+impl Norm {
+    /// Norm of a row of values.
+    pub(crate) fn row_norm<'a, I: IntoIterator<Item = &'a f64>>(&self, row: I) -> f64 {
+        let row = row.into_iter();
+        match self {
+            Norm::L1 => row.map(|value| value.abs()).sum::<f64>(),
+            Norm::L2 => row.map(|value| value * value).sum::<f64>().sqrt(),
+            Norm::Max => row.map(|value| value.abs()).fold(0.0, f64::max),
+        }
+    }
+}
+
+/// Struct for converting a matrix of token counts into a normalised tf-idf representation.
+///
+/// Tf means term-frequency while tf-idf means term-frequency times inverse document-frequency.
+/// The idea is to scale down the impact of words that occur very frequently across the corpus
+/// and are therefore less informative than words that occur in a small fraction of it.
+///
+/// # Fields
+/// `norm`: Norm used to scale each row, `None` leaves the rows as they are.
+///
+/// `smooth_idf`: Adds one to the document frequencies, as if an extra document contained
+///     every word once. This prevents divisions by zero.
+///
+/// `sublinear_tf`: Replaces the term-frequency `tf` with `1 + ln(tf)`.
+///
+/// `idf`: The inverse document frequency of each feature, learned by `fit`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::feature_extraction::{CountVectorizer, TfidfTransformer};
+///
+/// let mut count_vector = CountVectorizer::new();
+/// let mut tfidf = TfidfTransformer::default();
+/// let document = vec![
+///     "hello this is a test".to_string(),
+///     "this is another test".to_string(),
+/// ];
+/// let counts = count_vector.fit_transform(&document);
+/// let weighted = tfidf.fit_transform(&counts);
+/// assert_eq!(weighted.len(), 2);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TfidfTransformer {
+    pub norm: Option<Norm>,
+    pub smooth_idf: bool,
+    pub sublinear_tf: bool,
+    pub idf: Vec<f64>,
+}
+
+impl Default for TfidfTransformer {
+    /// Uses an `L2` norm, smoothed idf and plain term-frequencies.
+    fn default() -> Self {
+        Self::new(Some(Norm::L2), true, false)
+    }
+}
+
+impl TfidfTransformer {
+    /// Creates a new instance of `TfidfTransformer` with no learned idf weights.
+    ///
+    /// # Arguments
+    /// * `norm` - Norm used to scale each row, `None` for no scaling.
+    /// * `smooth_idf` - Whether to add one to the document frequencies.
+    /// * `sublinear_tf` - Whether to use `1 + ln(tf)` in place of `tf`.
+    ///
+    /// # Returns
+    /// A new instance of `TfidfTransformer`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::feature_extraction::{Norm, TfidfTransformer};
+    ///
+    /// let tfidf = TfidfTransformer::new(Some(Norm::L1), false, true);
+    /// assert_eq!(tfidf.idf, Vec::<f64>::new());
+    /// ```
+    pub fn new(norm: Option<Norm>, smooth_idf: bool, sublinear_tf: bool) -> Self {
+        Self {
+            norm,
+            smooth_idf,
+            sublinear_tf,
+            idf: Vec::new(),
+        }
+    }
+
+    /// Learns the idf weight of every feature from a matrix of token counts.
+    ///
+    /// With `smooth_idf` the weight is `ln((1 + n) / (1 + df)) + 1`, otherwise it is
+    /// `ln(n / df) + 1`, where `n` is the number of documents and `df` is the number of
+    /// documents that contain the feature.
+    ///
+    /// # Arguments
+    /// * `count_matrix` - A vector of vectors of token counts, such as the output of
+    ///   `CountVectorizer::transform`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::feature_extraction::TfidfTransformer;
+    ///
+    /// let mut tfidf = TfidfTransformer::default();
+    /// tfidf.fit(&vec![vec![1.0, 1.0], vec![0.0, 2.0]]);
+    /// assert_eq!(tfidf.idf[1], 1.0);
+    /// ```
+    pub fn fit<M: FeatureMatrix + ?Sized>(&mut self, count_matrix: &M) {
+        let mut document_frequency = zeros(count_matrix.n_cols());
+
+        for row in 0..count_matrix.n_rows() {
+            for (feature, count) in count_matrix.row_nonzeros(row) {
+                if count > 0.0 {
+                    document_frequency[feature] += 1.0;
+                }
+            }
+        }
+
+        let smooth = self.smooth_idf as i32 as f64;
+        let n_documents = count_matrix.n_rows() as f64 + smooth;
+
+        self.idf = document_frequency
+            .iter()
+            .map(|df| (n_documents / (df + smooth)).ln() + 1.0)
+            .collect();
+    }
+
+    /// Transforms a matrix of token counts into a tf-idf matrix using the learned idf weights.
+    ///
+    /// # Arguments
+    /// * `count_matrix` - A vector of vectors of token counts.
+    ///
+    /// # Returns
+    /// A vector of vectors, where each inner vector is the weighted (and optionally
+    /// normalised) version of the matching row of `count_matrix`.
+    ///
+    /// # Errors
+    /// * `DuckyError::NotFitted` - `fit` has not been called.
+    /// * `DuckyError::ShapeMismatch` - `count_matrix` has a different number of features than
+    ///   the matrix the idf weights were learned from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::feature_extraction::{Norm, TfidfTransformer};
+    ///
+    /// let mut tfidf = TfidfTransformer::new(Some(Norm::L1), true, false);
+    /// tfidf.fit(&vec![vec![1.0, 1.0], vec![1.0, 1.0]]);
+    /// assert_eq!(tfidf.transform(&vec![vec![3.0, 1.0]]).unwrap(), vec![vec![0.75, 0.25]]);
+    /// ```
+    pub fn transform(&self, count_matrix: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, DuckyError> {
+        Ok(self.transform_sparse(count_matrix)?.to_vec())
+    }
+
+    /// Same as `transform` but takes any `FeatureMatrix`, such as the `CsrMatrix` from
+    /// `CountVectorizer::transform_sparse`, and returns a `CsrMatrix`.
+    ///
+    /// # Arguments
+    /// * `count_matrix` - A matrix of token counts.
+    ///
+    /// # Returns
+    /// The tf-idf matrix of `count_matrix` as a `CsrMatrix`.
+    ///
+    /// # Errors
+    /// The same as `transform`.
+    pub fn transform_sparse<M: FeatureMatrix + ?Sized>(
+        &self,
+        count_matrix: &M,
+    ) -> Result<CsrMatrix, DuckyError> {
+        self.check_fitted(count_matrix)?;
+        Ok(self.weight(count_matrix))
+    }
+
+    /// Weights a matrix of token counts with the idf weights, which have to line up with its
+    /// features.
+    fn weight<M: FeatureMatrix + ?Sized>(&self, count_matrix: &M) -> CsrMatrix {
+        let mut tfidf_matrix = CsrMatrix::new(count_matrix.n_cols());
+
+        for row in 0..count_matrix.n_rows() {
+            let weighted_row: BTreeMap<usize, f64> = count_matrix
+                .row_nonzeros(row)
+                .map(|(feature, count)| {
+                    let tf = if self.sublinear_tf && count > 0.0 {
+                        1.0 + count.ln()
+                    } else {
+                        count
+                    };
+                    (feature, tf * self.idf[feature])
+                })
+                .collect();
+
+            push_normalized_row(&mut tfidf_matrix, weighted_row, self.norm);
+        }
+
+        tfidf_matrix
+    }
+
+    /// Learns the idf weights from a matrix of token counts and then transforms it.
+    ///
+    /// # Arguments
+    /// * `count_matrix` - A vector of vectors of token counts.
+    ///
+    /// # Returns
+    /// The tf-idf matrix of `count_matrix`.
+    pub fn fit_transform(&mut self, count_matrix: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        self.fit_transform_sparse(count_matrix).to_vec()
+    }
+
+    /// Same as `fit_transform` but takes any `FeatureMatrix` and returns a `CsrMatrix`.
+    ///
+    /// # Arguments
+    /// * `count_matrix` - A matrix of token counts.
+    ///
+    /// # Returns
+    /// The tf-idf matrix of `count_matrix` as a `CsrMatrix`.
+    pub fn fit_transform_sparse<M: FeatureMatrix + ?Sized>(
+        &mut self,
+        count_matrix: &M,
+    ) -> CsrMatrix {
+        self.fit(count_matrix);
+        self.weight(count_matrix)
+    }
+
+    /// Checks that `fit` has been called and that `count_matrix` has as many features as
+    /// the matrix the idf weights were learned from.
+    fn check_fitted<M: FeatureMatrix + ?Sized>(&self, count_matrix: &M) -> Result<(), DuckyError> {
+        if self.idf.is_empty() {
+            return Err(DuckyError::NotFitted);
+        }
+        if count_matrix.n_cols() != self.idf.len() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![self.idf.len()],
+                found: vec![count_matrix.n_cols()],
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<M: FeatureMatrix + ?Sized> traits::Fit<M, ()> for TfidfTransformer {
+    type Fitted = TfidfTransformer;
+
+    fn fit(mut self, count_matrix: &M, _: &()) -> Result<TfidfTransformer, DuckyError> {
+        count_matrix.validate()?;
+        TfidfTransformer::fit(&mut self, count_matrix);
+        Ok(self)
+    }
+}
+
+impl traits::Transform<[Vec<f64>]> for TfidfTransformer {
+    type Output = Vec<Vec<f64>>;
+
+    fn transform(&self, count_matrix: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, DuckyError> {
+        self.transform(count_matrix)
+    }
+}
+
+impl traits::Transform<Vec<Vec<f64>>> for TfidfTransformer {
+    type Output = Vec<Vec<f64>>;
+
+    fn transform(&self, count_matrix: &Vec<Vec<f64>>) -> Result<Vec<Vec<f64>>, DuckyError> {
+        traits::Transform::transform(self, count_matrix.as_slice())
+    }
+}
+
+impl traits::Transform<CsrMatrix> for TfidfTransformer {
+    type Output = CsrMatrix;
+
+    fn transform(&self, count_matrix: &CsrMatrix) -> Result<CsrMatrix, DuckyError> {
+        self.transform_sparse(count_matrix)
+    }
+}
+
+/// Struct for converting a collection of text documents straight to a matrix of tf-idf
+/// features. Equivalent to a `CountVectorizer` followed by a `TfidfTransformer`. The output
+/// is a `CsrMatrix`, use `transform_dense` for a dense vector of vectors.
+///
+/// # Fields
+/// `count_vectorizer`: Tokenizes and counts the documents, holds the learned `feature_names`.
+///
+/// `tfidf_transformer`: Weights the counts, holds the learned `idf`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::feature_extraction::TfidfVectorizer;
+///
+/// let mut tfidf_vector = TfidfVectorizer::default();
+/// let document = vec![
+///     "hello this is a test".to_string(),
+///     "this is another test".to_string(),
+/// ];
+/// let transformed_document = tfidf_vector.fit_transform(&document);
+/// assert_eq!(
+///     tfidf_vector.count_vectorizer.feature_names,
+///     vec!["hello", "this", "is", "a", "test", "another"]
+/// );
+/// assert_eq!(transformed_document.shape(), (2, 6));
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TfidfVectorizer {
+    pub count_vectorizer: CountVectorizer,
+    pub tfidf_transformer: TfidfTransformer,
+}
+
+impl TfidfVectorizer {
+    /// Creates a new instance of `TfidfVectorizer` with an empty vocabulary.
+    ///
+    /// # Arguments
+    /// * `norm` - Norm used to scale each row, `None` for no scaling.
+    /// * `smooth_idf` - Whether to add one to the document frequencies.
+    /// * `sublinear_tf` - Whether to use `1 + ln(tf)` in place of `tf`.
+    ///
+    /// # Returns
+    /// A new instance of `TfidfVectorizer`.
+    pub fn new(norm: Option<Norm>, smooth_idf: bool, sublinear_tf: bool) -> Self {
+        Self {
+            count_vectorizer: CountVectorizer::new(),
+            tfidf_transformer: TfidfTransformer::new(norm, smooth_idf, sublinear_tf),
+        }
+    }
+
+    /// Learns the vocabulary and the idf weights from the input data.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    pub fn fit(&mut self, input_document: &[String]) {
+        self.fit_transform(input_document);
+    }
+
+    /// Transforms the data into a tf-idf matrix using the learned vocabulary and idf weights.
+    /// Words that are not in the learned vocabulary are ignored.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Returns
+    /// A `CsrMatrix` with a row per document and a column per word in the learned vocabulary.
+    ///
+    /// # Errors
+    /// * `DuckyError::NotFitted` - neither `fit` nor `fit_transform` has been called.
+    /// * `DuckyError::ShapeMismatch` - the vocabulary and the idf weights do not line up.
+    pub fn transform(&self, input_document: &[String]) -> Result<CsrMatrix, DuckyError> {
+        let count_matrix = self.count_vectorizer.transform_sparse(input_document);
+        self.tfidf_transformer.transform_sparse(&count_matrix)
+    }
+
+    /// Learns the vocabulary and the idf weights from the input data and then transforms it.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Returns
+    /// The tf-idf matrix of `input_document` as a `CsrMatrix`.
+    pub fn fit_transform(&mut self, input_document: &[String]) -> CsrMatrix {
+        let count_matrix = self.count_vectorizer.fit_transform_sparse(input_document);
+        self.tfidf_transformer.fit_transform_sparse(&count_matrix)
+    }
+
+    /// Same as `transform` but returns the tf-idf matrix as a dense vector of vectors.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Returns
+    /// A vector of vectors, where each inner vector represents a document and contains
+    /// the tf-idf weight of each word in the learned vocabulary.
+    ///
+    /// # Errors
+    /// The same as `transform`.
+    pub fn transform_dense(&self, input_document: &[String]) -> Result<Vec<Vec<f64>>, DuckyError> {
+        Ok(self.transform(input_document)?.to_vec())
+    }
+
+    /// Same as `fit_transform` but returns the tf-idf matrix as a dense vector of vectors.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Returns
+    /// The tf-idf matrix of `input_document`.
+    pub fn fit_transform_dense(&mut self, input_document: &[String]) -> Vec<Vec<f64>> {
+        self.fit_transform(input_document).to_vec()
+    }
+}
+
+impl traits::Fit<[String], ()> for TfidfVectorizer {
+    type Fitted = TfidfVectorizer;
+
+    fn fit(mut self, input_document: &[String], _: &()) -> Result<TfidfVectorizer, DuckyError> {
+        TfidfVectorizer::fit(&mut self, input_document);
+        Ok(self)
+    }
+}
+
+impl traits::Transform<[String]> for TfidfVectorizer {
+    type Output = CsrMatrix;
+
+    fn transform(&self, input_document: &[String]) -> Result<CsrMatrix, DuckyError> {
+        self.transform(input_document)
+    }
+}
+
+/// Struct for converting a collection of text documents to a matrix of hashed token counts.
+///
+/// Rather than learning a vocabulary, every word is mapped to one of `n_features` columns
+/// using the 32 bit MurmurHash3 of the word. This keeps memory use fixed no matter how many
+/// distinct words are seen and means there is nothing to fit, so separate workers can
+/// vectorize their own shards of a corpus and still agree on the columns. The output is a
+/// `CsrMatrix`, so each document only takes up memory for the columns its words land in.
+///
+/// # Fields
+/// `n_features`: The number of columns in the output matrix, has to be positive.
+///
+/// `alternate_sign`: Adds the sign of the hash to each count, so that words colliding on
+///     the same column tend to cancel out rather than pile up.
+///
+/// `norm`: Norm used to scale each row, `None` leaves the rows as they are.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::feature_extraction::HashingVectorizer;
+///
+/// let hash_vector = HashingVectorizer::new(16, false, None).unwrap();
+/// let document = vec![
+///     "hello this is a test".to_string(),
+///     "this is another test".to_string(),
+/// ];
+/// let transformed_document = hash_vector.transform(&document);
+/// assert_eq!(transformed_document.shape(), (2, 16));
+/// assert_eq!(transformed_document.row(0).data.iter().sum::<f64>(), 5.0);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashingVectorizer {
+    pub n_features: usize,
+    pub alternate_sign: bool,
+    pub norm: Option<Norm>,
+}
+
+impl Default for HashingVectorizer {
+    /// Uses `2^20` features, alternating signs and an `L2` norm.
+    fn default() -> Self {
+        Self {
+            n_features: 1 << 20,
+            alternate_sign: true,
+            norm: Some(Norm::L2),
+        }
+    }
+}
+
+impl HashingVectorizer {
+    /// Creates a new instance of `HashingVectorizer`.
+    ///
+    /// # Arguments
+    /// * `n_features` - The number of columns in the output matrix.
+    /// * `alternate_sign` - Whether to add the sign of the hash to each count.
+    /// * `norm` - Norm used to scale each row, `None` for no scaling.
+    ///
+    /// # Returns
+    /// A new instance of `HashingVectorizer`, or `DuckyError::InvalidParameter` if
+    /// `n_features` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::error::DuckyError;
+    /// use ducky_learn::feature_extraction::HashingVectorizer;
+    ///
+    /// assert!(HashingVectorizer::new(8, true, None).is_ok());
+    /// assert!(matches!(
+    ///     HashingVectorizer::new(0, true, None),
+    ///     Err(DuckyError::InvalidParameter(_))
+    /// ));
+    /// ```
+    pub fn new(
+        n_features: usize,
+        alternate_sign: bool,
+        norm: Option<Norm>,
+    ) -> Result<Self, DuckyError> {
+        let hash_vector = Self {
+            n_features,
+            alternate_sign,
+            norm,
+        };
+        hash_vector.check_n_features()?;
+
+        Ok(hash_vector)
+    }
+
+    /// Transforms the data into a sparse matrix of hashed token counts.
+    ///
+    /// There is no `fit` step, the same words always land in the same columns for a
+    /// given `n_features`.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Returns
+    /// A `CsrMatrix` with a row per document and `n_features` columns.
+    ///
+    /// # Panics
+    /// If `n_features` has been set to 0 after the vectorizer was created. The
+    /// `traits::Transform` implementation returns `DuckyError::InvalidParameter` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::feature_extraction::HashingVectorizer;
+    ///
+    /// let hash_vector = HashingVectorizer::default();
+    /// let shard_1 = hash_vector.transform(&vec!["duck duck goose".to_string()]);
+    /// let shard_2 = hash_vector.transform(&vec!["duck duck goose".to_string()]);
+    /// assert_eq!(shard_1, shard_2);
+    /// assert_eq!(shard_1.nnz(), 2);
+    /// ```
+    pub fn transform(&self, input_document: &[String]) -> CsrMatrix {
+        let mut hash_matrix = CsrMatrix::new(self.n_features);
+
+        for sentence in input_document {
+            let mut row: BTreeMap<usize, f64> = BTreeMap::new();
+            for word in sentence.split(' ') {
+                let (column, sign) = self.hash_word(word);
+                *row.entry(column).or_insert(0.0) += sign;
+            }
+
+            push_normalized_row(&mut hash_matrix, row, self.norm);
+        }
+
+        hash_matrix
+    }
+
+    /// Same as `transform` but returns the hashed counts as a dense vector of vectors.
+    /// Every row holds `n_features` values, so keep `n_features` small when using this.
+    ///
+    /// # Arguments
+    /// * `input_document` - A vector of strings where each string represents a document.
+    ///
+    /// # Returns
+    /// A vector of vectors, where each inner vector represents a document and has
+    /// `n_features` entries.
+    pub fn transform_dense(&self, input_document: &[String]) -> Vec<Vec<f64>> {
+        self.transform(input_document).to_vec()
+    }
+
+    /// Checks `n_features` leaves at least one column to hash words into.
+    fn check_n_features(&self) -> Result<(), DuckyError> {
+        if self.n_features == 0 {
+            return Err(DuckyError::InvalidParameter(
+                "n_features has to be positive, found 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Maps a word to its column and the value to add to that column.
+    fn hash_word(&self, word: &str) -> (usize, f64) {
+        let hash = murmurhash3_32(word.as_bytes(), 0) as i32;
+        let column = hash.unsigned_abs() as usize % self.n_features;
+
+        if self.alternate_sign && hash < 0 {
+            (column, -1.0)
+        } else {
+            (column, 1.0)
+        }
+    }
+}
+
+/// There is nothing to learn, fitting only checks the parameters and returns the vectorizer
+/// unchanged.
+impl traits::Fit<[String], ()> for HashingVectorizer {
+    type Fitted = HashingVectorizer;
+
+    fn fit(self, _: &[String], _: &()) -> Result<HashingVectorizer, DuckyError> {
+        self.check_n_features()?;
+        Ok(self)
+    }
+}
+
+impl traits::Transform<[String]> for HashingVectorizer {
+    type Output = CsrMatrix;
+
+    fn transform(&self, input_document: &[String]) -> Result<CsrMatrix, DuckyError> {
+        self.check_n_features()?;
+        Ok(self.transform(input_document))
+    }
+}
+
+/// 32 bit x86 variant of MurmurHash3.
+///
+/// # Arguments
+/// * `key` - The bytes to hash.
+/// * `seed` - The seed of the hash.
+///
+/// # Returns
+/// The hash of `key`.
+fn murmurhash3_32(key: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+
+    let mut hash = seed;
+    let mut chunks = key.chunks_exact(4);
+
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+        hash ^= k;
+        hash = hash
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe6546b64);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0u32;
+        for (i, byte) in tail.iter().enumerate() {
+            k |= (*byte as u32) << (8 * i);
+        }
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+    }
+
+    hash ^= key.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85ebca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2ae35);
+    hash ^= hash >> 16;
+    hash
+}
+
+/// Helper function that scales a row by `norm` and appends it to a `CsrMatrix`.
+///
+/// # Arguments
+/// * `matrix` - The matrix to append the row to.
+/// * `row` - The row as a map of column to value.
+/// * `norm` - The norm to scale by, `None` for no scaling.
+fn push_normalized_row(matrix: &mut CsrMatrix, row: BTreeMap<usize, f64>, norm: Option<Norm>) {
+    let columns: Vec<usize> = row.keys().cloned().collect();
+    let mut values: Vec<f64> = row.into_values().collect();
+
+    if let Some(norm) = norm {
+        normalize_row(&mut values, norm);
+    }
+    matrix.push_row(columns.into_iter().zip(values));
+}
+
+/// Helper function that scales a row in place so that its norm is 1.
+/// Rows with a norm of 0 are left untouched.
+///
+/// # Arguments
+/// * `row` - The row to scale.
+/// * `norm` - The norm to scale by.
+fn normalize_row(row: &mut [f64], norm: Norm) {
+    let row_norm = norm.row_norm(row.iter());
+
+    if row_norm > 0.0 {
+        for value in row.iter_mut() {
+            *value /= row_norm;
+        }
     }
 }
 
@@ -148,7 +1012,7 @@ fn zeros(size: usize) -> Vec<f64> {
     for i in 0..size {
         zero_vec.push(0.0);
     }
-    return zero_vec;
+    zero_vec
 }
 
 #[cfg(test)]
@@ -214,4 +1078,290 @@ mod feature_extraction_tests {
         assert_eq!(count_vector.fit_transform(&document), transformed_doc);
         assert_eq!(count_vector.feature_names, feature_names)
     }
+
+    #[test]
+    fn test_tfidf_transformer_smooth_idf() {
+        let mut tfidf = TfidfTransformer::new(None, true, false);
+
+        let count_matrix = vec![
+            vec![3.0, 0.0, 1.0],
+            vec![2.0, 0.0, 0.0],
+            vec![3.0, 0.0, 0.0],
+            vec![4.0, 0.0, 0.0],
+        ];
+
+        tfidf.fit(&count_matrix);
+
+        assert!((tfidf.idf[0] - 1.0).abs() < 1e-9);
+        assert!((tfidf.idf[1] - (5f64.ln() + 1.0)).abs() < 1e-9);
+        assert!((tfidf.idf[2] - (2.5f64.ln() + 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tfidf_transformer_l2_norm() {
+        let mut tfidf = TfidfTransformer::new(Some(Norm::L2), false, false);
+
+        let count_matrix = vec![
+            vec![3.0, 0.0, 1.0],
+            vec![2.0, 0.0, 0.0],
+            vec![3.0, 0.0, 0.0],
+            vec![4.0, 0.0, 0.0],
+            vec![3.0, 2.0, 0.0],
+            vec![3.0, 0.0, 2.0],
+        ];
+
+        let transformed = tfidf.fit_transform(&count_matrix);
+
+        // Values from scikit-learn's TfidfTransformer with the same settings
+        let expected = vec![
+            vec![0.81940995, 0.0, 0.57320793],
+            vec![1.0, 0.0, 0.0],
+            vec![1.0, 0.0, 0.0],
+            vec![1.0, 0.0, 0.0],
+            vec![0.47330339, 0.88089948, 0.0],
+            vec![0.58149261, 0.0, 0.81355169],
+        ];
+
+        for (row, expected_row) in transformed.iter().zip(&expected) {
+            for (value, expected_value) in row.iter().zip(expected_row) {
+                assert!((value - expected_value).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tfidf_transformer_sublinear_l1() {
+        let mut tfidf = TfidfTransformer::new(Some(Norm::L1), false, true);
+
+        let count_matrix = vec![vec![1.0, 0.0], vec![1.0, 1.0]];
+
+        let transformed = tfidf.fit_transform(&count_matrix);
+        let row_sum: f64 = transformed[1].iter().sum();
+
+        assert_eq!(transformed[0], vec![1.0, 0.0]);
+        assert!((row_sum - 1.0).abs() < 1e-9);
+        assert!(transformed[1][1] > transformed[1][0]);
+    }
+
+    #[test]
+    fn test_tfidf_vectorizer_matches_pipeline() {
+        let document = vec![
+            "duck duck goose".to_string(),
+            "ducks taste nice".to_string(),
+            "goose taste nice".to_string(),
+        ];
+
+        let mut count_vector = CountVectorizer::new();
+        let mut tfidf = TfidfTransformer::default();
+        let expected = tfidf.fit_transform(&count_vector.fit_transform(&document));
+
+        let mut tfidf_vector = TfidfVectorizer::default();
+
+        assert_eq!(tfidf_vector.fit_transform_dense(&document), expected);
+        assert_eq!(tfidf_vector.transform_dense(&document).unwrap(), expected);
+        assert_eq!(
+            tfidf_vector.count_vectorizer.feature_names,
+            count_vector.feature_names
+        );
+    }
+
+    #[test]
+    fn test_murmurhash3_32() {
+        assert_eq!(murmurhash3_32(b"", 0), 0);
+        assert_eq!(murmurhash3_32(b"", 1), 0x514e28b7);
+        assert_eq!(murmurhash3_32(b"hello", 0), 0x248bfa47);
+        assert_eq!(
+            murmurhash3_32(b"The quick brown fox jumps over the lazy dog", 0),
+            0x2e4ff723
+        );
+    }
+
+    #[test]
+    fn test_hashing_vectorizer_counts() {
+        let hash_vector = HashingVectorizer::new(1 << 10, false, None).unwrap();
+
+        let document = vec!["duck duck goose".to_string(), "goose duck duck".to_string()];
+
+        let transformed_doc = hash_vector.transform_dense(&document);
+        let (duck_column, _) = hash_vector.hash_word("duck");
+        let (goose_column, _) = hash_vector.hash_word("goose");
+
+        assert_eq!(transformed_doc[0], transformed_doc[1]);
+        assert_eq!(transformed_doc[0][duck_column], 2.0);
+        assert_eq!(transformed_doc[0][goose_column], 1.0);
+        assert_eq!(transformed_doc[0].iter().sum::<f64>(), 3.0);
+    }
+
+    #[test]
+    fn test_hashing_vectorizer_alternate_sign() {
+        let signed = HashingVectorizer::new(8, true, None).unwrap();
+        let unsigned = HashingVectorizer::new(8, false, None).unwrap();
+
+        let words = [
+            "hello", "this", "is", "ducky", "duck", "chris", "taste", "nice",
+        ];
+        let mut signs: Vec<f64> = Vec::new();
+
+        for word in words {
+            let document = vec![word.to_string()];
+            let signed_row = &signed.transform_dense(&document)[0];
+            let unsigned_row = &unsigned.transform_dense(&document)[0];
+            let (column, sign) = signed.hash_word(word);
+
+            assert_eq!(unsigned_row[column], 1.0);
+            assert_eq!(signed_row[column], sign);
+            signs.push(sign);
+        }
+
+        assert!(signs.contains(&1.0));
+        assert!(signs.contains(&-1.0));
+    }
+
+    #[test]
+    fn test_hashing_vectorizer_l2_norm() {
+        let hash_vector = HashingVectorizer::new(32, true, Some(Norm::L2)).unwrap();
+
+        let transformed_doc = hash_vector.transform_dense(&["ducks taste nice".to_string()]);
+        let norm: f64 = transformed_doc[0].iter().map(|value| value * value).sum();
+
+        assert!((norm - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_count_vector_sparse_matches_dense() {
+        let mut count_vector = CountVectorizer::new();
+
+        let document = vec![
+            "hello this is ducky duck".to_string(),
+            "duck duck goose".to_string(),
+        ];
+
+        let count_matrix = count_vector.fit_transform_sparse(&document);
+
+        assert_eq!(count_matrix.shape(), (2, 6));
+        assert_eq!(count_matrix.nnz(), 7);
+        assert_eq!(count_matrix.to_vec(), count_vector.transform(&document));
+    }
+
+    #[test]
+    fn test_tfidf_sparse_matches_dense() {
+        let document = vec![
+            "duck duck goose".to_string(),
+            "ducks taste nice".to_string(),
+            "goose taste nice".to_string(),
+        ];
+
+        let mut tfidf_vector = TfidfVectorizer::new(Some(Norm::L2), true, true);
+        let dense = tfidf_vector.fit_transform_dense(&document);
+        let sparse = tfidf_vector.transform(&document).unwrap();
+
+        assert_eq!(sparse.to_vec(), dense);
+        assert_eq!(sparse.nnz(), 8);
+    }
+
+    #[test]
+    fn test_hashing_vectorizer_sparse() {
+        let hash_vector = HashingVectorizer::default();
+
+        let document = vec!["duck duck goose".to_string()];
+        let hash_matrix = hash_vector.transform(&document);
+
+        assert_eq!(hash_matrix.shape(), (1, 1 << 20));
+        assert_eq!(hash_matrix.nnz(), 2);
+        let norm: f64 = hash_matrix.data().iter().map(|value| value * value).sum();
+        assert!((norm - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unseen_words_are_ignored() {
+        use crate::traits::Transform;
+
+        let document = vec!["duck duck goose".to_string()];
+        let new_document = vec!["swan duck".to_string(), "swan".to_string()];
+
+        let mut count_vector = CountVectorizer::new();
+        count_vector.fit(&document);
+        assert_eq!(
+            count_vector.transform(&new_document),
+            vec![vec![1.0, 0.0], vec![0.0, 0.0]]
+        );
+        assert_eq!(
+            Transform::transform(&count_vector, new_document.as_slice()).map(|m| m.to_vec()),
+            Ok(vec![vec![1.0, 0.0], vec![0.0, 0.0]])
+        );
+
+        let mut tfidf_vector = TfidfVectorizer::new(Some(Norm::L1), true, false);
+        tfidf_vector.fit(&document);
+        assert_eq!(
+            Transform::transform(&tfidf_vector, new_document.as_slice()).map(|m| m.to_vec()),
+            Ok(vec![vec![1.0, 0.0], vec![0.0, 0.0]])
+        );
+    }
+
+    #[test]
+    fn test_hashing_vectorizer_zero_features() {
+        use crate::traits::{Fit, Transform};
+
+        assert!(matches!(
+            HashingVectorizer::new(0, false, None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+
+        let hash_vector = HashingVectorizer {
+            n_features: 0,
+            ..HashingVectorizer::default()
+        };
+        let document = vec!["duck".to_string()];
+
+        assert!(matches!(
+            Transform::transform(&hash_vector, document.as_slice()),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            Fit::fit(hash_vector, document.as_slice(), &()),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_transform_trait_errors() {
+        use crate::traits::Transform;
+
+        let mut tfidf = TfidfTransformer::default();
+
+        assert_eq!(
+            Transform::transform(&tfidf, vec![vec![1.0, 2.0]].as_slice()),
+            Err(DuckyError::NotFitted)
+        );
+        assert_eq!(
+            Transform::transform(&CountVectorizer::new(), vec!["duck".to_string()].as_slice()),
+            Err(DuckyError::NotFitted)
+        );
+
+        assert_eq!(
+            tfidf.transform_sparse(&vec![vec![1.0, 2.0]]),
+            Err(DuckyError::NotFitted)
+        );
+        assert_eq!(
+            TfidfVectorizer::default().transform(&["duck".to_string()]),
+            Err(DuckyError::NotFitted)
+        );
+
+        tfidf.fit(&vec![vec![1.0, 2.0]]);
+
+        assert_eq!(
+            tfidf.transform(&[vec![1.0, 2.0, 3.0]]),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![3]
+            })
+        );
+        assert_eq!(
+            Transform::transform(&tfidf, vec![vec![1.0, 2.0, 3.0]].as_slice()),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![3]
+            })
+        );
+    }
 }
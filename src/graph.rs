@@ -0,0 +1,860 @@
+use super::cost::Loss;
+use super::error::{check_targets, DuckyError};
+use super::layers::{Layer1d, Mode};
+use ndarray::{concatenate, s, Array1, Array2, ArrayView1, Axis};
+use serde::{Deserialize, Serialize};
+
+/// Handle to a node of a `Graph`, returned when the node is added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Position of the node in the graph, which is also its place in the topological order.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum Node {
+    Input(usize),
+    Layer(Box<dyn Layer1d>, NodeId),
+    Add(Vec<NodeId>),
+    Concat(Vec<NodeId>),
+}
+
+/// Model made of layers wired together as a directed acyclic graph instead of a chain, so it
+/// can hold residual connections, concatenations, several inputs and several outputs.
+///
+/// Every node can only take nodes that were added before it as parents, so the order the
+/// nodes are added in is a topological order. The forward pass runs the nodes in that order
+/// and the backward pass runs them in reverse, summing the gradients of nodes that feed into
+/// more than one other node.
+///
+/// A graph can be saved with `traits::Persist` when its `Dense1d` layers have named
+/// activations, the state of every layer included.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::activations::*;
+/// use ducky_learn::graph::Graph;
+/// use ducky_learn::layers::*;
+/// use ndarray::arr1;
+///
+/// let mut graph = Graph::new();
+/// let x = graph.input(2);
+/// let hidden = graph.layer(Dense1d::new(2, 2, relu_1d, deriv_relu_1d), x).unwrap();
+/// let residual = graph.add(&[x, hidden]).unwrap();
+/// let softmax = Dense1d::new_auto(2, 3, |z| z.softmax(0).unwrap(), None);
+/// let class = graph.layer(softmax, residual).unwrap();
+/// let value = graph.layer(Dense1d::new(2, 1, |x| x, |x| x.map(|_| 1.)), residual).unwrap();
+/// graph.output(class).unwrap();
+/// graph.output(value).unwrap();
+///
+/// let outputs = graph.pass(&[arr1(&[1., 2.])], Mode::Eval).unwrap();
+///
+/// assert_eq!(outputs[0].len(), 3);
+/// assert_eq!(outputs[1].len(), 1);
+/// ```
+#[derive(Default, Serialize, Deserialize)]
+pub struct Graph {
+    nodes: Vec<Node>,
+    inputs: Vec<NodeId>,
+    outputs: Vec<NodeId>,
+    // Loss and weight of every output, in the same order as `outputs`
+    losses: Vec<(Loss, f64)>,
+}
+
+impl Graph {
+    /// Create a graph without any nodes
+    ///
+    /// returns: `Graph`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of nodes in the graph
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the graph has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Input nodes of the graph, in the order their data is passed in
+    pub fn inputs(&self) -> &[NodeId] {
+        &self.inputs
+    }
+
+    /// Output nodes of the graph, in the order their results are returned
+    pub fn outputs(&self) -> &[NodeId] {
+        &self.outputs
+    }
+
+    /// Adds an input to the graph. Inputs are passed to `pass` in the order they were added.
+    ///
+    /// # Arguments
+    ///
+    /// * `size`: Length of the 1d arrays the input takes
+    ///
+    /// returns: `NodeId` of the input
+    pub fn input(&mut self, size: usize) -> NodeId {
+        let id = self.push(Node::Input(size));
+        self.inputs.push(id);
+        id
+    }
+
+    /// Adds a layer fed by the output of `parent`.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer`: Layer to run on the output of `parent`
+    /// * `parent`: Node the layer takes its input from
+    ///
+    /// returns: `Result<NodeId, DuckyError>` holding the node of the layer
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `parent` is not a node of the graph
+    pub fn layer<L: Layer1d + 'static>(
+        &mut self,
+        layer: L,
+        parent: NodeId,
+    ) -> Result<NodeId, DuckyError> {
+        self.check_parents(&[parent])?;
+        Ok(self.push(Node::Layer(Box::new(layer), parent)))
+    }
+
+    /// Adds a node that sums the outputs of `parents` element wise, as in a residual connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `parents`: Nodes whose outputs all have the same length
+    ///
+    /// returns: `Result<NodeId, DuckyError>` holding the node of the sum
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::EmptyInput`: `parents` is empty
+    /// * `DuckyError::InvalidParameter`: one of `parents` is not a node of the graph
+    pub fn add(&mut self, parents: &[NodeId]) -> Result<NodeId, DuckyError> {
+        self.check_parents(parents)?;
+        Ok(self.push(Node::Add(parents.to_vec())))
+    }
+
+    /// Adds a node that joins the outputs of `parents` end to end.
+    ///
+    /// # Arguments
+    ///
+    /// * `parents`: Nodes to join, in order
+    ///
+    /// returns: `Result<NodeId, DuckyError>` holding the node of the concatenation
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::EmptyInput`: `parents` is empty
+    /// * `DuckyError::InvalidParameter`: one of `parents` is not a node of the graph
+    pub fn concat(&mut self, parents: &[NodeId]) -> Result<NodeId, DuckyError> {
+        self.check_parents(parents)?;
+        Ok(self.push(Node::Concat(parents.to_vec())))
+    }
+
+    /// Marks `node` as an output of the graph, trained with the mean squared error. Outputs are
+    /// returned in the order they were marked.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `node` is not a node of the graph
+    pub fn output(&mut self, node: NodeId) -> Result<(), DuckyError> {
+        self.output_with_loss(node, Loss::MeanSquaredError, 1.)
+    }
+
+    /// Marks `node` as an output of the graph, trained with its own loss. Outputs are returned
+    /// in the order they were marked.
+    ///
+    /// # Arguments
+    ///
+    /// * `node`: Node to output
+    /// * `loss`: Loss `train` minimises for this output. `Loss::CrossEntropy` needs an output
+    ///   holding probabilities, such as a `Dense1d::new_auto` layer activated by
+    ///   `Tensor::softmax`, whose full Jacobian the tape applies to the gradient
+    /// * `weight`: What the loss is multiplied by in the total cost, so one output can matter
+    ///   more than another
+    ///
+    /// returns: `Result<(), DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `node` is not a node of the graph or `weight` is
+    ///   negative, NaN or infinite
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::cost::Loss;
+    /// use ducky_learn::graph::Graph;
+    /// use ducky_learn::layers::*;
+    ///
+    /// let mut graph = Graph::new();
+    /// let x = graph.input(2);
+    /// let softmax = Dense1d::new_auto(2, 3, |z| z.softmax(0).unwrap(), None);
+    /// let class = graph.layer(softmax, x).unwrap();
+    /// let value = graph.layer(Dense1d::new(2, 1, |x| x, |x| x.map(|_| 1.)), x).unwrap();
+    ///
+    /// graph.output_with_loss(class, Loss::CrossEntropy, 1.).unwrap();
+    /// graph.output_with_loss(value, Loss::MeanSquaredError, 0.5).unwrap();
+    ///
+    /// assert!(graph.output_with_loss(value, Loss::MeanSquaredError, -1.).is_err());
+    /// ```
+    pub fn output_with_loss(
+        &mut self,
+        node: NodeId,
+        loss: Loss,
+        weight: f64,
+    ) -> Result<(), DuckyError> {
+        self.check_parents(&[node])?;
+        if !(weight >= 0. && weight.is_finite()) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "loss weight has to be finite and not negative, found {}",
+                weight
+            )));
+        }
+        self.outputs.push(node);
+        self.losses.push((loss, weight));
+        Ok(())
+    }
+
+    /// Amount the regularizers of every layer in the graph add to the cost
+    pub fn penalty(&self) -> f64 {
+        self.nodes
+            .iter()
+            .map(|node| match node {
+                Node::Layer(layer, _) => layer.penalty(),
+                _ => 0.,
+            })
+            .sum()
+    }
+
+    fn push(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    fn check_parents(&self, parents: &[NodeId]) -> Result<(), DuckyError> {
+        if parents.is_empty() {
+            return Err(DuckyError::EmptyInput);
+        }
+        match parents.iter().find(|parent| parent.0 >= self.nodes.len()) {
+            Some(parent) => Err(DuckyError::InvalidParameter(format!(
+                "node {} is not in a graph of {} nodes",
+                parent.0,
+                self.nodes.len()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Feeds the inputs forward through the graph and returns its outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs`: One 1d array per input of the graph, in the order the inputs were added
+    /// * `mode`: Whether the graph is being trained or used for inference
+    ///
+    /// returns: `Result<Vec<Array1<f64>>, DuckyError>` holding one array per output
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the inputs do not line up with the graph or its layers
+    /// * `DuckyError::NonFinite`: an input contains a NaN or infinite value
+    pub fn pass(&self, inputs: &[Array1<f64>], mode: Mode) -> Result<Vec<Array1<f64>>, DuckyError> {
+        let (_, activation_vec) = self.forward_pass(inputs, mode)?;
+        Ok(self
+            .outputs
+            .iter()
+            .map(|output| activation_vec[output.0].clone())
+            .collect())
+    }
+
+    /// Feeds the inputs forward through every node of the graph in topological order.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs`: One 1d array per input of the graph, in the order the inputs were added
+    /// * `mode`: Whether the graph is being trained or used for inference
+    ///
+    /// returns: `Result<(Vec<Array1<f64>>, Vec<Array1<f64>>), DuckyError>` holding the weighted
+    /// input and the output of every node, indexed by `NodeId::index`. Nodes without a layer
+    /// use their output as their weighted input.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the inputs do not line up with the graph or its layers
+    /// * `DuckyError::NonFinite`: an input contains a NaN or infinite value
+    #[allow(clippy::type_complexity)]
+    pub fn forward_pass(
+        &self,
+        inputs: &[Array1<f64>],
+        mode: Mode,
+    ) -> Result<(Vec<Array1<f64>>, Vec<Array1<f64>>), DuckyError> {
+        check_targets(self.inputs.len(), inputs.len())?;
+
+        let mut weights_bias_vec: Vec<Array1<f64>> = Vec::with_capacity(self.nodes.len());
+        let mut activation_vec: Vec<Array1<f64>> = Vec::with_capacity(self.nodes.len());
+        let mut next_input = inputs.iter();
+
+        for node in self.nodes.iter() {
+            let (weight_pass, activation_pass) = match node {
+                Node::Input(size) => {
+                    let input = next_input.next().ok_or(DuckyError::EmptyInput)?;
+                    check_targets(*size, input.len())?;
+                    if input.iter().any(|value| !value.is_finite()) {
+                        return Err(DuckyError::NonFinite);
+                    }
+                    (input.clone(), input.clone())
+                }
+                Node::Layer(layer, parent) => layer.pass(activation_vec[parent.0].clone(), mode)?,
+                Node::Add(parents) => {
+                    let mut sum = activation_vec[parents[0].0].clone();
+                    for parent in &parents[1..] {
+                        check_targets(sum.len(), activation_vec[parent.0].len())?;
+                        sum += &activation_vec[parent.0];
+                    }
+                    (sum.clone(), sum)
+                }
+                Node::Concat(parents) => {
+                    let views: Vec<ArrayView1<f64>> = parents
+                        .iter()
+                        .map(|parent| activation_vec[parent.0].view())
+                        .collect();
+                    let joined = concatenate(Axis(0), &views).unwrap_or_else(|_| Array1::zeros(0));
+                    (joined.clone(), joined)
+                }
+            };
+
+            weights_bias_vec.push(weight_pass);
+            activation_vec.push(activation_pass);
+        }
+
+        Ok((weights_bias_vec, activation_vec))
+    }
+
+    /// Passes the gradients of the cost with respect to the outputs back through the graph in
+    /// reverse topological order, taking one gradient descent step on every layer that leads to
+    /// an output.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights_bias_vec`: Weighted inputs of each node, as returned by `forward_pass`
+    /// * `activation_vec`: Outputs of each node, as returned by `forward_pass`
+    /// * `output_gradients`: Gradient of the cost with respect to each output of the graph
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Vec<Array1<f64>>, DuckyError>` holding the gradient of the cost with
+    /// respect to each input of the graph
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the forward pass or the gradients do not line up with the
+    ///   graph
+    pub fn back_propagation(
+        &self,
+        weights_bias_vec: &[Array1<f64>],
+        activation_vec: &[Array1<f64>],
+        output_gradients: &[Array1<f64>],
+        learning_rate: f64,
+    ) -> Result<Vec<Array1<f64>>, DuckyError> {
+        check_targets(self.nodes.len(), weights_bias_vec.len())?;
+        check_targets(self.nodes.len(), activation_vec.len())?;
+        check_targets(self.outputs.len(), output_gradients.len())?;
+
+        let mut gradients: Vec<Option<Array1<f64>>> = vec![None; self.nodes.len()];
+        for (output, gradient) in self.outputs.iter().zip(output_gradients) {
+            check_targets(activation_vec[output.0].len(), gradient.len())?;
+            accumulate(&mut gradients[output.0], gradient.clone());
+        }
+
+        for (index, node) in self.nodes.iter().enumerate().rev() {
+            // Nodes that do not lead to an output have nothing to pass back
+            let Some(gradient) = gradients[index].take() else {
+                continue;
+            };
+            match node {
+                Node::Input(_) => gradients[index] = Some(gradient),
+                Node::Layer(layer, parent) => {
+                    let input_gradient = layer.back_pass(
+                        &activation_vec[parent.0],
+                        &weights_bias_vec[index],
+                        &gradient,
+                        learning_rate,
+                    )?;
+                    accumulate(&mut gradients[parent.0], input_gradient);
+                }
+                Node::Add(parents) => {
+                    for parent in parents {
+                        accumulate(&mut gradients[parent.0], gradient.clone());
+                    }
+                }
+                Node::Concat(parents) => {
+                    let mut start = 0;
+                    for parent in parents {
+                        let end = start + activation_vec[parent.0].len();
+                        accumulate(
+                            &mut gradients[parent.0],
+                            gradient.slice(s![start..end]).to_owned(),
+                        );
+                        start = end;
+                    }
+                }
+            }
+        }
+
+        Ok(self
+            .inputs
+            .iter()
+            .map(|input| {
+                gradients[input.0]
+                    .take()
+                    .unwrap_or_else(|| Array1::zeros(activation_vec[input.0].len()))
+            })
+            .collect())
+    }
+
+    /// Trains the graph with stochastic gradient descent on the weighted sum of the losses of
+    /// its outputs, set by `output` or `output_with_loss`, one row of the training data at a
+    /// time, and scores it on the test data after every epoch.
+    ///
+    /// # Arguments
+    ///
+    /// * `train_data`: Training data for each input, of shape( samples, input size )
+    /// * `train_lbl`: Target outputs of the training data for each output, of shape( samples,
+    ///   output size )
+    /// * `test_data`: Test data for each input, of shape( samples, input size )
+    /// * `test_lbl`: Target outputs of the test data for each output, of shape( samples,
+    ///   output size )
+    /// * `epochs`: Number of passes over the training data
+    /// * `learning_rate`: Size of each gradient descent step
+    ///
+    /// returns: `Result<Vec<f64>, DuckyError>` holding the weighted cost on the test data, plus the
+    /// penalties of any regularized layers, after each epoch
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the data and labels do not line up with each other or the
+    ///   graph
+    /// * `DuckyError::NonFinite`: the data contains a NaN or infinite value
+    /// * `DuckyError::InvalidParameter`: `learning_rate` is not positive
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::graph::Graph;
+    /// use ducky_learn::layers::*;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// let mut graph = Graph::new();
+    /// let x = graph.input(1);
+    /// let y = graph.layer(Dense1d::from(|x| x, |x| x.map(|_| 1.), arr2(&[[0.]]), arr1(&[0.])), x);
+    /// graph.output(y.unwrap()).unwrap();
+    ///
+    /// let data = vec![arr2(&[[1.], [2.]])];
+    /// let labels = vec![arr2(&[[2.], [4.]])];
+    ///
+    /// let costs = graph.train(&data, &labels, &data, &labels, 20, 0.05).unwrap();
+    ///
+    /// assert!(costs.last().unwrap() < &costs[0]);
+    /// ```
+    pub fn train(
+        &self,
+        train_data: &[Array2<f64>],
+        train_lbl: &[Array2<f64>],
+        test_data: &[Array2<f64>],
+        test_lbl: &[Array2<f64>],
+        epochs: usize,
+        learning_rate: f64,
+    ) -> Result<Vec<f64>, DuckyError> {
+        if !(learning_rate > 0.0 && learning_rate.is_finite()) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "learning_rate has to be positive, found {}",
+                learning_rate
+            )));
+        }
+        let train_rows = self.check_data(train_data, train_lbl)?;
+        let test_rows = self.check_data(test_data, test_lbl)?;
+
+        let mut costs = Vec::with_capacity(epochs);
+        for _ in 0..epochs {
+            for row in 0..train_rows {
+                let (inputs, targets) = rows(train_data, train_lbl, row);
+                let (weights_bias_vec, activation_vec) = self.forward_pass(&inputs, Mode::Train)?;
+                let output_gradients: Vec<Array1<f64>> = self
+                    .outputs
+                    .iter()
+                    .zip(&self.losses)
+                    .zip(targets)
+                    .map(|((output, (loss, weight)), target)| {
+                        loss.deriv(target, activation_vec[output.0].clone()) * *weight
+                    })
+                    .collect();
+                self.back_propagation(
+                    &weights_bias_vec,
+                    &activation_vec,
+                    &output_gradients,
+                    learning_rate,
+                )?;
+            }
+
+            let mut cost = 0.0;
+            for row in 0..test_rows {
+                let (inputs, targets) = rows(test_data, test_lbl, row);
+                let outputs = self.pass(&inputs, Mode::Eval)?;
+                for ((output, (loss, weight)), target) in
+                    outputs.iter().zip(&self.losses).zip(&targets)
+                {
+                    cost += weight * loss.cost(target, output);
+                }
+            }
+            costs.push(cost / test_rows.max(1) as f64 + self.penalty());
+        }
+
+        Ok(costs)
+    }
+
+    /// Checks there is one array per input and output, that they all have the same number of
+    /// rows and that the labels line up with the outputs, returning the number of rows.
+    fn check_data(
+        &self,
+        data: &[Array2<f64>],
+        labels: &[Array2<f64>],
+    ) -> Result<usize, DuckyError> {
+        check_targets(self.inputs.len(), data.len())?;
+        check_targets(self.outputs.len(), labels.len())?;
+
+        let n_rows = data
+            .iter()
+            .chain(labels)
+            .map(|array| array.nrows())
+            .next()
+            .unwrap_or(0);
+        for array in data.iter().chain(labels) {
+            check_targets(n_rows, array.nrows())?;
+        }
+
+        Ok(n_rows)
+    }
+}
+
+/// Adds `gradient` to the gradient already collected for a node.
+fn accumulate(total: &mut Option<Array1<f64>>, gradient: Array1<f64>) {
+    match total {
+        Some(total) => *total += &gradient,
+        None => *total = Some(gradient),
+    }
+}
+
+/// Takes row `row` of every input and label array.
+fn rows(
+    data: &[Array2<f64>],
+    labels: &[Array2<f64>],
+    row: usize,
+) -> (Vec<Array1<f64>>, Vec<Array1<f64>>) {
+    (
+        data.iter().map(|array| array.row(row).to_owned()).collect(),
+        labels
+            .iter()
+            .map(|array| array.row(row).to_owned())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod graph_tests {
+    use super::*;
+    use crate::activations::*;
+    use crate::cost::deriv_mean_squared_error;
+    use crate::layers::*;
+    use crate::train::{back_propagation, forward_pass};
+    use ndarray::{arr1, arr2};
+
+    fn identity_layer(weights: Array2<f64>, bias: Array1<f64>) -> Dense1d {
+        Dense1d::from(|x| x, |x| x.map(|_| 1f64), weights, bias)
+    }
+
+    #[test]
+    fn chain_matches_train() {
+        let chain = vec![
+            identity_layer(arr2(&[[1., 2.], [0.5, -1.]]), arr1(&[0.1, 0.])),
+            identity_layer(arr2(&[[1., -1.]]), arr1(&[0.2])),
+        ];
+        let mut graph = Graph::new();
+        let x = graph.input(2);
+        let hidden = graph
+            .layer(
+                identity_layer(arr2(&[[1., 2.], [0.5, -1.]]), arr1(&[0.1, 0.])),
+                x,
+            )
+            .unwrap();
+        let output = graph
+            .layer(identity_layer(arr2(&[[1., -1.]]), arr1(&[0.2])), hidden)
+            .unwrap();
+        graph.output(output).unwrap();
+
+        let data = arr1(&[1., -2.]);
+        let target = arr1(&[0.5]);
+        let (z, a) = forward_pass(&chain, data.clone(), Mode::Train).unwrap();
+        let expected = back_propagation(&chain, data.clone(), z, a.clone(), target.clone(), 0.1);
+
+        let (z, graph_a) = graph
+            .forward_pass(std::slice::from_ref(&data), Mode::Train)
+            .unwrap();
+        assert_eq!(graph_a[output.index()], a[1]);
+        let gradient = deriv_mean_squared_error(target, graph_a[output.index()].clone());
+        let found = graph
+            .back_propagation(&z, &graph_a, &[gradient], 0.1)
+            .unwrap();
+
+        assert_eq!(found, vec![expected.unwrap()]);
+        assert_eq!(
+            graph.pass(std::slice::from_ref(&data), Mode::Eval).unwrap(),
+            vec![forward_pass(&chain, data, Mode::Eval).unwrap().1[1].clone()]
+        );
+    }
+
+    #[test]
+    fn residual_add() {
+        let mut graph = Graph::new();
+        let x = graph.input(2);
+        let hidden = graph
+            .layer(
+                identity_layer(arr2(&[[2., 0.], [0., 3.]]), arr1(&[0., 0.])),
+                x,
+            )
+            .unwrap();
+        let sum = graph.add(&[x, hidden]).unwrap();
+        graph.output(sum).unwrap();
+
+        let (z, a) = graph.forward_pass(&[arr1(&[1., 1.])], Mode::Train).unwrap();
+        assert_eq!(a[sum.index()], arr1(&[3., 4.]));
+
+        // The skip connection passes the gradient straight through on top of the layer's
+        let gradient = graph
+            .back_propagation(&z, &a, &[arr1(&[1., 1.])], 1e-12)
+            .unwrap();
+        assert_eq!(gradient, vec![arr1(&[3., 4.])]);
+    }
+
+    #[test]
+    fn concat_splits_gradient() {
+        let mut graph = Graph::new();
+        let left = graph.input(1);
+        let right = graph.input(2);
+        let scaled = graph
+            .layer(identity_layer(arr2(&[[2.]]), arr1(&[0.])), left)
+            .unwrap();
+        let joined = graph.concat(&[scaled, right]).unwrap();
+        graph.output(joined).unwrap();
+
+        let inputs = [arr1(&[1.]), arr1(&[5., 6.])];
+        let (z, a) = graph.forward_pass(&inputs, Mode::Train).unwrap();
+        assert_eq!(a[joined.index()], arr1(&[2., 5., 6.]));
+
+        let gradient = graph
+            .back_propagation(&z, &a, &[arr1(&[1., 2., 3.])], 1e-12)
+            .unwrap();
+        assert_eq!(gradient, vec![arr1(&[2.]), arr1(&[2., 3.])]);
+    }
+
+    #[test]
+    fn unused_input_gets_zero_gradient() {
+        let mut graph = Graph::new();
+        let x = graph.input(1);
+        graph.input(2);
+        graph.output(x).unwrap();
+
+        let inputs = [arr1(&[1.]), arr1(&[1., 1.])];
+        let (z, a) = graph.forward_pass(&inputs, Mode::Train).unwrap();
+        let gradient = graph.back_propagation(&z, &a, &[arr1(&[4.])], 0.1).unwrap();
+
+        assert_eq!(gradient, vec![arr1(&[4.]), arr1(&[0., 0.])]);
+    }
+
+    #[test]
+    fn train_two_heads() {
+        let mut graph = Graph::new();
+        let x = graph.input(2);
+        let hidden = graph
+            .layer(Dense1d::new(2, 8, relu_1d, deriv_relu_1d), x)
+            .unwrap();
+        let class = graph
+            .layer(Dense1d::new(8, 2, |x| x, |x| x.map(|_| 1.)), hidden)
+            .unwrap();
+        let value = graph
+            .layer(Dense1d::new(8, 1, |x| x, |x| x.map(|_| 1.)), hidden)
+            .unwrap();
+        graph.output(class).unwrap();
+        graph.output(value).unwrap();
+
+        let data = vec![arr2(&[[0., 0.], [0., 1.], [1., 0.], [1., 1.]])];
+        let labels = vec![
+            arr2(&[[1., 0.], [0., 1.], [0., 1.], [1., 0.]]),
+            arr2(&[[0.], [0.5], [0.5], [1.]]),
+        ];
+
+        let costs = graph
+            .train(&data, &labels, &data, &labels, 200, 0.1)
+            .unwrap();
+
+        assert_eq!(costs.len(), 200);
+        assert!(costs.last().unwrap() < &costs[0]);
+    }
+
+    #[test]
+    fn train_losses_and_weights() {
+        let mut graph = Graph::new();
+        let x = graph.input(2);
+        let class = graph
+            .layer(
+                Dense1d::new_auto(2, 2, |z| z.softmax(0).unwrap(), Some(3)),
+                x,
+            )
+            .unwrap();
+        let value = graph
+            .layer(identity_layer(arr2(&[[1., 1.]]), arr1(&[0.])), x)
+            .unwrap();
+        graph
+            .output_with_loss(class, Loss::CrossEntropy, 1.)
+            .unwrap();
+        graph
+            .output_with_loss(value, Loss::MeanSquaredError, 0.)
+            .unwrap();
+
+        let data = vec![arr2(&[[1., 0.], [0., 1.]])];
+        let labels = vec![arr2(&[[1., 0.], [0., 1.]]), arr2(&[[5.], [5.]])];
+        let costs = graph
+            .train(&data, &labels, &data, &labels, 100, 0.1)
+            .unwrap();
+
+        assert!(costs.last().unwrap() < &costs[0]);
+        // An output with a weight of zero is not trained
+        let outputs = graph.pass(&[arr1(&[1., 0.])], Mode::Eval).unwrap();
+        assert_eq!(outputs[1], arr1(&[1.]));
+        assert!(outputs[0][0] > 0.5);
+
+        assert!(matches!(
+            graph.output_with_loss(class, Loss::CrossEntropy, f64::NAN),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn cross_entropy_softmax_head_gradient() {
+        // Through the full softmax Jacobian the gradient of the cross-entropy with respect to
+        // the weighted input is the prediction minus the target
+        let weights = arr2(&[[0.5, -1.], [1., 0.25], [-0.5, 0.5]]);
+        let layer = Dense1d::from_auto(|z| z.softmax(0).unwrap(), weights.clone(), arr1(&[0.; 3]));
+        let target = arr1(&[0., 1., 0.]);
+
+        let (z, a) = layer.pass(arr1(&[1., 2.]), Mode::Train).unwrap();
+        let output_gradient = Loss::CrossEntropy.deriv(target.clone(), a.clone());
+        let input_gradient = layer
+            .back_pass(&arr1(&[1., 2.]), &z, &output_gradient, 0.1)
+            .unwrap();
+
+        let expected = weights.t().dot(&(&a - &target));
+        assert!(input_gradient
+            .iter()
+            .zip(&expected)
+            .all(|(value, expected)| (value - expected).abs() < 1e-9));
+    }
+
+    #[test]
+    fn graph_write_read() {
+        use crate::activations::Activation;
+        use crate::traits::Persist;
+
+        let mut graph = Graph::new();
+        let x = graph.input(2);
+        let hidden = graph
+            .layer(Dense1d::new_named(2, 2, Activation::Tanh, Some(1)), x)
+            .unwrap();
+        let normalized = graph
+            .layer(BatchNorm1d::new(2, 0.1, 1e-5).unwrap(), hidden)
+            .unwrap();
+        let sum = graph.add(&[x, normalized]).unwrap();
+        let class = graph
+            .layer(Dense1d::new_named(2, 2, Activation::Softmax, Some(2)), sum)
+            .unwrap();
+        graph
+            .output_with_loss(class, Loss::CrossEntropy, 2.)
+            .unwrap();
+
+        let data = vec![arr2(&[[1., 0.], [0., 1.]])];
+        let labels = vec![arr2(&[[1., 0.], [0., 1.]])];
+        graph.train(&data, &labels, &data, &labels, 5, 0.1).unwrap();
+
+        let mut saved = Vec::new();
+        graph.write_to(&mut saved).unwrap();
+        let loaded = Graph::read_from(saved.as_slice()).unwrap();
+
+        let inputs = [arr1(&[0.5, -1.])];
+        assert_eq!(loaded.len(), graph.len());
+        assert_eq!(
+            loaded.pass(&inputs, Mode::Eval).unwrap(),
+            graph.pass(&inputs, Mode::Eval).unwrap()
+        );
+        // The losses are saved too
+        assert_eq!(
+            loaded
+                .train(&data, &labels, &data, &labels, 1, 0.1)
+                .unwrap(),
+            graph.train(&data, &labels, &data, &labels, 1, 0.1).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut graph = Graph::new();
+        let x = graph.input(2);
+
+        assert_eq!(graph.add(&[]), Err(DuckyError::EmptyInput));
+        assert!(matches!(
+            graph.concat(&[x, NodeId(5)]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(graph.output(NodeId(1)).is_err());
+
+        let short = graph.input(1);
+        let sum = graph.add(&[x, short]).unwrap();
+        graph.output(sum).unwrap();
+
+        assert_eq!(
+            graph.pass(&[arr1(&[1., 1.])], Mode::Eval),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![1]
+            })
+        );
+        assert_eq!(
+            graph.pass(&[arr1(&[1., 1.]), arr1(&[1.])], Mode::Eval),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![1]
+            })
+        );
+        assert_eq!(
+            graph.pass(&[arr1(&[f64::NAN, 1.]), arr1(&[1.])], Mode::Eval),
+            Err(DuckyError::NonFinite)
+        );
+
+        let data = vec![arr2(&[[1., 1.]]), arr2(&[[1.]])];
+        let labels = vec![arr2(&[[1., 1.]])];
+        assert!(matches!(
+            graph.train(&data, &labels, &data, &labels, 1, 0.),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(graph.train(&data, &[], &data, &[], 1, 0.1).is_err());
+    }
+}
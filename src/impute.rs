@@ -0,0 +1,638 @@
+extern crate ndarray;
+
+use super::error::{check_n_features, DuckyError};
+use super::preprocessing::{quantile, sorted_values};
+use super::traits;
+use super::util::{Fit, Unfit};
+use ndarray::{Array1, Array2, ArrayBase, ArrayView1, Data, Ix2};
+use serde::{Deserialize, Serialize};
+
+/// Value a `SimpleImputer` fills the missing values of a column with.
+///
+/// * `Mean` - The mean of the observed values of the column.
+/// * `Median` - The median of the observed values of the column.
+/// * `MostFrequent` - The most frequent observed value of the column, the smallest one wins ties.
+/// * `Constant` - The given value, for every column.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ImputeStrategy {
+    Mean,
+    Median,
+    MostFrequent,
+    Constant(f64),
+}
+
+/// Fills in missing values, marked as NaN, with a statistic of each column learned during fit.
+///
+/// # Fields
+/// - `strategy`: How the fill value of each column is worked out.
+/// - `statistics`: The fill value of each column learned during fit.
+///
+/// # Type parameters
+/// - `State`: Indicates whether the imputer has been fit. Can either be `Fit` or `Unfit`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::impute::{ImputeStrategy, SimpleImputer};
+/// use ndarray::array;
+///
+/// let x_train = array![[1.0, f64::NAN], [3.0, 4.0], [f64::NAN, 8.0]];
+///
+/// let imputer = SimpleImputer::new(ImputeStrategy::Mean).fit(&x_train).unwrap();
+///
+/// assert_eq!(imputer.statistics, array![2.0, 6.0]);
+/// assert_eq!(
+///     imputer.transform(&array![[f64::NAN, 1.0]]).unwrap(),
+///     array![[2.0, 1.0]]
+/// );
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimpleImputer<State = Unfit> {
+    pub strategy: ImputeStrategy,
+    pub statistics: Array1<f64>,
+
+    state: std::marker::PhantomData<State>,
+}
+
+impl Default for SimpleImputer {
+    fn default() -> Self {
+        Self::new(ImputeStrategy::Mean)
+    }
+}
+
+impl SimpleImputer {
+    /// Creates a new, unfitted `SimpleImputer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy`: How the fill value of each column is worked out
+    ///
+    /// returns: `SimpleImputer<Unfit>`
+    pub fn new(strategy: ImputeStrategy) -> Self {
+        Self {
+            strategy,
+            statistics: Default::default(),
+            state: Default::default(),
+        }
+    }
+
+    /// Learns the fill value of every column of `x`, ignoring missing values.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features ) with NaN marking missing values
+    ///
+    /// returns: `Result<SimpleImputer<Fit>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features, or a column has no observed
+    ///   values and the strategy is not `Constant`.
+    /// - `DuckyError::NonFinite` if `x` contains an infinite value, or the constant is not finite.
+    pub fn fit<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<SimpleImputer<Fit>, DuckyError> {
+        check_missing_input(x)?;
+
+        let statistics = match self.strategy {
+            ImputeStrategy::Constant(value) if !value.is_finite() => {
+                return Err(DuckyError::NonFinite)
+            }
+            ImputeStrategy::Constant(value) => Array1::from_elem(x.ncols(), value),
+            strategy => x
+                .columns()
+                .into_iter()
+                .map(|column| {
+                    let observed = sorted_values(column.iter().filter(|value| !value.is_nan()));
+                    if observed.is_empty() {
+                        return Err(DuckyError::EmptyInput);
+                    }
+
+                    Ok(match strategy {
+                        ImputeStrategy::Mean => {
+                            observed.iter().sum::<f64>() / observed.len() as f64
+                        }
+                        ImputeStrategy::Median => quantile(&observed, 0.5),
+                        _ => most_frequent(&observed),
+                    })
+                })
+                .collect::<Result<Array1<f64>, DuckyError>>()?,
+        };
+
+        Ok(SimpleImputer {
+            strategy: self.strategy,
+            statistics,
+            state: std::marker::PhantomData::<Fit>,
+        })
+    }
+
+    /// Learns the fill value of every column of `x` and fills in its missing values.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features ) with NaN marking missing values
+    ///
+    /// returns: `Result<(SimpleImputer<Fit>, Array2<f64>), DuckyError>`
+    pub fn fit_transform<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<(SimpleImputer<Fit>, Array2<f64>), DuckyError> {
+        let imputer = self.fit(x)?;
+        let imputed = imputer.transform(x)?;
+        Ok((imputer, imputed))
+    }
+}
+
+impl SimpleImputer<Fit> {
+    /// Fills in the missing values of `x` with the fill values learned during fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features ) with NaN marking missing values
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::ShapeMismatch` if `x` has a different number of features to the training data.
+    /// - `DuckyError::NonFinite` if `x` contains an infinite value.
+    pub fn transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_missing_input(x)?;
+        check_n_features(self.statistics.len(), x.ncols())?;
+
+        let mut imputed = x.to_owned();
+        for mut row in imputed.rows_mut() {
+            for (value, statistic) in row.iter_mut().zip(self.statistics.iter()) {
+                if value.is_nan() {
+                    *value = *statistic;
+                }
+            }
+        }
+        Ok(imputed)
+    }
+}
+
+/// How the neighbours of a `KNNImputer` are weighted.
+///
+/// * `Uniform` - Every neighbour counts the same.
+/// * `Distance` - Neighbours count by the inverse of their distance, so closer ones count more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KNNWeights {
+    Uniform,
+    Distance,
+}
+
+/// Fills in missing values, marked as NaN, with the average of the `n_neighbors` closest
+/// training rows that have the value.
+///
+/// Distances skip the coordinates missing in either row and are scaled up by the fraction
+/// of coordinates used. Values with no training row to take them from get the mean of the
+/// column.
+///
+/// # Fields
+/// - `n_neighbors`: Number of neighbours to average over.
+/// - `weights`: How the neighbours are weighted.
+/// - `fit_x`: The training data the neighbours are taken from.
+/// - `column_means`: Mean of the observed values of each training column.
+///
+/// # Type parameters
+/// - `State`: Indicates whether the imputer has been fit. Can either be `Fit` or `Unfit`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::impute::{KNNImputer, KNNWeights};
+/// use ndarray::array;
+///
+/// let x_train = array![[1.0, 1.0], [2.0, 2.0], [10.0, 10.0]];
+///
+/// let imputer = KNNImputer::new(2, KNNWeights::Uniform).fit(&x_train).unwrap();
+///
+/// assert_eq!(
+///     imputer.transform(&array![[1.5, f64::NAN]]).unwrap(),
+///     array![[1.5, 1.5]]
+/// );
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KNNImputer<State = Unfit> {
+    pub n_neighbors: usize,
+    pub weights: KNNWeights,
+    pub fit_x: Array2<f64>,
+    pub column_means: Array1<f64>,
+
+    state: std::marker::PhantomData<State>,
+}
+
+impl Default for KNNImputer {
+    fn default() -> Self {
+        Self::new(5, KNNWeights::Uniform)
+    }
+}
+
+impl KNNImputer {
+    /// Creates a new, unfitted `KNNImputer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_neighbors`: Number of neighbours to average over
+    /// * `weights`: How the neighbours are weighted
+    ///
+    /// returns: `KNNImputer<Unfit>`
+    pub fn new(n_neighbors: usize, weights: KNNWeights) -> Self {
+        Self {
+            n_neighbors,
+            weights,
+            fit_x: Default::default(),
+            column_means: Default::default(),
+            state: Default::default(),
+        }
+    }
+
+    /// Keeps `x` to take neighbours from.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features ) with NaN marking missing values
+    ///
+    /// returns: `Result<KNNImputer<Fit>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::InvalidParameter` if `n_neighbors` is 0.
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features, or a column has no observed values.
+    /// - `DuckyError::NonFinite` if `x` contains an infinite value.
+    pub fn fit<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<KNNImputer<Fit>, DuckyError> {
+        if self.n_neighbors == 0 {
+            return Err(DuckyError::InvalidParameter(
+                "n_neighbors has to be at least 1".to_string(),
+            ));
+        }
+        check_missing_input(x)?;
+
+        let column_means = SimpleImputer::new(ImputeStrategy::Mean).fit(x)?.statistics;
+
+        Ok(KNNImputer {
+            n_neighbors: self.n_neighbors,
+            weights: self.weights,
+            fit_x: x.to_owned(),
+            column_means,
+            state: std::marker::PhantomData::<Fit>,
+        })
+    }
+
+    /// Keeps `x` to take neighbours from and fills in its missing values.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features ) with NaN marking missing values
+    ///
+    /// returns: `Result<(KNNImputer<Fit>, Array2<f64>), DuckyError>`
+    pub fn fit_transform<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<(KNNImputer<Fit>, Array2<f64>), DuckyError> {
+        let imputer = self.fit(x)?;
+        let imputed = imputer.transform(x)?;
+        Ok((imputer, imputed))
+    }
+}
+
+impl KNNImputer<Fit> {
+    /// Fills in the missing values of `x` from the nearest training rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features ) with NaN marking missing values
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::ShapeMismatch` if `x` has a different number of features to the training data.
+    /// - `DuckyError::NonFinite` if `x` contains an infinite value.
+    pub fn transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_missing_input(x)?;
+        check_n_features(self.fit_x.ncols(), x.ncols())?;
+
+        let mut imputed = x.to_owned();
+        for mut row in imputed.rows_mut() {
+            if !row.iter().any(|value| value.is_nan()) {
+                continue;
+            }
+
+            let distances: Vec<f64> = self
+                .fit_x
+                .rows()
+                .into_iter()
+                .map(|fit_row| nan_euclidean_distance(row.view(), fit_row))
+                .collect();
+
+            for column in 0..row.len() {
+                if row[column].is_nan() {
+                    row[column] = self
+                        .neighbour_average(&distances, column)
+                        .unwrap_or(self.column_means[column]);
+                }
+            }
+        }
+        Ok(imputed)
+    }
+
+    /// Weighted average of `column` over the nearest training rows that have it, `None` if
+    /// no training row does.
+    fn neighbour_average(&self, distances: &[f64], column: usize) -> Option<f64> {
+        let mut neighbours: Vec<(f64, f64)> = distances
+            .iter()
+            .zip(self.fit_x.column(column))
+            .filter(|(distance, value)| distance.is_finite() && !value.is_nan())
+            .map(|(&distance, &value)| (distance, value))
+            .collect();
+        if neighbours.is_empty() {
+            return None;
+        }
+
+        neighbours.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        neighbours.truncate(self.n_neighbors);
+
+        // Exact matches take all of the weight, as their inverse distance is infinite
+        if self.weights == KNNWeights::Distance && neighbours[0].0 == 0.0 {
+            neighbours.retain(|(distance, _)| *distance == 0.0);
+        }
+
+        let weight = |distance: f64| match self.weights {
+            KNNWeights::Uniform => 1.0,
+            KNNWeights::Distance if distance == 0.0 => 1.0,
+            KNNWeights::Distance => 1.0 / distance,
+        };
+        let total_weight: f64 = neighbours
+            .iter()
+            .map(|(distance, _)| weight(*distance))
+            .sum();
+
+        Some(
+            neighbours
+                .iter()
+                .map(|(distance, value)| weight(*distance) * value)
+                .sum::<f64>()
+                / total_weight,
+        )
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Fit<ArrayBase<S, Ix2>, ()> for SimpleImputer<Unfit> {
+    type Fitted = SimpleImputer<Fit>;
+
+    fn fit(self, x: &ArrayBase<S, Ix2>, _: &()) -> Result<SimpleImputer<Fit>, DuckyError> {
+        self.fit(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Transform<ArrayBase<S, Ix2>> for SimpleImputer<Fit> {
+    type Output = Array2<f64>;
+
+    fn transform(&self, x: &ArrayBase<S, Ix2>) -> Result<Array2<f64>, DuckyError> {
+        self.transform(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Fit<ArrayBase<S, Ix2>, ()> for KNNImputer<Unfit> {
+    type Fitted = KNNImputer<Fit>;
+
+    fn fit(self, x: &ArrayBase<S, Ix2>, _: &()) -> Result<KNNImputer<Fit>, DuckyError> {
+        self.fit(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Transform<ArrayBase<S, Ix2>> for KNNImputer<Fit> {
+    type Output = Array2<f64>;
+
+    fn transform(&self, x: &ArrayBase<S, Ix2>) -> Result<Array2<f64>, DuckyError> {
+        self.transform(x)
+    }
+}
+
+/// Checks that `x` has at least one row and feature and no infinite values. NaN is
+/// allowed as it marks a missing value.
+fn check_missing_input<S: Data<Elem = f64>>(x: &ArrayBase<S, Ix2>) -> Result<(), DuckyError> {
+    if x.is_empty() {
+        return Err(DuckyError::EmptyInput);
+    }
+    if x.iter().any(|value| value.is_infinite()) {
+        return Err(DuckyError::NonFinite);
+    }
+    Ok(())
+}
+
+/// Most frequent of the sorted values, the smallest one wins ties.
+fn most_frequent(sorted: &[f64]) -> f64 {
+    let mut best = (sorted[0], 0);
+    let mut start = 0;
+    for end in 1..=sorted.len() {
+        if end == sorted.len() || sorted[end] != sorted[start] {
+            if end - start > best.1 {
+                best = (sorted[start], end - start);
+            }
+            start = end;
+        }
+    }
+    best.0
+}
+
+/// Euclidean distance over the coordinates present in both rows, scaled up by the
+/// fraction of coordinates used. Infinite if the rows have no coordinates in common.
+fn nan_euclidean_distance(a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+    let mut squared_sum = 0.0;
+    let mut n_present = 0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        if !x.is_nan() && !y.is_nan() {
+            squared_sum += (x - y).powi(2);
+            n_present += 1;
+        }
+    }
+
+    if n_present == 0 {
+        f64::INFINITY
+    } else {
+        (squared_sum * a.len() as f64 / n_present as f64).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod impute_tests {
+    use super::*;
+    use crate::traits::Persist;
+    use ndarray::array;
+
+    fn data() -> Array2<f64> {
+        array![
+            [1.0, 2.0, f64::NAN],
+            [f64::NAN, 4.0, 3.0],
+            [7.0, 4.0, 3.0],
+            [4.0, f64::NAN, 6.0]
+        ]
+    }
+
+    #[test]
+    fn simple_imputer_strategies() {
+        let x = data();
+
+        let mean = SimpleImputer::new(ImputeStrategy::Mean).fit(&x).unwrap();
+        let median = SimpleImputer::new(ImputeStrategy::Median).fit(&x).unwrap();
+        let most_frequent = SimpleImputer::new(ImputeStrategy::MostFrequent)
+            .fit(&x)
+            .unwrap();
+        let constant = SimpleImputer::new(ImputeStrategy::Constant(-1.0))
+            .fit(&x)
+            .unwrap();
+
+        assert_eq!(mean.statistics, array![4.0, 10.0 / 3.0, 4.0]);
+        assert_eq!(median.statistics, array![4.0, 4.0, 3.0]);
+        assert_eq!(most_frequent.statistics, array![1.0, 4.0, 3.0]);
+        assert_eq!(constant.statistics, array![-1.0, -1.0, -1.0]);
+    }
+
+    #[test]
+    fn simple_imputer_transform() {
+        let (imputer, imputed) = SimpleImputer::new(ImputeStrategy::Median)
+            .fit_transform(&data())
+            .unwrap();
+
+        assert_eq!(
+            imputed,
+            array![
+                [1.0, 2.0, 3.0],
+                [4.0, 4.0, 3.0],
+                [7.0, 4.0, 3.0],
+                [4.0, 4.0, 6.0]
+            ]
+        );
+        // Test data is filled with the statistics of the training data
+        assert_eq!(
+            imputer
+                .transform(&array![[f64::NAN, f64::NAN, 9.0]])
+                .unwrap(),
+            array![[4.0, 4.0, 9.0]]
+        );
+    }
+
+    #[test]
+    fn simple_imputer_errors() {
+        let imputer = SimpleImputer::default().fit(&data()).unwrap();
+
+        assert_eq!(
+            imputer.transform(&array![[1.0, 2.0]]).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![3],
+                found: vec![2]
+            }
+        );
+        assert_eq!(
+            imputer
+                .transform(&array![[1.0, f64::INFINITY, 2.0]])
+                .unwrap_err(),
+            DuckyError::NonFinite
+        );
+        assert_eq!(
+            SimpleImputer::default()
+                .fit(&array![[1.0, f64::NAN], [2.0, f64::NAN]])
+                .unwrap_err(),
+            DuckyError::EmptyInput
+        );
+        assert!(SimpleImputer::new(ImputeStrategy::Constant(0.0))
+            .fit(&array![[1.0, f64::NAN]])
+            .is_ok());
+    }
+
+    #[test]
+    fn most_frequent_ties() {
+        assert_eq!(most_frequent(&[1.0, 2.0, 2.0, 3.0, 3.0]), 2.0);
+        assert_eq!(most_frequent(&[5.0]), 5.0);
+    }
+
+    #[test]
+    fn nan_euclidean() {
+        let a = array![1.0, f64::NAN, 3.0, f64::NAN];
+        let b = array![1.0, 2.0, 6.0, 8.0];
+
+        // Only 2 of the 4 coordinates can be compared, so the distance is scaled by 4 / 2
+        assert!((nan_euclidean_distance(a.view(), b.view()) - 18.0_f64.sqrt()).abs() < 1e-9);
+        assert_eq!(
+            nan_euclidean_distance(array![f64::NAN, 1.0].view(), array![1.0, f64::NAN].view()),
+            f64::INFINITY
+        );
+    }
+
+    #[test]
+    fn knn_imputer() {
+        let x = array![
+            [1.0, 2.0, f64::NAN],
+            [3.0, 4.0, 3.0],
+            [f64::NAN, 6.0, 5.0],
+            [8.0, 8.0, 7.0]
+        ];
+
+        let (imputer, imputed) = KNNImputer::new(2, KNNWeights::Uniform)
+            .fit_transform(&x)
+            .unwrap();
+
+        assert_eq!(imputed[[0, 2]], 4.0);
+        assert_eq!(imputed[[2, 0]], 5.5);
+        assert_eq!(imputed[[1, 1]], 4.0);
+
+        // No training row shares a coordinate with this one, so it falls back to the column mean
+        assert_eq!(
+            imputer
+                .transform(&array![[f64::NAN, f64::NAN, f64::NAN]])
+                .unwrap(),
+            array![[4.0, 5.0, 5.0]]
+        );
+    }
+
+    #[test]
+    fn knn_imputer_distance_weights() {
+        let x = array![[1.0, 10.0], [4.0, 40.0], [10.0, 100.0]];
+
+        let imputer = KNNImputer::new(2, KNNWeights::Distance).fit(&x).unwrap();
+
+        // The first neighbour is half as far away as the second, so counts twice as much
+        let imputed = imputer.transform(&array![[2.0, f64::NAN]]).unwrap();
+        assert!((imputed[[0, 1]] - (2.0 * 10.0 + 40.0) / 3.0).abs() < 1e-9);
+
+        // An exact match takes all of the weight
+        let imputed = imputer.transform(&array![[1.0, f64::NAN]]).unwrap();
+        assert_eq!(imputed[[0, 1]], 10.0);
+
+        assert!(matches!(
+            KNNImputer::new(0, KNNWeights::Uniform).fit(&x),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn knn_imputer_write_read() {
+        let x = array![[1.0, f64::NAN], [3.0, 4.0], [f64::NAN, 6.0]];
+        let imputer = KNNImputer::default().fit(&x).unwrap();
+
+        let mut saved = Vec::new();
+        imputer.write_to(&mut saved).unwrap();
+        let loaded = KNNImputer::<Fit>::read_from(saved.as_slice()).unwrap();
+
+        assert!(loaded.fit_x[[0, 1]].is_nan());
+        assert_eq!(
+            loaded.transform(&x).unwrap(),
+            imputer.transform(&x).unwrap()
+        );
+    }
+}
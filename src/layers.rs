@@ -1,19 +1,49 @@
 extern crate ndarray;
 extern crate ndarray_rand;
 
+use super::activations::Activation;
+use super::attention::{
+    LearnedEncoding, MultiHeadAttention, SinusoidalEncoding, TransformerEncoderLayer,
+};
+use super::autograd::{Tape, Tensor};
+use super::conv::MaxPool1d;
+use super::embedding::Embedding;
+use super::error::{check_finite, check_targets, DuckyError};
+use super::recurrent::{Bidirectional, GruCell, LstmCell, Recurrent, SimpleRnnCell};
+use super::regularizers::{Constraint, Regularizer};
+use super::traits::Transform;
+use super::util::seeded_rng;
 use ndarray::prelude::*;
 use ndarray_rand::rand_distr::Uniform;
 use ndarray_rand::RandomExt;
-use std::sync::RwLock;
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::{Mutex, RwLock};
+
+/// Whether a model is being trained or used for inference. Layers such as `Dropout` only
+/// change their output while training.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Train,
+    Eval,
+}
 
 pub trait Layer1d {
     /// Feeds forward the 1d array through the layer.
     ///
     /// # Arguments
     ///
-    /// * `input_array`: Has to be the same size as the input size of the layer else will panic
+    /// * `input_array`: Has to be the same size as the input size of the layer
+    /// * `mode`: Whether the layer is being trained or used for inference
+    ///
+    /// returns: `Result<(Array1<f64>, Array1<f64>), DuckyError>` holding the weighted input
+    /// of the layer and the output of its activation function
+    ///
+    /// # Errors
     ///
-    /// returns: `Array1<f64>`
+    /// * `DuckyError::ShapeMismatch`: `input_array` is a different size to the input of the layer
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
     ///
     /// # Examples
     ///
@@ -28,17 +58,234 @@ pub trait Layer1d {
     ///                 arr1(&[1., 1.]) // len 2
     ///             );
     ///
-    /// let output = layer.pass(arr1(&[1., 1.]));
+    /// let (z, a) = layer.pass(arr1(&[1., 1.]), Mode::Eval).unwrap();
+    ///
+    /// assert_eq!(a, arr1(&[3., 3.]));
+    /// assert!(layer.pass(arr1(&[1.]), Mode::Eval).is_err());
+    /// ```
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError>;
+
+    /// Passes the gradient of the cost backwards through the layer, taking one gradient
+    /// descent step on the layer's parameters along the way.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The input the layer was passed during the forward pass
+    /// * `weighted_input`: The weighted input the layer returned during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of the layer
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array1<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to the input of the layer, computed before the parameters are updated
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::layers::*;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// let layer = Dense1d::from(
+    ///                 |x| x,
+    ///                 |x| x.map(|i| 1f64),
+    ///                 arr2(&[[1., 2.]]),
+    ///                 arr1(&[0.])
+    ///             );
+    ///
+    /// let input_array = arr1(&[1., 1.]);
+    /// let (z, _) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+    /// let input_gradient = layer.back_pass(&input_array, &z, &arr1(&[1.]), 0.5).unwrap();
+    ///
+    /// assert_eq!(input_gradient, arr1(&[1., 2.]));
+    /// assert_eq!(layer.pass(input_array, Mode::Eval).unwrap().1, arr1(&[1.5]));
+    /// ```
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError>;
+
+    /// Feeds forward a batch of rows through the layer, one row per sample.
+    ///
+    /// Layers whose output depends on the whole batch, such as `BatchNorm1d`, normalize the
+    /// rows together. The rest pass every row on its own, as `pass` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_batch`: Batch of shape( samples, input size )
+    /// * `mode`: Whether the layer is being trained or used for inference
+    ///
+    /// returns: `Result<(Array2<f64>, Array2<f64>), DuckyError>` holding the weighted inputs
+    /// of the layer and the outputs of its activation function, one row per sample
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the rows are a different size to the input of the layer
+    /// * `DuckyError::NonFinite`: `input_batch` contains a NaN or infinite value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::layers::*;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// let layer = Dense1d::from(|x| x, |x| x.map(|_| 1f64), arr2(&[[1., 2.]]), arr1(&[1.]));
+    ///
+    /// let (_, a) = layer.pass_batch(&arr2(&[[1., 1.], [0., 2.]]), Mode::Eval).unwrap();
+    ///
+    /// assert_eq!(a, arr2(&[[4.], [5.]]));
+    /// ```
+    fn pass_batch(
+        &self,
+        input_batch: &Array2<f64>,
+        mode: Mode,
+    ) -> Result<(Array2<f64>, Array2<f64>), DuckyError> {
+        let mut weighted_rows = Vec::with_capacity(input_batch.nrows());
+        let mut output_rows = Vec::with_capacity(input_batch.nrows());
+        for row in input_batch.rows() {
+            let (weighted_row, output_row) = self.pass(row.to_owned(), mode)?;
+            weighted_rows.push(weighted_row);
+            output_rows.push(output_row);
+        }
+
+        Ok((stack_rows(&weighted_rows)?, stack_rows(&output_rows)?))
+    }
+
+    /// Passes the gradient of the cost of a batch backwards through the layer, taking a
+    /// gradient descent step on the layer's parameters along the way.
+    ///
+    /// `Dense1d` and `BatchNorm1d` sum the gradients of their parameters over the batch and
+    /// take one step. Other layers take a step for every row, on the gradient of that row,
+    /// which adds up to about the same step when the learning rate is small.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_batch`: The batch the layer was passed during the forward pass
+    /// * `weighted_batch`: The weighted inputs the layer returned during the forward pass
+    /// * `output_gradient`: Gradient of the cost of the batch with respect to the output of
+    ///   the layer, one row per sample
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to the input of the layer
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with each other or the layer
+    fn back_pass_batch(
+        &self,
+        input_batch: &Array2<f64>,
+        weighted_batch: &Array2<f64>,
+        output_gradient: &Array2<f64>,
+        learning_rate: f64,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_targets(input_batch.nrows(), weighted_batch.nrows())?;
+        check_targets(input_batch.nrows(), output_gradient.nrows())?;
+
+        let rows = input_batch
+            .rows()
+            .into_iter()
+            .zip(weighted_batch.rows())
+            .zip(output_gradient.rows())
+            .map(|((input_row, weighted_row), gradient_row)| {
+                self.back_pass(
+                    &input_row.to_owned(),
+                    &weighted_row.to_owned(),
+                    &gradient_row.to_owned(),
+                    learning_rate,
+                )
+            })
+            .collect::<Result<Vec<_>, DuckyError>>()?;
+
+        stack_rows(&rows)
+    }
+
+    /// Amount the layer's regularizers add to the cost, which is zero for layers without any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::layers::*;
+    /// use ducky_learn::regularizers::Regularizer;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// let layer = Dense1d::from(|x| x, |x| x.map(|_| 1f64), arr2(&[[1., -2.]]), arr1(&[3.]))
+    ///     .with_regularizers(Some(Regularizer::L1(0.5)), None)
+    ///     .unwrap();
     ///
+    /// assert_eq!(layer.penalty(), 1.5);
+    /// assert_eq!(Dropout::new(0.5, None).unwrap().penalty(), 0.);
     /// ```
-    fn pass(&self, input_array: Array1<f64>) -> (Array1<f64>, Array1<f64>); // TODO: update doc
+    fn penalty(&self) -> f64 {
+        0.
+    }
+
+    /// The layer as a `SavedLayer`, which is how a model of boxed layers saves it.
+    ///
+    /// returns: `Result<SavedLayer, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::Persistence`: the layer can not be saved, as a layer from outside the
+    ///   crate or a `Dense1d` with a function pointer activation can not
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Err(cannot_save::<Self>())
+    }
+}
+
+/// Stacks rows of the same length into a batch, which has no columns when there are no rows.
+pub(crate) fn stack_rows(rows: &[Array1<f64>]) -> Result<Array2<f64>, DuckyError> {
+    let views: Vec<_> = rows.iter().map(|row| row.view()).collect();
+
+    match views.is_empty() {
+        true => Ok(Array2::zeros((0, 0))),
+        false => ndarray::stack(Axis(0), &views).map_err(|_| DuckyError::ShapeMismatch {
+            expected: vec![views[0].len()],
+            found: views.iter().map(|view| view.len()).collect(),
+        }),
+    }
+}
+
+// How a `Dense1d` activates its weighted input
+#[derive(Debug, Clone, Copy)]
+enum DenseActivation {
+    // Activation of arrays with its derivative written by hand
+    Manual {
+        activation: fn(Array1<f64>) -> Array1<f64>,
+        deriv_activation: fn(Array1<f64>) -> Array1<f64>,
+    },
+    // Activation built from tensor operations, differentiated on the tape
+    Auto(fn(&Tensor) -> Tensor),
+    // Activation known by name, differentiated on the tape
+    Named(Activation),
 }
 
+/// Fully connected layer. The activation either comes with a hand-written derivative, see
+/// `from` and `new`, is built from `autograd::Tensor` operations and differentiated on an
+/// `autograd::Tape`, see `from_auto` and `new_auto`, or is an `activations::Activation`, see
+/// `from_named` and `new_named`. Only the activation is recorded on a tape, the weighted input
+/// and the gradients of the weights and bias are plain products of arrays.
+///
+/// Only a layer with a named activation can be saved with `traits::Persist`, on its own or as
+/// part of a model, as function pointers can not be written down.
+#[derive(Debug)]
 pub struct Dense1d {
-    activation: fn(Array1<f64>) -> Array1<f64>,
-    deriv_activation: fn(Array1<f64>) -> Array1<f64>,
+    activation: DenseActivation,
     weights: RwLock<Array2<f64>>,
     bias: RwLock<Array1<f64>>,
+    kernel_regularizer: Option<Regularizer>,
+    bias_regularizer: Option<Regularizer>,
+    kernel_constraint: Option<Constraint>,
+    bias_constraint: Option<Constraint>,
 }
 
 impl Dense1d {
@@ -71,12 +318,14 @@ impl Dense1d {
         weights: Array2<f64>,
         bias: Array1<f64>,
     ) -> Self {
-        Self {
-            activation,
-            deriv_activation,
-            weights: RwLock::new(weights),
-            bias: RwLock::new(bias),
-        }
+        Self::with_activation(
+            DenseActivation::Manual {
+                activation,
+                deriv_activation,
+            },
+            weights,
+            bias,
+        )
     }
 
     /// Create randomly set weights and bias's for the dense1d layer.
@@ -101,7 +350,7 @@ impl Dense1d {
     ///     1., 1., 1., 1., 1.
     /// ]);
     ///
-    /// layer.pass(input_array);
+    /// layer.pass(input_array, Mode::Eval).unwrap();
     /// ```
     pub fn new(
         input_size: usize,
@@ -109,100 +358,1501 @@ impl Dense1d {
         activation_fn: fn(Array1<f64>) -> Array1<f64>,
         deriv_activation_fn: fn(Array1<f64>) -> Array1<f64>,
     ) -> Self {
-        Self {
-            activation: activation_fn,
-            deriv_activation: deriv_activation_fn,
-            weights: RwLock::new(Array2::random(
-                (layer_size, input_size),
-                Uniform::new(-1., 1.),
-            )),
-            bias: RwLock::new(Array1::random(layer_size, Uniform::new(-1., 1.))),
-        }
+        Self::from(
+            activation_fn,
+            deriv_activation_fn,
+            Array2::random((layer_size, input_size), Uniform::new(-1., 1.)),
+            Array1::random(layer_size, Uniform::new(-1., 1.)),
+        )
     }
-}
 
-impl Layer1d for Dense1d {
-    fn pass(&self, input_array: Array1<f64>) -> (Array1<f64>, Array1<f64>) {
-        let weights = self.weights.read().unwrap();
-        let bias = self.bias.read().unwrap();
+    /// Create Dense1d layer whose activation is written with `autograd::Tensor` operations,
+    /// so its derivative comes from automatic differentiation instead of a hand-written
+    /// `deriv_activation`
+    ///
+    /// # Arguments
+    ///
+    /// * `activation`: Activation function of the whole weighted input, built from tensor
+    ///   operations
+    /// * `weights`: 2d array that has to be of shape( output, input )
+    /// * `bias`: 1d array of basis that has to be the size of the output
+    ///
+    /// returns: `Dense1d`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::layers::*;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// // Softplus, without having to work out its derivative
+    /// let layer = Dense1d::from_auto(
+    ///     |z| (&z.exp() + 1.).ln(),
+    ///     arr2(&[[1., -1.]]),
+    ///     arr1(&[0.]),
+    /// );
+    ///
+    /// let (z, a) = layer.pass(arr1(&[2., 2.]), Mode::Train).unwrap();
+    /// assert_eq!(a, arr1(&[2f64.ln()]));
+    ///
+    /// let input_gradient = layer.back_pass(&arr1(&[2., 2.]), &z, &arr1(&[1.]), 0.1).unwrap();
+    /// assert_eq!(input_gradient, arr1(&[0.5, -0.5]));
+    /// ```
+    pub fn from_auto(
+        activation: fn(&Tensor) -> Tensor,
+        weights: Array2<f64>,
+        bias: Array1<f64>,
+    ) -> Self {
+        Self::with_activation(DenseActivation::Auto(activation), weights, bias)
+    }
 
-        assert_eq!(
-            weights.shape()[1],
-            input_array.shape()[0],
-            "Layer input size is {}, \
-            Layer was given size of {}",
-            weights.shape()[1],
-            input_array.shape()[0]
-        );
+    /// Create Dense1d layer with an activation written with `autograd::Tensor` operations,
+    /// and weights and bias's drawn uniformly from ±1 / sqrt( input size )
+    ///
+    /// # Arguments
+    ///
+    /// * `input_size`: size of input array
+    /// * `layer_size`: number of nodes in the layer
+    /// * `activation`: Activation function of the whole weighted input, built from tensor
+    ///   operations
+    /// * `seed`: Seed for drawing the weights, or `None` to seed it from the operating system
+    ///
+    /// returns: `Dense1d`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::layers::*;
+    /// use ndarray::arr1;
+    ///
+    /// let layer = Dense1d::new_auto(3, 2, |z| z.tanh(), Some(1));
+    ///
+    /// let (_, a) = layer.pass(arr1(&[1., 2., 3.]), Mode::Eval).unwrap();
+    /// assert!(a.iter().all(|value| value.abs() < 1.));
+    /// ```
+    pub fn new_auto(
+        input_size: usize,
+        layer_size: usize,
+        activation: fn(&Tensor) -> Tensor,
+        seed: Option<u64>,
+    ) -> Self {
+        let (weights, bias) = draw_dense(input_size, layer_size, seed);
+        Self::from_auto(activation, weights, bias)
+    }
 
-        let z = weights.dot(&input_array) + &*bias;
-        let a = (self.activation)(z.clone());
-        (z, a)
+    /// Create Dense1d layer with a named activation, so it can be saved
+    ///
+    /// # Arguments
+    ///
+    /// * `activation`: Activation function of the whole weighted input
+    /// * `weights`: 2d array that has to be of shape( output, input )
+    /// * `bias`: 1d array of basis that has to be the size of the output
+    ///
+    /// returns: `Dense1d`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::activations::Activation;
+    /// use ducky_learn::layers::*;
+    /// use ducky_learn::traits::Persist;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// let layer = Dense1d::from_named(Activation::Relu, arr2(&[[1., -1.]]), arr1(&[0.]));
+    ///
+    /// let mut saved = Vec::new();
+    /// layer.write_to(&mut saved).unwrap();
+    /// let loaded = Dense1d::read_from(saved.as_slice()).unwrap();
+    ///
+    /// assert_eq!(loaded.pass(arr1(&[2., 1.]), Mode::Eval).unwrap().1, arr1(&[1.]));
+    /// ```
+    pub fn from_named(activation: Activation, weights: Array2<f64>, bias: Array1<f64>) -> Self {
+        Self::with_activation(DenseActivation::Named(activation), weights, bias)
     }
-}
 
+    /// Create Dense1d layer with a named activation, and weights and bias's drawn uniformly
+    /// from ±1 / sqrt( input size )
+    ///
+    /// # Arguments
+    ///
+    /// * `input_size`: size of input array
+    /// * `layer_size`: number of nodes in the layer
+    /// * `activation`: Activation function of the whole weighted input
+    /// * `seed`: Seed for drawing the weights, or `None` to seed it from the operating system
+    ///
+    /// returns: `Dense1d`
+    pub fn new_named(
+        input_size: usize,
+        layer_size: usize,
+        activation: Activation,
+        seed: Option<u64>,
+    ) -> Self {
+        let (weights, bias) = draw_dense(input_size, layer_size, seed);
+        Self::from_named(activation, weights, bias)
+    }
 
-#[cfg(test)]
-mod layers_tests {
-    use super::*;
-    use ndarray::*;
-    use crate::activations::*;
+    fn with_activation(
+        activation: DenseActivation,
+        weights: Array2<f64>,
+        bias: Array1<f64>,
+    ) -> Self {
+        Self {
+            activation,
+            weights: RwLock::new(weights),
+            bias: RwLock::new(bias),
+            kernel_regularizer: None,
+            bias_regularizer: None,
+            kernel_constraint: None,
+            bias_constraint: None,
+        }
+    }
 
-    #[test]
-    fn dense1d_pass_arr1_1() {
-        let layer = Dense1d::from(
-            |x| x,
-            |x| x,
-            arr2(&[[1., 1., 1.], [1., 1., 1.], [1., 1., 1.]]),
-            arr1(&[1., 1., 1.]),
-        );
-        let input_array = arr1(&[1., 1., 1.]);
+    /// Sets the penalties added to the cost for the size of the weights and the bias. Their
+    /// gradients are added to the gradients of the weights and bias in `back_pass`.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel`: Regularizer of the weights, `None` leaves them unpenalized
+    /// * `bias`: Regularizer of the bias, `None` leaves it unpenalized
+    ///
+    /// returns: `Result<Dense1d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: a regularizer has a negative or non-finite coefficient
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::layers::*;
+    /// use ducky_learn::regularizers::Regularizer;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// let layer = Dense1d::from(|x| x, |x| x.map(|_| 1f64), arr2(&[[2.]]), arr1(&[0.]))
+    ///     .with_regularizers(Some(Regularizer::L2(0.5)), None)
+    ///     .unwrap();
+    ///
+    /// // Without any cost gradient the step only shrinks the weights
+    /// let (z, _) = layer.pass(arr1(&[1.]), Mode::Train).unwrap();
+    /// layer.back_pass(&arr1(&[1.]), &z, &arr1(&[0.]), 0.1).unwrap();
+    ///
+    /// assert_eq!(layer.weights(), arr2(&[[1.8]]));
+    /// ```
+    pub fn with_regularizers(
+        mut self,
+        kernel: Option<Regularizer>,
+        bias: Option<Regularizer>,
+    ) -> Result<Self, DuckyError> {
+        for regularizer in kernel.iter().chain(bias.iter()) {
+            regularizer.check()?;
+        }
+        self.kernel_regularizer = kernel;
+        self.bias_regularizer = bias;
 
-        assert_eq!(layer.pass(input_array).1, arr1(&[4., 4., 4.]))
+        Ok(self)
     }
 
-    #[test]
-    fn dense1d_pass_arr1_2() {
-        let layer = Dense1d::from(
-            |x| x,
-            |x| x,
-            arr2(&[
-                [1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1.],
-                [1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1.],
-                [1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1.],
-            ]),
-            arr1(&[1., 1., 1.]),
-        );
-        let input_array = arr1(&[1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1.]);
+    /// Sets the constraints applied to the weights and the bias after every gradient descent
+    /// step in `back_pass`.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel`: Constraint on the weights, where norms are taken over the weights going into
+    ///   each node. `None` leaves them unconstrained
+    /// * `bias`: Constraint on the bias, `None` leaves it unconstrained
+    ///
+    /// returns: `Result<Dense1d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: a max-norm constraint is not positive
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::layers::*;
+    /// use ducky_learn::regularizers::Constraint;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// let layer = Dense1d::from(|x| x, |x| x.map(|_| 1f64), arr2(&[[0.5, 0.]]), arr1(&[0.]))
+    ///     .with_constraints(Some(Constraint::MaxNorm(1.)), Some(Constraint::NonNegative))
+    ///     .unwrap();
+    ///
+    /// let (z, _) = layer.pass(arr1(&[1., 0.]), Mode::Train).unwrap();
+    /// layer.back_pass(&arr1(&[1., 0.]), &z, &arr1(&[-1.]), 1.).unwrap();
+    /// layer.back_pass(&arr1(&[1., 0.]), &z, &arr1(&[1.]), 1.).unwrap();
+    ///
+    /// assert_eq!(layer.weights(), arr2(&[[0., 0.]]));
+    /// assert_eq!(layer.bias(), arr1(&[0.]));
+    /// ```
+    pub fn with_constraints(
+        mut self,
+        kernel: Option<Constraint>,
+        bias: Option<Constraint>,
+    ) -> Result<Self, DuckyError> {
+        for constraint in kernel.iter().chain(bias.iter()) {
+            constraint.check()?;
+        }
+        self.kernel_constraint = kernel;
+        self.bias_constraint = bias;
 
-        assert_eq!(layer.pass(input_array).1, arr1(&[13.0, 13.0, 13.0]))
+        Ok(self)
     }
 
-    #[test]
-    #[should_panic]
-    fn dense1d_pass_arr1_diff_size() {
-        let layer = Dense1d::from(
-            |x| x,
-            |x| x,
-            arr2(&[[1., 1., 1., 1.], [1., 1., 1., 1.]]),
-            arr1(&[0., 0.]),
-        );
-        let input_array = arr1(&[1.]);
+    /// Weights of the layer, of shape( output, input ).
+    pub fn weights(&self) -> Array2<f64> {
+        self.weights.read().unwrap().clone()
+    }
 
-        layer.pass(input_array);
+    /// Bias of the layer.
+    pub fn bias(&self) -> Array1<f64> {
+        self.bias.read().unwrap().clone()
     }
 
-    #[test]
-    fn dense1d_new() {
-        let layer = Dense1d::new(5, 10, |x| x, |x| x);
+    /// Output of the activation function for the weighted input `z`.
+    fn activate(&self, z: Array1<f64>) -> Result<Array1<f64>, DuckyError> {
+        Ok(match self.activation {
+            DenseActivation::Manual { activation, .. } => activation(z),
+            DenseActivation::Auto(activation) => activation(&Tape::new().variable(z)).value_1d(),
+            DenseActivation::Named(activation) => {
+                activation.apply(&Tape::new().variable(z))?.value_1d()
+            }
+        })
+    }
 
-        let input_array = arr1(&[1., 1., 1., 1., 1.]);
+    /// Gradient of the cost with respect to the weighted input, from its gradient with respect
+    /// to the output of the activation function. Only the activation is recorded on a tape,
+    /// the weighted input of the forward pass is reused as its variable.
+    fn weighted_input_gradient(
+        &self,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let z = Tape::new().variable(weighted_input.clone());
+        let gradients = match self.activation {
+            DenseActivation::Manual {
+                deriv_activation, ..
+            } => return Ok(output_gradient * &deriv_activation(weighted_input.clone())),
+            DenseActivation::Auto(activation) => activation(&z).backward_with(output_gradient)?,
+            DenseActivation::Named(activation) => {
+                activation.apply(&z)?.backward_with(output_gradient)?
+            }
+        };
+        Ok(gradients.wrt(&z).into_dimensionality::<Ix1>().unwrap())
+    }
 
-        layer.pass(input_array);
+    /// Takes one gradient descent step on the weights and bias, adding the gradients of the
+    /// regularizers and applying the constraints afterwards.
+    fn step(
+        &self,
+        weights: &mut Array2<f64>,
+        bias: &mut Array1<f64>,
+        mut weight_gradient: Array2<f64>,
+        mut bias_gradient: Array1<f64>,
+        learning_rate: f64,
+    ) {
+        if let Some(regularizer) = &self.kernel_regularizer {
+            weight_gradient += &regularizer.gradient(&*weights);
+        }
+        if let Some(regularizer) = &self.bias_regularizer {
+            bias_gradient += &regularizer.gradient(&*bias);
+        }
+
+        weights.scaled_add(-learning_rate, &weight_gradient);
+        bias.scaled_add(-learning_rate, &bias_gradient);
+        if let Some(constraint) = &self.kernel_constraint {
+            constraint.apply(weights);
+        }
+        if let Some(constraint) = &self.bias_constraint {
+            constraint.apply(bias);
+        }
     }
+}
 
-    #[test]
-    fn dense1d_activation() {
+/// Gradient with respect to the input of a `Dropout` layer that multiplied it by `mask`. The
+/// gradient of the product does not depend on the input, so zeros stand in for it.
+fn back_mask<D: Dimension>(
+    output_gradient: &Array<f64, D>,
+    mask: Array<f64, D>,
+) -> Result<Array<f64, D>, DuckyError> {
+    let tape = Tape::new();
+    let x = tape.variable(Array::<f64, D>::zeros(mask.raw_dim()));
+    let gradients = x
+        .try_mul(&tape.variable(mask))?
+        .backward_with(output_gradient)?;
+
+    Ok(gradients.wrt(&x).into_dimensionality::<D>().unwrap())
+}
+
+/// Draws weights and bias's uniformly from ±1 / sqrt( input size ).
+fn draw_dense(
+    input_size: usize,
+    layer_size: usize,
+    seed: Option<u64>,
+) -> (Array2<f64>, Array1<f64>) {
+    let mut rng = seeded_rng(seed);
+    let bound = 1. / (input_size.max(1) as f64).sqrt();
+    let distribution = Uniform::new_inclusive(-bound, bound);
+
+    (
+        Array2::random_using((layer_size, input_size), distribution, &mut rng),
+        Array1::random_using(layer_size, distribution, &mut rng),
+    )
+}
+
+// What a `Dense1d` is saved as, which needs its activation to be named
+#[derive(Serialize, Deserialize)]
+struct SavedDense1d {
+    activation: Activation,
+    weights: Array2<f64>,
+    bias: Array1<f64>,
+    kernel_regularizer: Option<Regularizer>,
+    bias_regularizer: Option<Regularizer>,
+    kernel_constraint: Option<Constraint>,
+    bias_constraint: Option<Constraint>,
+}
+
+impl Serialize for Dense1d {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let activation = match self.activation {
+            DenseActivation::Named(activation) => activation,
+            _ => return Err(serde::ser::Error::custom(cannot_save::<Self>())),
+        };
+
+        SavedDense1d {
+            activation,
+            weights: self.weights(),
+            bias: self.bias(),
+            kernel_regularizer: self.kernel_regularizer,
+            bias_regularizer: self.bias_regularizer,
+            kernel_constraint: self.kernel_constraint,
+            bias_constraint: self.bias_constraint,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Dense1d {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedDense1d::deserialize(deserializer)?;
+
+        Ok(Self {
+            kernel_regularizer: saved.kernel_regularizer,
+            bias_regularizer: saved.bias_regularizer,
+            kernel_constraint: saved.kernel_constraint,
+            bias_constraint: saved.bias_constraint,
+            ..Self::from_named(saved.activation, saved.weights, saved.bias)
+        })
+    }
+}
+
+impl Clone for Dense1d {
+    fn clone(&self) -> Self {
+        Self {
+            kernel_regularizer: self.kernel_regularizer,
+            bias_regularizer: self.bias_regularizer,
+            kernel_constraint: self.kernel_constraint,
+            bias_constraint: self.bias_constraint,
+            ..Self::with_activation(self.activation, self.weights(), self.bias())
+        }
+    }
+}
+
+impl Layer1d for Dense1d {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let weights = self.weights.read().unwrap();
+        let bias = self.bias.read().unwrap();
+
+        if weights.shape()[1] != input_array.shape()[0] {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![weights.shape()[1]],
+                found: input_array.shape().to_vec(),
+            });
+        }
+        check_finite(&input_array)?;
+
+        let z = weights.dot(&input_array) + &*bias;
+        let a = self.activate(z.clone())?;
+        Ok((z, a))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let mut weights = self.weights.write().unwrap();
+        let mut bias = self.bias.write().unwrap();
+
+        if input_array.len() != weights.ncols() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![weights.ncols()],
+                found: input_array.shape().to_vec(),
+            });
+        }
+        for array in [weighted_input, output_gradient] {
+            if array.len() != weights.nrows() {
+                return Err(DuckyError::ShapeMismatch {
+                    expected: vec![weights.nrows()],
+                    found: array.shape().to_vec(),
+                });
+            }
+        }
+
+        let delta = self.weighted_input_gradient(weighted_input, output_gradient)?;
+        let input_gradient = weights.t().dot(&delta);
+        let weight_gradient = delta
+            .view()
+            .insert_axis(Axis(1))
+            .dot(&input_array.view().insert_axis(Axis(0)));
+        self.step(
+            &mut weights,
+            &mut bias,
+            weight_gradient,
+            delta,
+            learning_rate,
+        );
+
+        Ok(input_gradient)
+    }
+
+    fn pass_batch(
+        &self,
+        input_batch: &Array2<f64>,
+        mode: Mode,
+    ) -> Result<(Array2<f64>, Array2<f64>), DuckyError> {
+        let weights = self.weights.read().unwrap();
+        let bias = self.bias.read().unwrap();
+
+        if weights.ncols() != input_batch.ncols() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![input_batch.nrows(), weights.ncols()],
+                found: input_batch.shape().to_vec(),
+            });
+        }
+        check_finite(input_batch)?;
+
+        let z = input_batch.dot(&weights.t()) + &*bias;
+        let mut a = Array2::zeros(z.raw_dim());
+        for (mut a_row, z_row) in a.rows_mut().into_iter().zip(z.rows()) {
+            a_row.assign(&self.activate(z_row.to_owned())?);
+        }
+        Ok((z, a))
+    }
+
+    fn back_pass_batch(
+        &self,
+        input_batch: &Array2<f64>,
+        weighted_batch: &Array2<f64>,
+        output_gradient: &Array2<f64>,
+        learning_rate: f64,
+    ) -> Result<Array2<f64>, DuckyError> {
+        let mut weights = self.weights.write().unwrap();
+        let mut bias = self.bias.write().unwrap();
+
+        if input_batch.ncols() != weights.ncols() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![input_batch.nrows(), weights.ncols()],
+                found: input_batch.shape().to_vec(),
+            });
+        }
+        for array in [weighted_batch, output_gradient] {
+            if array.shape() != [input_batch.nrows(), weights.nrows()] {
+                return Err(DuckyError::ShapeMismatch {
+                    expected: vec![input_batch.nrows(), weights.nrows()],
+                    found: array.shape().to_vec(),
+                });
+            }
+        }
+
+        let mut delta = Array2::zeros(output_gradient.raw_dim());
+        for ((mut delta_row, z_row), gradient_row) in delta
+            .rows_mut()
+            .into_iter()
+            .zip(weighted_batch.rows())
+            .zip(output_gradient.rows())
+        {
+            delta_row.assign(
+                &self.weighted_input_gradient(&z_row.to_owned(), &gradient_row.to_owned())?,
+            );
+        }
+        let input_gradient = delta.dot(&*weights);
+        let weight_gradient = delta.t().dot(input_batch);
+        self.step(
+            &mut weights,
+            &mut bias,
+            weight_gradient,
+            delta.sum_axis(Axis(0)),
+            learning_rate,
+        );
+
+        Ok(input_gradient)
+    }
+
+    fn penalty(&self) -> f64 {
+        let kernel = self.kernel_regularizer.map_or(0., |regularizer| {
+            regularizer.penalty(&*self.weights.read().unwrap())
+        });
+        let bias = self.bias_regularizer.map_or(0., |regularizer| {
+            regularizer.penalty(&*self.bias.read().unwrap())
+        });
+        kernel + bias
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        match self.activation {
+            DenseActivation::Named(_) => Ok(SavedLayer::Dense1d(self.clone())),
+            _ => Err(cannot_save::<Self>()),
+        }
+    }
+}
+
+impl Transform<Array1<f64>> for Dense1d {
+    type Output = Array1<f64>;
+
+    fn transform(&self, input_array: &Array1<f64>) -> Result<Array1<f64>, DuckyError> {
+        Ok(self.pass(input_array.clone(), Mode::Eval)?.1)
+    }
+}
+
+impl<L: Layer1d + ?Sized> Layer1d for Box<L> {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        (**self).pass(input_array, mode)
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        (**self).back_pass(input_array, weighted_input, output_gradient, learning_rate)
+    }
+
+    fn pass_batch(
+        &self,
+        input_batch: &Array2<f64>,
+        mode: Mode,
+    ) -> Result<(Array2<f64>, Array2<f64>), DuckyError> {
+        (**self).pass_batch(input_batch, mode)
+    }
+
+    fn back_pass_batch(
+        &self,
+        input_batch: &Array2<f64>,
+        weighted_batch: &Array2<f64>,
+        output_gradient: &Array2<f64>,
+        learning_rate: f64,
+    ) -> Result<Array2<f64>, DuckyError> {
+        (**self).back_pass_batch(input_batch, weighted_batch, output_gradient, learning_rate)
+    }
+
+    fn penalty(&self) -> f64 {
+        (**self).penalty()
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        (**self).to_saved()
+    }
+}
+
+/// Error for a layer that can not be saved.
+pub(crate) fn cannot_save<T: ?Sized>() -> DuckyError {
+    DuckyError::Persistence(format!(
+        "{} can not be saved, see layers::SavedLayer for the layers that can",
+        std::any::type_name::<T>()
+    ))
+}
+
+/// Every layer of the crate a model of boxed layers can hold when it is saved, as returned by
+/// `Layer1d::to_saved`. It is how a `Vec<Box<dyn Layer1d>>`, a `DenseClassifier` of boxed
+/// layers or a `Graph` is written with `traits::Persist`, the state of every layer included.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::activations::Activation;
+/// use ducky_learn::layers::*;
+/// use ducky_learn::traits::Persist;
+/// use ndarray::arr1;
+///
+/// let model: Vec<Box<dyn Layer1d>> = vec![
+///     Box::new(Dense1d::new_named(2, 2, Activation::Relu, Some(1))),
+///     Box::new(BatchNorm1d::new(2, 0.1, 1e-5).unwrap()),
+/// ];
+/// model[1].pass(arr1(&[1., 2.]), Mode::Train).unwrap();
+///
+/// let mut saved = Vec::new();
+/// model.write_to(&mut saved).unwrap();
+/// let loaded = Vec::<Box<dyn Layer1d>>::read_from(saved.as_slice()).unwrap();
+///
+/// assert_eq!(
+///     loaded[1].pass(arr1(&[0., 0.]), Mode::Eval).unwrap(),
+///     model[1].pass(arr1(&[0., 0.]), Mode::Eval).unwrap()
+/// );
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SavedLayer {
+    Dense1d(Dense1d),
+    Dropout(Dropout),
+    BatchNorm1d(BatchNorm1d),
+    LayerNorm(LayerNorm),
+    Embedding(Embedding),
+    MultiHeadAttention(MultiHeadAttention),
+    SinusoidalEncoding(SinusoidalEncoding),
+    LearnedEncoding(LearnedEncoding),
+    TransformerEncoderLayer(Box<TransformerEncoderLayer>),
+    MaxPool1d(MaxPool1d),
+    SimpleRnn(Recurrent<SimpleRnnCell>),
+    Lstm(Recurrent<LstmCell>),
+    Gru(Recurrent<GruCell>),
+    BidirectionalSimpleRnn(Bidirectional<Recurrent<SimpleRnnCell>>),
+    BidirectionalLstm(Bidirectional<Recurrent<LstmCell>>),
+    BidirectionalGru(Bidirectional<Recurrent<GruCell>>),
+}
+
+impl SavedLayer {
+    /// The saved layer, boxed to go back into a model.
+    pub fn into_layer(self) -> Box<dyn Layer1d> {
+        match self {
+            SavedLayer::Dense1d(layer) => Box::new(layer),
+            SavedLayer::Dropout(layer) => Box::new(layer),
+            SavedLayer::BatchNorm1d(layer) => Box::new(layer),
+            SavedLayer::LayerNorm(layer) => Box::new(layer),
+            SavedLayer::Embedding(layer) => Box::new(layer),
+            SavedLayer::MultiHeadAttention(layer) => Box::new(layer),
+            SavedLayer::SinusoidalEncoding(layer) => Box::new(layer),
+            SavedLayer::LearnedEncoding(layer) => Box::new(layer),
+            SavedLayer::TransformerEncoderLayer(layer) => layer,
+            SavedLayer::MaxPool1d(layer) => Box::new(layer),
+            SavedLayer::SimpleRnn(layer) => Box::new(layer),
+            SavedLayer::Lstm(layer) => Box::new(layer),
+            SavedLayer::Gru(layer) => Box::new(layer),
+            SavedLayer::BidirectionalSimpleRnn(layer) => Box::new(layer),
+            SavedLayer::BidirectionalLstm(layer) => Box::new(layer),
+            SavedLayer::BidirectionalGru(layer) => Box::new(layer),
+        }
+    }
+}
+
+impl Serialize for Box<dyn Layer1d> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_saved()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Layer1d> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(SavedLayer::deserialize(deserializer)?.into_layer())
+    }
+}
+
+/// Inverted dropout layer. While training it zeroes each value with probability `rate` and
+/// scales the rest up by `1 / (1 - rate)`, so the expected output matches the input and
+/// nothing has to be rescaled at inference, where the layer passes its input straight through.
+///
+/// The mask drawn by the last training pass, one row per sample, is kept so `back_pass` and
+/// `back_pass_batch` only let the gradient through the values that were kept.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::layers::*;
+/// use ndarray::arr1;
+///
+/// let layer = Dropout::new(0.5, Some(42)).unwrap();
+/// let input_array = arr1(&[1., 1., 1., 1.]);
+///
+/// let (_, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+/// assert!(a.iter().all(|value| *value == 0. || *value == 2.));
+///
+/// let (_, a) = layer.pass(input_array.clone(), Mode::Eval).unwrap();
+/// assert_eq!(a, input_array);
+/// ```
+///
+/// Saving the layer keeps its rate. Once loaded it draws its masks from a new seed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Dropout {
+    rate: f64,
+    #[serde(skip, default = "unseeded_rng")]
+    rng: Mutex<StdRng>,
+    #[serde(skip)]
+    mask: RwLock<Array2<f64>>,
+}
+
+fn unseeded_rng() -> Mutex<StdRng> {
+    Mutex::new(seeded_rng(None))
+}
+
+impl Dropout {
+    /// Create Dropout layer
+    ///
+    /// # Arguments
+    ///
+    /// * `rate`: Probability of dropping each value while training, in [0, 1)
+    /// * `seed`: Seed for drawing the masks, or `None` to seed it from the operating system
+    ///
+    /// returns: `Result<Dropout, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `rate` is outside of [0, 1)
+    pub fn new(rate: f64, seed: Option<u64>) -> Result<Self, DuckyError> {
+        if !(0.0..1.0).contains(&rate) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "dropout rate has to be in [0, 1), found {}",
+                rate
+            )));
+        }
+
+        Ok(Self {
+            rate,
+            rng: Mutex::new(seeded_rng(seed)),
+            mask: RwLock::new(Array2::zeros((0, 0))),
+        })
+    }
+
+    /// Probability of dropping each value while training.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Draws a mask of the given shape in `Mode::Train` and keeps it for the backward pass.
+    fn draw_mask(&self, shape: (usize, usize), mode: Mode) -> Array2<f64> {
+        let mask = match mode {
+            Mode::Train => {
+                let mut rng = self.rng.lock().unwrap();
+                let scale = 1.0 / (1.0 - self.rate);
+                Array2::from_shape_simple_fn(shape, || {
+                    if rng.gen::<f64>() < self.rate {
+                        0.0
+                    } else {
+                        scale
+                    }
+                })
+            }
+            Mode::Eval => Array2::ones(shape),
+        };
+
+        *self.mask.write().unwrap() = mask.clone();
+        mask
+    }
+}
+
+impl Clone for Dropout {
+    fn clone(&self) -> Self {
+        Self {
+            rate: self.rate,
+            rng: Mutex::new(self.rng.lock().unwrap().clone()),
+            mask: RwLock::new(self.mask.read().unwrap().clone()),
+        }
+    }
+}
+
+impl Layer1d for Dropout {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        check_finite(&input_array)?;
+
+        let mask = self.draw_mask((1, input_array.len()), mode);
+        let a = &input_array * &mask.row(0);
+        Ok((input_array, a))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let mask = self.mask.read().unwrap();
+
+        if mask.shape() != [1, output_gradient.len()] {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![mask.ncols()],
+                found: output_gradient.shape().to_vec(),
+            });
+        }
+
+        back_mask(output_gradient, mask.row(0).to_owned())
+    }
+
+    fn pass_batch(
+        &self,
+        input_batch: &Array2<f64>,
+        mode: Mode,
+    ) -> Result<(Array2<f64>, Array2<f64>), DuckyError> {
+        check_finite(input_batch)?;
+
+        let mask = self.draw_mask(input_batch.dim(), mode);
+        Ok((input_batch.clone(), input_batch * &mask))
+    }
+
+    fn back_pass_batch(
+        &self,
+        input_batch: &Array2<f64>,
+        weighted_batch: &Array2<f64>,
+        output_gradient: &Array2<f64>,
+        learning_rate: f64,
+    ) -> Result<Array2<f64>, DuckyError> {
+        let mask = self.mask.read().unwrap();
+
+        if output_gradient.shape() != mask.shape() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: mask.shape().to_vec(),
+                found: output_gradient.shape().to_vec(),
+            });
+        }
+
+        back_mask(output_gradient, mask.clone())
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::Dropout(self.clone()))
+    }
+}
+
+/// Checks that `momentum` is in [0, 1], `epsilon` is positive and there is at least one
+/// feature to normalize.
+fn check_norm_parameters(
+    num_features: usize,
+    momentum: f64,
+    epsilon: f64,
+) -> Result<(), DuckyError> {
+    if num_features == 0 {
+        return Err(DuckyError::InvalidParameter(
+            "normalization needs at least one feature".to_string(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&momentum) {
+        return Err(DuckyError::InvalidParameter(format!(
+            "momentum has to be in [0, 1], found {}",
+            momentum
+        )));
+    }
+    if !(epsilon > 0.0 && epsilon.is_finite()) {
+        return Err(DuckyError::InvalidParameter(format!(
+            "epsilon has to be positive, found {}",
+            epsilon
+        )));
+    }
+
+    Ok(())
+}
+
+/// Records shifting and scaling `x` to zero mean and unit variance along `axis`, with its own
+/// mean and variance, on the tape of `x`.
+fn standardize(x: &Tensor, axis: usize, epsilon: f64) -> Result<Tensor, DuckyError> {
+    let centered = x.try_sub(&x.mean_axis(axis)?.insert_axis(axis)?)?;
+    let var = centered.powi(2).mean_axis(axis)?.insert_axis(axis)?;
+
+    centered.try_div(&(&var + epsilon).sqrt())
+}
+
+/// Records normalizing `x` with fixed statistics, such as running ones, on the tape of `x`.
+fn normalize_with(
+    x: &Tensor,
+    mean: Array1<f64>,
+    var: Array1<f64>,
+    epsilon: f64,
+) -> Result<Tensor, DuckyError> {
+    let std = var.mapv(|value| (value + epsilon).sqrt());
+
+    x.try_sub(&x.tape().variable(mean))?
+        .try_div(&x.tape().variable(std))
+}
+
+/// Records scaling `x_hat` by `gamma` and shifting it by `beta`, returning the output with the
+/// variables `gamma` and `beta` were recorded as.
+fn scale_shift(
+    x_hat: &Tensor,
+    gamma: &Array1<f64>,
+    beta: &Array1<f64>,
+) -> Result<(Tensor, Tensor, Tensor), DuckyError> {
+    let gamma = x_hat.tape().variable(gamma.clone());
+    let beta = x_hat.tape().variable(beta.clone());
+
+    Ok((x_hat.try_mul(&gamma)?.try_add(&beta)?, gamma, beta))
+}
+
+/// Back propagates `output_gradient` from `x_hat` scaled by `gamma` and shifted by `beta`,
+/// takes a gradient descent step on both and returns the gradient with respect to `x`, the
+/// tensor `x_hat` was normalized from.
+fn back_scale_shift<D: Dimension>(
+    x: &Tensor,
+    x_hat: &Tensor,
+    output_gradient: &Array<f64, D>,
+    gamma: &mut Array1<f64>,
+    beta: &mut Array1<f64>,
+    learning_rate: f64,
+) -> Result<Array<f64, D>, DuckyError> {
+    let (output, gamma_variable, beta_variable) = scale_shift(x_hat, gamma, beta)?;
+    let gradients = output.backward_with(output_gradient)?;
+
+    gamma.scaled_add(
+        -learning_rate,
+        &gradients
+            .wrt(&gamma_variable)
+            .into_dimensionality::<Ix1>()
+            .unwrap(),
+    );
+    beta.scaled_add(
+        -learning_rate,
+        &gradients
+            .wrt(&beta_variable)
+            .into_dimensionality::<Ix1>()
+            .unwrap(),
+    );
+    Ok(gradients.wrt(x).into_dimensionality::<D>().unwrap())
+}
+
+/// Batch normalization layer. Every feature is shifted and scaled to zero mean and unit
+/// variance, then scaled by the learnable `gamma` and shifted by the learnable `beta`.
+///
+/// `pass_batch` and `back_pass_batch` are true batch normalization: while training they
+/// normalize a whole batch of rows with the mean and variance of the batch, back propagate
+/// through those statistics and update the running statistics with `momentum`. They are what
+/// `train::train_batches` and a `DenseClassifier` with a batch size above one train with. A
+/// batch without rows is a `DuckyError::EmptyInput`.
+///
+/// `pass` and `back_pass` see one sample at a time, as `train`, a `DenseClassifier` with a
+/// batch size of one and `Graph` do, and a single sample has no variance of its own. In
+/// `Mode::Train` they make the layer an exponential
+/// moving average normalizer instead: every sample first updates the running mean and variance
+/// with `momentum`, then is normalized with them. Gradients do not flow through these statistics,
+/// so training this way behaves differently to training with batches.
+///
+/// Either way the running mean and variance are what `Mode::Eval` normalizes with.
+///
+/// The running statistics are saved with the layer by `traits::Persist`, whether it is saved
+/// on its own or as part of a `Vec<Box<dyn Layer1d>>`, `DenseClassifier` or `Graph`, see
+/// `SavedLayer`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::layers::*;
+/// use ndarray::{arr1, arr2};
+///
+/// let layer = BatchNorm1d::new(2, 0.1, 1e-5).unwrap();
+/// let x = arr2(&[[1., 10.], [3., 30.]]);
+///
+/// let (x_hat, _) = layer.pass_batch(&x, Mode::Train).unwrap();
+///
+/// assert!((x_hat[[0, 0]] + 1.).abs() < 1e-4);
+/// assert!((x_hat[[1, 1]] - 1.).abs() < 1e-4);
+/// assert_eq!(layer.running_mean(), arr1(&[0.2, 2.]));
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchNorm1d {
+    momentum: f64,
+    epsilon: f64,
+    gamma: RwLock<Array1<f64>>,
+    beta: RwLock<Array1<f64>>,
+    running_mean: RwLock<Array1<f64>>,
+    running_var: RwLock<Array1<f64>>,
+}
+
+impl BatchNorm1d {
+    /// Create BatchNorm1d layer with `gamma` set to one, `beta` to zero and running statistics
+    /// of zero mean and unit variance
+    ///
+    /// # Arguments
+    ///
+    /// * `num_features`: Size of the input array
+    /// * `momentum`: Weight of each new batch in the running statistics, in [0, 1]
+    /// * `epsilon`: Added to the variance to avoid dividing by zero
+    ///
+    /// returns: `Result<BatchNorm1d, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `num_features` is zero, `momentum` is outside of
+    ///   [0, 1] or `epsilon` is not positive
+    pub fn new(num_features: usize, momentum: f64, epsilon: f64) -> Result<Self, DuckyError> {
+        check_norm_parameters(num_features, momentum, epsilon)?;
+
+        Ok(Self {
+            momentum,
+            epsilon,
+            gamma: RwLock::new(Array1::ones(num_features)),
+            beta: RwLock::new(Array1::zeros(num_features)),
+            running_mean: RwLock::new(Array1::zeros(num_features)),
+            running_var: RwLock::new(Array1::ones(num_features)),
+        })
+    }
+
+    /// Weight of each new batch in the running statistics.
+    pub fn momentum(&self) -> f64 {
+        self.momentum
+    }
+
+    /// Value added to the variance to avoid dividing by zero.
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Learnable scale of every feature.
+    pub fn gamma(&self) -> Array1<f64> {
+        self.gamma.read().unwrap().clone()
+    }
+
+    /// Learnable shift of every feature.
+    pub fn beta(&self) -> Array1<f64> {
+        self.beta.read().unwrap().clone()
+    }
+
+    /// Running mean of every feature, used for inference.
+    pub fn running_mean(&self) -> Array1<f64> {
+        self.running_mean.read().unwrap().clone()
+    }
+
+    /// Running variance of every feature, used for inference.
+    pub fn running_var(&self) -> Array1<f64> {
+        self.running_var.read().unwrap().clone()
+    }
+
+    fn check_batch(&self, x: &Array2<f64>) -> Result<(), DuckyError> {
+        let num_features = self.gamma.read().unwrap().len();
+
+        if x.ncols() != num_features {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![x.nrows(), num_features],
+                found: x.shape().to_vec(),
+            });
+        }
+        if x.nrows() == 0 {
+            return Err(DuckyError::EmptyInput);
+        }
+        check_finite(x)
+    }
+}
+
+impl Clone for BatchNorm1d {
+    fn clone(&self) -> Self {
+        Self {
+            momentum: self.momentum,
+            epsilon: self.epsilon,
+            gamma: RwLock::new(self.gamma()),
+            beta: RwLock::new(self.beta()),
+            running_mean: RwLock::new(self.running_mean()),
+            running_var: RwLock::new(self.running_var()),
+        }
+    }
+}
+
+impl Layer1d for BatchNorm1d {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        check_targets(self.gamma.read().unwrap().len(), input_array.len())?;
+        check_finite(&input_array)?;
+
+        if mode == Mode::Train {
+            // Exponentially weighted mean and variance, both measured from the old mean
+            let mut running_mean = self.running_mean.write().unwrap();
+            let mut running_var = self.running_var.write().unwrap();
+            let delta = &input_array - &*running_mean;
+            running_mean.scaled_add(self.momentum, &delta);
+            *running_var = (&*running_var + delta.mapv(|value| value * value) * self.momentum)
+                * (1.0 - self.momentum);
+        }
+
+        let x_hat = normalize_with(
+            &Tape::new().variable(input_array),
+            self.running_mean(),
+            self.running_var(),
+            self.epsilon,
+        )?;
+        let (output, ..) = scale_shift(
+            &x_hat,
+            &self.gamma.read().unwrap(),
+            &self.beta.read().unwrap(),
+        )?;
+        Ok((x_hat.value_1d(), output.value_1d()))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let mut gamma = self.gamma.write().unwrap();
+        let mut beta = self.beta.write().unwrap();
+
+        for array in [input_array, weighted_input, output_gradient] {
+            check_targets(gamma.len(), array.len())?;
+        }
+
+        // The running statistics are constants of the forward pass, so the gradient does not
+        // flow through them
+        let x = Tape::new().variable(input_array.clone());
+        let x_hat = normalize_with(&x, self.running_mean(), self.running_var(), self.epsilon)?;
+        back_scale_shift(
+            &x,
+            &x_hat,
+            output_gradient,
+            &mut gamma,
+            &mut beta,
+            learning_rate,
+        )
+    }
+
+    fn pass_batch(
+        &self,
+        x: &Array2<f64>,
+        mode: Mode,
+    ) -> Result<(Array2<f64>, Array2<f64>), DuckyError> {
+        self.check_batch(x)?;
+
+        let (mean, var) = match mode {
+            Mode::Train => {
+                let mean = x.mean_axis(Axis(0)).unwrap();
+                let var = x.var_axis(Axis(0), 0.0);
+                // The running variance estimates the variance of the data, not of the batch
+                let unbiased = match x.nrows() {
+                    1 => var.clone(),
+                    n => &var * (n as f64 / (n - 1) as f64),
+                };
+
+                let mut running_mean = self.running_mean.write().unwrap();
+                let mut running_var = self.running_var.write().unwrap();
+                *running_mean = &*running_mean * (1.0 - self.momentum) + &mean * self.momentum;
+                *running_var = &*running_var * (1.0 - self.momentum) + unbiased * self.momentum;
+                (mean, var)
+            }
+            Mode::Eval => (self.running_mean(), self.running_var()),
+        };
+
+        let x_hat = normalize_with(&Tape::new().variable(x.clone()), mean, var, self.epsilon)?;
+        let (output, ..) = scale_shift(
+            &x_hat,
+            &self.gamma.read().unwrap(),
+            &self.beta.read().unwrap(),
+        )?;
+        Ok((x_hat.value_as::<Ix2>(), output.value_as::<Ix2>()))
+    }
+
+    fn back_pass_batch(
+        &self,
+        x: &Array2<f64>,
+        weighted_batch: &Array2<f64>,
+        output_gradient: &Array2<f64>,
+        learning_rate: f64,
+    ) -> Result<Array2<f64>, DuckyError> {
+        self.check_batch(x)?;
+        for array in [weighted_batch, output_gradient] {
+            if array.shape() != x.shape() {
+                return Err(DuckyError::ShapeMismatch {
+                    expected: x.shape().to_vec(),
+                    found: array.shape().to_vec(),
+                });
+            }
+        }
+
+        let mut gamma = self.gamma.write().unwrap();
+        let mut beta = self.beta.write().unwrap();
+
+        // The gradient flows through the mean and variance of the batch as well
+        let x = Tape::new().variable(x.clone());
+        let x_hat = standardize(&x, 0, self.epsilon)?;
+        back_scale_shift(
+            &x,
+            &x_hat,
+            output_gradient,
+            &mut gamma,
+            &mut beta,
+            learning_rate,
+        )
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::BatchNorm1d(self.clone()))
+    }
+}
+
+/// Layer normalization layer. Every sample is shifted and scaled to zero mean and unit
+/// variance across its features, then scaled by the learnable `gamma` and shifted by the
+/// learnable `beta`. Unlike `BatchNorm1d` it keeps no running statistics, so it behaves the
+/// same while training and at inference.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::layers::*;
+/// use ndarray::arr1;
+///
+/// let layer = LayerNorm::new(4, 1e-5).unwrap();
+///
+/// let (_, a) = layer.pass(arr1(&[1., 2., 3., 4.]), Mode::Eval).unwrap();
+///
+/// assert!(a.sum().abs() < 1e-9);
+/// assert!((a.var(0.) - 1.).abs() < 1e-4);
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LayerNorm {
+    epsilon: f64,
+    gamma: RwLock<Array1<f64>>,
+    beta: RwLock<Array1<f64>>,
+}
+
+impl LayerNorm {
+    /// Create LayerNorm layer with `gamma` set to one and `beta` to zero
+    ///
+    /// # Arguments
+    ///
+    /// * `num_features`: Size of the input array
+    /// * `epsilon`: Added to the variance to avoid dividing by zero
+    ///
+    /// returns: `Result<LayerNorm, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `num_features` is zero or `epsilon` is not positive
+    pub fn new(num_features: usize, epsilon: f64) -> Result<Self, DuckyError> {
+        check_norm_parameters(num_features, 0.0, epsilon)?;
+
+        Ok(Self {
+            epsilon,
+            gamma: RwLock::new(Array1::ones(num_features)),
+            beta: RwLock::new(Array1::zeros(num_features)),
+        })
+    }
+
+    /// Value added to the variance to avoid dividing by zero.
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Learnable scale of every feature.
+    pub fn gamma(&self) -> Array1<f64> {
+        self.gamma.read().unwrap().clone()
+    }
+
+    /// Learnable shift of every feature.
+    pub fn beta(&self) -> Array1<f64> {
+        self.beta.read().unwrap().clone()
+    }
+}
+
+impl Clone for LayerNorm {
+    fn clone(&self) -> Self {
+        Self {
+            epsilon: self.epsilon,
+            gamma: RwLock::new(self.gamma()),
+            beta: RwLock::new(self.beta()),
+        }
+    }
+}
+
+impl Layer1d for LayerNorm {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        _mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        check_targets(self.gamma.read().unwrap().len(), input_array.len())?;
+        check_finite(&input_array)?;
+
+        let x_hat = standardize(&Tape::new().variable(input_array), 0, self.epsilon)?;
+        let (output, ..) = scale_shift(
+            &x_hat,
+            &self.gamma.read().unwrap(),
+            &self.beta.read().unwrap(),
+        )?;
+        Ok((x_hat.value_1d(), output.value_1d()))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let mut gamma = self.gamma.write().unwrap();
+        let mut beta = self.beta.write().unwrap();
+
+        for array in [input_array, weighted_input, output_gradient] {
+            check_targets(gamma.len(), array.len())?;
+        }
+
+        let x = Tape::new().variable(input_array.clone());
+        let x_hat = standardize(&x, 0, self.epsilon)?;
+        back_scale_shift(
+            &x,
+            &x_hat,
+            output_gradient,
+            &mut gamma,
+            &mut beta,
+            learning_rate,
+        )
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::LayerNorm(self.clone()))
+    }
+}
+
+#[cfg(test)]
+mod layers_tests {
+    use super::*;
+    use crate::activations::*;
+    use crate::test_util::numerical_gradient;
+    use crate::traits::Persist;
+    use ndarray::*;
+
+    #[test]
+    fn dense1d_pass_arr1_1() {
+        let layer = Dense1d::from(
+            |x| x,
+            |x| x,
+            arr2(&[[1., 1., 1.], [1., 1., 1.], [1., 1., 1.]]),
+            arr1(&[1., 1., 1.]),
+        );
+        let input_array = arr1(&[1., 1., 1.]);
+
+        assert_eq!(
+            layer.pass(input_array, Mode::Eval).unwrap().1,
+            arr1(&[4., 4., 4.])
+        )
+    }
+
+    #[test]
+    fn dense1d_pass_arr1_2() {
+        let layer = Dense1d::from(
+            |x| x,
+            |x| x,
+            arr2(&[
+                [1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1.],
+                [1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1.],
+                [1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1.],
+            ]),
+            arr1(&[1., 1., 1.]),
+        );
+        let input_array = arr1(&[1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1., 1.]);
+
+        assert_eq!(
+            layer.pass(input_array, Mode::Eval).unwrap().1,
+            arr1(&[13.0, 13.0, 13.0])
+        )
+    }
+
+    #[test]
+    fn dense1d_pass_arr1_diff_size() {
+        let layer = Dense1d::from(
+            |x| x,
+            |x| x,
+            arr2(&[[1., 1., 1., 1.], [1., 1., 1., 1.]]),
+            arr1(&[0., 0.]),
+        );
+        let input_array = arr1(&[1.]);
+
+        assert_eq!(
+            layer.pass(input_array, Mode::Eval),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![4],
+                found: vec![1]
+            })
+        );
+    }
+
+    #[test]
+    fn dense1d_pass_arr1_nan() {
+        let layer = Dense1d::from(|x| x, |x| x, arr2(&[[1., 1.]]), arr1(&[0.]));
+
+        assert_eq!(
+            layer.pass(arr1(&[1., f64::NAN]), Mode::Eval),
+            Err(DuckyError::NonFinite)
+        );
+    }
+
+    #[test]
+    fn dense1d_new() {
+        let layer = Dense1d::new(5, 10, |x| x, |x| x);
+
+        let input_array = arr1(&[1., 1., 1., 1., 1.]);
+
+        layer.pass(input_array, Mode::Eval).unwrap();
+    }
+
+    #[test]
+    fn dense1d_back_pass() {
+        let layer = Dense1d::from(
+            relu_1d,
+            deriv_relu_1d,
+            arr2(&[[1., -1.], [2., 1.]]),
+            arr1(&[0., 0.]),
+        );
+        let input_array = arr1(&[1., 2.]);
+        let (z, _) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+
+        // The first node is switched off by relu, so only the second one learns
+        let input_gradient = layer
+            .back_pass(&input_array, &z, &arr1(&[1., 1.]), 0.1)
+            .unwrap();
+
+        assert_eq!(input_gradient, arr1(&[2., 1.]));
+        assert_eq!(
+            *layer.weights.read().unwrap(),
+            arr2(&[[1., -1.], [1.9, 0.8]])
+        );
+        assert_eq!(*layer.bias.read().unwrap(), arr1(&[0., -0.1]));
+    }
+
+    #[test]
+    fn dense1d_back_pass_diff_size() {
+        let layer = Dense1d::from(|x| x, |x| x, arr2(&[[1., 1.]]), arr1(&[0.]));
+
+        assert!(matches!(
+            layer.back_pass(&arr1(&[1.]), &arr1(&[1.]), &arr1(&[1.]), 0.1),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            layer.back_pass(&arr1(&[1., 1.]), &arr1(&[1.]), &arr1(&[1., 1.]), 0.1),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn dense1d_clone() {
+        let layer = Dense1d::new(2, 2, |x| x, |x| x);
+        let copy = layer.clone();
+        let input_array = arr1(&[1., 1.]);
+        let (z, _) = copy.pass(input_array.clone(), Mode::Train).unwrap();
+
+        copy.back_pass(&input_array, &z, &arr1(&[1., 1.]), 0.1)
+            .unwrap();
+
+        assert_ne!(
+            layer.pass(input_array.clone(), Mode::Eval).unwrap(),
+            copy.pass(input_array, Mode::Eval).unwrap()
+        );
+    }
+
+    #[test]
+    fn dense1d_activation() {
         let layer = Dense1d::from(
             relu_1d,
             deriv_relu_1d,
@@ -211,6 +1861,494 @@ mod layers_tests {
         );
         let input_array = arr1(&[1., 1., 1.]);
 
-        assert_eq!(layer.pass(input_array).1, arr1(&[0., 0., 4.]))
+        assert_eq!(
+            layer.pass(input_array, Mode::Eval).unwrap().1,
+            arr1(&[0., 0., 4.])
+        )
+    }
+
+    #[test]
+    fn dropout_train_mask() {
+        let layer = Dropout::new(0.25, Some(42)).unwrap();
+        let input_array = Array1::from_elem(1000, 3.);
+
+        let (z, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let dropped = a.iter().filter(|value| **value == 0.).count();
+
+        assert_eq!(z, input_array);
+        assert!(a.iter().all(|value| *value == 0. || *value == 4.));
+        assert!(dropped > 200 && dropped < 300);
+    }
+
+    #[test]
+    fn dropout_eval_identity() {
+        let layer = Dropout::new(0.9, Some(42)).unwrap();
+        let input_array = arr1(&[1., -2., 3.]);
+
+        assert_eq!(
+            layer.pass(input_array.clone(), Mode::Eval).unwrap().1,
+            input_array
+        );
+        assert_eq!(
+            layer
+                .back_pass(&input_array, &input_array, &arr1(&[1., 1., 1.]), 0.1)
+                .unwrap(),
+            arr1(&[1., 1., 1.])
+        );
+    }
+
+    #[test]
+    fn dropout_seeded() {
+        let input_array = Array1::ones(50);
+        let first = Dropout::new(0.5, Some(7)).unwrap();
+        let second = Dropout::new(0.5, Some(7)).unwrap();
+
+        assert_eq!(
+            first.pass(input_array.clone(), Mode::Train).unwrap(),
+            second.pass(input_array, Mode::Train).unwrap()
+        );
+    }
+
+    #[test]
+    fn dropout_back_pass() {
+        let layer = Dropout::new(0.5, Some(42)).unwrap();
+        let input_array = Array1::ones(20);
+        let (z, a) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+
+        let input_gradient = layer
+            .back_pass(&input_array, &z, &Array1::ones(20), 0.1)
+            .unwrap();
+
+        assert_eq!(input_gradient, a);
+        assert!(matches!(
+            layer.back_pass(&input_array, &z, &Array1::ones(3), 0.1),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn dropout_back_pass_batch() {
+        let layer = Dropout::new(0.5, Some(42)).unwrap();
+        let input_batch = Array2::ones((4, 10));
+        let (z, a) = layer.pass_batch(&input_batch, Mode::Train).unwrap();
+
+        let input_gradient = layer
+            .back_pass_batch(&input_batch, &z, &Array2::ones((4, 10)), 0.1)
+            .unwrap();
+
+        assert_eq!(input_gradient, a);
+        assert_ne!(a.row(0), a.row(1));
+        assert!(matches!(
+            layer.back_pass(&arr1(&[1.]), &arr1(&[1.]), &arr1(&[1.]), 0.1),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn dropout_invalid_rate() {
+        assert!(matches!(
+            Dropout::new(1.0, None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            Dropout::new(-0.1, None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            Dropout::new(f64::NAN, None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn batch_norm_eval_running_stats() {
+        let layer = BatchNorm1d::new(2, 0.1, 1e-5).unwrap();
+        let (x_hat, a) = layer.pass(arr1(&[2., -1.]), Mode::Eval).unwrap();
+
+        assert!((&a - &arr1(&[2., -1.])).iter().all(|d| d.abs() < 1e-4));
+        assert_eq!(x_hat, a);
+        assert_eq!(layer.running_mean(), arr1(&[0., 0.]));
+        assert_eq!(layer.running_var(), arr1(&[1., 1.]));
+    }
+
+    #[test]
+    fn batch_norm_train_updates_running_stats() {
+        let layer = BatchNorm1d::new(2, 0.5, 1e-5).unwrap();
+        layer.pass(arr1(&[2., 4.]), Mode::Train).unwrap();
+
+        assert_eq!(layer.running_mean(), arr1(&[1., 2.]));
+        assert_eq!(layer.running_var(), arr1(&[1.5, 4.5]));
+
+        layer
+            .pass_batch(&arr2(&[[0., 0.], [2., 4.]]), Mode::Train)
+            .unwrap();
+
+        assert_eq!(layer.running_mean(), arr1(&[1., 2.]));
+        assert_eq!(layer.running_var(), arr1(&[1.75, 6.25]));
+    }
+
+    #[test]
+    fn batch_norm_back_pass() {
+        let layer = BatchNorm1d::new(2, 0.1, 1e-5).unwrap();
+        *layer.gamma.write().unwrap() = arr1(&[2., 3.]);
+        *layer.running_var.write().unwrap() = arr1(&[4., 9.]);
+        let input_array = arr1(&[2., 3.]);
+        let (x_hat, _) = layer.pass(input_array.clone(), Mode::Eval).unwrap();
+
+        let input_gradient = layer
+            .back_pass(&input_array, &x_hat, &arr1(&[1., 1.]), 0.1)
+            .unwrap();
+
+        assert!((&input_gradient - &arr1(&[1., 1.]))
+            .iter()
+            .all(|d| d.abs() < 1e-5));
+        assert!((&layer.gamma() - &(arr1(&[2., 3.]) - &x_hat * 0.1))
+            .iter()
+            .all(|d| d.abs() < 1e-12));
+        assert_eq!(layer.beta(), arr1(&[-0.1, -0.1]));
+    }
+
+    #[test]
+    fn batch_norm_batch_normalizes() {
+        let layer = BatchNorm1d::new(2, 0.1, 1e-8).unwrap();
+        let x = arr2(&[[1., 100.], [2., 300.], [6., 200.]]);
+
+        let (_, a) = layer.pass_batch(&x, Mode::Train).unwrap();
+
+        for column in a.columns() {
+            assert!(column.mean().unwrap().abs() < 1e-9);
+            assert!((column.var(0.) - 1.).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn batch_norm_back_pass_batch() {
+        let layer = BatchNorm1d::new(2, 0.1, 1e-5).unwrap();
+        *layer.gamma.write().unwrap() = arr1(&[1.5, -0.5]);
+        *layer.beta.write().unwrap() = arr1(&[0.2, 0.1]);
+        let x = arr2(&[[1., 4.], [2., -3.], [0.5, 2.]]);
+        let output_gradient = arr2(&[[0.3, -1.], [1., 0.2], [-0.4, 0.7]]);
+
+        let expected = numerical_gradient(&x, &output_gradient, |x| {
+            layer.clone().pass_batch(x, Mode::Train).unwrap().1
+        });
+        let (x_hat, _) = layer.clone().pass_batch(&x, Mode::Train).unwrap();
+        let input_gradient = layer
+            .back_pass_batch(&x, &x_hat, &output_gradient, 0.)
+            .unwrap();
+
+        assert!((&input_gradient - &expected).iter().all(|d| d.abs() < 1e-6));
+        assert!(matches!(
+            layer.back_pass_batch(&x, &x_hat, &arr2(&[[1., 1.]]), 0.1),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn batch_norm_invalid() {
+        let layer = BatchNorm1d::new(2, 0.1, 1e-5).unwrap();
+
+        assert!(matches!(
+            layer.pass(arr1(&[1.]), Mode::Train),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            layer.pass_batch(&Array2::zeros((0, 2)), Mode::Train),
+            Err(DuckyError::EmptyInput)
+        ));
+        assert!(matches!(
+            BatchNorm1d::new(2, 1.5, 1e-5),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            BatchNorm1d::new(2, 0.1, 0.),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            BatchNorm1d::new(0, 0.1, 1e-5),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn batch_norm_write_read() {
+        let layer = BatchNorm1d::new(3, 0.2, 1e-5).unwrap();
+        layer
+            .pass_batch(&arr2(&[[1., 2., 3.], [3., 5., 9.]]), Mode::Train)
+            .unwrap();
+
+        let mut saved = Vec::new();
+        layer.write_to(&mut saved).unwrap();
+        let loaded = BatchNorm1d::read_from(saved.as_slice()).unwrap();
+
+        assert_eq!(loaded.running_mean(), layer.running_mean());
+        assert_eq!(loaded.running_var(), layer.running_var());
+        assert_eq!(
+            loaded.pass(arr1(&[1., 1., 1.]), Mode::Eval).unwrap(),
+            layer.pass(arr1(&[1., 1., 1.]), Mode::Eval).unwrap()
+        );
+    }
+
+    #[test]
+    fn layer_norm_pass() {
+        let layer = LayerNorm::new(3, 1e-8).unwrap();
+
+        let (x_hat, a) = layer.pass(arr1(&[1., 4., 7.]), Mode::Train).unwrap();
+
+        assert_eq!(x_hat, a);
+        assert!((&a - &arr1(&[-1.5f64.sqrt(), 0., 1.5f64.sqrt()]))
+            .iter()
+            .all(|d| d.abs() < 1e-6));
+        assert!(matches!(
+            layer.pass(arr1(&[1., 2.]), Mode::Eval),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            LayerNorm::new(3, -1.),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn layer_norm_back_pass() {
+        let layer = LayerNorm::new(4, 1e-5).unwrap();
+        *layer.gamma.write().unwrap() = arr1(&[1., 2., -1., 0.5]);
+        let input_array = arr1(&[0.5, -1., 2., 3.]);
+        let output_gradient = arr1(&[1., -0.5, 0.3, 2.]);
+
+        let expected = numerical_gradient(&input_array, &output_gradient, |x| {
+            layer.pass(x.clone(), Mode::Train).unwrap().1
+        });
+        let (x_hat, _) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let input_gradient = layer
+            .back_pass(&input_array, &x_hat, &output_gradient, 0.1)
+            .unwrap();
+
+        assert!((&input_gradient - &expected).iter().all(|d| d.abs() < 1e-6));
+        assert_eq!(layer.beta(), &output_gradient * -0.1);
+    }
+
+    #[test]
+    fn layer_norm_write_read() {
+        let layer = LayerNorm::new(2, 1e-5).unwrap();
+        *layer.beta.write().unwrap() = arr1(&[0.5, -0.5]);
+
+        let mut saved = Vec::new();
+        layer.write_to(&mut saved).unwrap();
+        let loaded = LayerNorm::read_from(saved.as_slice()).unwrap();
+
+        assert_eq!(loaded.beta(), layer.beta());
+        assert_eq!(loaded.epsilon(), layer.epsilon());
+    }
+
+    #[test]
+    fn boxed_model_write_read() {
+        let model: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(
+                Dense1d::new_named(3, 2, Activation::Tanh, Some(1))
+                    .with_regularizers(Some(Regularizer::L2(0.1)), None)
+                    .unwrap(),
+            ),
+            Box::new(BatchNorm1d::new(2, 0.2, 1e-5).unwrap()),
+            Box::new(Dropout::new(0.5, Some(2)).unwrap()),
+            Box::new(LayerNorm::new(2, 1e-5).unwrap()),
+        ];
+        for input_array in [arr1(&[1., 2., 3.]), arr1(&[-1., 0.5, 2.])] {
+            crate::train::forward_pass(&model, input_array, Mode::Train).unwrap();
+        }
+
+        let mut saved = Vec::new();
+        model.write_to(&mut saved).unwrap();
+        let loaded = Vec::<Box<dyn Layer1d>>::read_from(saved.as_slice()).unwrap();
+
+        let input_array = arr1(&[0.5, -1., 1.]);
+        assert_eq!(
+            crate::train::forward_pass(&loaded, input_array.clone(), Mode::Eval).unwrap(),
+            crate::train::forward_pass(&model, input_array, Mode::Eval).unwrap()
+        );
+        assert_eq!(loaded[0].penalty(), model[0].penalty());
+        match loaded[1].to_saved().unwrap() {
+            SavedLayer::BatchNorm1d(layer) => {
+                assert_ne!(layer.running_mean(), arr1(&[0., 0.]));
+            }
+            saved => panic!("expected a BatchNorm1d, found {:?}", saved),
+        }
+    }
+
+    #[test]
+    fn function_pointer_dense1d_can_not_be_saved() {
+        let layer = Dense1d::new(2, 2, relu_1d, deriv_relu_1d);
+        assert!(matches!(
+            layer.write_to(Vec::new()),
+            Err(DuckyError::Persistence(_))
+        ));
+
+        let model: Vec<Box<dyn Layer1d>> = vec![Box::new(layer)];
+        assert!(matches!(
+            model.write_to(Vec::new()),
+            Err(DuckyError::Persistence(_))
+        ));
+        assert!(matches!(
+            Dense1d::new_auto(2, 2, |z| z.relu(), None).to_saved(),
+            Err(DuckyError::Persistence(_))
+        ));
+    }
+
+    #[test]
+    fn dense1d_auto_matches_manual() {
+        let weights = arr2(&[[0.5, -1., 0.25], [1., 0.5, -0.5]]);
+        let bias = arr1(&[0.1, -0.2]);
+        let auto = Dense1d::from_auto(|z| z.relu(), weights.clone(), bias.clone());
+        let manual = Dense1d::from(relu_1d, deriv_relu_1d, weights, bias);
+        let input_array = arr1(&[1., 2., -1.]);
+        let output_gradient = arr1(&[0.5, -2.]);
+
+        let (auto_z, auto_a) = auto.pass(input_array.clone(), Mode::Train).unwrap();
+        let (manual_z, manual_a) = manual.pass(input_array.clone(), Mode::Train).unwrap();
+        assert_eq!(auto_z, manual_z);
+        assert_eq!(auto_a, manual_a);
+
+        assert_eq!(
+            auto.back_pass(&input_array, &auto_z, &output_gradient, 0.1)
+                .unwrap(),
+            manual
+                .back_pass(&input_array, &manual_z, &output_gradient, 0.1)
+                .unwrap()
+        );
+        assert_eq!(auto.weights(), manual.weights());
+        assert_eq!(auto.bias(), manual.bias());
+    }
+
+    #[test]
+    fn dense1d_batch_matches_rows() {
+        let weights = arr2(&[[0.5, -0.2], [0.1, 0.4], [-0.3, 0.2]]);
+        let bias = arr1(&[0.1, 0., -0.1]);
+        let layer = Dense1d::from_named(Activation::Softmax, weights.clone(), bias.clone());
+        let input_batch = arr2(&[[1., 2.], [-1., 0.5]]);
+        let output_gradient = arr2(&[[0.2, -0.1, 0.3], [-0.4, 0.1, 0.2]]);
+
+        let (z, a) = layer.pass_batch(&input_batch, Mode::Train).unwrap();
+        let mut input_gradients = Vec::new();
+        let mut weight_step = Array2::<f64>::zeros(weights.raw_dim());
+        for (row, gradient_row) in input_batch.rows().into_iter().zip(output_gradient.rows()) {
+            let row_layer = layer.clone();
+            let (row_z, row_a) = row_layer.pass(row.to_owned(), Mode::Train).unwrap();
+            assert!((&z.row(input_gradients.len()).to_owned() - &row_z)
+                .iter()
+                .all(|d| d.abs() < 1e-12));
+            assert!((&a.row(input_gradients.len()).to_owned() - &row_a)
+                .iter()
+                .all(|d| d.abs() < 1e-12));
+
+            input_gradients.push(
+                row_layer
+                    .back_pass(&row.to_owned(), &row_z, &gradient_row.to_owned(), 1.)
+                    .unwrap(),
+            );
+            weight_step = weight_step + &weights - row_layer.weights();
+        }
+
+        let input_gradient = layer
+            .back_pass_batch(&input_batch, &z, &output_gradient, 1.)
+            .unwrap();
+
+        assert!((&input_gradient - &stack_rows(&input_gradients).unwrap())
+            .iter()
+            .all(|d| d.abs() < 1e-12));
+        assert!((&layer.weights() - &(&weights - &weight_step))
+            .iter()
+            .all(|d| d.abs() < 1e-12));
+        assert!(matches!(
+            layer.pass_batch(&arr2(&[[1.]]), Mode::Eval),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            layer.back_pass_batch(&input_batch, &z, &arr2(&[[1., 1., 1.]]), 1.),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn dense1d_auto_errors() {
+        let layer = Dense1d::new_auto(3, 2, |z| z.tanh(), Some(1));
+
+        assert_eq!(
+            layer.pass(arr1(&[1., 2.]), Mode::Eval),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![3],
+                found: vec![2],
+            })
+        );
+        assert_eq!(
+            layer.back_pass(&arr1(&[1., 2., 3.]), &arr1(&[0., 0.]), &arr1(&[1.]), 0.1),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![1],
+            })
+        );
+        assert_eq!(
+            layer.pass(arr1(&[1., f64::NAN, 3.]), Mode::Eval),
+            Err(DuckyError::NonFinite)
+        );
+    }
+
+    #[test]
+    fn dense1d_regularized_step() {
+        let layer = Dense1d::from(|x| x, |x| x.map(|_| 1f64), arr2(&[[1., -2.]]), arr1(&[0.5]))
+            .with_regularizers(
+                Some(Regularizer::ElasticNet { l1: 0.1, l2: 0.5 }),
+                Some(Regularizer::L1(1.)),
+            )
+            .unwrap();
+
+        assert_eq!(layer.penalty(), 0.1 * 3. + 0.5 * 5. + 0.5);
+
+        let input_array = arr1(&[1., 1.]);
+        let (z, _) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        let input_gradient = layer
+            .back_pass(&input_array, &z, &arr1(&[1.]), 0.1)
+            .unwrap();
+
+        // The penalty only changes the parameters, not the gradient passed back
+        assert_eq!(input_gradient, arr1(&[1., -2.]));
+        let expected = arr2(&[[1. - 0.1 * (1. + 0.1 + 1.), -2. - 0.1 * (1. - 0.1 - 2.)]]);
+        assert!((layer.weights() - expected).iter().all(|x| x.abs() < 1e-12));
+        assert!((layer.bias()[0] - (0.5 - 0.1 * 2.)).abs() < 1e-12);
+        assert_eq!(layer.clone().penalty(), layer.penalty());
+    }
+
+    #[test]
+    fn dense1d_constrained_step() {
+        let layer = Dense1d::from(
+            |x| x,
+            |x| x.map(|_| 1f64),
+            arr2(&[[0., 0.], [1., 0.]]),
+            arr1(&[0., 0.]),
+        )
+        .with_constraints(Some(Constraint::UnitNorm), Some(Constraint::MaxNorm(1.)))
+        .unwrap();
+
+        let input_array = arr1(&[3., 4.]);
+        let (z, _) = layer.pass(input_array.clone(), Mode::Train).unwrap();
+        layer
+            .back_pass(&input_array, &z, &arr1(&[-1., 0.]), 1.)
+            .unwrap();
+
+        assert_eq!(layer.weights(), arr2(&[[0.6, 0.8], [1., 0.]]));
+        assert_eq!(layer.bias(), arr1(&[1., 0.]));
+    }
+
+    #[test]
+    fn dense1d_regularizer_errors() {
+        let layer = || Dense1d::new(2, 1, |x| x, |x| x);
+
+        assert!(matches!(
+            layer().with_regularizers(None, Some(Regularizer::L2(-1.))),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            layer().with_constraints(Some(Constraint::MaxNorm(f64::INFINITY)), None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
     }
 }
@@ -5,11 +5,29 @@
 extern crate ndarray;
 
 pub mod activations;
+pub mod attention;
+pub mod autograd;
+pub mod conv;
 pub mod cost;
+pub mod embedding;
+pub mod graph;
 pub mod layers;
 pub mod optimizers;
+pub mod recurrent;
+pub mod regularizers;
 pub mod train;
 
+pub mod error;
 pub mod feature_extraction;
+pub mod impute;
+pub mod metrics;
+pub mod model_selection;
 pub mod naive_bayes;
+pub mod pipeline;
+pub mod preprocessing;
+pub mod sparse;
+pub mod traits;
 pub mod util;
+
+#[cfg(test)]
+pub(crate) mod test_util;
@@ -0,0 +1,1662 @@
+extern crate ndarray;
+
+use super::error::{check_finite, check_targets, DuckyError};
+use super::preprocessing::quantile;
+use super::util::unique_labels;
+use ndarray::{Array1, Array2, ArrayBase, ArrayView1, ArrayView2, Axis, Data, Ix1, Ix2};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// How per-class scores are combined into a single score.
+///
+/// * `Micro` - Counts the true positives, false positives and false negatives over every class
+///   before working out the score.
+/// * `Macro` - The unweighted mean of the score of each class.
+/// * `Weighted` - The mean of the score of each class, weighted by its number of true samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Average {
+    Micro,
+    Macro,
+    Weighted,
+}
+
+/// Fraction of predictions that are correct.
+///
+/// # Arguments
+///
+/// * `y_true`: The true labels
+/// * `y_pred`: The predicted labels
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no labels.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_pred` differ in length.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::accuracy_score;
+///
+/// let y_true = vec!["cat", "dog", "dog", "cat"];
+/// let y_pred = vec!["cat", "dog", "cat", "cat"];
+///
+/// assert_eq!(accuracy_score(&y_true, &y_pred).unwrap(), 0.75);
+/// ```
+pub fn accuracy_score<L: PartialEq>(y_true: &[L], y_pred: &[L]) -> Result<f64, DuckyError> {
+    check_labels(y_true, y_pred)?;
+
+    let correct = y_true
+        .iter()
+        .zip(y_pred)
+        .filter(|(truth, prediction)| truth == prediction)
+        .count();
+    Ok(correct as f64 / y_true.len() as f64)
+}
+
+/// Counts of every combination of true and predicted label.
+///
+/// # Fields
+/// - `labels`: Every label seen in `y_true` or `y_pred`, in order of first appearance in
+///   `y_true` and then `y_pred`.
+/// - `counts`: `counts[[i, j]]` is the number of samples with true label `labels[i]` that were
+///   predicted as `labels[j]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfusionMatrix<L> {
+    pub labels: Vec<L>,
+    pub counts: Array2<usize>,
+}
+
+/// Builds the confusion matrix of a set of predictions.
+///
+/// # Arguments
+///
+/// * `y_true`: The true labels
+/// * `y_pred`: The predicted labels
+///
+/// returns: `Result<ConfusionMatrix<L>, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no labels.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_pred` differ in length.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::confusion_matrix;
+/// use ndarray::array;
+///
+/// let y_true = vec![1, 0, 1, 1];
+/// let y_pred = vec![1, 0, 0, 1];
+///
+/// let matrix = confusion_matrix(&y_true, &y_pred).unwrap();
+///
+/// assert_eq!(matrix.labels, vec![1, 0]);
+/// assert_eq!(matrix.counts, array![[2, 1], [0, 1]]);
+/// ```
+pub fn confusion_matrix<L: Hash + Eq + Clone>(
+    y_true: &[L],
+    y_pred: &[L],
+) -> Result<ConfusionMatrix<L>, DuckyError> {
+    check_labels(y_true, y_pred)?;
+
+    let labels = unique_labels(&[y_true, y_pred].concat());
+    let index: HashMap<&L, usize> = labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| (label, i))
+        .collect();
+
+    let mut counts = Array2::zeros((labels.len(), labels.len()));
+    for (truth, prediction) in y_true.iter().zip(y_pred) {
+        counts[[index[truth], index[prediction]]] += 1;
+    }
+
+    Ok(ConfusionMatrix { labels, counts })
+}
+
+impl<L> ConfusionMatrix<L> {
+    /// Precision, recall, F1 and support of every label, in the order of `labels`.
+    fn per_class(&self) -> Vec<ClassScores> {
+        let true_counts = self.counts.sum_axis(Axis(1));
+        let predicted_counts = self.counts.sum_axis(Axis(0));
+
+        (0..self.labels.len())
+            .map(|i| {
+                let true_positives = self.counts[[i, i]] as f64;
+                let precision = safe_divide(true_positives, predicted_counts[i] as f64);
+                let recall = safe_divide(true_positives, true_counts[i] as f64);
+                ClassScores {
+                    precision,
+                    recall,
+                    f1: f1(precision, recall),
+                    support: true_counts[i],
+                }
+            })
+            .collect()
+    }
+
+    /// Precision, recall and F1 of every label combined with `average`.
+    fn averaged(&self, average: Average) -> (f64, f64, f64) {
+        let scores = self.per_class();
+        match average {
+            Average::Micro => {
+                let true_positives: usize = self.counts.diag().sum();
+                let total: usize = self.counts.sum();
+                // Every false positive of one class is a false negative of another, so micro
+                // precision, recall and F1 are all the accuracy.
+                let micro = safe_divide(true_positives as f64, total as f64);
+                (micro, micro, micro)
+            }
+            Average::Macro => {
+                let n = scores.len() as f64;
+                (
+                    scores.iter().map(|score| score.precision).sum::<f64>() / n,
+                    scores.iter().map(|score| score.recall).sum::<f64>() / n,
+                    scores.iter().map(|score| score.f1).sum::<f64>() / n,
+                )
+            }
+            Average::Weighted => {
+                let total = scores.iter().map(|score| score.support).sum::<usize>() as f64;
+                let weighted = |value: fn(&ClassScores) -> f64| {
+                    scores
+                        .iter()
+                        .map(|score| value(score) * score.support as f64)
+                        .sum::<f64>()
+                        / total
+                };
+                (
+                    weighted(|score| score.precision),
+                    weighted(|score| score.recall),
+                    weighted(|score| score.f1),
+                )
+            }
+        }
+    }
+}
+
+/// Scores of a single class.
+#[derive(Debug, Clone, Copy)]
+struct ClassScores {
+    precision: f64,
+    recall: f64,
+    f1: f64,
+    support: usize,
+}
+
+/// Fraction of the samples predicted as a class that really are that class, combined over
+/// every class with `average`. Classes that are never predicted have a precision of 0.
+///
+/// # Arguments
+///
+/// * `y_true`: The true labels
+/// * `y_pred`: The predicted labels
+/// * `average`: How the precision of each class is combined
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no labels.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_pred` differ in length.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::{precision_score, Average};
+///
+/// let y_true = vec!["a", "a", "b", "b"];
+/// let y_pred = vec!["a", "b", "b", "b"];
+///
+/// // "a" has a precision of 1 and "b" of 2/3
+/// assert_eq!(precision_score(&y_true, &y_pred, Average::Macro).unwrap(), (1.0 + 2.0 / 3.0) / 2.0);
+/// ```
+pub fn precision_score<L: Hash + Eq + Clone>(
+    y_true: &[L],
+    y_pred: &[L],
+    average: Average,
+) -> Result<f64, DuckyError> {
+    Ok(confusion_matrix(y_true, y_pred)?.averaged(average).0)
+}
+
+/// Fraction of the samples of a class that were predicted as that class, combined over
+/// every class with `average`. Classes with no true samples have a recall of 0.
+///
+/// # Arguments
+///
+/// * `y_true`: The true labels
+/// * `y_pred`: The predicted labels
+/// * `average`: How the recall of each class is combined
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no labels.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_pred` differ in length.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::{recall_score, Average};
+///
+/// let y_true = vec!["a", "a", "b", "b"];
+/// let y_pred = vec!["a", "b", "b", "b"];
+///
+/// assert_eq!(recall_score(&y_true, &y_pred, Average::Macro).unwrap(), 0.75);
+/// ```
+pub fn recall_score<L: Hash + Eq + Clone>(
+    y_true: &[L],
+    y_pred: &[L],
+    average: Average,
+) -> Result<f64, DuckyError> {
+    Ok(confusion_matrix(y_true, y_pred)?.averaged(average).1)
+}
+
+/// Harmonic mean of precision and recall, combined over every class with `average`.
+///
+/// # Arguments
+///
+/// * `y_true`: The true labels
+/// * `y_pred`: The predicted labels
+/// * `average`: How the F1 score of each class is combined
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no labels.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_pred` differ in length.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::{f1_score, Average};
+///
+/// let y_true = vec!["a", "a", "b", "b"];
+/// let y_pred = vec!["a", "a", "b", "b"];
+///
+/// assert_eq!(f1_score(&y_true, &y_pred, Average::Weighted).unwrap(), 1.0);
+/// ```
+pub fn f1_score<L: Hash + Eq + Clone>(
+    y_true: &[L],
+    y_pred: &[L],
+    average: Average,
+) -> Result<f64, DuckyError> {
+    Ok(confusion_matrix(y_true, y_pred)?.averaged(average).2)
+}
+
+/// Precision, recall, F1 and support of every label, along with their averages.
+///
+/// Printing the report with `{}` gives a table of the scores.
+///
+/// # Fields
+/// - `labels`: Every label seen in `y_true` or `y_pred`, the order of every per-label field.
+/// - `precision`: Precision of each label.
+/// - `recall`: Recall of each label.
+/// - `f1`: F1 score of each label.
+/// - `support`: Number of true samples of each label.
+/// - `accuracy`: Fraction of predictions that are correct.
+/// - `macro_avg`: Macro averaged `(precision, recall, f1)`.
+/// - `weighted_avg`: Support weighted `(precision, recall, f1)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassificationReport<L> {
+    pub labels: Vec<L>,
+    pub precision: Vec<f64>,
+    pub recall: Vec<f64>,
+    pub f1: Vec<f64>,
+    pub support: Vec<usize>,
+    pub accuracy: f64,
+    pub macro_avg: (f64, f64, f64),
+    pub weighted_avg: (f64, f64, f64),
+}
+
+/// Builds a report of the main classification scores.
+///
+/// # Arguments
+///
+/// * `y_true`: The true labels
+/// * `y_pred`: The predicted labels
+///
+/// returns: `Result<ClassificationReport<L>, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no labels.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_pred` differ in length.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::classification_report;
+///
+/// let y_true = vec!["duck", "duck", "goose", "goose"];
+/// let y_pred = vec!["duck", "goose", "goose", "goose"];
+///
+/// let report = classification_report(&y_true, &y_pred).unwrap();
+///
+/// assert_eq!(report.recall, vec![0.5, 1.0]);
+/// assert_eq!(report.accuracy, 0.75);
+/// println!("{}", report);
+/// ```
+pub fn classification_report<L: Hash + Eq + Clone>(
+    y_true: &[L],
+    y_pred: &[L],
+) -> Result<ClassificationReport<L>, DuckyError> {
+    let matrix = confusion_matrix(y_true, y_pred)?;
+    let scores = matrix.per_class();
+
+    Ok(ClassificationReport {
+        precision: scores.iter().map(|score| score.precision).collect(),
+        recall: scores.iter().map(|score| score.recall).collect(),
+        f1: scores.iter().map(|score| score.f1).collect(),
+        support: scores.iter().map(|score| score.support).collect(),
+        accuracy: matrix.averaged(Average::Micro).0,
+        macro_avg: matrix.averaged(Average::Macro),
+        weighted_avg: matrix.averaged(Average::Weighted),
+        labels: matrix.labels,
+    })
+}
+
+impl<L: fmt::Display> fmt::Display for ClassificationReport<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self.labels.iter().map(|label| label.to_string()).collect();
+        let width = names
+            .iter()
+            .map(String::len)
+            .chain(["weighted avg".len()])
+            .max()
+            .unwrap();
+        let total: usize = self.support.iter().sum();
+
+        writeln!(
+            f,
+            "{:>width$} {:>9} {:>9} {:>9} {:>9}",
+            "", "precision", "recall", "f1-score", "support"
+        )?;
+        writeln!(f)?;
+        for (i, name) in names.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>width$} {:>9.2} {:>9.2} {:>9.2} {:>9}",
+                name, self.precision[i], self.recall[i], self.f1[i], self.support[i]
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>width$} {:>9} {:>9} {:>9.2} {:>9}",
+            "accuracy", "", "", self.accuracy, total
+        )?;
+        for (name, (precision, recall, f1)) in [
+            ("macro avg", self.macro_avg),
+            ("weighted avg", self.weighted_avg),
+        ] {
+            writeln!(
+                f,
+                "{:>width$} {:>9.2} {:>9.2} {:>9.2} {:>9}",
+                name, precision, recall, f1, total
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Index of the largest value of every row, turning one-hot rows or predicted class
+/// probabilities, such as the output of a network, into class indices.
+///
+/// # Arguments
+///
+/// * `y`: Matrix of shape( samples, classes )
+///
+/// returns: `Vec<usize>`
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::{accuracy_score, argmax_rows};
+/// use ndarray::array;
+///
+/// let y_true = array![[1., 0., 0.], [0., 0., 1.]];
+/// let y_prob = array![[0.7, 0.2, 0.1], [0.1, 0.6, 0.3]];
+///
+/// assert_eq!(argmax_rows(&y_prob), vec![0, 1]);
+/// assert_eq!(accuracy_score(&argmax_rows(&y_true), &argmax_rows(&y_prob)).unwrap(), 0.5);
+/// ```
+pub fn argmax_rows<S: Data<Elem = f64>>(y: &ArrayBase<S, Ix2>) -> Vec<usize> {
+    y.rows()
+        .into_iter()
+        .map(|row| {
+            let mut max_index = 0;
+            for (index, value) in row.iter().enumerate() {
+                if *value > row[max_index] {
+                    max_index = index;
+                }
+            }
+            max_index
+        })
+        .collect()
+}
+
+/// Mean negative log-likelihood of the true classes under the predicted probabilities,
+/// also known as cross-entropy loss.
+///
+/// Each row of `y_prob` is rescaled to sum to 1 and its probabilities are clipped to
+/// `[1e-15, 1 - 1e-15]` so that a confident wrong prediction gives a large but finite loss.
+///
+/// # Arguments
+///
+/// * `y_true`: One-hot true classes of shape( samples, classes )
+/// * `y_prob`: Predicted probabilities of shape( samples, classes )
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no samples.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_prob` differ in shape.
+/// - `DuckyError::NonFinite` if either contains a NaN or infinite value.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::log_loss;
+/// use ndarray::array;
+///
+/// let y_true = array![[1., 0.], [0., 1.]];
+/// let y_prob = array![[0.5, 0.5], [0.5, 0.5]];
+///
+/// assert!((log_loss(&y_true, &y_prob).unwrap() - 2f64.ln()).abs() < 1e-12);
+/// ```
+pub fn log_loss<S1: Data<Elem = f64>, S2: Data<Elem = f64>>(
+    y_true: &ArrayBase<S1, Ix2>,
+    y_prob: &ArrayBase<S2, Ix2>,
+) -> Result<f64, DuckyError> {
+    check_matrices(y_true, y_prob)?;
+
+    let epsilon = 1e-15;
+    let mut total = 0.0;
+    for (truth, probabilities) in y_true.rows().into_iter().zip(y_prob.rows()) {
+        let row_sum: f64 = probabilities.sum();
+        for (is_class, probability) in truth.iter().zip(probabilities) {
+            let probability = safe_divide(*probability, row_sum).clamp(epsilon, 1.0 - epsilon);
+            total -= is_class * probability.ln();
+        }
+    }
+    Ok(total / y_true.nrows() as f64)
+}
+
+/// Area under the receiver operating characteristic curve of a binary classifier, the
+/// chance that a random positive sample is scored above a random negative one.
+///
+/// # Arguments
+///
+/// * `y_true`: Whether each sample is positive
+/// * `y_score`: Score of each sample, higher meaning more likely positive
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no samples.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_score` differ in length.
+/// - `DuckyError::NonFinite` if `y_score` contains a NaN or infinite value.
+/// - `DuckyError::InvalidParameter` if `y_true` is all positive or all negative.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::roc_auc_score;
+///
+/// let y_true = vec![false, false, true, true];
+/// let y_score = vec![0.1, 0.4, 0.35, 0.8];
+///
+/// assert_eq!(roc_auc_score(&y_true, &y_score).unwrap(), 0.75);
+/// ```
+pub fn roc_auc_score(y_true: &[bool], y_score: &[f64]) -> Result<f64, DuckyError> {
+    let curve = binary_curve(y_true, y_score)?;
+    let (positives, negatives) = curve.totals();
+
+    // Trapezoids between consecutive thresholds, with ties forming a diagonal
+    let mut area = 0.0;
+    let (mut last_tp, mut last_fp) = (0.0, 0.0);
+    for &(tp, fp) in &curve.points {
+        area += (fp - last_fp) * (tp + last_tp) / 2.0;
+        last_tp = tp;
+        last_fp = fp;
+    }
+    Ok(area / (positives * negatives))
+}
+
+/// Area under the precision-recall curve of a binary classifier, summarised as average
+/// precision: the precision at each threshold weighted by the increase in recall.
+///
+/// # Arguments
+///
+/// * `y_true`: Whether each sample is positive
+/// * `y_score`: Score of each sample, higher meaning more likely positive
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no samples.
+/// - `DuckyError::ShapeMismatch` if `y_true` and `y_score` differ in length.
+/// - `DuckyError::NonFinite` if `y_score` contains a NaN or infinite value.
+/// - `DuckyError::InvalidParameter` if `y_true` is all positive or all negative.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::average_precision_score;
+///
+/// let y_true = vec![false, false, true, true];
+/// let y_score = vec![0.1, 0.4, 0.35, 0.8];
+///
+/// // Recall 0.5 at precision 1, then recall 1 at precision 2/3
+/// let expected = 0.5 * 1.0 + 0.5 * 2.0 / 3.0;
+/// assert!((average_precision_score(&y_true, &y_score).unwrap() - expected).abs() < 1e-12);
+/// ```
+pub fn average_precision_score(y_true: &[bool], y_score: &[f64]) -> Result<f64, DuckyError> {
+    let curve = binary_curve(y_true, y_score)?;
+    let (positives, _) = curve.totals();
+
+    let mut area = 0.0;
+    let mut last_recall = 0.0;
+    for &(tp, fp) in &curve.points {
+        let recall = tp / positives;
+        area += (recall - last_recall) * tp / (tp + fp);
+        last_recall = recall;
+    }
+    Ok(area)
+}
+
+/// One-vs-rest ROC-AUC of a multi-class classifier, such as a network with a softmax output.
+///
+/// # Arguments
+///
+/// * `y_true`: One-hot true classes of shape( samples, classes )
+/// * `y_score`: Predicted scores of shape( samples, classes )
+/// * `average`: `Macro` and `Weighted` combine the score of each class, `Micro` scores every
+///   cell of the matrices as one binary problem
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `roc_auc_score`, along with `DuckyError::ShapeMismatch` if `y_true` and
+/// `y_score` differ in shape.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::{roc_auc_score_one_hot, Average};
+/// use ndarray::array;
+///
+/// let y_true = array![[1., 0.], [0., 1.], [1., 0.], [0., 1.]];
+/// let y_score = array![[0.9, 0.1], [0.2, 0.8], [0.6, 0.4], [0.3, 0.7]];
+///
+/// assert_eq!(roc_auc_score_one_hot(&y_true, &y_score, Average::Macro).unwrap(), 1.0);
+/// ```
+pub fn roc_auc_score_one_hot<S1: Data<Elem = f64>, S2: Data<Elem = f64>>(
+    y_true: &ArrayBase<S1, Ix2>,
+    y_score: &ArrayBase<S2, Ix2>,
+    average: Average,
+) -> Result<f64, DuckyError> {
+    one_vs_rest(y_true, y_score, average, roc_auc_score)
+}
+
+/// One-vs-rest average precision of a multi-class classifier, such as a network with a
+/// softmax output.
+///
+/// # Arguments
+///
+/// * `y_true`: One-hot true classes of shape( samples, classes )
+/// * `y_score`: Predicted scores of shape( samples, classes )
+/// * `average`: `Macro` and `Weighted` combine the score of each class, `Micro` scores every
+///   cell of the matrices as one binary problem
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `average_precision_score`, along with `DuckyError::ShapeMismatch` if `y_true`
+/// and `y_score` differ in shape.
+pub fn average_precision_score_one_hot<S1: Data<Elem = f64>, S2: Data<Elem = f64>>(
+    y_true: &ArrayBase<S1, Ix2>,
+    y_score: &ArrayBase<S2, Ix2>,
+    average: Average,
+) -> Result<f64, DuckyError> {
+    one_vs_rest(y_true, y_score, average, average_precision_score)
+}
+
+/// Scores every column of one-hot `y_true` against the same column of `y_score` with a
+/// binary `score` and combines them with `average`.
+fn one_vs_rest<S1: Data<Elem = f64>, S2: Data<Elem = f64>>(
+    y_true: &ArrayBase<S1, Ix2>,
+    y_score: &ArrayBase<S2, Ix2>,
+    average: Average,
+    score: fn(&[bool], &[f64]) -> Result<f64, DuckyError>,
+) -> Result<f64, DuckyError> {
+    check_matrices(y_true, y_score)?;
+
+    if average == Average::Micro {
+        let truth: Vec<bool> = y_true.iter().map(|value| *value > 0.5).collect();
+        let scores: Vec<f64> = y_score.iter().cloned().collect();
+        return score(&truth, &scores);
+    }
+
+    let mut total = 0.0;
+    let mut total_weight = 0.0;
+    for (truth, scores) in y_true.columns().into_iter().zip(y_score.columns()) {
+        let truth: Vec<bool> = truth.iter().map(|value| *value > 0.5).collect();
+        let scores: Vec<f64> = scores.iter().cloned().collect();
+        let weight = match average {
+            Average::Weighted => truth.iter().filter(|is_class| **is_class).count() as f64,
+            _ => 1.0,
+        };
+        total += weight * score(&truth, &scores)?;
+        total_weight += weight;
+    }
+    Ok(total / total_weight)
+}
+
+/// Cumulative true and false positive counts of a binary classifier at every distinct
+/// score threshold, from the highest score down.
+struct BinaryCurve {
+    points: Vec<(f64, f64)>,
+}
+
+impl BinaryCurve {
+    /// Total number of positive and negative samples.
+    fn totals(&self) -> (f64, f64) {
+        *self.points.last().unwrap()
+    }
+}
+
+/// Builds the `BinaryCurve` of a set of scores.
+fn binary_curve(y_true: &[bool], y_score: &[f64]) -> Result<BinaryCurve, DuckyError> {
+    check_labels(y_true, y_score)?;
+    check_finite(y_score)?;
+
+    let mut order: Vec<usize> = (0..y_score.len()).collect();
+    order.sort_by(|&a, &b| y_score[b].partial_cmp(&y_score[a]).unwrap());
+
+    let mut points = Vec::new();
+    let (mut tp, mut fp) = (0.0, 0.0);
+    for (position, &i) in order.iter().enumerate() {
+        if y_true[i] {
+            tp += 1.0;
+        } else {
+            fp += 1.0;
+        }
+        let is_last_of_tie = match order.get(position + 1) {
+            Some(&next) => y_score[next] != y_score[i],
+            None => true,
+        };
+        if is_last_of_tie {
+            points.push((tp, fp));
+        }
+    }
+
+    if tp == 0.0 || fp == 0.0 {
+        return Err(DuckyError::InvalidParameter(
+            "y_true has to contain both positive and negative samples".to_string(),
+        ));
+    }
+    Ok(BinaryCurve { points })
+}
+
+/// Regression targets of one or more outputs, as accepted by the regression metrics.
+///
+/// Implemented for a single output as a slice, `Vec<f64>` or `Array1<f64>`, and for
+/// multiple outputs as an `Array2<f64>` of shape( samples, outputs ).
+pub trait RegressionTargets {
+    /// The targets as a matrix of shape( samples, outputs ).
+    fn as_columns(&self) -> ArrayView2<'_, f64>;
+}
+
+impl<S: Data<Elem = f64>> RegressionTargets for ArrayBase<S, Ix1> {
+    fn as_columns(&self) -> ArrayView2<'_, f64> {
+        self.view().insert_axis(Axis(1))
+    }
+}
+
+impl<S: Data<Elem = f64>> RegressionTargets for ArrayBase<S, Ix2> {
+    fn as_columns(&self) -> ArrayView2<'_, f64> {
+        self.view()
+    }
+}
+
+impl RegressionTargets for [f64] {
+    fn as_columns(&self) -> ArrayView2<'_, f64> {
+        ArrayView1::from(self).insert_axis(Axis(1))
+    }
+}
+
+impl RegressionTargets for Vec<f64> {
+    fn as_columns(&self) -> ArrayView2<'_, f64> {
+        self.as_slice().as_columns()
+    }
+}
+
+/// Mean squared error, averaged over the outputs.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if there are no samples.
+/// - `DuckyError::ShapeMismatch` if `y_true`, `y_pred` and `sample_weight` do not line up.
+/// - `DuckyError::NonFinite` if any of them contain a NaN or infinite value.
+/// - `DuckyError::InvalidParameter` if a sample weight is negative or they sum to 0.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::mean_squared_error;
+///
+/// let y_true = vec![3.0, -0.5, 2.0, 7.0];
+/// let y_pred = vec![2.5, 0.0, 2.0, 8.0];
+///
+/// assert_eq!(mean_squared_error(&y_true, &y_pred, None).unwrap(), 0.375);
+/// ```
+pub fn mean_squared_error<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<f64, DuckyError> {
+    average_outputs(mean_squared_error_raw(y_true, y_pred, sample_weight)?)
+}
+
+/// Mean squared error of every output.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<Array1<f64>, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+pub fn mean_squared_error_raw<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<Array1<f64>, DuckyError> {
+    per_output(
+        y_true,
+        y_pred,
+        sample_weight,
+        |truth, prediction, weights| {
+            weighted_mean(
+                truth.iter().zip(prediction).map(|(t, p)| (t - p).powi(2)),
+                weights,
+            )
+        },
+    )
+}
+
+/// Root mean squared error, averaged over the outputs.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::root_mean_squared_error;
+///
+/// let y_true = vec![1.0, 2.0];
+/// let y_pred = vec![4.0, 6.0];
+///
+/// assert_eq!(root_mean_squared_error(&y_true, &y_pred, None).unwrap(), 12.5f64.sqrt());
+/// ```
+pub fn root_mean_squared_error<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<f64, DuckyError> {
+    average_outputs(root_mean_squared_error_raw(y_true, y_pred, sample_weight)?)
+}
+
+/// Root mean squared error of every output.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<Array1<f64>, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+pub fn root_mean_squared_error_raw<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<Array1<f64>, DuckyError> {
+    Ok(mean_squared_error_raw(y_true, y_pred, sample_weight)?.mapv(f64::sqrt))
+}
+
+/// Mean absolute error, averaged over the outputs.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::mean_absolute_error;
+///
+/// let y_true = vec![3.0, -0.5, 2.0, 7.0];
+/// let y_pred = vec![2.5, 0.0, 2.0, 8.0];
+///
+/// assert_eq!(mean_absolute_error(&y_true, &y_pred, None).unwrap(), 0.5);
+/// ```
+pub fn mean_absolute_error<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<f64, DuckyError> {
+    average_outputs(mean_absolute_error_raw(y_true, y_pred, sample_weight)?)
+}
+
+/// Mean absolute error of every output.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<Array1<f64>, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+pub fn mean_absolute_error_raw<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<Array1<f64>, DuckyError> {
+    per_output(
+        y_true,
+        y_pred,
+        sample_weight,
+        |truth, prediction, weights| {
+            weighted_mean(
+                truth.iter().zip(prediction).map(|(t, p)| (t - p).abs()),
+                weights,
+            )
+        },
+    )
+}
+
+/// Median absolute error, averaged over the outputs. Robust to a few very bad predictions.
+///
+/// Without weights the median of an even number of errors is the mean of the middle two.
+/// With weights it is the smallest error at which half of the total weight is reached.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::median_absolute_error;
+///
+/// let y_true = vec![3.0, -0.5, 2.0, 7.0];
+/// let y_pred = vec![2.5, 0.0, 2.0, 8.0];
+///
+/// assert_eq!(median_absolute_error(&y_true, &y_pred, None).unwrap(), 0.5);
+/// ```
+pub fn median_absolute_error<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<f64, DuckyError> {
+    average_outputs(median_absolute_error_raw(y_true, y_pred, sample_weight)?)
+}
+
+/// Median absolute error of every output.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<Array1<f64>, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+pub fn median_absolute_error_raw<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<Array1<f64>, DuckyError> {
+    let unweighted = sample_weight.is_none();
+    per_output(
+        y_true,
+        y_pred,
+        sample_weight,
+        |truth, prediction, weights| {
+            let mut errors: Vec<(f64, f64)> = truth
+                .iter()
+                .zip(prediction)
+                .map(|(t, p)| (t - p).abs())
+                .zip(weights.iter().cloned())
+                .collect();
+            errors.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            if unweighted {
+                let sorted: Vec<f64> = errors.iter().map(|(error, _)| *error).collect();
+                return quantile(&sorted, 0.5);
+            }
+
+            let half = weights.sum() / 2.0;
+            let mut cumulative = 0.0;
+            for (error, weight) in &errors {
+                cumulative += weight;
+                if cumulative >= half {
+                    return *error;
+                }
+            }
+            errors.last().unwrap().0
+        },
+    )
+}
+
+/// Coefficient of determination R², averaged over the outputs. 1 is a perfect fit and 0 is
+/// no better than always predicting the mean; it can be negative.
+///
+/// An output whose true values are constant scores 1 if it is predicted perfectly and 0
+/// otherwise.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::r2_score;
+/// use ndarray::array;
+///
+/// let y_true = array![[1.0, 10.0], [2.0, 20.0], [3.0, 30.0]];
+/// let y_pred = array![[1.0, 20.0], [2.0, 20.0], [3.0, 20.0]];
+///
+/// // The first output is perfect, the second is the mean
+/// assert_eq!(r2_score(&y_true, &y_pred, None).unwrap(), 0.5);
+/// ```
+pub fn r2_score<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<f64, DuckyError> {
+    average_outputs(r2_score_raw(y_true, y_pred, sample_weight)?)
+}
+
+/// Coefficient of determination R² of every output.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<Array1<f64>, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+pub fn r2_score_raw<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<Array1<f64>, DuckyError> {
+    per_output(
+        y_true,
+        y_pred,
+        sample_weight,
+        |truth, prediction, weights| {
+            let residual = weighted_mean(
+                truth.iter().zip(prediction).map(|(t, p)| (t - p).powi(2)),
+                weights,
+            );
+            let mean = weighted_mean(truth.iter().cloned(), weights);
+            let total = weighted_mean(truth.iter().map(|t| (t - mean).powi(2)), weights);
+            one_minus_ratio(residual, total)
+        },
+    )
+}
+
+/// Explained variance score, averaged over the outputs. Like R², but does not penalise
+/// predictions that are off by a constant.
+///
+/// An output whose true values are constant scores 1 if the errors are constant and 0
+/// otherwise.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::explained_variance_score;
+///
+/// let y_true = vec![1.0, 2.0, 3.0];
+/// let y_pred = vec![2.0, 3.0, 4.0];
+///
+/// assert_eq!(explained_variance_score(&y_true, &y_pred, None).unwrap(), 1.0);
+/// ```
+pub fn explained_variance_score<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<f64, DuckyError> {
+    average_outputs(explained_variance_score_raw(y_true, y_pred, sample_weight)?)
+}
+
+/// Explained variance score of every output.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<Array1<f64>, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+pub fn explained_variance_score_raw<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<Array1<f64>, DuckyError> {
+    per_output(
+        y_true,
+        y_pred,
+        sample_weight,
+        |truth, prediction, weights| {
+            let errors: Vec<f64> = truth.iter().zip(prediction).map(|(t, p)| t - p).collect();
+            let mean_error = weighted_mean(errors.iter().cloned(), weights);
+            let error_variance =
+                weighted_mean(errors.iter().map(|e| (e - mean_error).powi(2)), weights);
+
+            let mean = weighted_mean(truth.iter().cloned(), weights);
+            let variance = weighted_mean(truth.iter().map(|t| (t - mean).powi(2)), weights);
+            one_minus_ratio(error_variance, variance)
+        },
+    )
+}
+
+/// Mean absolute percentage error, averaged over the outputs. Returned as a fraction, so
+/// 0.1 means predictions are off by 10% on average.
+///
+/// True values of 0 are treated as `f64::EPSILON`, so predicting them wrong gives a very
+/// large error.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<f64, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::mean_absolute_percentage_error;
+///
+/// let y_true = vec![100.0, 200.0];
+/// let y_pred = vec![110.0, 180.0];
+///
+/// assert!((mean_absolute_percentage_error(&y_true, &y_pred, None).unwrap() - 0.1).abs() < 1e-12);
+/// ```
+pub fn mean_absolute_percentage_error<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<f64, DuckyError> {
+    average_outputs(mean_absolute_percentage_error_raw(
+        y_true,
+        y_pred,
+        sample_weight,
+    )?)
+}
+
+/// Mean absolute percentage error of every output.
+///
+/// # Arguments
+///
+/// * `y_true`: The true targets
+/// * `y_pred`: The predicted targets
+/// * `sample_weight`: Optional weight of each sample
+///
+/// returns: `Result<Array1<f64>, DuckyError>`
+///
+/// # Errors
+///
+/// The same as `mean_squared_error`.
+pub fn mean_absolute_percentage_error_raw<T: RegressionTargets + ?Sized>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+) -> Result<Array1<f64>, DuckyError> {
+    per_output(
+        y_true,
+        y_pred,
+        sample_weight,
+        |truth, prediction, weights| {
+            weighted_mean(
+                truth
+                    .iter()
+                    .zip(prediction)
+                    .map(|(t, p)| (t - p).abs() / t.abs().max(f64::EPSILON)),
+                weights,
+            )
+        },
+    )
+}
+
+/// Checks the targets and weights and applies `metric` to every output, passing it the true
+/// and predicted values of the output and the sample weights.
+fn per_output<T, F>(
+    y_true: &T,
+    y_pred: &T,
+    sample_weight: Option<&[f64]>,
+    metric: F,
+) -> Result<Array1<f64>, DuckyError>
+where
+    T: RegressionTargets + ?Sized,
+    F: Fn(ArrayView1<f64>, ArrayView1<f64>, &Array1<f64>) -> f64,
+{
+    let (y_true, y_pred) = (y_true.as_columns(), y_pred.as_columns());
+    check_matrices(&y_true, &y_pred)?;
+
+    let weights = match sample_weight {
+        Some(weights) => {
+            check_targets(y_true.nrows(), weights.len())?;
+            check_finite(weights)?;
+            if weights.iter().any(|weight| *weight < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+                return Err(DuckyError::InvalidParameter(
+                    "sample_weight has to be non-negative with a positive sum".to_string(),
+                ));
+            }
+            Array1::from(weights.to_vec())
+        }
+        None => Array1::ones(y_true.nrows()),
+    };
+
+    Ok(y_true
+        .columns()
+        .into_iter()
+        .zip(y_pred.columns())
+        .map(|(truth, prediction)| metric(truth, prediction, &weights))
+        .collect())
+}
+
+/// Uniform average of the score of every output.
+fn average_outputs(scores: Array1<f64>) -> Result<f64, DuckyError> {
+    scores.mean().ok_or(DuckyError::EmptyInput)
+}
+
+/// Mean of `values` weighted by `weights`.
+fn weighted_mean<I: Iterator<Item = f64>>(values: I, weights: &Array1<f64>) -> f64 {
+    values
+        .zip(weights.iter())
+        .map(|(value, weight)| value * weight)
+        .sum::<f64>()
+        / weights.sum()
+}
+
+/// `1 - numerator / denominator`, scoring a zero denominator as 1 if the numerator is also
+/// zero and 0 otherwise.
+fn one_minus_ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator != 0.0 {
+        1.0 - numerator / denominator
+    } else if numerator == 0.0 {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Checks that there is at least one label and that `y_true` and `y_pred` line up.
+fn check_labels<T, P>(y_true: &[T], y_pred: &[P]) -> Result<(), DuckyError> {
+    if y_true.is_empty() {
+        return Err(DuckyError::EmptyInput);
+    }
+    check_targets(y_true.len(), y_pred.len())
+}
+
+/// Checks that two matrices have the same, non-empty shape and only finite values.
+fn check_matrices<S1: Data<Elem = f64>, S2: Data<Elem = f64>>(
+    y_true: &ArrayBase<S1, Ix2>,
+    y_pred: &ArrayBase<S2, Ix2>,
+) -> Result<(), DuckyError> {
+    if y_true.is_empty() {
+        return Err(DuckyError::EmptyInput);
+    }
+    if y_true.shape() != y_pred.shape() {
+        return Err(DuckyError::ShapeMismatch {
+            expected: y_true.shape().to_vec(),
+            found: y_pred.shape().to_vec(),
+        });
+    }
+    check_finite(y_true)?;
+    check_finite(y_pred)
+}
+
+/// `numerator / denominator`, or 0 when the denominator is 0.
+fn safe_divide(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        0.0
+    } else {
+        numerator / denominator
+    }
+}
+
+/// Harmonic mean of precision and recall.
+fn f1(precision: f64, recall: f64) -> f64 {
+    safe_divide(2.0 * precision * recall, precision + recall)
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use super::*;
+    use ndarray::array;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    fn labels() -> (Vec<String>, Vec<String>) {
+        let y_true = ["a", "a", "a", "b", "b", "c"]
+            .iter()
+            .map(|label| label.to_string())
+            .collect();
+        let y_pred = ["a", "b", "a", "b", "c", "c"]
+            .iter()
+            .map(|label| label.to_string())
+            .collect();
+        (y_true, y_pred)
+    }
+
+    #[test]
+    fn accuracy() {
+        let (y_true, y_pred) = labels();
+        assert_close(accuracy_score(&y_true, &y_pred).unwrap(), 4.0 / 6.0);
+    }
+
+    #[test]
+    fn confusion() {
+        let (y_true, y_pred) = labels();
+        let matrix = confusion_matrix(&y_true, &y_pred).unwrap();
+
+        assert_eq!(matrix.labels, vec!["a", "b", "c"]);
+        assert_eq!(matrix.counts, array![[2, 1, 0], [0, 1, 1], [0, 0, 1]]);
+    }
+
+    #[test]
+    fn precision_recall_f1_averages() {
+        let (y_true, y_pred) = labels();
+
+        // Per class: precision (1, 1/2, 1/2), recall (2/3, 1/2, 1), f1 (4/5, 1/2, 2/3)
+        assert_close(
+            precision_score(&y_true, &y_pred, Average::Macro).unwrap(),
+            2.0 / 3.0,
+        );
+        assert_close(
+            recall_score(&y_true, &y_pred, Average::Macro).unwrap(),
+            (2.0 / 3.0 + 0.5 + 1.0) / 3.0,
+        );
+        assert_close(
+            f1_score(&y_true, &y_pred, Average::Macro).unwrap(),
+            (0.8 + 0.5 + 2.0 / 3.0) / 3.0,
+        );
+        assert_close(
+            precision_score(&y_true, &y_pred, Average::Weighted).unwrap(),
+            (3.0 * 1.0 + 2.0 * 0.5 + 0.5) / 6.0,
+        );
+        assert_close(
+            f1_score(&y_true, &y_pred, Average::Weighted).unwrap(),
+            (3.0 * 0.8 + 2.0 * 0.5 + 2.0 / 3.0) / 6.0,
+        );
+        assert_close(
+            precision_score(&y_true, &y_pred, Average::Micro).unwrap(),
+            4.0 / 6.0,
+        );
+        assert_close(
+            recall_score(&y_true, &y_pred, Average::Micro).unwrap(),
+            4.0 / 6.0,
+        );
+        assert_close(
+            f1_score(&y_true, &y_pred, Average::Micro).unwrap(),
+            4.0 / 6.0,
+        );
+    }
+
+    #[test]
+    fn never_predicted_class() {
+        let y_true = vec![0, 1, 2];
+        let y_pred = vec![0, 1, 1];
+
+        let report = classification_report(&y_true, &y_pred).unwrap();
+
+        assert_eq!(report.precision, vec![1.0, 0.5, 0.0]);
+        assert_eq!(report.recall, vec![1.0, 1.0, 0.0]);
+        assert_eq!(report.f1[2], 0.0);
+        assert_eq!(report.support, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn report_display() {
+        let (y_true, y_pred) = labels();
+        let report = classification_report(&y_true, &y_pred).unwrap().to_string();
+
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].contains("precision"));
+        assert!(lines[2]
+            .trim_start()
+            .starts_with("a      1.00      0.67      0.80         3"));
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("accuracy") && line.contains("0.67")));
+        assert!(lines.last().unwrap().contains("weighted avg"));
+    }
+
+    #[test]
+    fn label_errors() {
+        let empty: Vec<String> = vec![];
+        assert_eq!(
+            accuracy_score(&empty, &empty).unwrap_err(),
+            DuckyError::EmptyInput
+        );
+        assert_eq!(
+            f1_score(&[1, 2], &[1], Average::Macro).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![1]
+            }
+        );
+    }
+
+    #[test]
+    fn one_hot_network_outputs() {
+        let y_true = array![[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+        let y_prob = array![[0.8, 0.1, 0.1], [0.3, 0.4, 0.3], [0.5, 0.2, 0.3]];
+
+        assert_close(
+            accuracy_score(&argmax_rows(&y_true), &argmax_rows(&y_prob)).unwrap(),
+            2.0 / 3.0,
+        );
+        assert_close(
+            log_loss(&y_true, &y_prob).unwrap(),
+            -(0.8f64.ln() + 0.4f64.ln() + 0.3f64.ln()) / 3.0,
+        );
+    }
+
+    #[test]
+    fn log_loss_clips_and_checks() {
+        let y_true = array![[1., 0.]];
+
+        assert_close(
+            log_loss(&y_true, &array![[0., 1.]]).unwrap(),
+            -(1e-15f64).ln(),
+        );
+        // Rows are rescaled to sum to 1
+        assert_close(log_loss(&y_true, &array![[2., 2.]]).unwrap(), 2f64.ln());
+        assert!(matches!(
+            log_loss(&y_true, &array![[1., 0., 0.]]),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert_eq!(
+            log_loss(&y_true, &array![[f64::NAN, 1.]]).unwrap_err(),
+            DuckyError::NonFinite
+        );
+    }
+
+    #[test]
+    fn roc_auc() {
+        let y_true = vec![true, false, true, false, true];
+        let y_score = vec![0.9, 0.8, 0.7, 0.2, 0.5];
+
+        // 4 of the 6 positive-negative pairs are ranked correctly
+        assert_close(roc_auc_score(&y_true, &y_score).unwrap(), 4.0 / 6.0);
+
+        // Ties count as half
+        assert_close(roc_auc_score(&[true, false], &[0.5, 0.5]).unwrap(), 0.5);
+        assert!(matches!(
+            roc_auc_score(&[true, true], &[0.1, 0.2]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn average_precision() {
+        let y_true = vec![true, false, true, false, true];
+        let y_score = vec![0.9, 0.8, 0.7, 0.2, 0.5];
+
+        // Positives are found at ranks 1, 3 and 4
+        let expected = (1.0 + 2.0 / 3.0 + 3.0 / 4.0) / 3.0;
+        assert_close(
+            average_precision_score(&y_true, &y_score).unwrap(),
+            expected,
+        );
+        assert_close(
+            average_precision_score(&[true, false, true], &[0.2, 0.2, 0.2]).unwrap(),
+            2.0 / 3.0,
+        );
+    }
+
+    #[test]
+    fn one_vs_rest_averages() {
+        let y_true = array![[1., 0.], [0., 1.], [0., 1.], [0., 1.]];
+        let y_score = array![[0.4, 0.6], [0.3, 0.7], [0.6, 0.4], [0.1, 0.9]];
+
+        // Both columns rank 2 of their 3 pairs correctly
+        assert_close(
+            roc_auc_score_one_hot(&y_true, &y_score, Average::Macro).unwrap(),
+            2.0 / 3.0,
+        );
+        assert_close(
+            roc_auc_score_one_hot(&y_true, &y_score, Average::Weighted).unwrap(),
+            2.0 / 3.0,
+        );
+        assert!(roc_auc_score_one_hot(&y_true, &y_score, Average::Micro).is_ok());
+        assert!(average_precision_score_one_hot(&y_true, &y_score, Average::Macro).unwrap() > 0.5);
+    }
+    #[test]
+    fn regression_single_output() {
+        let y_true = vec![3.0, -0.5, 2.0, 7.0];
+        let y_pred = vec![2.5, 0.0, 2.0, 8.0];
+
+        assert_close(mean_squared_error(&y_true, &y_pred, None).unwrap(), 0.375);
+        assert_close(
+            root_mean_squared_error(&y_true, &y_pred, None).unwrap(),
+            0.375f64.sqrt(),
+        );
+        assert_close(mean_absolute_error(&y_true, &y_pred, None).unwrap(), 0.5);
+        assert_close(median_absolute_error(&y_true, &y_pred, None).unwrap(), 0.5);
+        // Mean 2.875, total sum of squares 29.1875, residual sum of squares 1.5
+        assert_close(
+            r2_score(&y_true, &y_pred, None).unwrap(),
+            1.0 - 1.5 / 29.1875,
+        );
+        // Errors 0.5, -0.5, 0, -1 have mean -0.25 and variance 0.3125
+        assert_close(
+            explained_variance_score(&y_true, &y_pred, None).unwrap(),
+            1.0 - 0.3125 / (29.1875 / 4.0),
+        );
+        assert_close(
+            mean_absolute_percentage_error(&y_true, &y_pred, None).unwrap(),
+            (0.5 / 3.0 + 1.0 + 0.0 + 1.0 / 7.0) / 4.0,
+        );
+    }
+
+    #[test]
+    fn regression_multi_output() {
+        let y_true = array![[0.5, 1.0], [-1.0, 1.0], [7.0, -6.0]];
+        let y_pred = array![[0.0, 2.0], [-1.0, 2.0], [8.0, -5.0]];
+
+        let raw = mean_squared_error_raw(&y_true, &y_pred, None).unwrap();
+        assert_close(raw[0], 1.25 / 3.0);
+        assert_close(raw[1], 1.0);
+        assert_close(
+            mean_squared_error(&y_true, &y_pred, None).unwrap(),
+            (1.25 / 3.0 + 1.0) / 2.0,
+        );
+        assert_eq!(
+            mean_absolute_error_raw(&y_true, &y_pred, None)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Ndarray single outputs work the same as vectors
+        let column = y_true.column(0).to_owned();
+        assert_close(
+            mean_squared_error(&column, &y_pred.column(0).to_owned(), None).unwrap(),
+            raw[0],
+        );
+    }
+
+    #[test]
+    fn regression_sample_weight() {
+        let y_true = vec![1.0, 2.0, 3.0];
+        let y_pred = vec![1.0, 4.0, 6.0];
+
+        // A weight of 0 drops the sample
+        assert_close(
+            mean_absolute_error(&y_true, &y_pred, Some(&[1.0, 0.0, 1.0])).unwrap(),
+            1.5,
+        );
+        assert_close(
+            mean_squared_error(&y_true, &y_pred, Some(&[2.0, 1.0, 1.0])).unwrap(),
+            (4.0 + 9.0) / 4.0,
+        );
+        // Half of the weight is reached at the error of the heaviest sample
+        assert_close(
+            median_absolute_error(&y_true, &y_pred, Some(&[1.0, 1.0, 5.0])).unwrap(),
+            3.0,
+        );
+        assert_close(
+            r2_score(&y_true, &y_true, Some(&[1.0, 2.0, 3.0])).unwrap(),
+            1.0,
+        );
+    }
+
+    #[test]
+    fn regression_constant_target() {
+        let y_true = vec![2.0, 2.0];
+
+        assert_eq!(r2_score(&y_true, &y_true, None).unwrap(), 1.0);
+        assert_eq!(r2_score(&y_true, &vec![1.0, 3.0], None).unwrap(), 0.0);
+        assert_eq!(
+            explained_variance_score(&y_true, &vec![3.0, 3.0], None).unwrap(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn regression_errors() {
+        let y_true = vec![1.0, 2.0];
+
+        assert_eq!(
+            mean_squared_error(&y_true, &vec![1.0], None).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![2, 1],
+                found: vec![1, 1]
+            }
+        );
+        assert_eq!(
+            mean_squared_error(&y_true, &y_true, Some(&[1.0])).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![1]
+            }
+        );
+        assert!(matches!(
+            mean_squared_error(&y_true, &y_true, Some(&[1.0, -1.0])),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert_eq!(
+            r2_score(&y_true, &vec![1.0, f64::NAN], None).unwrap_err(),
+            DuckyError::NonFinite
+        );
+        let empty: Vec<f64> = vec![];
+        assert_eq!(
+            mean_absolute_error(&empty, &empty, None).unwrap_err(),
+            DuckyError::EmptyInput
+        );
+    }
+}
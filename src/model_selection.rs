@@ -0,0 +1,1710 @@
+extern crate ndarray;
+
+use super::error::{check_targets, DuckyError};
+use super::traits;
+use super::util::{seeded_rng, unique_labels};
+use ndarray::{Array1, Array2, ArrayBase, Axis, Data, Ix2};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Data split into a training and a test set by `train_test_split`.
+///
+/// # Fields
+/// - `x_train`: Rows of the data in the training set.
+/// - `x_test`: Rows of the data in the test set.
+/// - `y_train`: Labels of the training set.
+/// - `y_test`: Labels of the test set.
+///
+/// # Type parameters
+/// - `L`: The type of the labels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainTestSplit<L> {
+    pub x_train: Array2<f64>,
+    pub x_test: Array2<f64>,
+    pub y_train: Vec<L>,
+    pub y_test: Vec<L>,
+}
+
+/// Splits the data into a training and a test set.
+///
+/// The test set gets `test_size` of the samples, rounded up. Without shuffling the last samples
+/// form the test set. With `stratify` every label keeps the same share of samples in both sets,
+/// as close as the sizes allow.
+///
+/// # Arguments
+///
+/// * `x`: Data of shape( samples, features )
+/// * `y`: Label of each sample
+/// * `test_size`: Share of the samples to put in the test set, between 0 and 1
+/// * `shuffle`: Whether to shuffle the samples before splitting
+/// * `stratify`: Whether to keep the share of each label the same in both sets
+/// * `seed`: Seed for the shuffle, or `None` to seed it from the operating system
+///
+/// returns: `Result<TrainTestSplit<L>, DuckyError>`
+///
+/// # Errors
+///
+/// - `DuckyError::EmptyInput` if `x` has no rows.
+/// - `DuckyError::ShapeMismatch` if `y` does not have a label for each row of `x`.
+/// - `DuckyError::InvalidParameter` if `test_size` leaves either set empty.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::model_selection::train_test_split;
+/// use ndarray::array;
+///
+/// let x = array![[1.0], [2.0], [3.0], [4.0]];
+/// let y = vec!["a", "a", "b", "b"];
+///
+/// let split = train_test_split(&x, &y, 0.5, false, false, None).unwrap();
+/// assert_eq!(split.x_test, array![[3.0], [4.0]]);
+/// assert_eq!(split.y_train, vec!["a", "a"]);
+///
+/// let split = train_test_split(&x, &y, 0.5, true, true, Some(42)).unwrap();
+/// assert_eq!(split.y_test.iter().filter(|label| **label == "a").count(), 1);
+/// ```
+pub fn train_test_split<S: Data<Elem = f64>, L: Hash + Eq + Clone>(
+    x: &ArrayBase<S, Ix2>,
+    y: &[L],
+    test_size: f64,
+    shuffle: bool,
+    stratify: bool,
+    seed: Option<u64>,
+) -> Result<TrainTestSplit<L>, DuckyError> {
+    if x.nrows() == 0 {
+        return Err(DuckyError::EmptyInput);
+    }
+    check_targets(x.nrows(), y.len())?;
+
+    let n_samples = y.len();
+    let n_test = (test_size * n_samples as f64).ceil() as usize;
+    if !(test_size > 0.0 && test_size < 1.0) || n_test == 0 || n_test >= n_samples {
+        return Err(DuckyError::InvalidParameter(format!(
+            "test_size of {} leaves no samples in one of the sets of {} samples",
+            test_size, n_samples
+        )));
+    }
+
+    let mut rng = seeded_rng(seed);
+    let (mut train, mut test) = if stratify {
+        let mut train = Vec::with_capacity(n_samples - n_test);
+        let mut test = Vec::with_capacity(n_test);
+        let mut groups = class_indices(y);
+        let class_tests = allocate(&groups, n_test);
+        for (indices, class_test) in groups.iter_mut().zip(class_tests) {
+            if shuffle {
+                indices.shuffle(&mut rng);
+            }
+            test.extend_from_slice(&indices[..class_test]);
+            train.extend_from_slice(&indices[class_test..]);
+        }
+        (train, test)
+    } else {
+        let mut indices: Vec<usize> = (0..n_samples).collect();
+        if shuffle {
+            indices.shuffle(&mut rng);
+        }
+        let test = indices.split_off(n_samples - n_test);
+        (indices, test)
+    };
+
+    if shuffle {
+        train.shuffle(&mut rng);
+        test.shuffle(&mut rng);
+    } else {
+        train.sort_unstable();
+        test.sort_unstable();
+    }
+
+    Ok(TrainTestSplit {
+        x_train: x.select(Axis(0), &train),
+        x_test: x.select(Axis(0), &test),
+        y_train: select_labels(y, &train),
+        y_test: select_labels(y, &test),
+    })
+}
+
+/// Indices of the samples in the training and test set of one cross-validation fold, both in
+/// ascending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fold {
+    pub train: Vec<usize>,
+    pub test: Vec<usize>,
+}
+
+/// Trait for strategies that split samples into cross-validation folds.
+///
+/// # Type parameters
+/// - `L`: The type of the labels.
+pub trait CrossValidator<L> {
+    /// Splits the samples into folds, each using a different test set.
+    ///
+    /// # Parameters
+    /// - `y`: Label of each sample. Strategies that only need the number of samples ignore
+    ///   the labels themselves.
+    ///
+    /// # Returns
+    /// The folds, or a `DuckyError` if the samples can not be split.
+    fn split(&self, y: &[L]) -> Result<Vec<Fold>, DuckyError>;
+}
+
+/// Splits the samples into `n_splits` consecutive folds, each used once as the test set.
+///
+/// The first `n_samples % n_splits` folds get one extra sample.
+///
+/// # Fields
+/// - `n_splits`: Number of folds, at least 2.
+/// - `shuffle`: Whether to shuffle the samples before splitting.
+/// - `seed`: Seed for the shuffle, or `None` to seed it from the operating system.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::model_selection::{CrossValidator, KFold};
+///
+/// let folds = KFold::new(3, false, None).split(&[0, 0, 1, 1, 1]).unwrap();
+///
+/// assert_eq!(folds[0].test, vec![0, 1]);
+/// assert_eq!(folds[0].train, vec![2, 3, 4]);
+/// assert_eq!(folds[2].test, vec![4]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct KFold {
+    pub n_splits: usize,
+    pub shuffle: bool,
+    pub seed: Option<u64>,
+}
+
+impl Default for KFold {
+    fn default() -> Self {
+        Self::new(5, false, None)
+    }
+}
+
+impl KFold {
+    /// Creates a new `KFold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_splits`: Number of folds, at least 2
+    /// * `shuffle`: Whether to shuffle the samples before splitting
+    /// * `seed`: Seed for the shuffle, or `None` to seed it from the operating system
+    ///
+    /// returns: `KFold`
+    pub fn new(n_splits: usize, shuffle: bool, seed: Option<u64>) -> Self {
+        KFold {
+            n_splits,
+            shuffle,
+            seed,
+        }
+    }
+}
+
+impl<L> CrossValidator<L> for KFold {
+    fn split(&self, y: &[L]) -> Result<Vec<Fold>, DuckyError> {
+        check_n_splits(self.n_splits, y.len())?;
+
+        let mut indices: Vec<usize> = (0..y.len()).collect();
+        if self.shuffle {
+            indices.shuffle(&mut seeded_rng(self.seed));
+        }
+
+        let mut start = 0;
+        Ok((0..self.n_splits)
+            .map(|fold| {
+                let size = y.len() / self.n_splits + usize::from(fold < y.len() % self.n_splits);
+                start += size;
+                fold_from_test(y.len(), indices[start - size..start].to_vec())
+            })
+            .collect())
+    }
+}
+
+/// Splits the samples into `n_splits` folds that each keep about the same share of every label.
+///
+/// The samples of each label are dealt out over the folds in turn, so fold sizes differ by at
+/// most one.
+///
+/// # Fields
+/// - `n_splits`: Number of folds, at least 2.
+/// - `shuffle`: Whether to shuffle the samples of each label before splitting.
+/// - `seed`: Seed for the shuffle, or `None` to seed it from the operating system.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::model_selection::{CrossValidator, StratifiedKFold};
+///
+/// let y = vec!["a", "a", "a", "a", "b", "b"];
+/// let folds = StratifiedKFold::new(2, false, None).split(&y).unwrap();
+///
+/// assert_eq!(folds[0].test, vec![0, 2, 4]);
+/// assert_eq!(folds[1].test, vec![1, 3, 5]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct StratifiedKFold {
+    pub n_splits: usize,
+    pub shuffle: bool,
+    pub seed: Option<u64>,
+}
+
+impl Default for StratifiedKFold {
+    fn default() -> Self {
+        Self::new(5, false, None)
+    }
+}
+
+impl StratifiedKFold {
+    /// Creates a new `StratifiedKFold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_splits`: Number of folds, at least 2
+    /// * `shuffle`: Whether to shuffle the samples of each label before splitting
+    /// * `seed`: Seed for the shuffle, or `None` to seed it from the operating system
+    ///
+    /// returns: `StratifiedKFold`
+    pub fn new(n_splits: usize, shuffle: bool, seed: Option<u64>) -> Self {
+        StratifiedKFold {
+            n_splits,
+            shuffle,
+            seed,
+        }
+    }
+}
+
+impl<L: Hash + Eq + Clone> CrossValidator<L> for StratifiedKFold {
+    fn split(&self, y: &[L]) -> Result<Vec<Fold>, DuckyError> {
+        check_n_splits(self.n_splits, y.len())?;
+
+        let mut rng = seeded_rng(self.seed);
+        let mut tests = vec![Vec::new(); self.n_splits];
+        let mut position = 0;
+        for mut indices in class_indices(y) {
+            if self.shuffle {
+                indices.shuffle(&mut rng);
+            }
+            for index in indices {
+                tests[position % self.n_splits].push(index);
+                position += 1;
+            }
+        }
+
+        Ok(tests
+            .into_iter()
+            .map(|test| fold_from_test(y.len(), test))
+            .collect())
+    }
+}
+
+/// Splits the samples into `n_splits` folds so that all samples of a group end up in the same
+/// fold, keeping groups such as repeated measurements of one subject out of the training set
+/// they are tested against.
+///
+/// Groups are handed out from largest to smallest, each to the fold with the fewest samples.
+///
+/// # Fields
+/// - `n_splits`: Number of folds, at least 2 and at most the number of groups.
+/// - `groups`: Group of each sample.
+///
+/// # Type parameters
+/// - `G`: The type of the groups.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::model_selection::{CrossValidator, GroupKFold};
+///
+/// let groups = vec![1, 1, 1, 2, 2, 3];
+/// let folds = GroupKFold::new(2, groups).split(&[0; 6]).unwrap();
+///
+/// assert_eq!(folds[0].test, vec![0, 1, 2]);
+/// assert_eq!(folds[1].test, vec![3, 4, 5]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GroupKFold<G> {
+    pub n_splits: usize,
+    pub groups: Vec<G>,
+}
+
+impl<G> GroupKFold<G> {
+    /// Creates a new `GroupKFold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_splits`: Number of folds, at least 2 and at most the number of groups
+    /// * `groups`: Group of each sample
+    ///
+    /// returns: `GroupKFold<G>`
+    pub fn new(n_splits: usize, groups: Vec<G>) -> Self {
+        GroupKFold { n_splits, groups }
+    }
+}
+
+impl<L, G: Hash + Eq + Clone> CrossValidator<L> for GroupKFold<G> {
+    fn split(&self, y: &[L]) -> Result<Vec<Fold>, DuckyError> {
+        check_targets(y.len(), self.groups.len())?;
+        check_n_splits(self.n_splits, y.len())?;
+
+        let mut groups = class_indices(&self.groups);
+        if groups.len() < self.n_splits {
+            return Err(DuckyError::InvalidParameter(format!(
+                "n_splits of {} is more than the {} groups",
+                self.n_splits,
+                groups.len()
+            )));
+        }
+        groups.sort_by_key(|indices| std::cmp::Reverse(indices.len()));
+
+        let mut tests: Vec<Vec<usize>> = vec![Vec::new(); self.n_splits];
+        for indices in groups {
+            let smallest = (0..self.n_splits)
+                .min_by_key(|fold| tests[*fold].len())
+                .unwrap();
+            tests[smallest].extend(indices);
+        }
+
+        Ok(tests
+            .into_iter()
+            .map(|test| fold_from_test(y.len(), test))
+            .collect())
+    }
+}
+
+/// Splits time ordered samples into `n_splits` folds whose test sets follow each other at the
+/// end of the samples, each trained only on samples that come before it.
+///
+/// # Fields
+/// - `n_splits`: Number of folds, at least 2.
+/// - `max_train_size`: Largest number of samples to train on, keeping the most recent ones.
+///   `None` trains on every earlier sample.
+/// - `test_size`: Number of samples in each test set. `None` uses
+///   `n_samples / (n_splits + 1)`.
+/// - `gap`: Number of samples to leave out between the end of each training set and the start
+///   of its test set.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::model_selection::{CrossValidator, TimeSeriesSplit};
+///
+/// let folds = TimeSeriesSplit::new(3, None, None, 0).split(&[0; 6]).unwrap();
+///
+/// assert_eq!(folds[0].train, vec![0, 1, 2]);
+/// assert_eq!(folds[0].test, vec![3]);
+/// assert_eq!(folds[2].train, vec![0, 1, 2, 3, 4]);
+/// assert_eq!(folds[2].test, vec![5]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct TimeSeriesSplit {
+    pub n_splits: usize,
+    pub max_train_size: Option<usize>,
+    pub test_size: Option<usize>,
+    pub gap: usize,
+}
+
+impl Default for TimeSeriesSplit {
+    fn default() -> Self {
+        Self::new(5, None, None, 0)
+    }
+}
+
+impl TimeSeriesSplit {
+    /// Creates a new `TimeSeriesSplit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_splits`: Number of folds, at least 2
+    /// * `max_train_size`: Largest number of samples to train on, or `None` for no limit
+    /// * `test_size`: Number of samples in each test set, or `None` to split them evenly
+    /// * `gap`: Number of samples to leave out between each training and test set
+    ///
+    /// returns: `TimeSeriesSplit`
+    pub fn new(
+        n_splits: usize,
+        max_train_size: Option<usize>,
+        test_size: Option<usize>,
+        gap: usize,
+    ) -> Self {
+        TimeSeriesSplit {
+            n_splits,
+            max_train_size,
+            test_size,
+            gap,
+        }
+    }
+}
+
+impl<L> CrossValidator<L> for TimeSeriesSplit {
+    fn split(&self, y: &[L]) -> Result<Vec<Fold>, DuckyError> {
+        let n_samples = y.len();
+        check_n_splits(self.n_splits, n_samples)?;
+
+        let test_size = self.test_size.unwrap_or(n_samples / (self.n_splits + 1));
+        if test_size == 0 || self.n_splits * test_size + self.gap >= n_samples {
+            return Err(DuckyError::InvalidParameter(format!(
+                "{} splits with a test size of {} and a gap of {} leave no samples to train on \
+                 out of {}",
+                self.n_splits, test_size, self.gap, n_samples
+            )));
+        }
+
+        Ok((0..self.n_splits)
+            .map(|fold| {
+                let test_start = n_samples - (self.n_splits - fold) * test_size;
+                let train_end = test_start - self.gap;
+                let train_start = self
+                    .max_train_size
+                    .map_or(0, |max_size| train_end.saturating_sub(max_size));
+                Fold {
+                    train: (train_start..train_end).collect(),
+                    test: (test_start..test_start + test_size).collect(),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Scores a model on every fold of a cross-validation strategy, fitting a fresh copy of the
+/// model on the training set of each fold.
+///
+/// # Arguments
+///
+/// * `model`: The unfitted model, copied for every fold
+/// * `x`: Data of shape( samples, features )
+/// * `y`: Label of each sample
+/// * `cv`: The cross-validation strategy
+/// * `scoring`: Scores the predictions on a test set, called with the true and predicted labels
+///
+/// returns: `Result<Array1<f64>, DuckyError>` holding the score of each fold
+///
+/// # Errors
+///
+/// - `DuckyError::ShapeMismatch` if `y` does not have a label for each row of `x`.
+/// - Any error from `cv`, fitting or predicting with the model, or `scoring`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::accuracy_score;
+/// use ducky_learn::model_selection::{cross_val_score, StratifiedKFold};
+/// use ducky_learn::naive_bayes::GaussianNaiveBayes;
+/// use ndarray::array;
+///
+/// let x = array![[1.0, 0.1], [1.2, 0.0], [0.9, 0.2], [0.0, 1.1], [0.1, 0.9], [0.2, 1.0]];
+/// let y = vec!["a", "a", "a", "b", "b", "b"];
+///
+/// let scores = cross_val_score(
+///     &GaussianNaiveBayes::new(),
+///     &x,
+///     &y,
+///     &StratifiedKFold::new(3, false, None),
+///     accuracy_score,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(scores.len(), 3);
+/// assert_eq!(scores.mean().unwrap(), 1.0);
+/// ```
+pub fn cross_val_score<M, S, L, C, F>(
+    model: &M,
+    x: &ArrayBase<S, Ix2>,
+    y: &[L],
+    cv: &C,
+    scoring: F,
+) -> Result<Array1<f64>, DuckyError>
+where
+    M: Clone + traits::Fit<Array2<f64>, [L]>,
+    M::Fitted: traits::Predict<Array2<f64>, Output = Vec<L>>,
+    S: Data<Elem = f64>,
+    L: Clone,
+    C: CrossValidator<L>,
+    F: Fn(&[L], &[L]) -> Result<f64, DuckyError>,
+{
+    check_targets(x.nrows(), y.len())?;
+
+    cv.split(y)?
+        .into_iter()
+        .map(|fold| {
+            let fitted = traits::Fit::fit(
+                model.clone(),
+                &x.select(Axis(0), &fold.train),
+                &select_labels(y, &fold.train) as &[L],
+            )?;
+            let predicted = traits::Predict::predict(&fitted, &x.select(Axis(0), &fold.test))?;
+            scoring(&select_labels(y, &fold.test), &predicted)
+        })
+        .collect()
+}
+
+/// Value of one hyperparameter in a search.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    Float(f64),
+    Int(i64),
+    Bool(bool),
+    Text(String),
+}
+
+impl ParamValue {
+    /// The value as a float, converting integers. `None` for other kinds of values.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ParamValue::Float(value) => Some(*value),
+            ParamValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    /// The value as a `usize`. `None` for negative integers and other kinds of values.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            ParamValue::Int(value) => usize::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    /// The value as a bool. `None` for other kinds of values.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ParamValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value as text. `None` for other kinds of values.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ParamValue::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<f64> for ParamValue {
+    fn from(value: f64) -> Self {
+        ParamValue::Float(value)
+    }
+}
+
+impl From<i64> for ParamValue {
+    fn from(value: i64) -> Self {
+        ParamValue::Int(value)
+    }
+}
+
+impl From<usize> for ParamValue {
+    fn from(value: usize) -> Self {
+        ParamValue::Int(value as i64)
+    }
+}
+
+impl From<bool> for ParamValue {
+    fn from(value: bool) -> Self {
+        ParamValue::Bool(value)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(value: &str) -> Self {
+        ParamValue::Text(value.to_string())
+    }
+}
+
+/// One set of hyperparameters, by name, that a model factory builds a model from.
+pub type Params = BTreeMap<String, ParamValue>;
+
+/// The values to try for each hyperparameter in a `GridSearchCV`.
+pub type ParamGrid = BTreeMap<String, Vec<ParamValue>>;
+
+/// How a `RandomizedSearchCV` samples one hyperparameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamDistribution {
+    /// One of the values, each equally likely.
+    Values(Vec<ParamValue>),
+    /// A float uniformly between the bounds, including the lower one.
+    Uniform(f64, f64),
+    /// A float whose logarithm is uniform between the logarithms of the bounds, for
+    /// parameters such as `alpha` that span orders of magnitude.
+    LogUniform(f64, f64),
+    /// An integer from the lower bound up to, but not including, the upper bound.
+    IntRange(i64, i64),
+}
+
+impl ParamDistribution {
+    /// Checks that the distribution can be sampled from.
+    fn validate(&self, name: &str) -> Result<(), DuckyError> {
+        let valid = match self {
+            ParamDistribution::Values(values) => !values.is_empty(),
+            ParamDistribution::Uniform(low, high) => low < high && high.is_finite(),
+            ParamDistribution::LogUniform(low, high) => {
+                *low > 0.0 && low < high && high.is_finite()
+            }
+            ParamDistribution::IntRange(low, high) => low < high,
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(DuckyError::InvalidParameter(format!(
+                "can not sample {} from {:?}",
+                name, self
+            ))),
+        }
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> ParamValue {
+        match self {
+            ParamDistribution::Values(values) => values[rng.gen_range(0..values.len())].clone(),
+            ParamDistribution::Uniform(low, high) => ParamValue::Float(rng.gen_range(*low..*high)),
+            ParamDistribution::LogUniform(low, high) => {
+                ParamValue::Float(rng.gen_range(low.ln()..high.ln()).exp())
+            }
+            ParamDistribution::IntRange(low, high) => ParamValue::Int(rng.gen_range(*low..*high)),
+        }
+    }
+}
+
+/// Cross-validation scores of one set of hyperparameters.
+///
+/// # Fields
+/// - `params`: The hyperparameters.
+/// - `scores`: Score of each fold.
+/// - `mean_score`: Mean of the fold scores.
+/// - `std_score`: Standard deviation of the fold scores.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateScore {
+    pub params: Params,
+    pub scores: Array1<f64>,
+    pub mean_score: f64,
+    pub std_score: f64,
+}
+
+/// Outcome of a hyperparameter search.
+///
+/// # Fields
+/// - `best_index`: Index of the best candidate in `candidates`.
+/// - `best_params`: Hyperparameters with the highest mean score, ignoring NaN scores.
+/// - `best_score`: Mean cross-validation score of `best_params`.
+/// - `candidates`: Scores of every candidate, in the order they were tried.
+/// - `best_model`: Model built from `best_params` and fit on all of the data.
+///
+/// # Type parameters
+/// - `T`: The type of the fitted model.
+#[derive(Debug, Clone)]
+pub struct SearchResult<T> {
+    pub best_index: usize,
+    pub best_params: Params,
+    pub best_score: f64,
+    pub candidates: Vec<CandidateScore>,
+    pub best_model: T,
+}
+
+/// Tries every combination of the hyperparameters in a grid, scoring each with
+/// cross-validation and refitting the best one on all of the data.
+///
+/// # Fields
+/// - `param_grid`: The values to try for each hyperparameter. Combinations are tried in order
+///   of the names, with the last name changing fastest.
+/// - `factory`: Builds an unfitted model from a set of hyperparameters.
+/// - `cv`: The cross-validation strategy.
+/// - `n_jobs`: Number of threads to score candidates on.
+///
+/// # Type parameters
+/// - `F`: The type of the model factory.
+/// - `C`: The type of the cross-validation strategy.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::accuracy_score;
+/// use ducky_learn::model_selection::{GridSearchCV, ParamGrid, StratifiedKFold};
+/// use ducky_learn::naive_bayes::StdNaiveBayes;
+/// use ndarray::array;
+///
+/// let x = array![[3.0, 0.0], [2.0, 1.0], [3.0, 1.0], [0.0, 3.0], [1.0, 2.0], [1.0, 3.0]];
+/// let y = vec!["a", "a", "a", "b", "b", "b"];
+///
+/// let mut param_grid = ParamGrid::new();
+/// param_grid.insert("alpha".to_string(), vec![0.1.into(), 1.0.into(), 10.0.into()]);
+///
+/// let search = GridSearchCV::new(
+///     param_grid,
+///     |params| StdNaiveBayes::new(params["alpha"].as_f64().unwrap()),
+///     StratifiedKFold::new(3, false, None),
+///     2,
+/// );
+/// let result = search.fit(&x, &y, accuracy_score).unwrap();
+///
+/// assert_eq!(result.candidates.len(), 3);
+/// assert_eq!(result.best_score, 1.0);
+/// assert_eq!(result.best_model.predict(&x).unwrap(), y);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GridSearchCV<F, C> {
+    pub param_grid: ParamGrid,
+    pub factory: F,
+    pub cv: C,
+    pub n_jobs: usize,
+}
+
+impl<F, C> GridSearchCV<F, C> {
+    /// Creates a new `GridSearchCV`.
+    ///
+    /// # Arguments
+    ///
+    /// * `param_grid`: The values to try for each hyperparameter
+    /// * `factory`: Builds an unfitted model from a set of hyperparameters
+    /// * `cv`: The cross-validation strategy
+    /// * `n_jobs`: Number of threads to score candidates on, at least 1
+    ///
+    /// returns: `GridSearchCV<F, C>`
+    pub fn new<M>(param_grid: ParamGrid, factory: F, cv: C, n_jobs: usize) -> Self
+    where
+        F: Fn(&Params) -> M,
+    {
+        GridSearchCV {
+            param_grid,
+            factory,
+            cv,
+            n_jobs,
+        }
+    }
+
+    /// Every combination of the hyperparameters in the grid.
+    ///
+    /// returns: `Result<Vec<Params>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::InvalidParameter` if a hyperparameter has no values to try.
+    pub fn candidates(&self) -> Result<Vec<Params>, DuckyError> {
+        let mut candidates = vec![Params::new()];
+        for (name, values) in &self.param_grid {
+            if values.is_empty() {
+                return Err(DuckyError::InvalidParameter(format!(
+                    "no values to try for {}",
+                    name
+                )));
+            }
+            candidates = candidates
+                .into_iter()
+                .flat_map(|params| {
+                    values.iter().map(move |value| {
+                        let mut params = params.clone();
+                        params.insert(name.clone(), value.clone());
+                        params
+                    })
+                })
+                .collect();
+        }
+        Ok(candidates)
+    }
+
+    /// Scores every candidate with cross-validation and refits the best one on all of the
+    /// data. Higher scores are better, so negate error metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( samples, features )
+    /// * `y`: Label of each sample
+    /// * `scoring`: Scores the predictions on a test set, called with the true and predicted
+    ///   labels
+    ///
+    /// returns: `Result<SearchResult<M::Fitted>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::InvalidParameter` if the grid is invalid or `n_jobs` is 0.
+    /// - Any error from cross-validating or refitting a model.
+    pub fn fit<M, S, L, Sc>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+        y: &[L],
+        scoring: Sc,
+    ) -> Result<SearchResult<M::Fitted>, DuckyError>
+    where
+        F: Fn(&Params) -> M + Sync,
+        C: CrossValidator<L> + Sync,
+        M: Clone + traits::Fit<Array2<f64>, [L]>,
+        M::Fitted: traits::Predict<Array2<f64>, Output = Vec<L>>,
+        S: Data<Elem = f64> + Sync,
+        L: Clone + Sync,
+        Sc: Fn(&[L], &[L]) -> Result<f64, DuckyError> + Sync,
+    {
+        search(
+            self.candidates()?,
+            &self.factory,
+            &self.cv,
+            self.n_jobs,
+            x,
+            y,
+            scoring,
+        )
+    }
+}
+
+/// Tries `n_iter` sets of hyperparameters sampled from a distribution for each, scoring each
+/// with cross-validation and refitting the best one on all of the data.
+///
+/// # Fields
+/// - `param_distributions`: How to sample each hyperparameter.
+/// - `n_iter`: Number of sets of hyperparameters to try.
+/// - `factory`: Builds an unfitted model from a set of hyperparameters.
+/// - `cv`: The cross-validation strategy.
+/// - `n_jobs`: Number of threads to score candidates on.
+/// - `seed`: Seed for the sampling, or `None` to seed it from the operating system.
+///
+/// # Type parameters
+/// - `F`: The type of the model factory.
+/// - `C`: The type of the cross-validation strategy.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::metrics::accuracy_score;
+/// use ducky_learn::model_selection::{KFold, ParamDistribution, RandomizedSearchCV};
+/// use ducky_learn::naive_bayes::StdNaiveBayes;
+/// use std::collections::BTreeMap;
+/// use ndarray::array;
+///
+/// let x = array![[3.0, 0.0], [0.0, 3.0], [2.0, 1.0], [1.0, 2.0]];
+/// let y = vec!["a", "b", "a", "b"];
+///
+/// let mut distributions = BTreeMap::new();
+/// distributions.insert("alpha".to_string(), ParamDistribution::LogUniform(0.01, 10.0));
+///
+/// let search = RandomizedSearchCV::new(
+///     distributions,
+///     5,
+///     |params| StdNaiveBayes::new(params["alpha"].as_f64().unwrap()),
+///     KFold::new(2, false, None),
+///     1,
+///     Some(42),
+/// );
+/// let result = search.fit(&x, &y, accuracy_score).unwrap();
+///
+/// assert_eq!(result.candidates.len(), 5);
+/// assert!(result.candidates.iter().all(|candidate| {
+///     let alpha = candidate.params["alpha"].as_f64().unwrap();
+///     (0.01..10.0).contains(&alpha)
+/// }));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RandomizedSearchCV<F, C> {
+    pub param_distributions: BTreeMap<String, ParamDistribution>,
+    pub n_iter: usize,
+    pub factory: F,
+    pub cv: C,
+    pub n_jobs: usize,
+    pub seed: Option<u64>,
+}
+
+impl<F, C> RandomizedSearchCV<F, C> {
+    /// Creates a new `RandomizedSearchCV`.
+    ///
+    /// # Arguments
+    ///
+    /// * `param_distributions`: How to sample each hyperparameter
+    /// * `n_iter`: Number of sets of hyperparameters to try, at least 1
+    /// * `factory`: Builds an unfitted model from a set of hyperparameters
+    /// * `cv`: The cross-validation strategy
+    /// * `n_jobs`: Number of threads to score candidates on, at least 1
+    /// * `seed`: Seed for the sampling, or `None` to seed it from the operating system
+    ///
+    /// returns: `RandomizedSearchCV<F, C>`
+    pub fn new<M>(
+        param_distributions: BTreeMap<String, ParamDistribution>,
+        n_iter: usize,
+        factory: F,
+        cv: C,
+        n_jobs: usize,
+        seed: Option<u64>,
+    ) -> Self
+    where
+        F: Fn(&Params) -> M,
+    {
+        RandomizedSearchCV {
+            param_distributions,
+            n_iter,
+            factory,
+            cv,
+            n_jobs,
+            seed,
+        }
+    }
+
+    /// Samples `n_iter` sets of hyperparameters.
+    ///
+    /// returns: `Result<Vec<Params>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::InvalidParameter` if `n_iter` is 0 or a distribution can not be
+    ///   sampled from.
+    pub fn candidates(&self) -> Result<Vec<Params>, DuckyError> {
+        if self.n_iter == 0 {
+            return Err(DuckyError::InvalidParameter(
+                "n_iter has to be at least 1".to_string(),
+            ));
+        }
+        for (name, distribution) in &self.param_distributions {
+            distribution.validate(name)?;
+        }
+
+        let mut rng = seeded_rng(self.seed);
+        Ok((0..self.n_iter)
+            .map(|_| {
+                self.param_distributions
+                    .iter()
+                    .map(|(name, distribution)| (name.clone(), distribution.sample(&mut rng)))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Scores every sampled candidate with cross-validation and refits the best one on all of
+    /// the data. Higher scores are better, so negate error metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( samples, features )
+    /// * `y`: Label of each sample
+    /// * `scoring`: Scores the predictions on a test set, called with the true and predicted
+    ///   labels
+    ///
+    /// returns: `Result<SearchResult<M::Fitted>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::InvalidParameter` if the distributions or `n_iter` are invalid, or
+    ///   `n_jobs` is 0.
+    /// - Any error from cross-validating or refitting a model.
+    pub fn fit<M, S, L, Sc>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+        y: &[L],
+        scoring: Sc,
+    ) -> Result<SearchResult<M::Fitted>, DuckyError>
+    where
+        F: Fn(&Params) -> M + Sync,
+        C: CrossValidator<L> + Sync,
+        M: Clone + traits::Fit<Array2<f64>, [L]>,
+        M::Fitted: traits::Predict<Array2<f64>, Output = Vec<L>>,
+        S: Data<Elem = f64> + Sync,
+        L: Clone + Sync,
+        Sc: Fn(&[L], &[L]) -> Result<f64, DuckyError> + Sync,
+    {
+        search(
+            self.candidates()?,
+            &self.factory,
+            &self.cv,
+            self.n_jobs,
+            x,
+            y,
+            scoring,
+        )
+    }
+}
+
+/// Cross-validates every candidate, spread over `n_jobs` threads, and refits the one with the
+/// highest mean score on all of the data. Ties go to the earlier candidate.
+fn search<F, C, M, S, L, Sc>(
+    candidates: Vec<Params>,
+    factory: &F,
+    cv: &C,
+    n_jobs: usize,
+    x: &ArrayBase<S, Ix2>,
+    y: &[L],
+    scoring: Sc,
+) -> Result<SearchResult<M::Fitted>, DuckyError>
+where
+    F: Fn(&Params) -> M + Sync,
+    C: CrossValidator<L> + Sync,
+    M: Clone + traits::Fit<Array2<f64>, [L]>,
+    M::Fitted: traits::Predict<Array2<f64>, Output = Vec<L>>,
+    S: Data<Elem = f64> + Sync,
+    L: Clone + Sync,
+    Sc: Fn(&[L], &[L]) -> Result<f64, DuckyError> + Sync,
+{
+    if n_jobs == 0 {
+        return Err(DuckyError::InvalidParameter(
+            "n_jobs has to be at least 1".to_string(),
+        ));
+    }
+
+    let score = |params: &Params| cross_val_score(&factory(params), x, y, cv, &scoring);
+    let mut fold_scores: Vec<(usize, Result<Array1<f64>, DuckyError>)> = if n_jobs == 1 {
+        candidates.iter().map(score).enumerate().collect()
+    } else {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..n_jobs)
+                .map(|job| {
+                    let (candidates, score) = (&candidates, &score);
+                    scope.spawn(move || {
+                        (job..candidates.len())
+                            .step_by(n_jobs)
+                            .map(|index| (index, score(&candidates[index])))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    };
+    fold_scores.sort_by_key(|(index, _)| *index);
+
+    let mut scored = Vec::with_capacity(candidates.len());
+    for (params, (_, scores)) in candidates.into_iter().zip(fold_scores) {
+        let scores = scores?;
+        let mean_score = scores.mean().unwrap_or(f64::NAN);
+        scored.push(CandidateScore {
+            std_score: scores.std(0.0),
+            params,
+            scores,
+            mean_score,
+        });
+    }
+
+    let best_index = best_candidate(&scored);
+    let best_params = scored[best_index].params.clone();
+    let best_model = traits::Fit::fit(factory(&best_params), &x.to_owned(), y)?;
+
+    Ok(SearchResult {
+        best_index,
+        best_score: scored[best_index].mean_score,
+        best_params,
+        candidates: scored,
+        best_model,
+    })
+}
+
+/// Index of the candidate with the highest mean score, the first one wins ties. A NaN mean
+/// score never beats a real one, so a candidate whose scoring failed is only picked if all did.
+fn best_candidate(scored: &[CandidateScore]) -> usize {
+    let mut best = 0;
+    for (index, candidate) in scored.iter().enumerate() {
+        let best_score = scored[best].mean_score;
+        if candidate.mean_score > best_score
+            || (best_score.is_nan() && !candidate.mean_score.is_nan())
+        {
+            best = index;
+        }
+    }
+    best
+}
+
+/// Checks that `n_samples` can be split into `n_splits` folds.
+fn check_n_splits(n_splits: usize, n_samples: usize) -> Result<(), DuckyError> {
+    if n_samples == 0 {
+        return Err(DuckyError::EmptyInput);
+    }
+    if n_splits < 2 || n_splits > n_samples {
+        return Err(DuckyError::InvalidParameter(format!(
+            "n_splits has to be between 2 and the {} samples, found {}",
+            n_samples, n_splits
+        )));
+    }
+    Ok(())
+}
+
+/// Indices of the samples of each label, grouped by label in the order they first appear.
+fn class_indices<L: Hash + Eq + Clone>(y: &[L]) -> Vec<Vec<usize>> {
+    let classes = unique_labels(y);
+    let class_index: HashMap<&L, usize> = classes
+        .iter()
+        .enumerate()
+        .map(|(index, class)| (class, index))
+        .collect();
+
+    let mut indices = vec![Vec::new(); classes.len()];
+    for (index, label) in y.iter().enumerate() {
+        indices[class_index[label]].push(index);
+    }
+    indices
+}
+
+/// Shares `n_test` samples out over the groups in proportion to their size, giving the
+/// samples left over after rounding down to the groups with the largest remainders.
+fn allocate(groups: &[Vec<usize>], n_test: usize) -> Vec<usize> {
+    let n_samples: usize = groups.iter().map(Vec::len).sum();
+    let exact: Vec<f64> = groups
+        .iter()
+        .map(|indices| (indices.len() * n_test) as f64 / n_samples as f64)
+        .collect();
+    let mut counts: Vec<usize> = exact.iter().map(|share| share.floor() as usize).collect();
+
+    let mut by_remainder: Vec<usize> = (0..groups.len()).collect();
+    by_remainder.sort_by(|a, b| {
+        (exact[*b] - counts[*b] as f64)
+            .partial_cmp(&(exact[*a] - counts[*a] as f64))
+            .unwrap()
+    });
+    let left_over = n_test - counts.iter().sum::<usize>();
+    for group in by_remainder.into_iter().take(left_over) {
+        counts[group] += 1;
+    }
+    counts
+}
+
+/// Fold testing on `test` and training on every other sample.
+fn fold_from_test(n_samples: usize, mut test: Vec<usize>) -> Fold {
+    test.sort_unstable();
+    let mut in_test = vec![false; n_samples];
+    for index in &test {
+        in_test[*index] = true;
+    }
+    Fold {
+        train: (0..n_samples).filter(|index| !in_test[*index]).collect(),
+        test,
+    }
+}
+
+/// Labels of the samples at `indices`.
+fn select_labels<L: Clone>(y: &[L], indices: &[usize]) -> Vec<L> {
+    indices.iter().map(|index| y[*index].clone()).collect()
+}
+
+#[cfg(test)]
+mod model_selection_tests {
+    use super::*;
+    use crate::activations::{deriv_relu_1d, relu_1d};
+    use crate::layers::Dense1d;
+    use crate::metrics::accuracy_score;
+    use crate::naive_bayes::{GaussianNaiveBayes, StdNaiveBayes};
+    use crate::train::DenseClassifier;
+    use ndarray::{arr1, array};
+
+    fn sorted(mut indices: Vec<usize>) -> Vec<usize> {
+        indices.sort_unstable();
+        indices
+    }
+
+    fn blobs() -> (Array2<f64>, Vec<String>) {
+        let x = array![
+            [3.0, 0.0],
+            [2.5, 0.5],
+            [3.0, 1.0],
+            [2.0, 0.0],
+            [0.0, 3.0],
+            [0.5, 2.5],
+            [1.0, 3.0],
+            [0.0, 2.0],
+        ];
+        let y = [
+            "left", "left", "left", "left", "right", "right", "right", "right",
+        ]
+        .iter()
+        .map(|label| label.to_string())
+        .collect();
+        (x, y)
+    }
+
+    #[test]
+    fn train_test_split_sizes() {
+        let x = Array2::from_shape_fn((10, 2), |(row, col)| (row * 2 + col) as f64);
+        let y: Vec<usize> = (0..10).collect();
+
+        let split = train_test_split(&x, &y, 0.25, true, false, Some(7)).unwrap();
+
+        assert_eq!(split.x_train.nrows(), 7);
+        assert_eq!(split.x_test.nrows(), 3);
+        // Rows and labels are kept together and every sample is used once
+        for (row, label) in split.x_test.rows().into_iter().zip(&split.y_test) {
+            assert_eq!(row[0], (label * 2) as f64);
+        }
+        let mut all = split.y_train.clone();
+        all.extend(&split.y_test);
+        assert_eq!(sorted(all), y);
+    }
+
+    #[test]
+    fn train_test_split_seeded() {
+        let x = Array2::from_shape_fn((20, 1), |(row, _)| row as f64);
+        let y: Vec<usize> = (0..20).collect();
+
+        let first = train_test_split(&x, &y, 0.3, true, false, Some(3)).unwrap();
+        let second = train_test_split(&x, &y, 0.3, true, false, Some(3)).unwrap();
+        let unshuffled = train_test_split(&x, &y, 0.3, false, false, Some(3)).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first.y_test, unshuffled.y_test);
+        assert_eq!(unshuffled.y_test, (14..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn train_test_split_stratified() {
+        let x = Array2::zeros((10, 1));
+        let y = vec![0, 0, 0, 0, 0, 0, 1, 1, 1, 2];
+
+        let split = train_test_split(&x, &y, 0.5, true, true, Some(1)).unwrap();
+        let count = |labels: &[i32], label| labels.iter().filter(|l| **l == label).count();
+
+        assert_eq!(split.y_test.len(), 5);
+        assert_eq!(count(&split.y_test, 0), 3);
+        assert_eq!(count(&split.y_train, 0), 3);
+        // Labels 1 and 2 tie for the sample left over, and the first one seen gets it
+        assert_eq!(count(&split.y_test, 1), 2);
+        assert_eq!(count(&split.y_test, 2), 0);
+    }
+
+    #[test]
+    fn train_test_split_errors() {
+        let x = Array2::zeros((4, 1));
+        let y = vec![0; 4];
+
+        for test_size in [0.0, 1.0, -0.5, f64::NAN] {
+            assert!(matches!(
+                train_test_split(&x, &y, test_size, false, false, None),
+                Err(DuckyError::InvalidParameter(_))
+            ));
+        }
+        assert!(matches!(
+            train_test_split(&x, &y, 0.9, false, false, None),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert_eq!(
+            train_test_split(&x, &y[..3], 0.5, false, false, None),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![4],
+                found: vec![3]
+            })
+        );
+        assert_eq!(
+            train_test_split(&Array2::zeros((0, 1)), &y[..0], 0.5, false, false, None),
+            Err(DuckyError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn kfold_split() {
+        let y = vec![0; 7];
+        let folds = KFold::new(3, false, None).split(&y).unwrap();
+
+        assert_eq!(folds.len(), 3);
+        assert_eq!(folds[0].test, vec![0, 1, 2]);
+        assert_eq!(folds[1].test, vec![3, 4]);
+        assert_eq!(folds[2].test, vec![5, 6]);
+        assert_eq!(folds[1].train, vec![0, 1, 2, 5, 6]);
+    }
+
+    #[test]
+    fn kfold_shuffle() {
+        let y = vec![0; 12];
+        let folds = KFold::new(4, true, Some(11)).split(&y).unwrap();
+
+        assert_eq!(folds, KFold::new(4, true, Some(11)).split(&y).unwrap());
+        let tests: Vec<usize> = folds.iter().flat_map(|fold| fold.test.clone()).collect();
+        assert_eq!(sorted(tests), (0..12).collect::<Vec<_>>());
+        for fold in &folds {
+            assert_eq!(fold.test.len(), 3);
+            assert_eq!(fold.train.len(), 9);
+        }
+    }
+
+    #[test]
+    fn kfold_errors() {
+        assert!(matches!(
+            CrossValidator::<i32>::split(&KFold::new(1, false, None), &[0, 0]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            KFold::new(3, false, None).split(&[0, 0]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert_eq!(
+            KFold::default().split(&Vec::<i32>::new()),
+            Err(DuckyError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn stratified_kfold_split() {
+        let y = vec!["a", "b", "a", "a", "b", "a", "a", "b", "a"];
+        let folds = StratifiedKFold::new(3, true, Some(5)).split(&y).unwrap();
+
+        for fold in &folds {
+            let labels = select_labels(&y, &fold.test);
+            assert_eq!(labels.iter().filter(|l| **l == "a").count(), 2);
+            assert_eq!(labels.iter().filter(|l| **l == "b").count(), 1);
+        }
+        let tests: Vec<usize> = folds.iter().flat_map(|fold| fold.test.clone()).collect();
+        assert_eq!(sorted(tests), (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn group_kfold_split() {
+        let groups = vec!["x", "y", "x", "z", "w", "y", "x", "w"];
+        let folds = GroupKFold::new(2, groups.clone()).split(&[0; 8]).unwrap();
+
+        // x (3) goes to fold 0, y (2) and w (2) to fold 1, then z (1) to fold 0
+        assert_eq!(folds[0].test, vec![0, 2, 3, 6]);
+        assert_eq!(folds[1].test, vec![1, 4, 5, 7]);
+        for fold in &folds {
+            for train in &fold.train {
+                assert!(fold.test.iter().all(|test| groups[*test] != groups[*train]));
+            }
+        }
+    }
+
+    #[test]
+    fn group_kfold_errors() {
+        assert!(matches!(
+            GroupKFold::new(3, vec![1, 1, 2, 2]).split(&[0; 4]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert_eq!(
+            GroupKFold::new(2, vec![1, 2]).split(&[0; 3]),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![3],
+                found: vec![2]
+            })
+        );
+    }
+
+    #[test]
+    fn time_series_split() {
+        let y = vec![0; 10];
+
+        let folds = TimeSeriesSplit::new(2, Some(3), Some(2), 1)
+            .split(&y)
+            .unwrap();
+        assert_eq!(folds[0].train, vec![2, 3, 4]);
+        assert_eq!(folds[0].test, vec![6, 7]);
+        assert_eq!(folds[1].train, vec![4, 5, 6]);
+        assert_eq!(folds[1].test, vec![8, 9]);
+    }
+
+    #[test]
+    fn time_series_split_errors() {
+        assert!(matches!(
+            TimeSeriesSplit::new(3, None, Some(3), 0).split(&[0; 9]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            TimeSeriesSplit::default().split(&[0; 5]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn cross_val_score_naive_bayes() {
+        let (x, y) = blobs();
+        let cv = StratifiedKFold::new(4, true, Some(0));
+
+        let std_scores =
+            cross_val_score(&StdNaiveBayes::new(1.0), &x, &y, &cv, accuracy_score).unwrap();
+        let gaussian_scores =
+            cross_val_score(&GaussianNaiveBayes::new(), &x, &y, &cv, accuracy_score).unwrap();
+
+        assert_eq!(std_scores, arr1(&[1.0; 4]));
+        assert_eq!(gaussian_scores.len(), 4);
+    }
+
+    #[test]
+    fn cross_val_score_dense() {
+        let (x, y) = blobs();
+        let layers = vec![Dense1d::from(
+            |x| x,
+            |x| x.map(|_| 1f64),
+            array![[0.1, 0.0], [0.0, 0.1]],
+            arr1(&[0.0, 0.0]),
+        )];
+        let model = DenseClassifier::new(layers, 50, 0.05);
+
+        let scores = cross_val_score(
+            &model,
+            &x,
+            &y,
+            &StratifiedKFold::new(2, false, None),
+            accuracy_score,
+        )
+        .unwrap();
+
+        assert_eq!(scores, arr1(&[1.0, 1.0]));
+    }
+
+    #[test]
+    fn cross_val_score_scoring_error() {
+        let (x, y) = blobs();
+
+        assert_eq!(
+            cross_val_score(
+                &StdNaiveBayes::new(1.0),
+                &x,
+                &y,
+                &KFold::new(2, false, None),
+                |_: &[String], _: &[String]| Err(DuckyError::EmptyInput),
+            ),
+            Err(DuckyError::EmptyInput)
+        );
+        assert!(matches!(
+            cross_val_score(
+                &StdNaiveBayes::new(1.0),
+                &x,
+                &y[..4],
+                &KFold::default(),
+                accuracy_score
+            ),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+    fn alpha_grid() -> ParamGrid {
+        let mut param_grid = ParamGrid::new();
+        param_grid.insert(
+            "alpha".to_string(),
+            vec![0.5.into(), 1.0.into(), 2.0.into()],
+        );
+        param_grid
+    }
+
+    #[test]
+    fn param_value_conversions() {
+        assert_eq!(ParamValue::from(3usize).as_f64(), Some(3.0));
+        assert_eq!(ParamValue::from(3usize).as_usize(), Some(3));
+        assert_eq!(ParamValue::from(-3i64).as_usize(), None);
+        assert_eq!(ParamValue::from(0.5).as_usize(), None);
+        assert_eq!(ParamValue::from(true).as_bool(), Some(true));
+        assert_eq!(ParamValue::from("relu").as_str(), Some("relu"));
+        assert_eq!(ParamValue::from("relu").as_f64(), None);
+    }
+
+    #[test]
+    fn grid_search_candidates() {
+        let mut param_grid = alpha_grid();
+        param_grid.insert("fit_prior".to_string(), vec![true.into(), false.into()]);
+        let search = GridSearchCV::new(
+            param_grid,
+            |_: &Params| StdNaiveBayes::new(1.0),
+            KFold::default(),
+            1,
+        );
+
+        let candidates = search.candidates().unwrap();
+
+        assert_eq!(candidates.len(), 6);
+        assert_eq!(candidates[0]["alpha"], ParamValue::Float(0.5));
+        assert_eq!(candidates[0]["fit_prior"], ParamValue::Bool(true));
+        assert_eq!(candidates[1]["alpha"], ParamValue::Float(0.5));
+        assert_eq!(candidates[1]["fit_prior"], ParamValue::Bool(false));
+        assert_eq!(candidates[5]["alpha"], ParamValue::Float(2.0));
+
+        let mut empty = alpha_grid();
+        empty.insert("fit_prior".to_string(), vec![]);
+        assert!(matches!(
+            GridSearchCV::new(empty, |_: &Params| (), KFold::default(), 1).candidates(),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn grid_search_fit() {
+        let (x, y) = blobs();
+        let cv = StratifiedKFold::new(2, true, Some(4));
+        let factory = |params: &Params| StdNaiveBayes::new(params["alpha"].as_f64().unwrap());
+
+        let result = GridSearchCV::new(alpha_grid(), factory, cv, 1)
+            .fit(&x, &y, accuracy_score)
+            .unwrap();
+
+        assert_eq!(result.candidates.len(), 3);
+        for candidate in &result.candidates {
+            assert_eq!(
+                candidate.scores,
+                cross_val_score(&factory(&candidate.params), &x, &y, &cv, accuracy_score).unwrap()
+            );
+            assert_eq!(candidate.mean_score, candidate.scores.mean().unwrap());
+        }
+        // Every candidate is perfect, so the first one wins
+        assert_eq!(result.best_index, 0);
+        assert_eq!(result.best_params["alpha"], ParamValue::Float(0.5));
+        assert_eq!(result.best_score, 1.0);
+        assert_eq!(result.best_model.alpha, 0.5);
+        assert_eq!(result.best_model.predict(&x).unwrap(), y);
+    }
+
+    #[test]
+    fn grid_search_picks_best() {
+        let x = array![
+            [1.0, 0.0],
+            [0.0, 1.0],
+            [1.0, 0.2],
+            [0.1, 1.0],
+            [1.0, 1.0],
+            [0.3, 0.3]
+        ];
+        let y = vec![0, 1, 0, 1, 1, 0];
+        let mut param_grid = ParamGrid::new();
+        param_grid.insert(
+            "alpha".to_string(),
+            vec![0.01.into(), 1.0.into(), 100.0.into()],
+        );
+
+        let result = GridSearchCV::new(
+            param_grid,
+            |params: &Params| StdNaiveBayes::new(params["alpha"].as_f64().unwrap()),
+            KFold::new(3, false, None),
+            1,
+        )
+        .fit(&x, &y, accuracy_score)
+        .unwrap();
+
+        let best = result
+            .candidates
+            .iter()
+            .position(|candidate| {
+                result
+                    .candidates
+                    .iter()
+                    .all(|other| other.mean_score <= candidate.mean_score)
+            })
+            .unwrap();
+        assert_eq!(result.best_index, best);
+        assert_eq!(result.best_score, result.candidates[best].mean_score);
+        assert_eq!(result.best_params, result.candidates[best].params);
+    }
+
+    #[test]
+    fn best_candidate_skips_nan() {
+        let candidate = |mean_score: f64| CandidateScore {
+            params: Params::new(),
+            scores: array![mean_score],
+            mean_score,
+            std_score: 0.0,
+        };
+
+        assert_eq!(
+            best_candidate(&[candidate(f64::NAN), candidate(0.5), candidate(0.7)]),
+            2
+        );
+        assert_eq!(
+            best_candidate(&[candidate(0.5), candidate(f64::NAN), candidate(0.4)]),
+            0
+        );
+        assert_eq!(
+            best_candidate(&[candidate(f64::NAN), candidate(f64::NAN)]),
+            0
+        );
+    }
+
+    #[test]
+    fn grid_search_threads() {
+        let (x, y) = blobs();
+        let mut param_grid = alpha_grid();
+        param_grid.insert(
+            "unused".to_string(),
+            vec![1usize.into(), 2usize.into(), 3usize.into()],
+        );
+        let factory = |_: &Params| GaussianNaiveBayes::new();
+        let cv = KFold::new(4, true, Some(9));
+
+        let sequential = GridSearchCV::new(param_grid.clone(), factory, cv, 1)
+            .fit(&x, &y, accuracy_score)
+            .unwrap();
+        let threaded = GridSearchCV::new(param_grid, factory, cv, 4)
+            .fit(&x, &y, accuracy_score)
+            .unwrap();
+
+        assert_eq!(threaded.candidates, sequential.candidates);
+        assert_eq!(threaded.best_index, sequential.best_index);
+        assert!(matches!(
+            GridSearchCV::new(alpha_grid(), factory, cv, 0).fit(&x, &y, accuracy_score),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn grid_search_layer_sizes() {
+        let (x, y) = blobs();
+        let mut param_grid = ParamGrid::new();
+        param_grid.insert("hidden".to_string(), vec![2usize.into(), 4usize.into()]);
+        let factory = |params: &Params| {
+            let hidden = params["hidden"].as_usize().unwrap();
+            DenseClassifier::new(
+                vec![
+                    Dense1d::new(2, hidden, relu_1d, deriv_relu_1d),
+                    Dense1d::new(hidden, 2, |x| x, |x| x.map(|_| 1f64)),
+                ],
+                5,
+                0.01,
+            )
+        };
+
+        let result =
+            GridSearchCV::new(param_grid, factory, StratifiedKFold::new(2, false, None), 2)
+                .fit(&x, &y, accuracy_score)
+                .unwrap();
+
+        assert_eq!(result.candidates.len(), 2);
+        assert_eq!(result.best_model.predict(&x).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn randomized_search_candidates() {
+        let mut distributions = BTreeMap::new();
+        distributions.insert("alpha".to_string(), ParamDistribution::Uniform(0.5, 1.5));
+        distributions.insert("hidden".to_string(), ParamDistribution::IntRange(2, 5));
+        distributions.insert(
+            "activation".to_string(),
+            ParamDistribution::Values(vec!["relu".into(), "identity".into()]),
+        );
+        let search = RandomizedSearchCV::new(
+            distributions.clone(),
+            20,
+            |_: &Params| (),
+            KFold::default(),
+            1,
+            Some(8),
+        );
+
+        let candidates = search.candidates().unwrap();
+
+        assert_eq!(candidates.len(), 20);
+        assert_eq!(candidates, search.candidates().unwrap());
+        for params in &candidates {
+            assert!((0.5..1.5).contains(&params["alpha"].as_f64().unwrap()));
+            assert!((2..5).contains(&params["hidden"].as_usize().unwrap()));
+            assert!(["relu", "identity"].contains(&params["activation"].as_str().unwrap()));
+        }
+
+        distributions.insert("alpha".to_string(), ParamDistribution::LogUniform(0.0, 1.0));
+        let invalid =
+            RandomizedSearchCV::new(distributions, 5, |_: &Params| (), KFold::default(), 1, None);
+        assert!(matches!(
+            invalid.candidates(),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn randomized_search_fit() {
+        let (x, y) = blobs();
+        let mut distributions = BTreeMap::new();
+        distributions.insert(
+            "alpha".to_string(),
+            ParamDistribution::LogUniform(0.1, 10.0),
+        );
+
+        let result = RandomizedSearchCV::new(
+            distributions,
+            4,
+            |params: &Params| StdNaiveBayes::new(params["alpha"].as_f64().unwrap()),
+            StratifiedKFold::new(2, false, None),
+            2,
+            Some(1),
+        )
+        .fit(&x, &y, accuracy_score)
+        .unwrap();
+
+        assert_eq!(result.candidates.len(), 4);
+        assert_eq!(
+            result.best_model.alpha,
+            result.best_params["alpha"].as_f64().unwrap()
+        );
+        assert_eq!(result.best_score, 1.0);
+    }
+}
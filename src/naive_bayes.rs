@@ -1,5 +1,16 @@
-use super::util::{Fit, Unfit};
-use std::collections::{HashMap, HashSet};
+extern crate ndarray;
+
+use super::error::{check_n_features, check_targets, DuckyError};
+use super::sparse::FeatureMatrix;
+use super::traits;
+use super::util::{unique_labels, Fit, Unfit};
+use ndarray::{Array1, Array2, Axis};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Added to probabilities before taking their log so a probability of zero stays finite.
+const SMALL_NUMBER: f64 = 1e-9;
 
 /// Implementation of a standard Naive Bayes classifier.
 ///
@@ -7,12 +18,19 @@ use std::collections::{HashMap, HashSet};
 ///
 /// # Parameters
 /// - `alpha`: The Laplace smoothing factor.
+/// - `classes`: The unique class labels seen during fit, in order of first appearance. Also the
+///   column order of `predict_proba`.
 /// - `probability_of_class`: HashMap storing the probabilities of each class.
-/// - `probability_of_feat_by_class`: HashMap storing the probabilities of each feature given a class.
+/// - `class_log_prior`: Log probability of each class, in the order of `classes`.
+/// - `feature_log_prob`: Matrix of shape( classes, features ) holding the log probability of each
+///   feature given a class, rows in the order of `classes`. The same probabilities keyed by class
+///   are available from `probability_of_feat_by_class`.
 /// - `state`: PhantomData indicating whether the classifier has been fit.
 ///
 /// # Type parameters
 /// - `State`: Indicates whether the classifier has been fit. Can either be `Fit` or `Unfit`.
+/// - `L`: The type of the class labels, anything `Hash + Eq + Clone`. Set by `fit` from the
+///   labels it is given.
 ///
 /// # Example
 ///
@@ -33,16 +51,33 @@ use std::collections::{HashMap, HashSet};
 /// ];
 ///
 /// let mut nb = StdNaiveBayes::new(1.0);
-/// let nb = nb.fit(&x_train, &y_train);
-/// let y_pred = nb.predict(&x_test);
+/// let nb = nb.fit(&x_train, &y_train).unwrap();
+/// let y_pred = nb.predict(&x_test).unwrap();
 ///
 /// // y_pred will hold the predicted classes for x_test
 /// ```
-#[derive(Debug)]
-pub struct StdNaiveBayes<State = Unfit> {
+///
+/// ndarray data and non-string labels work the same way:
+///
+/// ```
+/// use ducky_learn::naive_bayes::StdNaiveBayes;
+/// use ndarray::array;
+///
+/// let x_train = array![[3.0, 0.0], [2.0, 1.0], [0.0, 3.0], [1.0, 2.0]];
+/// let y_train: Vec<u8> = vec![0, 0, 1, 1];
+///
+/// let nb = StdNaiveBayes::new(1.0).fit(&x_train.view(), &y_train).unwrap();
+///
+/// assert_eq!(nb.predict(&array![[4.0, 0.0], [0.0, 4.0]]).unwrap(), vec![0, 1]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "L: Deserialize<'de> + Hash + Eq"))]
+pub struct StdNaiveBayes<State = Unfit, L = String> {
     pub alpha: f64,
-    pub probability_of_class: HashMap<String, f64>,
-    pub probability_of_feat_by_class: HashMap<String, HashMap<String, f64>>,
+    pub classes: Vec<L>,
+    pub probability_of_class: HashMap<L, f64>,
+    pub class_log_prior: Array1<f64>,
+    pub feature_log_prob: Array2<f64>,
 
     state: std::marker::PhantomData<State>,
 }
@@ -58,8 +93,10 @@ impl StdNaiveBayes {
     pub fn new(alpha: f64) -> Self {
         Self {
             alpha,
+            classes: Default::default(),
             probability_of_class: Default::default(),
-            probability_of_feat_by_class: Default::default(),
+            class_log_prior: Default::default(),
+            feature_log_prob: Default::default(),
 
             state: Default::default(),
         }
@@ -68,103 +105,173 @@ impl StdNaiveBayes {
     /// Fits the `StdNaiveBayes` classifier to the training data.
     ///
     /// # Parameters
-    /// - `x`: The training data.
-    /// - `y`: The target values.
+    /// - `x`: The training data, either a dense `Vec<Vec<f64>>`, an ndarray `Array2`/`ArrayView2`
+    ///   or a sparse `CsrMatrix`.
+    /// - `y`: The target values, of any `Hash + Eq + Clone` label type.
     ///
     /// # Returns
     /// The fitted `StdNaiveBayes` classifier.
-    pub fn fit(mut self, x: &Vec<Vec<f64>>, y: &Vec<String>) -> StdNaiveBayes<Fit> {
-        let mut y_counts: HashMap<String, i32> = HashMap::new();
-        for class in y {
-            let counter = y_counts.entry(class.to_string()).or_insert(0);
-            *counter += 1;
-        }
-
-        let total_rows = y.len() as f64;
-        let unique_classes: HashSet<String> = y.into_iter().cloned().collect();
-
-        for uniq_class in &unique_classes {
-            self.probability_of_class.insert(
-                uniq_class.to_string(),
-                *y_counts.get(uniq_class).unwrap() as f64 / total_rows,
-            );
-
-            let mut class_feat_probs: HashMap<String, f64> = HashMap::new();
-            let mut sum_of_feats_in_class = 0.0;
-            for (i, class) in y.iter().enumerate() {
-                if class == uniq_class {
-                    for (j, feat_count) in x[i].iter().enumerate() {
-                        let counter = class_feat_probs.entry(j.to_string()).or_insert(0.0);
-                        *counter += *feat_count;
-                        sum_of_feats_in_class += *feat_count;
-                    }
-                }
-            }
-            sum_of_feats_in_class += self.alpha * x[0].len() as f64;
+    ///
+    /// # Errors
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::ShapeMismatch` if `x` and `y` differ in length or the rows of `x` differ in length.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn fit<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone>(
+        self,
+        x: &X,
+        y: &[L],
+    ) -> Result<StdNaiveBayes<Fit, L>, DuckyError> {
+        x.validate()?;
+        check_targets(x.n_rows(), y.len())?;
+
+        let classes = unique_labels(y);
+        let probability_of_class = calculate_class_probability(&classes, y);
+        let class_index: HashMap<&L, usize> = classes
+            .iter()
+            .enumerate()
+            .map(|(index, class)| (class, index))
+            .collect();
 
-            for (feat, count) in class_feat_probs.iter_mut() {
-                *count = (*count + self.alpha) / sum_of_feats_in_class;
+        let mut feature_counts = Array2::<f64>::zeros((classes.len(), x.n_cols()));
+        for (row, class) in y.iter().enumerate() {
+            let mut class_counts = feature_counts.row_mut(class_index[class]);
+            for (j, feat_count) in x.row_nonzeros(row) {
+                class_counts[j] += feat_count;
             }
-
-            self.probability_of_feat_by_class
-                .insert(uniq_class.to_string(), class_feat_probs);
         }
 
-        StdNaiveBayes {
+        let sum_of_feats_by_class =
+            feature_counts.sum_axis(Axis(1)) + self.alpha * x.n_cols() as f64;
+        let feature_log_prob = ((feature_counts + self.alpha)
+            / sum_of_feats_by_class.insert_axis(Axis(1))
+            + SMALL_NUMBER)
+            .mapv(f64::ln);
+        let class_log_prior = classes
+            .iter()
+            .map(|class| (probability_of_class[class] + SMALL_NUMBER).ln())
+            .collect();
+
+        Ok(StdNaiveBayes {
             alpha: self.alpha,
-            probability_of_class: self.probability_of_class.clone(),
-            probability_of_feat_by_class: self.probability_of_feat_by_class.clone(),
+            classes,
+            probability_of_class,
+            class_log_prior,
+            feature_log_prob,
 
             state: std::marker::PhantomData::<Fit>,
-        }
+        })
     }
 }
 
-impl StdNaiveBayes<Fit> {
+impl<L: Hash + Eq + Clone> StdNaiveBayes<Fit, L> {
+    /// Probability of each feature given a class, keyed by class and then by the feature's
+    /// column index as a string.
+    ///
+    /// returns: `HashMap<L, HashMap<String, f64>>` built from `feature_log_prob`
+    pub fn probability_of_feat_by_class(&self) -> HashMap<L, HashMap<String, f64>> {
+        self.classes
+            .iter()
+            .zip(self.feature_log_prob.rows())
+            .map(|(class, log_probs)| {
+                let feat_probs = log_probs
+                    .iter()
+                    .enumerate()
+                    .map(|(j, log_prob)| (j.to_string(), log_prob.exp() - SMALL_NUMBER))
+                    .collect();
+                (class.clone(), feat_probs)
+            })
+            .collect()
+    }
+
     /// Predicts the target values for the given data.
     ///
     /// # Parameters
-    /// - `x`: The data to predict target values for.
+    /// - `x`: The data to predict target values for, either a dense `Vec<Vec<f64>>`, an
+    ///   ndarray `Array2`/`ArrayView2` or a sparse `CsrMatrix`.
     ///
     /// # Returns
     /// The predicted target values.
     ///
-    /// # Panics
-    /// This function will panic if the classifier has not been fit.
-    pub fn predict(&self, x: &Vec<Vec<f64>>) -> Vec<String> {
-        let mut y_pred: Vec<String> = Vec::new();
-        let unique_classes: Vec<String> = self.probability_of_class.keys().cloned().collect();
-        let class_probabilities: Vec<f64> = self.probability_of_class.values().cloned().collect();
-        let small_number = 1e-9;
-
-        for row in x {
-            let mut row_probabilities: Vec<f64> = Vec::new();
-            for (i, class) in unique_classes.iter().enumerate() {
-                let mut log_sum = (class_probabilities[i] + small_number).ln();
-                for (j, feat_count) in row.iter().enumerate() {
-                    if *feat_count > 0.0 {
-                        let prob = self
-                            .probability_of_feat_by_class
-                            .get(class)
-                            .unwrap()
-                            .get(&j.to_string())
-                            .unwrap();
-                        log_sum += (*feat_count * (*prob + small_number).ln());
+    /// # Errors
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::ShapeMismatch` if `x` has a different number of features to the training data.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn predict<X: FeatureMatrix + ?Sized>(&self, x: &X) -> Result<Vec<L>, DuckyError> {
+        Ok(self
+            .joint_log_likelihood(x)?
+            .iter()
+            .map(|row_probabilities| self.classes[argmax(row_probabilities)].clone())
+            .collect())
+    }
+
+    /// Predicts the probability of each class for the given data.
+    ///
+    /// # Parameters
+    /// - `x`: The data to predict probabilities for, either a dense `Vec<Vec<f64>>`, an
+    ///   ndarray `Array2`/`ArrayView2` or a sparse `CsrMatrix`.
+    ///
+    /// # Returns
+    /// A vector per row holding the probability of each class, in the order of `classes`.
+    ///
+    /// # Errors
+    /// The same as `predict`.
+    pub fn predict_proba<X: FeatureMatrix + ?Sized>(
+        &self,
+        x: &X,
+    ) -> Result<Vec<Vec<f64>>, DuckyError> {
+        Ok(self
+            .joint_log_likelihood(x)?
+            .iter()
+            .map(|row_probabilities| normalize_log_probabilities(row_probabilities))
+            .collect())
+    }
+
+    /// Unnormalised log probability of each class for every row of `x`.
+    fn joint_log_likelihood<X: FeatureMatrix + ?Sized>(
+        &self,
+        x: &X,
+    ) -> Result<Vec<Vec<f64>>, DuckyError> {
+        x.validate()?;
+        check_n_features(self.feature_log_prob.ncols(), x.n_cols())?;
+
+        // Only positive counts are scored, a negative count has no meaning as a word count
+        Ok((0..x.n_rows())
+            .map(|row| {
+                let mut row_probabilities = self.class_log_prior.clone();
+                for (j, feat_count) in x.row_nonzeros(row) {
+                    if feat_count > 0.0 {
+                        row_probabilities.scaled_add(feat_count, &self.feature_log_prob.column(j));
                     }
                 }
-                row_probabilities.push(log_sum);
-            }
+                row_probabilities.to_vec()
+            })
+            .collect())
+    }
+}
 
-            let max_prob_index = row_probabilities
-                .iter()
-                .enumerate()
-                .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
-                .unwrap()
-                .0;
-            y_pred.push(unique_classes[max_prob_index].to_string());
-        }
+impl<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone> traits::Fit<X, [L]> for StdNaiveBayes<Unfit> {
+    type Fitted = StdNaiveBayes<Fit, L>;
 
-        y_pred
+    fn fit(self, x: &X, y: &[L]) -> Result<StdNaiveBayes<Fit, L>, DuckyError> {
+        self.fit(x, y)
+    }
+}
+
+impl<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone> traits::Predict<X> for StdNaiveBayes<Fit, L> {
+    type Output = Vec<L>;
+
+    fn predict(&self, x: &X) -> Result<Vec<L>, DuckyError> {
+        self.predict(x)
+    }
+}
+
+impl<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone> traits::PredictProba<X>
+    for StdNaiveBayes<Fit, L>
+{
+    type Output = Vec<Vec<f64>>;
+
+    fn predict_proba(&self, x: &X) -> Result<Vec<Vec<f64>>, DuckyError> {
+        self.predict_proba(x)
     }
 }
 
@@ -178,16 +285,24 @@ impl StdNaiveBayes<Fit> {
 /// that has not yet been trained on data, while a `Fit` model has been trained and
 /// can be used for making predictions.
 ///
+/// The class labels can be of any `Hash + Eq + Clone` type `L`, which is set by `fit`
+/// from the labels it is given.
+///
 /// # Fields
 ///
-/// * `classes` - A vector of unique class labels (targets) that the model may predict.
+/// * `var_smoothing` - Portion of the largest feature variance added to the variance of
+///   every feature, so a feature that is constant within a class does not make its
+///   probability divide by zero. Defaults to `1e-9`.
+///
+/// * `classes` - A vector of unique class labels (targets) that the model may predict,
+///   in order of first appearance in the training targets.
 ///
 /// * `probability_of_class` - A hashmap where keys are the class labels and the values
 ///   are the corresponding prior probabilities of each class.
 ///
 /// * `probability_of_feat_by_class` - A hashmap where keys are the class labels and
 ///   the values are vectors of tuples. Each tuple represents the mean and standard
-///   deviation of a particular feature for that class.
+///   deviation of a particular feature for that class, with `var_smoothing` applied.
 ///
 /// * `state` - A marker for the model's state. This is either `Unfit` (for a newly
 ///   instantiated model) or `Fit` (for a model that has been trained on data).
@@ -213,25 +328,34 @@ impl StdNaiveBayes<Fit> {
 ///     "class2".to_string(),
 /// ];
 ///
-/// let model = model.fit(&x_train, &y_train);
+/// let model = model.fit(&x_train, &y_train).unwrap();
 ///
 /// let x_test: Vec<Vec<f64>> = vec![
 ///     vec![1.5, 2.5],
 ///     vec![3.5, 4.5],
 /// ];
 ///
-/// let predictions = model.predict(&x_test);
+/// let predictions = model.predict(&x_test).unwrap();
 ///
 /// println!("{:?}", predictions);
 /// ```
-pub struct GaussianNaiveBayes<State = Unfit> {
-    pub classes: Vec<String>,
-    pub probability_of_class: HashMap<String, f64>,
-    pub probability_of_feat_by_class: HashMap<String, Vec<(f64, f64)>>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "L: Deserialize<'de> + Hash + Eq"))]
+pub struct GaussianNaiveBayes<State = Unfit, L = String> {
+    pub var_smoothing: f64,
+    pub classes: Vec<L>,
+    pub probability_of_class: HashMap<L, f64>,
+    pub probability_of_feat_by_class: HashMap<L, Vec<(f64, f64)>>,
 
     state: std::marker::PhantomData<State>,
 }
 
+impl Default for GaussianNaiveBayes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GaussianNaiveBayes {
     /// Creates a new `GaussianNaiveBayes` instance with an `Unfit` state.
     ///
@@ -247,6 +371,7 @@ impl GaussianNaiveBayes {
     /// ```
     pub fn new() -> Self {
         Self {
+            var_smoothing: 1e-9,
             classes: Default::default(),
             probability_of_class: Default::default(),
             probability_of_feat_by_class: Default::default(),
@@ -255,18 +380,41 @@ impl GaussianNaiveBayes {
         }
     }
 
+    /// Sets the portion of the largest feature variance added to the variance of every feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `var_smoothing` - Has to be positive and finite, checked by `fit`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The model with the new `var_smoothing`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::naive_bayes::GaussianNaiveBayes;
+    ///
+    /// let model = GaussianNaiveBayes::new().with_var_smoothing(1e-6);
+    /// assert_eq!(model.var_smoothing, 1e-6);
+    /// ```
+    pub fn with_var_smoothing(mut self, var_smoothing: f64) -> Self {
+        self.var_smoothing = var_smoothing;
+        self
+    }
+
     /// Fits the model on the provided dataset, updating the model's state to `Fit`.
     ///
     /// # Arguments
     ///
-    /// * `x` - A reference to a vector of vectors, where each inner vector represents
-    ///   the features of a data point.
+    /// * `x` - The features of each data point, one row per data point. Either a
+    ///   `Vec<Vec<f64>>`, an ndarray `Array2`/`ArrayView2` or a `CsrMatrix`.
     ///
-    /// * `y` - A reference to a vector of class labels for each data point in `x`.
+    /// * `y` - The class label of each data point in `x`, of any `Hash + Eq + Clone` type.
     ///
     /// # Returns
     ///
-    /// * `GaussianNaiveBayes<Fit>` - The same model instance with updated fields
+    /// * `GaussianNaiveBayes<Fit, L>` - The same model instance with updated fields
     ///   and state set to `Fit`.
     ///
     /// # Examples
@@ -289,37 +437,87 @@ impl GaussianNaiveBayes {
     ///     "class2".to_string(),
     /// ];
     ///
-    /// let model = model.fit(&x_train, &y_train);
+    /// let model = model.fit(&x_train, &y_train).unwrap();
     /// ```
-    pub fn fit(mut self, x: &Vec<Vec<f64>>, y: &Vec<String>) -> GaussianNaiveBayes<Fit> {
-        let uniq_classes: Vec<String> = y
-            .clone()
-            .into_iter()
-            .collect::<HashSet<String>>()
-            .into_iter()
-            .collect::<Vec<String>>();
+    ///
+    /// ```
+    /// use ducky_learn::naive_bayes::GaussianNaiveBayes;
+    /// use ndarray::array;
+    ///
+    /// let x_train = array![[0.1, 0.5], [0.2, 0.7], [0.6, 0.6], [0.8, 0.5]];
+    /// let y_train: Vec<usize> = vec![3, 3, 7, 7];
+    ///
+    /// let model = GaussianNaiveBayes::new().fit(&x_train.view(), &y_train).unwrap();
+    ///
+    /// assert_eq!(model.classes, vec![3, 7]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter` - If `var_smoothing` is not positive and finite.
+    ///
+    /// * `DuckyError::EmptyInput` - If `x` has no rows or features.
+    ///
+    /// * `DuckyError::ShapeMismatch` - If `x` and `y` differ in length or the rows of `x`
+    ///   differ in length.
+    ///
+    /// * `DuckyError::NonFinite` - If `x` contains a NaN or infinite value.
+    pub fn fit<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone>(
+        self,
+        x: &X,
+        y: &[L],
+    ) -> Result<GaussianNaiveBayes<Fit, L>, DuckyError> {
+        if !(self.var_smoothing > 0.0 && self.var_smoothing.is_finite()) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "var_smoothing {} has to be positive and finite",
+                self.var_smoothing
+            )));
+        }
+        x.validate()?;
+        check_targets(x.n_rows(), y.len())?;
+
+        let uniq_classes = unique_labels(y);
+        let mut probability_of_feat_by_class = calculate_feature_probability(x, y, &uniq_classes)?;
+
+        // Scaled by the spread of the data like sklearn, falling back to `var_smoothing`
+        // itself when every feature is constant
+        let largest_variance = x
+            .to_array2()
+            .var_axis(Axis(0), 0.0)
+            .fold(0.0, |largest: f64, variance| largest.max(*variance));
+        let epsilon = if largest_variance > 0.0 {
+            self.var_smoothing * largest_variance
+        } else {
+            self.var_smoothing
+        };
+        for feature_probs in probability_of_feat_by_class.values_mut() {
+            for (_, std_dev) in feature_probs.iter_mut() {
+                *std_dev = (std_dev.powi(2) + epsilon).sqrt();
+            }
+        }
 
-        GaussianNaiveBayes {
+        Ok(GaussianNaiveBayes {
+            var_smoothing: self.var_smoothing,
             probability_of_class: calculate_class_probability(&uniq_classes, y),
-            probability_of_feat_by_class: calculate_feature_probability(x, y, &uniq_classes),
+            probability_of_feat_by_class,
             classes: uniq_classes,
 
             state: std::marker::PhantomData::<Fit>,
-        }
+        })
     }
 }
 
-impl GaussianNaiveBayes<Fit> {
+impl<L: Hash + Eq + Clone> GaussianNaiveBayes<Fit, L> {
     /// Predicts the class of the provided data points.
     ///
     /// # Arguments
     ///
-    /// * `x` - A reference to a vector of vectors, where each inner vector represents
-    ///   the features of a data point.
+    /// * `x` - The features of each data point, one row per data point. Either a
+    ///   `Vec<Vec<f64>>`, an ndarray `Array2`/`ArrayView2` or a `CsrMatrix`.
     ///
     /// # Returns
     ///
-    /// * `Vec<String>` - A vector of predicted class labels for each data point in `x`.
+    /// * `Vec<L>` - A vector of predicted class labels for each data point in `x`.
     ///
     /// # Examples
     ///
@@ -329,23 +527,93 @@ impl GaussianNaiveBayes<Fit> {
     /// let model = GaussianNaiveBayes::new().fit(
     ///     &vec![vec![0.1, 0.5], vec![0.6, 0.6]],
     ///     &vec!["class1".to_string(), "class2".to_string()]
-    /// );
+    /// ).unwrap();
     ///
     /// let x_test: Vec<Vec<f64>> = vec![
     ///     vec![1.5, 2.5],
     ///     vec![3.5, 4.5],
     /// ];
     ///
-    /// let predictions = model.predict(&x_test);
+    /// let predictions = model.predict(&x_test).unwrap();
     ///
     /// println!("{:?}", predictions);
     /// ```
-    pub fn predict(&self, x: &Vec<Vec<f64>>) -> Vec<String> {
-        let mut predictions: Vec<String> = Vec::new();
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::EmptyInput` - If `x` has no rows or features.
+    ///
+    /// * `DuckyError::ShapeMismatch` - If the rows of `x` have a different number of
+    ///   features to the training data.
+    ///
+    /// * `DuckyError::NonFinite` - If `x` contains a NaN or infinite value.
+    pub fn predict<X: FeatureMatrix + ?Sized>(&self, x: &X) -> Result<Vec<L>, DuckyError> {
+        Ok(self
+            .joint_log_likelihood(x)?
+            .iter()
+            .map(|class_probs| self.classes[argmax(class_probs)].clone())
+            .collect())
+    }
+
+    /// Predicts the probability of each class for the provided data points.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The features of each data point, one row per data point. Either a
+    ///   `Vec<Vec<f64>>`, an ndarray `Array2`/`ArrayView2` or a `CsrMatrix`.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<f64>>` - A vector per data point holding the probability of each class,
+    ///   in the order of `classes`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::naive_bayes::GaussianNaiveBayes;
+    ///
+    /// let model = GaussianNaiveBayes::new().fit(
+    ///     &vec![vec![0.1, 0.5], vec![0.2, 0.7], vec![0.6, 0.6], vec![0.8, 0.5]],
+    ///     &vec!["class1".to_string(), "class1".to_string(), "class2".to_string(), "class2".to_string()]
+    /// ).unwrap();
+    ///
+    /// let probabilities = model.predict_proba(&vec![vec![0.15, 0.6]]).unwrap();
+    ///
+    /// assert!((probabilities[0].iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The same as `predict`.
+    pub fn predict_proba<X: FeatureMatrix + ?Sized>(
+        &self,
+        x: &X,
+    ) -> Result<Vec<Vec<f64>>, DuckyError> {
+        Ok(self
+            .joint_log_likelihood(x)?
+            .iter()
+            .map(|class_probs| normalize_log_probabilities(class_probs))
+            .collect())
+    }
 
-        for data in x.iter() {
-            let mut max_prob = f64::NEG_INFINITY;
-            let mut max_class = String::from("");
+    /// Unnormalised log probability of each class for every data point in `x`.
+    fn joint_log_likelihood<X: FeatureMatrix + ?Sized>(
+        &self,
+        x: &X,
+    ) -> Result<Vec<Vec<f64>>, DuckyError> {
+        x.validate()?;
+        let n_features = self
+            .probability_of_feat_by_class
+            .values()
+            .next()
+            .map_or(0, Vec::len);
+        check_n_features(n_features, x.n_cols())?;
+
+        let x = x.to_array2();
+        let mut joint_log_likelihood: Vec<Vec<f64>> = Vec::with_capacity(x.nrows());
+
+        for data in x.rows() {
+            let mut class_probs: Vec<f64> = Vec::with_capacity(self.classes.len());
 
             for class in &self.classes {
                 let mut class_prob = self.probability_of_class.get(class).unwrap().ln();
@@ -353,29 +621,81 @@ impl GaussianNaiveBayes<Fit> {
                 if let Some(feature_probs) = self.probability_of_feat_by_class.get(class) {
                     for (index, &(mean, std_dev)) in feature_probs.iter().enumerate() {
                         let feature_value = data[index];
-                        let feature_prob = calculate_probability(feature_value, mean, std_dev);
-                        class_prob += feature_prob.ln();
+                        class_prob += calculate_log_probability(feature_value, mean, std_dev);
                     }
                 }
 
-                if class_prob > max_prob {
-                    max_prob = class_prob;
-                    max_class = class.clone();
-                }
+                class_probs.push(class_prob);
             }
-            predictions.push(max_class);
+            joint_log_likelihood.push(class_probs);
         }
 
-        predictions
+        Ok(joint_log_likelihood)
+    }
+}
+
+impl<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone> traits::Fit<X, [L]>
+    for GaussianNaiveBayes<Unfit>
+{
+    type Fitted = GaussianNaiveBayes<Fit, L>;
+
+    fn fit(self, x: &X, y: &[L]) -> Result<GaussianNaiveBayes<Fit, L>, DuckyError> {
+        self.fit(x, y)
+    }
+}
+
+impl<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone> traits::Predict<X>
+    for GaussianNaiveBayes<Fit, L>
+{
+    type Output = Vec<L>;
+
+    fn predict(&self, x: &X) -> Result<Vec<L>, DuckyError> {
+        self.predict(x)
+    }
+}
+
+impl<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone> traits::PredictProba<X>
+    for GaussianNaiveBayes<Fit, L>
+{
+    type Output = Vec<Vec<f64>>;
+
+    fn predict_proba(&self, x: &X) -> Result<Vec<Vec<f64>>, DuckyError> {
+        self.predict_proba(x)
+    }
+}
+
+/// Index of the largest value, the first one wins ties.
+fn argmax(values: &[f64]) -> usize {
+    let mut max_index = 0;
+    for (index, value) in values.iter().enumerate() {
+        if *value > values[max_index] {
+            max_index = index;
+        }
     }
+    max_index
 }
 
-fn calculate_mean(data: &Vec<f64>) -> f64 {
+/// Turns unnormalised log probabilities into probabilities that sum to one,
+/// using the log-sum-exp trick to avoid underflow.
+fn normalize_log_probabilities(log_probs: &[f64]) -> Vec<f64> {
+    let max_log_prob = log_probs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let sum_exp: f64 = log_probs
+        .iter()
+        .map(|value| (value - max_log_prob).exp())
+        .sum();
+
+    log_probs
+        .iter()
+        .map(|value| (value - max_log_prob).exp() / sum_exp)
+        .collect()
+}
+
+fn calculate_mean(data: &[f64]) -> f64 {
     let sum: f64 = data.iter().sum();
     sum / data.len() as f64
 }
 
-fn calculate_std_dev(data: &Vec<f64>, mean: f64) -> f64 {
+fn calculate_std_dev(data: &[f64], mean: f64) -> f64 {
     let variance: f64 = data
         .iter()
         .map(|&value| {
@@ -393,14 +713,20 @@ fn calculate_probability(x: f64, mean: f64, std_dev: f64) -> f64 {
     (1.0 / (2.0 * std::f64::consts::PI * std_dev.powi(2)).sqrt()) * exponent
 }
 
-fn calculate_class_probability(
-    uniq_classes: &Vec<String>,
-    all_classes: &Vec<String>,
-) -> HashMap<String, f64> {
-    let mut class_probability: HashMap<String, f64> = HashMap::new();
+/// Log of `calculate_probability`, computed directly so values far from the mean do not
+/// underflow to a probability of zero.
+fn calculate_log_probability(x: f64, mean: f64, std_dev: f64) -> f64 {
+    let variance = std_dev.powi(2);
+    -((x - mean).powi(2)) / (2.0 * variance) - 0.5 * (2.0 * std::f64::consts::PI * variance).ln()
+}
+
+fn calculate_class_probability<L: Hash + Eq + Clone>(
+    uniq_classes: &[L],
+    all_classes: &[L],
+) -> HashMap<L, f64> {
     let total = all_classes.len() as f64;
 
-    let mut class_counts: HashMap<&String, f64> = HashMap::new();
+    let mut class_counts: HashMap<&L, f64> = HashMap::new();
 
     // Calculate the counts for each class in one pass
     for class in all_classes {
@@ -417,62 +743,55 @@ fn calculate_class_probability(
         .collect()
 }
 
-fn calculate_feature_probability(
-    x: &Vec<Vec<f64>>,
-    y: &Vec<String>,
-    uniq_classes: &Vec<String>,
-) -> HashMap<String, Vec<(f64, f64)>> {
-    let mut return_feature_prob: HashMap<String, Vec<(f64, f64)>> = HashMap::new();
+fn calculate_feature_probability<X: FeatureMatrix + ?Sized, L: Hash + Eq + Clone>(
+    x: &X,
+    y: &[L],
+    uniq_classes: &[L],
+) -> Result<HashMap<L, Vec<(f64, f64)>>, DuckyError> {
+    let mut return_feature_prob: HashMap<L, Vec<(f64, f64)>> = HashMap::new();
 
-    if x.len() != y.len() {
-        return HashMap::new();
-    }
+    check_targets(x.n_rows(), y.len())?;
+    let x = x.to_array2();
 
     for class in uniq_classes {
-        let x_class: Vec<_> = x
-            .iter()
-            .zip(y)
-            .filter_map(|(x, y)| if y == class { Some(x.clone()) } else { None })
-            .collect();
+        let class_rows: Vec<usize> = (0..y.len()).filter(|&i| &y[i] == class).collect();
 
-        if x_class.is_empty() {
+        if class_rows.is_empty() {
             continue;
         }
 
-        let num_features = x_class[0].len();
+        let x_class = x.select(Axis(0), &class_rows);
 
-        for i in 0..num_features {
-            let feature_values: Vec<_> = x_class.iter().map(|features| features[i]).collect();
-
-            // calculate the mean
-            let mean: f64 = feature_values.iter().sum::<f64>() / feature_values.len() as f64;
+        let feature_probs = x_class
+            .columns()
+            .into_iter()
+            .map(|feature_values| {
+                let feature_values = feature_values.to_vec();
+                let mean = calculate_mean(&feature_values);
+                (mean, calculate_std_dev(&feature_values, mean))
+            })
+            .collect();
 
-            // calculate the standard deviation
-            let variance: f64 = feature_values
-                .iter()
-                .map(|value| {
-                    let diff = mean - *value;
-                    diff * diff
-                })
-                .sum::<f64>()
-                / feature_values.len() as f64;
-
-            let std_dev = variance.sqrt();
-
-            return_feature_prob
-                .entry(class.to_string())
-                .or_insert_with(|| Vec::with_capacity(num_features))
-                .push((mean, std_dev));
-        }
+        return_feature_prob.insert(class.clone(), feature_probs);
     }
 
-    return_feature_prob
+    Ok(return_feature_prob)
 }
 
 #[cfg(test)]
 mod calculation_functions_tests {
     use super::*;
 
+    #[test]
+    fn test_calculate_log_probability() {
+        assert!(
+            (calculate_log_probability(1.0, 0.5, 2.0) - calculate_probability(1.0, 0.5, 2.0).ln())
+                .abs()
+                < 1e-12
+        );
+        assert!(calculate_log_probability(1.0, 0.0, 1e-5).is_finite());
+    }
+
     #[test]
     fn test_calculate_class_probability() {
         let uniq_classes = vec![
@@ -514,7 +833,7 @@ mod calculation_functions_tests {
 
         let sum: f64 = probabilities.values().sum();
 
-        assert!(1.0 - sum < f64::EPSILON);
+        assert!((1.0 - sum).abs() < f64::EPSILON);
     }
 
     #[test]
@@ -533,7 +852,7 @@ mod calculation_functions_tests {
             vec![3.0, 3.0],
         ];
 
-        let feature_probabilities = calculate_feature_probability(&x, &y, &uniq_classes);
+        let feature_probabilities = calculate_feature_probability(&x, &y, &uniq_classes).unwrap();
 
         let class1_probabilities = feature_probabilities.get("class1").unwrap();
         assert!((class1_probabilities[0].0 - 1.5).abs() < f64::EPSILON);
@@ -554,7 +873,7 @@ mod calculation_functions_tests {
         let y = vec![];
         let x = vec![];
 
-        let feature_probabilities = calculate_feature_probability(&x, &y, &uniq_classes);
+        let feature_probabilities = calculate_feature_probability(&x, &y, &uniq_classes).unwrap();
 
         assert!(feature_probabilities.is_empty());
     }
@@ -575,7 +894,7 @@ mod calculation_functions_tests {
             vec![2.0, 2.0],
         ];
 
-        let feature_probabilities = calculate_feature_probability(&x, &y, &uniq_classes);
+        let feature_probabilities = calculate_feature_probability(&x, &y, &uniq_classes).unwrap();
 
         let class1_probabilities = feature_probabilities.get("class1").unwrap();
         assert!((class1_probabilities[0].0 - 2.0).abs() < f64::EPSILON);
@@ -598,7 +917,13 @@ mod calculation_functions_tests {
 
         let feature_probabilities = calculate_feature_probability(&x, &y, &uniq_classes);
 
-        assert!(feature_probabilities.is_empty());
+        assert_eq!(
+            feature_probabilities,
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![0],
+                found: vec![2]
+            })
+        );
     }
 
     #[test]
@@ -611,10 +936,7 @@ mod calculation_functions_tests {
     fn test_calculate_std_dev() {
         let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
         let mean = calculate_mean(&data);
-        assert_eq!(
-            (calculate_std_dev(&data, mean) - 1.414213).abs() < 0.00001,
-            true
-        );
+        assert!((calculate_std_dev(&data, mean) - std::f64::consts::SQRT_2).abs() < 0.00001);
     }
 
     #[test]
@@ -622,16 +944,15 @@ mod calculation_functions_tests {
         let x = 2.0;
         let mean = 2.0;
         let std_dev = 1.0;
-        assert_eq!(
-            (calculate_probability(x, mean, std_dev) - 0.398942).abs() < 0.00001,
-            true
-        );
+        assert!((calculate_probability(x, mean, std_dev) - 0.398942).abs() < 0.00001);
     }
 }
 
 #[cfg(test)]
 mod naive_bayes_tests {
     use super::*;
+    use crate::sparse::CsrMatrix;
+    use ndarray::array;
 
     #[test]
     fn test_fit_std() {
@@ -649,10 +970,56 @@ mod naive_bayes_tests {
             "class1".to_string(),
         ];
 
-        let model = model.fit(&x, &y);
+        let model = model.fit(&x, &y).unwrap();
 
         assert!((model.probability_of_class.get("class1").unwrap() - 2.0 / 3.0).abs() < 1e-9);
         assert!((model.probability_of_class.get("class2").unwrap() - 1.0 / 3.0).abs() < 1e-9);
+
+        let expected = array![
+            [5.0 / 15.0, 4.0 / 15.0, 6.0 / 15.0],
+            [3.0 / 9.0, 4.0 / 9.0, 2.0 / 9.0]
+        ];
+        assert_eq!(model.feature_log_prob.shape(), &[2, 3]);
+        for (log_prob, prob) in model.feature_log_prob.iter().zip(expected.iter()) {
+            assert!((log_prob.exp() - prob).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_probability_of_feat_by_class_std() {
+        let x: Vec<Vec<f64>> = vec![
+            vec![1.0, 2.0, 3.0],
+            vec![2.0, 3.0, 1.0],
+            vec![3.0, 1.0, 2.0],
+        ];
+        let y: Vec<String> = vec![
+            "class1".to_string(),
+            "class2".to_string(),
+            "class1".to_string(),
+        ];
+
+        let model = StdNaiveBayes::new(1.0).fit(&x, &y).unwrap();
+        let probability_of_feat_by_class = model.probability_of_feat_by_class();
+
+        let class1 = &probability_of_feat_by_class["class1"];
+        assert!((class1["0"] - 5.0 / 15.0).abs() < 1e-9);
+        assert!((class1["2"] - 6.0 / 15.0).abs() < 1e-9);
+        let class2 = &probability_of_feat_by_class["class2"];
+        assert!((class2["1"] - 4.0 / 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_predict_std_ignores_negative_values() {
+        let x: Vec<Vec<f64>> = vec![vec![3.0, 0.0], vec![0.0, 3.0]];
+        let y: Vec<String> = vec!["class1".to_string(), "class2".to_string()];
+        let model = StdNaiveBayes::new(1.0).fit(&x, &y).unwrap();
+
+        let x_test: Vec<Vec<f64>> = vec![vec![1.0, -5.0], vec![1.0, 0.0]];
+
+        assert_eq!(
+            model.predict_proba(&x_test).unwrap()[0],
+            model.predict_proba(&x_test).unwrap()[1]
+        );
     }
 
     #[test]
@@ -675,16 +1042,152 @@ mod naive_bayes_tests {
             "class1".to_string(),
         ];
 
-        let model = model.fit(&x, &y);
+        let model = model.fit(&x, &y).unwrap();
 
         let x_test: Vec<Vec<f64>> =
             vec![vec![1.5, 2.5, 3.5, 1.5, 2.5], vec![5.5, 4.5, 5.5, 4.5, 4.5]];
 
-        let predictions = model.predict(&x_test);
+        let predictions = model.predict(&x_test).unwrap();
 
         assert_eq!(predictions, vec!["class1", "class2"]);
     }
 
+    #[test]
+    fn test_predict_proba_std() {
+        let x: Vec<Vec<f64>> = vec![
+            vec![4.0, 0.0, 1.0],
+            vec![3.0, 1.0, 0.0],
+            vec![0.0, 4.0, 1.0],
+            vec![1.0, 3.0, 0.0],
+        ];
+
+        let y: Vec<String> = vec![
+            "class2".to_string(),
+            "class2".to_string(),
+            "class1".to_string(),
+            "class1".to_string(),
+        ];
+
+        let model = StdNaiveBayes::new(1.0).fit(&x, &y).unwrap();
+        let probabilities = model
+            .predict_proba(&vec![vec![5.0, 0.0, 0.0], vec![0.0, 5.0, 0.0]])
+            .unwrap();
+
+        assert_eq!(model.classes, vec!["class2", "class1"]);
+        for row in &probabilities {
+            assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+        assert!(probabilities[0][0] > 0.9);
+        assert!(probabilities[1][1] > 0.9);
+    }
+
+    #[test]
+    fn test_predict_std_sparse() {
+        let x: Vec<Vec<f64>> = vec![
+            vec![1.0, 0.0, 3.0, 0.0, 2.0],
+            vec![2.0, 0.0, 4.0, 0.0, 0.0],
+            vec![0.0, 4.0, 0.0, 4.0, 0.0],
+            vec![0.0, 5.0, 1.0, 5.0, 0.0],
+        ];
+
+        let y: Vec<String> = vec![
+            "class1".to_string(),
+            "class1".to_string(),
+            "class2".to_string(),
+            "class2".to_string(),
+        ];
+
+        let x_test: Vec<Vec<f64>> =
+            vec![vec![1.0, 0.0, 2.0, 0.0, 0.0], vec![0.0, 3.0, 0.0, 1.0, 0.0]];
+
+        let dense_model = StdNaiveBayes::new(1.0).fit(&x, &y).unwrap();
+        let sparse_model = StdNaiveBayes::new(1.0)
+            .fit(&CsrMatrix::from_dense(&x), &y)
+            .unwrap();
+
+        assert_eq!(sparse_model.feature_log_prob, dense_model.feature_log_prob);
+        assert_eq!(
+            sparse_model
+                .predict(&CsrMatrix::from_dense(&x_test))
+                .unwrap(),
+            vec!["class1", "class2"]
+        );
+        assert_eq!(
+            dense_model.predict(&x_test).unwrap(),
+            vec!["class1", "class2"]
+        );
+    }
+
+    #[test]
+    fn test_fit_std_errors() {
+        let y: Vec<String> = vec!["class1".to_string(), "class2".to_string()];
+
+        let empty: Vec<Vec<f64>> = vec![];
+        let ragged: Vec<Vec<f64>> = vec![vec![1.0, 2.0], vec![1.0]];
+        let nan: Vec<Vec<f64>> = vec![vec![1.0, f64::NAN], vec![1.0, 2.0]];
+        let too_long: Vec<Vec<f64>> = vec![vec![1.0], vec![2.0], vec![3.0]];
+
+        assert_eq!(
+            StdNaiveBayes::new(1.0).fit(&empty, &y).unwrap_err(),
+            DuckyError::EmptyInput
+        );
+        assert!(matches!(
+            StdNaiveBayes::new(1.0).fit(&ragged, &y),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert_eq!(
+            StdNaiveBayes::new(1.0).fit(&nan, &y).unwrap_err(),
+            DuckyError::NonFinite
+        );
+        assert_eq!(
+            StdNaiveBayes::new(1.0).fit(&too_long, &y).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![3],
+                found: vec![2]
+            }
+        );
+    }
+
+    #[test]
+    fn test_predict_std_wrong_features() {
+        let x: Vec<Vec<f64>> = vec![vec![1.0, 2.0], vec![2.0, 1.0]];
+        let y: Vec<String> = vec!["class1".to_string(), "class2".to_string()];
+
+        let model = StdNaiveBayes::new(1.0).fit(&x, &y).unwrap();
+
+        assert_eq!(
+            model.predict(&vec![vec![1.0, 2.0, 3.0]]).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![3]
+            }
+        );
+    }
+
+    #[test]
+    fn test_gaus_errors() {
+        let x: Vec<Vec<f64>> = vec![vec![1.0, 2.0], vec![2.0, 1.0]];
+        let y: Vec<String> = vec!["class1".to_string(), "class2".to_string()];
+
+        assert_eq!(
+            GaussianNaiveBayes::new().fit(&[], &y).unwrap_err(),
+            DuckyError::EmptyInput
+        );
+        assert_eq!(
+            GaussianNaiveBayes::new()
+                .fit(&[vec![f64::INFINITY, 2.0], vec![2.0, 1.0]], &y)
+                .unwrap_err(),
+            DuckyError::NonFinite
+        );
+
+        let model = GaussianNaiveBayes::new().fit(&x, &y).unwrap();
+
+        assert!(matches!(
+            model.predict(&[vec![1.0]]),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_new_gaus() {
         let model: GaussianNaiveBayes = GaussianNaiveBayes::new();
@@ -709,27 +1212,19 @@ mod naive_bayes_tests {
             "class2".to_string(),
             "class2".to_string(),
         ];
-        let model = model.fit(&x, &y);
+        let model = model.fit(&x, &y).unwrap();
 
         assert_eq!(model.classes.len(), 2);
         assert!(model.classes.contains(&"class1".to_string()));
         assert!(model.classes.contains(&"class2".to_string()));
 
         assert_eq!(model.probability_of_class.len(), 2);
-        assert!(model
-            .probability_of_class
-            .contains_key(&"class1".to_string()));
-        assert!(model
-            .probability_of_class
-            .contains_key(&"class2".to_string()));
+        assert!(model.probability_of_class.contains_key("class1"));
+        assert!(model.probability_of_class.contains_key("class2"));
 
         assert_eq!(model.probability_of_feat_by_class.len(), 2);
-        assert!(model
-            .probability_of_feat_by_class
-            .contains_key(&"class1".to_string()));
-        assert!(model
-            .probability_of_feat_by_class
-            .contains_key(&"class2".to_string()));
+        assert!(model.probability_of_feat_by_class.contains_key("class1"));
+        assert!(model.probability_of_feat_by_class.contains_key("class2"));
     }
 
     #[test]
@@ -747,13 +1242,127 @@ mod naive_bayes_tests {
             "class2".to_string(),
             "class2".to_string(),
         ];
-        let model = model.fit(&x, &y);
+        let model = model.fit(&x, &y).unwrap();
 
         let x_test = vec![vec![2.0, 1.0], vec![4.0, 3.0]];
 
-        let predictions = model.predict(&x_test);
+        let predictions = model.predict(&x_test).unwrap();
         assert_eq!(predictions.len(), x_test.len());
         assert_eq!(predictions[0], "class1");
         assert_eq!(predictions[1], "class2");
     }
+
+    #[test]
+    fn test_predict_gaus_constant_feature() {
+        // The second feature is constant within each class
+        let x = vec![
+            vec![1.0, 0.0],
+            vec![2.0, 0.0],
+            vec![5.0, 1.0],
+            vec![6.0, 1.0],
+        ];
+        let y = vec![
+            "class1".to_string(),
+            "class1".to_string(),
+            "class2".to_string(),
+            "class2".to_string(),
+        ];
+        let model = GaussianNaiveBayes::new().fit(&x, &y).unwrap();
+
+        let x_test = vec![vec![1.5, 0.0], vec![5.5, 1.0], vec![1.5, 0.5]];
+
+        assert_eq!(model.predict(&x_test).unwrap()[..2], ["class1", "class2"]);
+        for probabilities in model.predict_proba(&x_test).unwrap() {
+            assert!(probabilities
+                .iter()
+                .all(|probability| probability.is_finite()));
+        }
+
+        let all_constant = vec![vec![2.0, 2.0], vec![2.0, 2.0]];
+        let y = vec!["class1".to_string(), "class2".to_string()];
+        let model = GaussianNaiveBayes::new().fit(&all_constant, &y).unwrap();
+        for probabilities in model.predict_proba(&all_constant).unwrap() {
+            assert!(probabilities
+                .iter()
+                .all(|probability| probability.is_finite()));
+        }
+    }
+
+    #[test]
+    fn test_gaus_var_smoothing_errors() {
+        let x: Vec<Vec<f64>> = vec![vec![1.0, 2.0], vec![2.0, 1.0]];
+        let y: Vec<String> = vec!["class1".to_string(), "class2".to_string()];
+
+        for var_smoothing in [0.0, -1.0, f64::NAN] {
+            assert!(matches!(
+                GaussianNaiveBayes::new()
+                    .with_var_smoothing(var_smoothing)
+                    .fit(&x, &y),
+                Err(DuckyError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_std_ndarray_labels() {
+        let x = array![
+            [3.0, 0.0, 1.0],
+            [2.0, 1.0, 0.0],
+            [0.0, 3.0, 1.0],
+            [1.0, 2.0, 0.0],
+        ];
+        let y: Vec<u8> = vec![4, 4, 9, 9];
+
+        let model = StdNaiveBayes::new(1.0).fit(&x.view(), &y).unwrap();
+        let dense_model = StdNaiveBayes::new(1.0)
+            .fit(
+                &x.outer_iter().map(|row| row.to_vec()).collect::<Vec<_>>(),
+                &y,
+            )
+            .unwrap();
+
+        assert_eq!(model.classes, vec![4, 9]);
+        assert_eq!(model.feature_log_prob, dense_model.feature_log_prob);
+        assert_eq!(
+            model
+                .predict(&array![[4.0, 0.0, 0.0], [0.0, 4.0, 0.0]])
+                .unwrap(),
+            vec![4, 9]
+        );
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Fruit {
+        Apple,
+        Banana,
+    }
+
+    #[test]
+    fn test_gaus_ndarray_labels() {
+        let x = array![[2.0, 1.0], [3.0, 2.0], [7.5, 6.5], [8.0, 7.0]];
+        let y = vec![Fruit::Apple, Fruit::Apple, Fruit::Banana, Fruit::Banana];
+
+        let model = GaussianNaiveBayes::new().fit(&x.view(), &y).unwrap();
+
+        assert_eq!(model.classes, vec![Fruit::Apple, Fruit::Banana]);
+        assert_eq!(
+            model
+                .predict(&array![[2.5, 1.5], [7.0, 7.0]].view())
+                .unwrap(),
+            vec![Fruit::Apple, Fruit::Banana]
+        );
+        assert_eq!(
+            model.predict(&array![[1.0, 2.0, 3.0]]).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![3]
+            }
+        );
+        assert_eq!(
+            GaussianNaiveBayes::new()
+                .fit(&array![[f64::NAN, 1.0]], &[Fruit::Apple])
+                .unwrap_err(),
+            DuckyError::NonFinite
+        );
+    }
 }
@@ -0,0 +1,505 @@
+extern crate ndarray;
+
+use super::error::DuckyError;
+use super::traits::{self, Transform};
+use super::util::{Fit, Unfit};
+use serde::{Deserialize, Serialize};
+
+/// Two transformers applied one after the other, fit as a single transformer.
+///
+/// The second transformer is fit on the output of the first, so chains can be nested to join
+/// up any number of steps.
+///
+/// # Fields
+/// - `first`: The transformer applied to the input.
+/// - `second`: The transformer applied to the output of `first`.
+///
+/// # Type parameters
+/// - `A`: The type of the first transformer.
+/// - `B`: The type of the second transformer.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::impute::SimpleImputer;
+/// use ducky_learn::pipeline::Chain;
+/// use ducky_learn::preprocessing::MaxAbsScaler;
+/// use ducky_learn::traits::{Fit, Transform};
+/// use ndarray::array;
+///
+/// let x = array![[f64::NAN, 4.0], [2.0, -2.0], [4.0, 0.0]];
+///
+/// let chain = Chain::new(SimpleImputer::default(), MaxAbsScaler::new())
+///     .fit(&x, &())
+///     .unwrap();
+///
+/// assert_eq!(
+///     chain.transform(&x).unwrap(),
+///     array![[0.75, 1.0], [0.5, -0.5], [1.0, 0.0]]
+/// );
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chain<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A, B> Chain<A, B> {
+    /// Creates a new chain of two transformers.
+    ///
+    /// # Arguments
+    ///
+    /// * `first`: The transformer applied to the input
+    /// * `second`: The transformer applied to the output of `first`
+    ///
+    /// returns: `Chain<A, B>`
+    pub fn new(first: A, second: B) -> Self {
+        Chain { first, second }
+    }
+}
+
+impl<X, A, B> traits::Fit<X, ()> for Chain<A, B>
+where
+    X: ?Sized,
+    A: traits::Fit<X, ()>,
+    A::Fitted: Transform<X>,
+    B: traits::Fit<<A::Fitted as Transform<X>>::Output, ()>,
+{
+    type Fitted = Chain<A::Fitted, B::Fitted>;
+
+    fn fit(self, x: &X, _: &()) -> Result<Self::Fitted, DuckyError> {
+        let first = self.first.fit(x, &())?;
+        let second = self.second.fit(&first.transform(x)?, &())?;
+        Ok(Chain { first, second })
+    }
+}
+
+impl<X, A, B> Transform<X> for Chain<A, B>
+where
+    X: ?Sized,
+    A: Transform<X>,
+    B: Transform<A::Output>,
+{
+    type Output = B::Output;
+
+    fn transform(&self, x: &X) -> Result<B::Output, DuckyError> {
+        self.second.transform(&self.first.transform(x)?)
+    }
+}
+
+/// A transformer followed by a final model, fit and used as a single model.
+///
+/// Fitting the pipeline fits the transformer, transforms the training data with it and fits
+/// the model on the result. Predicting reuses the fitted transformer, so new data always goes
+/// through the same steps the model was trained on. Use a `Chain` to put more than one
+/// transformer in front of the model.
+///
+/// A pipeline is only `Fit` once both of its steps have been fit, and it can be saved and
+/// loaded as one unit with `traits::Persist` when both steps can.
+///
+/// # Fields
+/// - `transformer`: The transformer applied to the input.
+/// - `model`: The model fit on, and predicting from, the output of `transformer`.
+///
+/// # Type parameters
+/// - `T`: The type of the transformer.
+/// - `M`: The type of the model.
+/// - `State`: Indicates whether the pipeline has been fit. Can either be `Fit` or `Unfit`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::feature_extraction::{CountVectorizer, TfidfTransformer};
+/// use ducky_learn::naive_bayes::StdNaiveBayes;
+/// use ducky_learn::pipeline::{Chain, Pipeline};
+///
+/// let documents = vec![
+///     "duck duck goose".to_string(),
+///     "goose goose duck".to_string(),
+/// ];
+/// let y = vec!["duck".to_string(), "goose".to_string()];
+///
+/// let pipeline = Pipeline::new(
+///     Chain::new(CountVectorizer::new(), TfidfTransformer::default()),
+///     StdNaiveBayes::new(1.0),
+/// )
+/// .fit(documents.as_slice(), y.as_slice())
+/// .unwrap();
+///
+/// let predictions = pipeline.predict(["goose duck goose".to_string()].as_slice()).unwrap();
+/// assert_eq!(predictions, vec!["goose".to_string()]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pipeline<T, M, State = Unfit> {
+    pub transformer: T,
+    pub model: M,
+
+    state: std::marker::PhantomData<State>,
+}
+
+impl<T, M> Pipeline<T, M> {
+    /// Creates a new, unfitted pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `transformer`: The transformer applied to the input
+    /// * `model`: The model fit on the output of `transformer`
+    ///
+    /// returns: `Pipeline<T, M, Unfit>`
+    pub fn new(transformer: T, model: M) -> Self {
+        Pipeline {
+            transformer,
+            model,
+            state: std::marker::PhantomData,
+        }
+    }
+
+    /// Fits the transformer on `x`, then fits the model on the transformed `x` and `y`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The training data
+    /// * `y`: The target values
+    ///
+    /// returns: `Result<Pipeline<T::Fitted, M::Fitted, Fit>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Any error from fitting or transforming with the transformer, or from fitting the model.
+    pub fn fit<X, Y>(self, x: &X, y: &Y) -> Result<Pipeline<T::Fitted, M::Fitted, Fit>, DuckyError>
+    where
+        X: ?Sized,
+        Y: ?Sized,
+        T: traits::Fit<X, ()>,
+        T::Fitted: Transform<X>,
+        M: traits::Fit<<T::Fitted as Transform<X>>::Output, Y>,
+    {
+        let transformer = self.transformer.fit(x, &())?;
+        let model = self.model.fit(&transformer.transform(x)?, y)?;
+
+        Ok(Pipeline {
+            transformer,
+            model,
+            state: std::marker::PhantomData,
+        })
+    }
+}
+
+impl<T, M> Pipeline<T, M, Fit> {
+    /// Runs `x` through the fitted transformer.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The data to transform
+    ///
+    /// returns: `Result<T::Output, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Any error from the transformer.
+    pub fn transform<X: ?Sized>(&self, x: &X) -> Result<T::Output, DuckyError>
+    where
+        T: Transform<X>,
+    {
+        self.transformer.transform(x)
+    }
+
+    /// Predicts the target values of `x` by transforming it and passing it to the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The data to predict target values for
+    ///
+    /// returns: `Result<M::Output, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Any error from the transformer or the model.
+    pub fn predict<X: ?Sized>(&self, x: &X) -> Result<M::Output, DuckyError>
+    where
+        T: Transform<X>,
+        M: traits::Predict<T::Output>,
+    {
+        self.model.predict(&self.transformer.transform(x)?)
+    }
+
+    /// Predicts the probability of each class for `x` by transforming it and passing it to
+    /// the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The data to predict probabilities for
+    ///
+    /// returns: `Result<M::Output, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Any error from the transformer or the model.
+    pub fn predict_proba<X: ?Sized>(&self, x: &X) -> Result<M::Output, DuckyError>
+    where
+        T: Transform<X>,
+        M: traits::PredictProba<T::Output>,
+    {
+        self.model.predict_proba(&self.transformer.transform(x)?)
+    }
+}
+
+impl<X, Y, T, M> traits::Fit<X, Y> for Pipeline<T, M>
+where
+    X: ?Sized,
+    Y: ?Sized,
+    T: traits::Fit<X, ()>,
+    T::Fitted: Transform<X>,
+    M: traits::Fit<<T::Fitted as Transform<X>>::Output, Y>,
+{
+    type Fitted = Pipeline<T::Fitted, M::Fitted, Fit>;
+
+    fn fit(self, x: &X, y: &Y) -> Result<Self::Fitted, DuckyError> {
+        Pipeline::fit(self, x, y)
+    }
+}
+
+impl<X, T, M> traits::Predict<X> for Pipeline<T, M, Fit>
+where
+    X: ?Sized,
+    T: Transform<X>,
+    M: traits::Predict<T::Output>,
+{
+    type Output = M::Output;
+
+    fn predict(&self, x: &X) -> Result<M::Output, DuckyError> {
+        Pipeline::predict(self, x)
+    }
+}
+
+impl<X, T, M> traits::PredictProba<X> for Pipeline<T, M, Fit>
+where
+    X: ?Sized,
+    T: Transform<X>,
+    M: traits::PredictProba<T::Output>,
+{
+    type Output = M::Output;
+
+    fn predict_proba(&self, x: &X) -> Result<M::Output, DuckyError> {
+        Pipeline::predict_proba(self, x)
+    }
+}
+
+#[cfg(test)]
+mod pipeline_tests {
+    use super::*;
+    use crate::feature_extraction::{CountVectorizer, TfidfTransformer};
+    use crate::impute::SimpleImputer;
+    use crate::metrics::accuracy_score;
+    use crate::model_selection::{cross_val_score, StratifiedKFold};
+    use crate::naive_bayes::{GaussianNaiveBayes, StdNaiveBayes};
+    use crate::preprocessing::StandardScaler;
+    use crate::sparse::CsrMatrix;
+    use crate::traits::Persist;
+    use ndarray::{array, Array2};
+
+    fn documents() -> (Vec<String>, Vec<String>) {
+        let documents = vec![
+            "duck duck goose".to_string(),
+            "duck duck duck".to_string(),
+            "goose goose goose".to_string(),
+            "goose goose duck".to_string(),
+        ];
+        let y = vec![
+            "duck".to_string(),
+            "duck".to_string(),
+            "goose".to_string(),
+            "goose".to_string(),
+        ];
+        (documents, y)
+    }
+
+    fn numeric() -> (Array2<f64>, Vec<String>) {
+        let x = array![
+            [1.0, f64::NAN],
+            [1.2, 0.1],
+            [0.9, 0.0],
+            [1.1, 0.2],
+            [0.0, 1.0],
+            [f64::NAN, 1.2],
+            [0.1, 0.9],
+            [0.2, 1.1],
+        ];
+        let y = ["a", "a", "a", "a", "b", "b", "b", "b"]
+            .iter()
+            .map(|label| label.to_string())
+            .collect();
+        (x, y)
+    }
+
+    #[test]
+    fn pipeline_matches_steps() {
+        let (documents, y) = documents();
+
+        let pipeline = Pipeline::new(CountVectorizer::new(), StdNaiveBayes::new(1.0))
+            .fit(documents.as_slice(), y.as_slice())
+            .unwrap();
+
+        let mut vectorizer = CountVectorizer::new();
+        let counts = vectorizer.fit_transform(&documents);
+        let model = StdNaiveBayes::new(1.0).fit(&counts, &y).unwrap();
+
+        assert_eq!(pipeline.transformer.feature_names, vectorizer.feature_names);
+        assert_eq!(
+            pipeline.transform(documents.as_slice()).unwrap(),
+            CsrMatrix::from_dense(&counts)
+        );
+        assert_eq!(
+            pipeline.predict(documents.as_slice()).unwrap(),
+            model.predict(&counts).unwrap()
+        );
+        assert_eq!(
+            pipeline.predict_proba(documents.as_slice()).unwrap(),
+            model.predict_proba(&counts).unwrap()
+        );
+    }
+
+    #[test]
+    fn pipeline_unseen_words() {
+        let (documents, y) = documents();
+        let pipeline = Pipeline::new(
+            Chain::new(CountVectorizer::new(), TfidfTransformer::default()),
+            StdNaiveBayes::new(1.0),
+        )
+        .fit(documents.as_slice(), y.as_slice())
+        .unwrap();
+
+        let new_documents = vec![
+            "swan goose goose".to_string(),
+            "swan duck".to_string(),
+            "swan".to_string(),
+        ];
+        let predictions = pipeline.predict(new_documents.as_slice()).unwrap();
+        let probabilities = pipeline.predict_proba(new_documents.as_slice()).unwrap();
+
+        assert_eq!(predictions[..2], ["goose".to_string(), "duck".to_string()]);
+        assert_eq!(probabilities.len(), 3);
+        for row in probabilities {
+            assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn pipeline_chain() {
+        let (x, y) = numeric();
+
+        let pipeline = Pipeline::new(
+            Chain::new(SimpleImputer::default(), StandardScaler::default()),
+            GaussianNaiveBayes::new(),
+        )
+        .fit(&x, y.as_slice())
+        .unwrap();
+
+        let scaled = pipeline.transform(&x).unwrap();
+        assert!(scaled.iter().all(|value| value.is_finite()));
+        assert!(scaled.column(0).mean().unwrap().abs() < 1e-12);
+        assert_eq!(pipeline.predict(&x).unwrap(), y);
+    }
+
+    #[test]
+    fn pipeline_errors() {
+        let (x, y) = numeric();
+
+        // The NaN reaches the scaler without an imputer in front of it
+        assert_eq!(
+            Pipeline::new(StandardScaler::default(), GaussianNaiveBayes::new())
+                .fit(&x, y.as_slice())
+                .unwrap_err(),
+            DuckyError::NonFinite
+        );
+
+        let pipeline = Pipeline::new(SimpleImputer::default(), GaussianNaiveBayes::new())
+            .fit(&x, y.as_slice())
+            .unwrap();
+        assert!(matches!(
+            pipeline.predict(&array![[1.0, 2.0, 3.0]]),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn pipeline_cross_val_score() {
+        let (x, y) = numeric();
+        let pipeline = Pipeline::new(
+            Chain::new(SimpleImputer::default(), StandardScaler::default()),
+            GaussianNaiveBayes::new(),
+        );
+
+        let scores = cross_val_score(
+            &pipeline,
+            &x,
+            &y,
+            &StratifiedKFold::new(2, false, None),
+            accuracy_score,
+        )
+        .unwrap();
+
+        assert_eq!(scores.len(), 2);
+    }
+
+    #[test]
+    fn pipeline_write_read() {
+        let (documents, y) = documents();
+        let pipeline = Pipeline::new(
+            Chain::new(CountVectorizer::new(), TfidfTransformer::default()),
+            StdNaiveBayes::new(1.0),
+        )
+        .fit(documents.as_slice(), y.as_slice())
+        .unwrap();
+
+        let mut saved = Vec::new();
+        pipeline.write_to(&mut saved).unwrap();
+        let loaded: Pipeline<Chain<CountVectorizer, TfidfTransformer>, StdNaiveBayes<Fit>, Fit> =
+            Persist::read_from(saved.as_slice()).unwrap();
+
+        let new_documents = vec!["goose duck goose".to_string(), "duck".to_string()];
+        assert_eq!(
+            loaded.predict(new_documents.as_slice()).unwrap(),
+            pipeline.predict(new_documents.as_slice()).unwrap()
+        );
+        assert_eq!(
+            loaded.predict_proba(new_documents.as_slice()).unwrap(),
+            pipeline.predict_proba(new_documents.as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn pipeline_save_load() {
+        let (x, y) = numeric();
+        let pipeline = Pipeline::new(SimpleImputer::default(), GaussianNaiveBayes::new())
+            .fit(&x, y.as_slice())
+            .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("ducky_learn_pipeline_{}.bin", std::process::id()));
+
+        pipeline.save(&path).unwrap();
+        let loaded = Pipeline::<SimpleImputer<Fit>, GaussianNaiveBayes<Fit>, Fit>::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            loaded.unwrap().predict(&x).unwrap(),
+            pipeline.predict(&x).unwrap()
+        );
+    }
+
+    #[test]
+    fn pipeline_load_errors() {
+        let missing = std::env::temp_dir().join("ducky_learn_pipeline_missing.bin");
+
+        assert!(matches!(
+            Pipeline::<SimpleImputer<Fit>, GaussianNaiveBayes<Fit>, Fit>::load(&missing),
+            Err(DuckyError::Persistence(_))
+        ));
+        assert!(matches!(
+            Pipeline::<SimpleImputer<Fit>, GaussianNaiveBayes<Fit>, Fit>::read_from(
+                [1u8, 2, 3].as_slice()
+            ),
+            Err(DuckyError::Persistence(_))
+        ));
+    }
+}
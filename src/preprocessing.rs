@@ -0,0 +1,982 @@
+extern crate ndarray;
+
+use super::error::{check_n_features, DuckyError};
+use super::feature_extraction::Norm;
+use super::sparse::FeatureMatrix;
+use super::traits;
+use super::util::{Fit, Unfit};
+use ndarray::{Array1, Array2, ArrayBase, Axis, Data, Ix2};
+use serde::{Deserialize, Serialize};
+
+/// Standardizes every column to zero mean and unit variance.
+///
+/// Columns with zero variance are left unscaled.
+///
+/// # Fields
+/// - `with_mean`: Whether to subtract the mean of each column.
+/// - `with_std`: Whether to divide by the standard deviation of each column.
+/// - `mean`: Mean of each column seen during fit.
+/// - `scale`: Standard deviation of each column seen during fit, 1 for columns with zero variance.
+///
+/// # Type parameters
+/// - `State`: Indicates whether the scaler has been fit. Can either be `Fit` or `Unfit`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::preprocessing::StandardScaler;
+/// use ndarray::array;
+///
+/// let x = array![[1.0, 10.0], [3.0, 10.0]];
+///
+/// let scaler = StandardScaler::default().fit(&x).unwrap();
+/// let scaled = scaler.transform(&x).unwrap();
+///
+/// assert_eq!(scaled, array![[-1.0, 0.0], [1.0, 0.0]]);
+/// assert_eq!(scaler.inverse_transform(&scaled).unwrap(), x);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandardScaler<State = Unfit> {
+    pub with_mean: bool,
+    pub with_std: bool,
+    pub mean: Array1<f64>,
+    pub scale: Array1<f64>,
+
+    state: std::marker::PhantomData<State>,
+}
+
+impl Default for StandardScaler {
+    fn default() -> Self {
+        Self::new(true, true)
+    }
+}
+
+impl StandardScaler {
+    /// Creates a new, unfitted `StandardScaler`.
+    ///
+    /// # Arguments
+    ///
+    /// * `with_mean`: Whether to subtract the mean of each column
+    /// * `with_std`: Whether to divide by the standard deviation of each column
+    ///
+    /// returns: `StandardScaler<Unfit>`
+    pub fn new(with_mean: bool, with_std: bool) -> Self {
+        Self {
+            with_mean,
+            with_std,
+            mean: Default::default(),
+            scale: Default::default(),
+            state: Default::default(),
+        }
+    }
+
+    /// Learns the mean and standard deviation of every column of `x`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<StandardScaler<Fit>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn fit<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<StandardScaler<Fit>, DuckyError> {
+        x.validate()?;
+
+        Ok(StandardScaler {
+            with_mean: self.with_mean,
+            with_std: self.with_std,
+            mean: x.mean_axis(Axis(0)).unwrap(),
+            scale: handle_zeros(x.std_axis(Axis(0), 0.0)),
+            state: std::marker::PhantomData::<Fit>,
+        })
+    }
+
+    /// Learns the mean and standard deviation of every column of `x` and standardizes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<(StandardScaler<Fit>, Array2<f64>), DuckyError>`
+    pub fn fit_transform<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<(StandardScaler<Fit>, Array2<f64>), DuckyError> {
+        let scaler = self.fit(x)?;
+        let scaled = scaler.transform(x)?;
+        Ok((scaler, scaled))
+    }
+}
+
+impl StandardScaler<Fit> {
+    /// Standardizes every column of `x` with the statistics learned during fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::ShapeMismatch` if `x` has a different number of features to the training data.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_n_features(self.mean.len(), x.ncols())?;
+        x.validate()?;
+
+        let mut scaled = x.to_owned();
+        if self.with_mean {
+            scaled -= &self.mean;
+        }
+        if self.with_std {
+            scaled /= &self.scale;
+        }
+        Ok(scaled)
+    }
+
+    /// Undoes `transform`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Standardized data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// The same as `transform`.
+    pub fn inverse_transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_n_features(self.mean.len(), x.ncols())?;
+        x.validate()?;
+
+        let mut unscaled = x.to_owned();
+        if self.with_std {
+            unscaled *= &self.scale;
+        }
+        if self.with_mean {
+            unscaled += &self.mean;
+        }
+        Ok(unscaled)
+    }
+}
+
+/// Scales every column into a fixed range, by default `[0, 1]`.
+///
+/// # Fields
+/// - `feature_range`: The `(min, max)` every column is scaled into.
+/// - `data_min`: Minimum of each column seen during fit.
+/// - `data_max`: Maximum of each column seen during fit.
+/// - `scale`: Factor each column is multiplied by.
+/// - `min`: Amount added to each column after scaling.
+///
+/// # Type parameters
+/// - `State`: Indicates whether the scaler has been fit. Can either be `Fit` or `Unfit`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::preprocessing::MinMaxScaler;
+/// use ndarray::array;
+///
+/// let x = array![[0.0, 8.0], [4.0, 0.0]];
+///
+/// let (scaler, scaled) = MinMaxScaler::default().fit_transform(&x).unwrap();
+///
+/// assert_eq!(scaled, array![[0.0, 1.0], [1.0, 0.0]]);
+/// assert_eq!(scaler.transform(&array![[2.0, 2.0]]).unwrap(), array![[0.5, 0.25]]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinMaxScaler<State = Unfit> {
+    pub feature_range: (f64, f64),
+    pub data_min: Array1<f64>,
+    pub data_max: Array1<f64>,
+    pub scale: Array1<f64>,
+    pub min: Array1<f64>,
+
+    state: std::marker::PhantomData<State>,
+}
+
+impl Default for MinMaxScaler {
+    fn default() -> Self {
+        Self::new((0.0, 1.0))
+    }
+}
+
+impl MinMaxScaler {
+    /// Creates a new, unfitted `MinMaxScaler`.
+    ///
+    /// # Arguments
+    ///
+    /// * `feature_range`: The `(min, max)` every column is scaled into
+    ///
+    /// returns: `MinMaxScaler<Unfit>`
+    pub fn new(feature_range: (f64, f64)) -> Self {
+        Self {
+            feature_range,
+            data_min: Default::default(),
+            data_max: Default::default(),
+            scale: Default::default(),
+            min: Default::default(),
+            state: Default::default(),
+        }
+    }
+
+    /// Learns the minimum and maximum of every column of `x`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<MinMaxScaler<Fit>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::InvalidParameter` if `feature_range` is not increasing.
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn fit<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<MinMaxScaler<Fit>, DuckyError> {
+        let (range_min, range_max) = self.feature_range;
+        if range_min.partial_cmp(&range_max) != Some(std::cmp::Ordering::Less) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "feature_range {:?} has to be increasing",
+                self.feature_range
+            )));
+        }
+        x.validate()?;
+
+        let data_min = x.fold_axis(Axis(0), f64::INFINITY, |acc, value| acc.min(*value));
+        let data_max = x.fold_axis(Axis(0), f64::NEG_INFINITY, |acc, value| acc.max(*value));
+        let scale = (range_max - range_min) / handle_zeros(&data_max - &data_min);
+        let min = range_min - &data_min * &scale;
+
+        Ok(MinMaxScaler {
+            feature_range: self.feature_range,
+            data_min,
+            data_max,
+            scale,
+            min,
+            state: std::marker::PhantomData::<Fit>,
+        })
+    }
+
+    /// Learns the minimum and maximum of every column of `x` and scales it.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<(MinMaxScaler<Fit>, Array2<f64>), DuckyError>`
+    pub fn fit_transform<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<(MinMaxScaler<Fit>, Array2<f64>), DuckyError> {
+        let scaler = self.fit(x)?;
+        let scaled = scaler.transform(x)?;
+        Ok((scaler, scaled))
+    }
+}
+
+impl MinMaxScaler<Fit> {
+    /// Scales every column of `x` with the minimum and maximum learned during fit. Values
+    /// outside of the training range end up outside of `feature_range`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::ShapeMismatch` if `x` has a different number of features to the training data.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_n_features(self.scale.len(), x.ncols())?;
+        x.validate()?;
+        Ok(x * &self.scale + &self.min)
+    }
+
+    /// Undoes `transform`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Scaled data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// The same as `transform`.
+    pub fn inverse_transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_n_features(self.scale.len(), x.ncols())?;
+        x.validate()?;
+        Ok((x - &self.min) / &self.scale)
+    }
+}
+
+/// Scales every column by its maximum absolute value, so the values end up in `[-1, 1]`.
+///
+/// Does not shift the data, so zeros stay zeros.
+///
+/// # Fields
+/// - `max_abs`: Maximum absolute value of each column seen during fit.
+/// - `scale`: Factor each column is divided by, 1 for columns that are all zero.
+///
+/// # Type parameters
+/// - `State`: Indicates whether the scaler has been fit. Can either be `Fit` or `Unfit`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::preprocessing::MaxAbsScaler;
+/// use ndarray::array;
+///
+/// let x = array![[-4.0, 0.0], [2.0, 0.0]];
+///
+/// let (scaler, scaled) = MaxAbsScaler::new().fit_transform(&x).unwrap();
+///
+/// assert_eq!(scaled, array![[-1.0, 0.0], [0.5, 0.0]]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaxAbsScaler<State = Unfit> {
+    pub max_abs: Array1<f64>,
+    pub scale: Array1<f64>,
+
+    state: std::marker::PhantomData<State>,
+}
+
+impl Default for MaxAbsScaler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MaxAbsScaler {
+    /// Creates a new, unfitted `MaxAbsScaler`.
+    pub fn new() -> Self {
+        Self {
+            max_abs: Default::default(),
+            scale: Default::default(),
+            state: Default::default(),
+        }
+    }
+
+    /// Learns the maximum absolute value of every column of `x`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<MaxAbsScaler<Fit>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn fit<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<MaxAbsScaler<Fit>, DuckyError> {
+        x.validate()?;
+
+        let max_abs = x.fold_axis(Axis(0), 0.0, |acc: &f64, value| acc.max(value.abs()));
+        Ok(MaxAbsScaler {
+            scale: handle_zeros(max_abs.clone()),
+            max_abs,
+            state: std::marker::PhantomData::<Fit>,
+        })
+    }
+
+    /// Learns the maximum absolute value of every column of `x` and scales it.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<(MaxAbsScaler<Fit>, Array2<f64>), DuckyError>`
+    pub fn fit_transform<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<(MaxAbsScaler<Fit>, Array2<f64>), DuckyError> {
+        let scaler = self.fit(x)?;
+        let scaled = scaler.transform(x)?;
+        Ok((scaler, scaled))
+    }
+}
+
+impl MaxAbsScaler<Fit> {
+    /// Scales every column of `x` by the maximum absolute value learned during fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::ShapeMismatch` if `x` has a different number of features to the training data.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_n_features(self.scale.len(), x.ncols())?;
+        x.validate()?;
+        Ok(x / &self.scale)
+    }
+
+    /// Undoes `transform`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Scaled data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// The same as `transform`.
+    pub fn inverse_transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_n_features(self.scale.len(), x.ncols())?;
+        x.validate()?;
+        Ok(x * &self.scale)
+    }
+}
+
+/// Centers every column on its median and scales it by its interquartile range, so
+/// outliers have little effect on the scaling.
+///
+/// # Fields
+/// - `with_centering`: Whether to subtract the median of each column.
+/// - `with_scaling`: Whether to divide by the quantile range of each column.
+/// - `quantile_range`: The `(lower, upper)` percentiles the range is measured between,
+///   by default `(25.0, 75.0)`.
+/// - `center`: Median of each column seen during fit.
+/// - `scale`: Quantile range of each column seen during fit, 1 for columns where it is zero.
+///
+/// # Type parameters
+/// - `State`: Indicates whether the scaler has been fit. Can either be `Fit` or `Unfit`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::preprocessing::RobustScaler;
+/// use ndarray::array;
+///
+/// let x = array![[1.0], [2.0], [3.0], [4.0], [100.0]];
+///
+/// let scaler = RobustScaler::default().fit(&x).unwrap();
+///
+/// assert_eq!(scaler.center, array![3.0]);
+/// assert_eq!(scaler.scale, array![2.0]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RobustScaler<State = Unfit> {
+    pub with_centering: bool,
+    pub with_scaling: bool,
+    pub quantile_range: (f64, f64),
+    pub center: Array1<f64>,
+    pub scale: Array1<f64>,
+
+    state: std::marker::PhantomData<State>,
+}
+
+impl Default for RobustScaler {
+    fn default() -> Self {
+        Self::new(true, true, (25.0, 75.0))
+    }
+}
+
+impl RobustScaler {
+    /// Creates a new, unfitted `RobustScaler`.
+    ///
+    /// # Arguments
+    ///
+    /// * `with_centering`: Whether to subtract the median of each column
+    /// * `with_scaling`: Whether to divide by the quantile range of each column
+    /// * `quantile_range`: The `(lower, upper)` percentiles the range is measured between
+    ///
+    /// returns: `RobustScaler<Unfit>`
+    pub fn new(with_centering: bool, with_scaling: bool, quantile_range: (f64, f64)) -> Self {
+        Self {
+            with_centering,
+            with_scaling,
+            quantile_range,
+            center: Default::default(),
+            scale: Default::default(),
+            state: Default::default(),
+        }
+    }
+
+    /// Learns the median and quantile range of every column of `x`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<RobustScaler<Fit>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::InvalidParameter` if `quantile_range` is not increasing within `[0, 100]`.
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn fit<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<RobustScaler<Fit>, DuckyError> {
+        let (lower, upper) = self.quantile_range;
+        if !(0.0 <= lower && lower < upper && upper <= 100.0) {
+            return Err(DuckyError::InvalidParameter(format!(
+                "quantile_range {:?} has to be increasing within [0, 100]",
+                self.quantile_range
+            )));
+        }
+        x.validate()?;
+
+        let mut center = Array1::zeros(x.ncols());
+        let mut range = Array1::zeros(x.ncols());
+        for (column, values) in x.columns().into_iter().enumerate() {
+            let sorted = sorted_values(values.iter());
+            center[column] = quantile(&sorted, 0.5);
+            range[column] = quantile(&sorted, upper / 100.0) - quantile(&sorted, lower / 100.0);
+        }
+
+        Ok(RobustScaler {
+            with_centering: self.with_centering,
+            with_scaling: self.with_scaling,
+            quantile_range: self.quantile_range,
+            center,
+            scale: handle_zeros(range),
+            state: std::marker::PhantomData::<Fit>,
+        })
+    }
+
+    /// Learns the median and quantile range of every column of `x` and scales it.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<(RobustScaler<Fit>, Array2<f64>), DuckyError>`
+    pub fn fit_transform<S: Data<Elem = f64>>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<(RobustScaler<Fit>, Array2<f64>), DuckyError> {
+        let scaler = self.fit(x)?;
+        let scaled = scaler.transform(x)?;
+        Ok((scaler, scaled))
+    }
+}
+
+impl RobustScaler<Fit> {
+    /// Centers and scales every column of `x` with the statistics learned during fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::ShapeMismatch` if `x` has a different number of features to the training data.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_n_features(self.center.len(), x.ncols())?;
+        x.validate()?;
+
+        let mut scaled = x.to_owned();
+        if self.with_centering {
+            scaled -= &self.center;
+        }
+        if self.with_scaling {
+            scaled /= &self.scale;
+        }
+        Ok(scaled)
+    }
+
+    /// Undoes `transform`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Scaled data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// The same as `transform`.
+    pub fn inverse_transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        check_n_features(self.center.len(), x.ncols())?;
+        x.validate()?;
+
+        let mut unscaled = x.to_owned();
+        if self.with_scaling {
+            unscaled *= &self.scale;
+        }
+        if self.with_centering {
+            unscaled += &self.center;
+        }
+        Ok(unscaled)
+    }
+}
+
+/// Scales every row to unit norm.
+///
+/// Works on each row on its own, so there is nothing to fit and no `inverse_transform`,
+/// as the norm of each row is not kept. Rows that are all zero are left untouched.
+///
+/// # Fields
+/// - `norm`: The norm every row is scaled to, `Norm::L1`, `Norm::L2` or `Norm::Max`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::feature_extraction::Norm;
+/// use ducky_learn::preprocessing::Normalizer;
+/// use ndarray::array;
+///
+/// let x = array![[3.0, 4.0], [0.0, 0.0]];
+///
+/// assert_eq!(Normalizer::new(Norm::L2).transform(&x).unwrap(), array![[0.6, 0.8], [0.0, 0.0]]);
+/// assert_eq!(Normalizer::new(Norm::Max).transform(&x).unwrap(), array![[0.75, 1.0], [0.0, 0.0]]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Normalizer {
+    pub norm: Norm,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self::new(Norm::L2)
+    }
+}
+
+impl Normalizer {
+    /// Creates a new `Normalizer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `norm`: The norm every row is scaled to
+    ///
+    /// returns: `Normalizer`
+    pub fn new(norm: Norm) -> Self {
+        Self { norm }
+    }
+
+    /// Scales every row of `x` to unit norm.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( rows, features )
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::EmptyInput` if `x` has no rows or features.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    pub fn transform<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Array2<f64>, DuckyError> {
+        x.validate()?;
+
+        let mut normalized = x.to_owned();
+        for mut row in normalized.rows_mut() {
+            let row_norm = self.norm.row_norm(row.iter());
+            if row_norm > 0.0 {
+                row /= row_norm;
+            }
+        }
+        Ok(normalized)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Fit<ArrayBase<S, Ix2>, ()> for StandardScaler<Unfit> {
+    type Fitted = StandardScaler<Fit>;
+
+    fn fit(self, x: &ArrayBase<S, Ix2>, _: &()) -> Result<StandardScaler<Fit>, DuckyError> {
+        self.fit(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Transform<ArrayBase<S, Ix2>> for StandardScaler<Fit> {
+    type Output = Array2<f64>;
+
+    fn transform(&self, x: &ArrayBase<S, Ix2>) -> Result<Array2<f64>, DuckyError> {
+        self.transform(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Fit<ArrayBase<S, Ix2>, ()> for MinMaxScaler<Unfit> {
+    type Fitted = MinMaxScaler<Fit>;
+
+    fn fit(self, x: &ArrayBase<S, Ix2>, _: &()) -> Result<MinMaxScaler<Fit>, DuckyError> {
+        self.fit(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Transform<ArrayBase<S, Ix2>> for MinMaxScaler<Fit> {
+    type Output = Array2<f64>;
+
+    fn transform(&self, x: &ArrayBase<S, Ix2>) -> Result<Array2<f64>, DuckyError> {
+        self.transform(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Fit<ArrayBase<S, Ix2>, ()> for MaxAbsScaler<Unfit> {
+    type Fitted = MaxAbsScaler<Fit>;
+
+    fn fit(self, x: &ArrayBase<S, Ix2>, _: &()) -> Result<MaxAbsScaler<Fit>, DuckyError> {
+        self.fit(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Transform<ArrayBase<S, Ix2>> for MaxAbsScaler<Fit> {
+    type Output = Array2<f64>;
+
+    fn transform(&self, x: &ArrayBase<S, Ix2>) -> Result<Array2<f64>, DuckyError> {
+        self.transform(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Fit<ArrayBase<S, Ix2>, ()> for RobustScaler<Unfit> {
+    type Fitted = RobustScaler<Fit>;
+
+    fn fit(self, x: &ArrayBase<S, Ix2>, _: &()) -> Result<RobustScaler<Fit>, DuckyError> {
+        self.fit(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Transform<ArrayBase<S, Ix2>> for RobustScaler<Fit> {
+    type Output = Array2<f64>;
+
+    fn transform(&self, x: &ArrayBase<S, Ix2>) -> Result<Array2<f64>, DuckyError> {
+        self.transform(x)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Fit<ArrayBase<S, Ix2>, ()> for Normalizer {
+    type Fitted = Normalizer;
+
+    fn fit(self, x: &ArrayBase<S, Ix2>, _: &()) -> Result<Normalizer, DuckyError> {
+        Ok(self)
+    }
+}
+
+impl<S: Data<Elem = f64>> traits::Transform<ArrayBase<S, Ix2>> for Normalizer {
+    type Output = Array2<f64>;
+
+    fn transform(&self, x: &ArrayBase<S, Ix2>) -> Result<Array2<f64>, DuckyError> {
+        self.transform(x)
+    }
+}
+
+/// Replaces scales of zero with one so constant columns are left as they are.
+fn handle_zeros(mut scale: Array1<f64>) -> Array1<f64> {
+    scale.mapv_inplace(|value| if value == 0.0 { 1.0 } else { value });
+    scale
+}
+
+/// Copies values into a sorted vector, none of them can be NaN.
+pub(crate) fn sorted_values<'a, I: IntoIterator<Item = &'a f64>>(values: I) -> Vec<f64> {
+    let mut sorted: Vec<f64> = values.into_iter().cloned().collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted
+}
+
+/// The `q` quantile of sorted values, linearly interpolating between the two closest values.
+///
+/// # Arguments
+///
+/// * `sorted`: Sorted, non-empty values
+/// * `q`: Quantile between 0 and 1
+///
+/// returns: `f64`
+pub(crate) fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+#[cfg(test)]
+mod preprocessing_tests {
+    use super::*;
+    use ndarray::array;
+
+    fn assert_close(a: &Array2<f64>, b: &Array2<f64>) {
+        assert_eq!(a.shape(), b.shape());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-9, "{} != {}", a, b);
+        }
+    }
+
+    fn data() -> Array2<f64> {
+        array![
+            [1.0, -2.0, 5.0],
+            [2.0, 0.0, 5.0],
+            [6.0, 4.0, 5.0],
+            [3.0, 2.0, 5.0]
+        ]
+    }
+
+    #[test]
+    fn standard_scaler() {
+        let x = data();
+        let (scaler, scaled) = StandardScaler::default().fit_transform(&x).unwrap();
+
+        assert_close(
+            &scaled.mean_axis(Axis(0)).unwrap().insert_axis(Axis(0)),
+            &array![[0.0, 0.0, 0.0]],
+        );
+        assert_close(
+            &scaled.std_axis(Axis(0), 0.0).insert_axis(Axis(0)),
+            &array![[1.0, 1.0, 0.0]],
+        );
+        assert_eq!(scaler.scale[2], 1.0);
+        assert_close(&scaler.inverse_transform(&scaled).unwrap(), &x);
+    }
+
+    #[test]
+    fn standard_scaler_without_mean() {
+        let x = array![[1.0], [3.0]];
+        let scaler = StandardScaler::new(false, true).fit(&x).unwrap();
+
+        assert_eq!(scaler.transform(&x).unwrap(), array![[1.0], [3.0]]);
+        assert_eq!(scaler.mean, array![2.0]);
+    }
+
+    #[test]
+    fn min_max_scaler() {
+        let x = data();
+        let (scaler, scaled) = MinMaxScaler::new((-1.0, 1.0)).fit_transform(&x).unwrap();
+
+        assert_close(
+            &scaled,
+            &array![
+                [-1.0, -1.0, -1.0],
+                [-0.6, -1.0 / 3.0, -1.0],
+                [1.0, 1.0, -1.0],
+                [-0.2, 1.0 / 3.0, -1.0]
+            ],
+        );
+        assert_close(&scaler.inverse_transform(&scaled).unwrap(), &x);
+        assert!(matches!(
+            MinMaxScaler::new((1.0, 1.0)).fit(&x),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn max_abs_scaler() {
+        let x = data();
+        let (scaler, scaled) = MaxAbsScaler::new().fit_transform(&x).unwrap();
+
+        assert_eq!(scaler.max_abs, array![6.0, 4.0, 5.0]);
+        assert_close(
+            &scaled.slice(ndarray::s![2..3, ..]).to_owned(),
+            &array![[1.0, 1.0, 1.0]],
+        );
+        assert_close(&scaler.inverse_transform(&scaled).unwrap(), &x);
+    }
+
+    #[test]
+    fn robust_scaler() {
+        let x = data();
+        let (scaler, scaled) = RobustScaler::default().fit_transform(&x).unwrap();
+
+        assert_eq!(scaler.center, array![2.5, 1.0, 5.0]);
+        assert_eq!(scaler.scale, array![2.0, 3.0, 1.0]);
+        assert_close(&scaler.inverse_transform(&scaled).unwrap(), &x);
+        assert!(matches!(
+            RobustScaler::new(true, true, (75.0, 25.0)).fit(&x),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn normalizer() {
+        let x = array![[1.0, -3.0], [0.0, 0.0]];
+
+        assert_close(
+            &Normalizer::new(Norm::L1).transform(&x).unwrap(),
+            &array![[0.25, -0.75], [0.0, 0.0]],
+        );
+        assert_close(
+            &Normalizer::new(Norm::Max).transform(&x.view()).unwrap(),
+            &array![[1.0 / 3.0, -1.0], [0.0, 0.0]],
+        );
+    }
+
+    #[test]
+    fn scaler_errors() {
+        let scaler = StandardScaler::default().fit(&data()).unwrap();
+
+        assert_eq!(
+            scaler.transform(&array![[1.0, 2.0]]).unwrap_err(),
+            DuckyError::ShapeMismatch {
+                expected: vec![3],
+                found: vec![2]
+            }
+        );
+        assert_eq!(
+            scaler.transform(&array![[1.0, f64::NAN, 2.0]]).unwrap_err(),
+            DuckyError::NonFinite
+        );
+        assert_eq!(
+            MaxAbsScaler::new()
+                .fit(&Array2::<f64>::zeros((0, 3)))
+                .unwrap_err(),
+            DuckyError::EmptyInput
+        );
+    }
+
+    #[test]
+    fn quantile_interpolates() {
+        let sorted = vec![1.0, 2.0, 3.0, 4.0];
+
+        assert_eq!(quantile(&sorted, 0.0), 1.0);
+        assert_eq!(quantile(&sorted, 0.5), 2.5);
+        assert_eq!(quantile(&sorted, 0.25), 1.75);
+        assert_eq!(quantile(&sorted, 1.0), 4.0);
+    }
+}
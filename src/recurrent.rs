@@ -0,0 +1,1278 @@
+use super::autograd::{stack, Tape, Tensor};
+use super::error::{check_finite, DuckyError};
+use super::layers::{cannot_save, Layer1d, Mode, SavedLayer};
+use super::util::seeded_rng;
+use ndarray::concatenate;
+use ndarray::prelude::*;
+use ndarray_rand::rand_distr::Uniform;
+use ndarray_rand::RandomExt;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::RwLock;
+
+/// What a recurrent layer outputs for a batch of sequences of shape( batch, time, features ).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReturnMode {
+    /// The output of every step, of shape( batch, time, outputs ). Masked steps output zeros.
+    Sequences,
+    /// The output after the last unmasked step, of shape( batch, 1, outputs ).
+    Last,
+}
+
+/// Weights shared by every recurrent cell. The columns hold one block of `hidden_size`
+/// columns per gate of the cell.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurrentWeights {
+    /// Weights of the input, of shape( input size, gates * hidden size )
+    pub input: Array2<f64>,
+    /// Weights of the previous hidden state, of shape( hidden size, gates * hidden size )
+    pub hidden: Array2<f64>,
+    /// Bias of every gate, of length gates * hidden size
+    pub bias: Array1<f64>,
+}
+
+impl RecurrentWeights {
+    fn hidden_size(&self) -> usize {
+        self.hidden.nrows()
+    }
+
+    /// Records the weights on `tape`, so the gradient of a run can be taken with respect to
+    /// them.
+    fn record(&self, tape: &Rc<Tape>) -> WeightTensors {
+        WeightTensors {
+            input: tape.variable(self.input.clone()),
+            hidden: tape.variable(self.hidden.clone()),
+            bias: tape.variable(self.bias.clone()),
+        }
+    }
+}
+
+/// `RecurrentWeights` recorded on an `autograd::Tape`, for the cells to take their steps with.
+#[derive(Debug, Clone)]
+pub struct WeightTensors {
+    /// Weights of the input, of shape( input size, gates * hidden size )
+    pub input: Tensor,
+    /// Weights of the previous hidden state, of shape( hidden size, gates * hidden size )
+    pub hidden: Tensor,
+    /// Bias of every gate, of length gates * hidden size
+    pub bias: Tensor,
+}
+
+impl WeightTensors {
+    fn hidden_size(&self) -> usize {
+        self.hidden.shape()[0]
+    }
+
+    /// Weighted input of every gate for the inputs `x` and previous hidden states `h`.
+    fn gates(&self, x: &Tensor, h: &Tensor) -> Result<Tensor, DuckyError> {
+        x.matmul(&self.input)?
+            .try_add(&h.matmul(&self.hidden)?)?
+            .try_add(&self.bias)
+    }
+
+    /// Columns of the gate `index` of `gates`.
+    fn gate(&self, gates: &Tensor, index: usize) -> Result<Tensor, DuckyError> {
+        let hidden_size = self.hidden_size();
+        gates.slice_axis(1, index * hidden_size..(index + 1) * hidden_size)
+    }
+}
+
+/// Update rule of a recurrent layer, taking one step along a batch of sequences. The steps
+/// are recorded on an `autograd::Tape`, which backpropagates through time.
+pub trait RecurrentCell {
+    /// Number of gates, each with `hidden_size` columns of weights.
+    const GATES: usize;
+    /// Number of arrays of shape( batch, hidden size ) carried between steps. The first one
+    /// is the hidden state, which is also the output of the step.
+    const STATES: usize;
+
+    /// Adjusts the starting bias of a new layer.
+    fn init_bias(_bias: &mut Array1<f64>, _hidden_size: usize) {}
+
+    /// Takes one step.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights`: Weights of the layer
+    /// * `x`: Input of the step, of shape( batch, input size )
+    /// * `state`: States from the previous step
+    ///
+    /// returns: `Result<Vec<Tensor>, DuckyError>` holding the new states
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `x` or `state` do not line up with `weights`
+    fn step(
+        weights: &WeightTensors,
+        x: &Tensor,
+        state: &[Tensor],
+    ) -> Result<Vec<Tensor>, DuckyError>;
+
+    /// A layer of this cell as a `SavedLayer`, for cells that models of boxed layers can be
+    /// saved with.
+    fn saved_layer(_layer: &Recurrent<Self>) -> Result<SavedLayer, DuckyError>
+    where
+        Self: Sized,
+    {
+        Err(cannot_save::<Recurrent<Self>>())
+    }
+
+    /// A bidirectional layer of this cell as a `SavedLayer`, like `saved_layer`.
+    fn saved_bidirectional(
+        _layer: &Bidirectional<Recurrent<Self>>,
+    ) -> Result<SavedLayer, DuckyError>
+    where
+        Self: Sized,
+    {
+        Err(cannot_save::<Bidirectional<Recurrent<Self>>>())
+    }
+}
+
+/// Elman cell: h = tanh( x W + h U + b ).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleRnnCell;
+
+impl RecurrentCell for SimpleRnnCell {
+    const GATES: usize = 1;
+    const STATES: usize = 1;
+
+    fn step(
+        weights: &WeightTensors,
+        x: &Tensor,
+        state: &[Tensor],
+    ) -> Result<Vec<Tensor>, DuckyError> {
+        Ok(vec![weights.gates(x, &state[0])?.tanh()])
+    }
+
+    fn saved_layer(layer: &Recurrent<Self>) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::SimpleRnn(layer.clone()))
+    }
+
+    fn saved_bidirectional(
+        layer: &Bidirectional<Recurrent<Self>>,
+    ) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::BidirectionalSimpleRnn(layer.clone()))
+    }
+}
+
+/// Long short-term memory cell with input, forget, cell and output gates, in that order.
+/// The forget gate starts with a bias of one so new layers remember by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LstmCell;
+
+impl RecurrentCell for LstmCell {
+    const GATES: usize = 4;
+    const STATES: usize = 2;
+
+    fn init_bias(bias: &mut Array1<f64>, hidden_size: usize) {
+        bias.slice_mut(s![hidden_size..2 * hidden_size])
+            .mapv_inplace(|value| value + 1.);
+    }
+
+    fn step(
+        weights: &WeightTensors,
+        x: &Tensor,
+        state: &[Tensor],
+    ) -> Result<Vec<Tensor>, DuckyError> {
+        let gates = weights.gates(x, &state[0])?;
+
+        let input_gate = weights.gate(&gates, 0)?.sigmoid();
+        let forget_gate = weights.gate(&gates, 1)?.sigmoid();
+        let cell_gate = weights.gate(&gates, 2)?.tanh();
+        let output_gate = weights.gate(&gates, 3)?.sigmoid();
+        let c = forget_gate.try_mul(&state[1])? + input_gate * cell_gate;
+        let h = output_gate * c.tanh();
+
+        Ok(vec![h, c])
+    }
+
+    fn saved_layer(layer: &Recurrent<Self>) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::Lstm(layer.clone()))
+    }
+
+    fn saved_bidirectional(
+        layer: &Bidirectional<Recurrent<Self>>,
+    ) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::BidirectionalLstm(layer.clone()))
+    }
+}
+
+/// Gated recurrent unit with update, reset and candidate gates, in that order. The reset
+/// gate is applied to the previous hidden state before its weights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GruCell;
+
+impl RecurrentCell for GruCell {
+    const GATES: usize = 3;
+    const STATES: usize = 1;
+
+    fn step(
+        weights: &WeightTensors,
+        x: &Tensor,
+        state: &[Tensor],
+    ) -> Result<Vec<Tensor>, DuckyError> {
+        let hidden_size = weights.hidden_size();
+        let h = &state[0];
+        let x_gates = x.matmul(&weights.input)?.try_add(&weights.bias)?;
+        let h_gates = h.matmul(&weights.hidden.slice_axis(1, 0..2 * hidden_size)?)?;
+
+        let update_gate = (weights.gate(&x_gates, 0)? + weights.gate(&h_gates, 0)?).sigmoid();
+        let reset_gate = (weights.gate(&x_gates, 1)? + weights.gate(&h_gates, 1)?).sigmoid();
+        let candidate = (weights.gate(&x_gates, 2)?
+            + reset_gate
+                .try_mul(h)?
+                .matmul(&weights.gate(&weights.hidden, 2)?)?)
+        .tanh();
+        let new_h = (-&update_gate + 1.) * candidate + update_gate.try_mul(h)?;
+
+        Ok(vec![new_h])
+    }
+
+    fn saved_layer(layer: &Recurrent<Self>) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::Gru(layer.clone()))
+    }
+
+    fn saved_bidirectional(
+        layer: &Bidirectional<Recurrent<Self>>,
+    ) -> Result<SavedLayer, DuckyError> {
+        Ok(SavedLayer::BidirectionalGru(layer.clone()))
+    }
+}
+
+/// Layer that runs over a batch of sequences of shape( batch, time, features ) one step at a
+/// time and outputs the state after every step.
+pub trait SequenceLayer {
+    /// Number of features of every step of the input.
+    fn input_size(&self) -> usize;
+
+    /// Number of features of every step of the output.
+    fn output_size(&self) -> usize;
+
+    /// Runs over a batch of sequences.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of sequences of shape( batch, time, input size )
+    /// * `mask`: Which steps of each sequence are real, of shape( batch, time ). Masked steps
+    ///   leave the state as it was
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the state after every step, of
+    /// shape( batch, time, output size )
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `input_array` or `mask` do not line up with the layer
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    fn states(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<Array3<f64>, DuckyError>;
+
+    /// Backpropagates through time, taking one gradient descent step on the weights. Weight
+    /// gradients are summed over the batch and the steps.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The batch the layer was passed during the forward pass
+    /// * `mask`: The mask the layer was passed during the forward pass
+    /// * `states_gradient`: Gradient of the cost with respect to every state returned by
+    ///   `states`
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to `input_array`, computed before the weights are updated
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    fn back_states(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+        states_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError>;
+
+    /// A bidirectional layer of two of these layers as a `SavedLayer`, for layers that models
+    /// of boxed layers can be saved with.
+    fn saved_bidirectional(_layer: &Bidirectional<Self>) -> Result<SavedLayer, DuckyError>
+    where
+        Self: Sized,
+    {
+        Err(cannot_save::<Bidirectional<Self>>())
+    }
+}
+
+/// Checks a batch of sequences and its mask against the input size of a layer.
+pub(crate) fn check_sequences(
+    input_size: usize,
+    input_array: &Array3<f64>,
+    mask: Option<&Array2<bool>>,
+) -> Result<(), DuckyError> {
+    let (batch, time, features) = input_array.dim();
+
+    if features != input_size {
+        return Err(DuckyError::ShapeMismatch {
+            expected: vec![batch, time, input_size],
+            found: input_array.shape().to_vec(),
+        });
+    }
+    if let Some(mask) = mask {
+        if mask.dim() != (batch, time) {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![batch, time],
+                found: mask.shape().to_vec(),
+            });
+        }
+    }
+    if time == 0 {
+        return Err(DuckyError::EmptyInput);
+    }
+    check_finite(input_array)
+}
+
+/// Checks that `array` has the shape `expected`.
+pub(crate) fn check_shape(expected: &[usize], array: &Array3<f64>) -> Result<(), DuckyError> {
+    if array.shape() != expected {
+        return Err(DuckyError::ShapeMismatch {
+            expected: expected.to_vec(),
+            found: array.shape().to_vec(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Column of ones for the real steps and zeros for the masked steps of step `time`.
+fn mask_column(mask: Option<&Array2<bool>>, time: usize) -> Option<Array2<f64>> {
+    mask.map(|mask| {
+        mask.column(time)
+            .mapv(|real| real as i32 as f64)
+            .insert_axis(Axis(1))
+    })
+}
+
+/// Zeroes the masked steps of `sequences`.
+fn zero_masked(mut sequences: Array3<f64>, mask: Option<&Array2<bool>>) -> Array3<f64> {
+    if let Some(mask) = mask {
+        for ((image, time), real) in mask.indexed_iter() {
+            if !real {
+                sequences.slice_mut(s![image, time, ..]).fill(0.);
+            }
+        }
+    }
+
+    sequences
+}
+
+/// Reverses the steps of a batch of sequences.
+fn reversed<A: Clone, D: Dimension>(array: &Array<A, D>) -> Array<A, D> {
+    let mut array = array.clone();
+    array.invert_axis(Axis(1));
+    array.as_standard_layout().into_owned()
+}
+
+/// Shape of the output of `pass_batch` for sequences of `time` steps.
+fn output_shape(
+    return_mode: ReturnMode,
+    batch: usize,
+    time: usize,
+    output_size: usize,
+) -> [usize; 3] {
+    match return_mode {
+        ReturnMode::Sequences => [batch, time, output_size],
+        ReturnMode::Last => [batch, 1, output_size],
+    }
+}
+
+/// Reshapes a flattened sample of `time * features` values into a batch of one sequence.
+pub(crate) fn sequence(
+    features: usize,
+    input_array: &Array1<f64>,
+) -> Result<Array3<f64>, DuckyError> {
+    let time = input_array.len() / features.max(1);
+
+    if time == 0 || features * time != input_array.len() {
+        return Err(DuckyError::ShapeMismatch {
+            expected: vec![features * time.max(1)],
+            found: input_array.shape().to_vec(),
+        });
+    }
+
+    Ok(input_array
+        .to_shape((1, time, features))
+        .unwrap()
+        .into_owned())
+}
+
+/// Reshapes the flattened gradient of a `Layer1d` output into the output of `pass_batch`.
+pub(crate) fn output_gradient(
+    shape: [usize; 3],
+    output_gradient: &Array1<f64>,
+) -> Result<Array3<f64>, DuckyError> {
+    Array3::from_shape_vec(shape, output_gradient.to_vec()).map_err(|_| DuckyError::ShapeMismatch {
+        expected: vec![shape.iter().product()],
+        found: output_gradient.shape().to_vec(),
+    })
+}
+
+/// Recurrent layer for batches of sequences of shape( batch, time, features ), trained with
+/// backpropagation through time on an `autograd::Tape`. The cell `C` decides how the state is
+/// updated; use the `SimpleRNN`, `LSTM` and `GRU` aliases.
+///
+/// Variable-length sequences are padded to the same length and passed with a mask of their
+/// real steps. Masked steps leave the state as it was, so `ReturnMode::Last` returns the
+/// state after the last real step of each sequence.
+///
+/// As a `Layer1d` it takes one unmasked sample at a time, flattened step by step into an
+/// array of length `time * input_size`, so it can be stacked with `Dense1d` layers in
+/// `train::train` and `DenseClassifier`.
+///
+/// The weights are saved with `traits::Persist`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::recurrent::*;
+/// use ndarray::{arr2, Array3};
+///
+/// let layer = LSTM::new(3, 8, ReturnMode::Last, Some(42));
+///
+/// // Two sequences of three features, the second one only two steps long
+/// let input_array = Array3::ones((2, 4, 3));
+/// let mask = arr2(&[[true, true, true, true], [true, true, false, false]]);
+///
+/// let output = layer.pass_batch(&input_array, Some(&mask)).unwrap();
+/// assert_eq!(output.shape(), &[2, 1, 8]);
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Recurrent<C> {
+    return_mode: ReturnMode,
+    weights: RwLock<RecurrentWeights>,
+    cell: PhantomData<C>,
+}
+
+/// Elman recurrent layer, h = tanh( x W + h U + b ).
+#[allow(clippy::upper_case_acronyms)]
+pub type SimpleRNN = Recurrent<SimpleRnnCell>;
+
+/// Long short-term memory layer.
+#[allow(clippy::upper_case_acronyms)]
+pub type LSTM = Recurrent<LstmCell>;
+
+/// Gated recurrent unit layer.
+#[allow(clippy::upper_case_acronyms)]
+pub type GRU = Recurrent<GruCell>;
+
+impl<C: RecurrentCell> Recurrent<C> {
+    /// Create recurrent layer with randomly set weights and bias's, drawn uniformly from
+    /// ±1 / sqrt( hidden size )
+    ///
+    /// # Arguments
+    ///
+    /// * `input_size`: Number of features of every step of the input
+    /// * `hidden_size`: Number of features of the state, and so of every step of the output
+    /// * `return_mode`: Whether to output every step or only the last one
+    /// * `seed`: Seed for drawing the weights, or `None` to seed it from the operating system
+    ///
+    /// returns: `Recurrent<C>`
+    pub fn new(
+        input_size: usize,
+        hidden_size: usize,
+        return_mode: ReturnMode,
+        seed: Option<u64>,
+    ) -> Self {
+        let mut rng = seeded_rng(seed);
+        let bound = 1. / (hidden_size.max(1) as f64).sqrt();
+        let distribution = Uniform::new_inclusive(-bound, bound);
+        let columns = C::GATES * hidden_size;
+
+        let mut bias = Array1::random_using(columns, distribution, &mut rng);
+        C::init_bias(&mut bias, hidden_size);
+
+        Self {
+            return_mode,
+            weights: RwLock::new(RecurrentWeights {
+                input: Array2::random_using((input_size, columns), distribution, &mut rng),
+                hidden: Array2::random_using((hidden_size, columns), distribution, &mut rng),
+                bias,
+            }),
+            cell: PhantomData,
+        }
+    }
+
+    /// Create recurrent layer with full control over the weights
+    ///
+    /// # Arguments
+    ///
+    /// * `weights`: Weights of the layer. `hidden` has to be of shape( hidden size,
+    ///   gates * hidden size ), `input` of shape( input size, gates * hidden size ) and
+    ///   `bias` of length gates * hidden size
+    /// * `return_mode`: Whether to output every step or only the last one
+    ///
+    /// returns: `Result<Recurrent<C>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the weights do not line up with each other or the cell
+    pub fn from(weights: RecurrentWeights, return_mode: ReturnMode) -> Result<Self, DuckyError> {
+        let hidden_size = weights.hidden_size();
+        let columns = C::GATES * hidden_size;
+
+        for (expected, found) in [
+            (vec![hidden_size, columns], weights.hidden.shape()),
+            (vec![weights.input.nrows(), columns], weights.input.shape()),
+            (vec![columns], weights.bias.shape()),
+        ] {
+            if expected != found {
+                return Err(DuckyError::ShapeMismatch {
+                    expected,
+                    found: found.to_vec(),
+                });
+            }
+        }
+
+        Ok(Self {
+            return_mode,
+            weights: RwLock::new(weights),
+            cell: PhantomData,
+        })
+    }
+
+    /// Whether the layer outputs every step or only the last one.
+    pub fn return_mode(&self) -> ReturnMode {
+        self.return_mode
+    }
+
+    /// Weights of the layer.
+    pub fn weights(&self) -> RecurrentWeights {
+        self.weights.read().unwrap().clone()
+    }
+
+    /// Records the cell running over every step of `input_array` on the tape of `weights`.
+    ///
+    /// returns: the input as a tensor, and the hidden state after every step, of
+    /// shape( batch, time, hidden size )
+    fn run(
+        weights: &WeightTensors,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<(Tensor, Tensor), DuckyError> {
+        let tape = weights.hidden.tape();
+        let (batch, time, _) = input_array.dim();
+        let input = tape.variable(input_array.clone());
+        let mut state =
+            vec![tape.variable(Array2::<f64>::zeros((batch, weights.hidden_size()))); C::STATES];
+        let mut hidden = Vec::with_capacity(time);
+
+        for step in 0..time {
+            let x = input.index_axis(1, step)?;
+            let mut new_state = C::step(weights, &x, &state)?;
+
+            // Masked steps leave the state as it was
+            if let Some(real) = mask_column(mask, step) {
+                let masked = tape.variable(real.mapv(|real| 1. - real));
+                let real = tape.variable(real);
+                for (new, previous) in new_state.iter_mut().zip(&state) {
+                    *new = new.try_mul(&real)? + previous.try_mul(&masked)?;
+                }
+            }
+
+            hidden.push(new_state[0].clone());
+            state = new_state;
+        }
+
+        Ok((input, stack(1, &hidden)?))
+    }
+
+    /// Feeds forward a batch of sequences through the layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of sequences of shape( batch, time, input size )
+    /// * `mask`: Which steps of each sequence are real, of shape( batch, time )
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` of shape( batch, time, hidden size ) for
+    /// `ReturnMode::Sequences` or ( batch, 1, hidden size ) for `ReturnMode::Last`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `input_array` or `mask` do not line up with the layer
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let states = self.states(input_array, mask)?;
+
+        Ok(match self.return_mode {
+            ReturnMode::Sequences => zero_masked(states, mask),
+            ReturnMode::Last => states.slice(s![.., -1.., ..]).to_owned(),
+        })
+    }
+
+    /// Backpropagates the gradient of the output of `pass_batch` through time, taking one
+    /// gradient descent step on the weights along the way.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The batch the layer was passed during the forward pass
+    /// * `mask`: The mask the layer was passed during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of `pass_batch`
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to `input_array`, computed before the weights are updated
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layer
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn back_pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+        output_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let (batch, time, _) = input_array.dim();
+        let output_size = self.output_size();
+        check_shape(
+            &output_shape(self.return_mode, batch, time, output_size),
+            output_gradient,
+        )?;
+
+        let states_gradient = match self.return_mode {
+            ReturnMode::Sequences => zero_masked(output_gradient.clone(), mask),
+            ReturnMode::Last => {
+                let mut states_gradient = Array3::zeros((batch, time, output_size));
+                states_gradient
+                    .slice_mut(s![.., -1.., ..])
+                    .assign(output_gradient);
+                states_gradient
+            }
+        };
+        self.back_states(input_array, mask, &states_gradient, learning_rate)
+    }
+}
+
+impl<C> Clone for Recurrent<C> {
+    fn clone(&self) -> Self {
+        Self {
+            return_mode: self.return_mode,
+            weights: RwLock::new(self.weights.read().unwrap().clone()),
+            cell: PhantomData,
+        }
+    }
+}
+
+impl<C: RecurrentCell> SequenceLayer for Recurrent<C> {
+    fn input_size(&self) -> usize {
+        self.weights.read().unwrap().input.nrows()
+    }
+
+    fn output_size(&self) -> usize {
+        self.weights.read().unwrap().hidden_size()
+    }
+
+    fn states(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let weights = self.weights.read().unwrap();
+        check_sequences(weights.input.nrows(), input_array, mask)?;
+
+        let (_, states) = Self::run(&weights.record(&Tape::new()), input_array, mask)?;
+        Ok(states.value_as::<Ix3>())
+    }
+
+    fn back_states(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+        states_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let mut weights = self.weights.write().unwrap();
+        check_sequences(weights.input.nrows(), input_array, mask)?;
+        let (batch, time, _) = input_array.dim();
+        check_shape(&[batch, time, weights.hidden_size()], states_gradient)?;
+
+        let variables = weights.record(&Tape::new());
+        let (input, states) = Self::run(&variables, input_array, mask)?;
+        let gradients = states.backward_with(states_gradient)?;
+        let gradient =
+            |tensor: &Tensor| gradients.wrt(tensor).into_dimensionality::<Ix2>().unwrap();
+
+        weights
+            .input
+            .scaled_add(-learning_rate, &gradient(&variables.input));
+        weights
+            .hidden
+            .scaled_add(-learning_rate, &gradient(&variables.hidden));
+        weights.bias.scaled_add(
+            -learning_rate,
+            &gradients
+                .wrt(&variables.bias)
+                .into_dimensionality::<Ix1>()
+                .unwrap(),
+        );
+
+        Ok(gradients.wrt(&input).into_dimensionality().unwrap())
+    }
+
+    fn saved_bidirectional(layer: &Bidirectional<Self>) -> Result<SavedLayer, DuckyError> {
+        C::saved_bidirectional(layer)
+    }
+}
+
+impl<C: RecurrentCell> Layer1d for Recurrent<C> {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        _mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let output = self.pass_batch(&sequence(self.input_size(), &input_array)?, None)?;
+        let output: Array1<f64> = output.iter().cloned().collect();
+        Ok((output.clone(), output))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        _weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let input_array = sequence(self.input_size(), input_array)?;
+        let shape = output_shape(
+            self.return_mode,
+            1,
+            input_array.shape()[1],
+            self.output_size(),
+        );
+
+        let input_gradient = self.back_pass_batch(
+            &input_array,
+            None,
+            &self::output_gradient(shape, output_gradient)?,
+            learning_rate,
+        )?;
+        Ok(input_gradient.iter().cloned().collect())
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        C::saved_layer(self)
+    }
+}
+
+/// Runs one layer forwards and another backwards over every sequence, and joins their
+/// outputs feature by feature, so every step sees both what came before and after it.
+///
+/// The backward layer reads each sequence from its end, so with a mask the padding at the
+/// end of shorter sequences is skipped before it reaches any real steps. For
+/// `ReturnMode::Last` the backward layer contributes its state after reading the whole
+/// sequence, which is after the first step.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::recurrent::*;
+/// use ndarray::Array3;
+///
+/// let layer = Bidirectional::new(
+///     GRU::new(3, 8, ReturnMode::Sequences, Some(1)),
+///     GRU::new(3, 8, ReturnMode::Sequences, Some(2)),
+///     ReturnMode::Sequences,
+/// )
+/// .unwrap();
+///
+/// let output = layer.pass_batch(&Array3::ones((2, 4, 3)), None).unwrap();
+/// assert_eq!(output.shape(), &[2, 4, 16]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bidirectional<R> {
+    pub forward: R,
+    pub backward: R,
+    return_mode: ReturnMode,
+}
+
+impl<R: SequenceLayer> Bidirectional<R> {
+    /// Create Bidirectional layer
+    ///
+    /// # Arguments
+    ///
+    /// * `forward`: Layer reading the sequences from the start. Its own return mode is not
+    ///   used
+    /// * `backward`: Layer reading the sequences from the end. Its own return mode is not
+    ///   used
+    /// * `return_mode`: Whether to output every step or only the last one
+    ///
+    /// returns: `Result<Bidirectional<R>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the layers take a different number of input features
+    pub fn new(forward: R, backward: R, return_mode: ReturnMode) -> Result<Self, DuckyError> {
+        if forward.input_size() != backward.input_size() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![forward.input_size()],
+                found: vec![backward.input_size()],
+            });
+        }
+
+        Ok(Self {
+            forward,
+            backward,
+            return_mode,
+        })
+    }
+
+    /// Whether the layer outputs every step or only the last one.
+    pub fn return_mode(&self) -> ReturnMode {
+        self.return_mode
+    }
+
+    /// Number of features of every step of the output of the forward and backward layers
+    /// together.
+    pub fn output_size(&self) -> usize {
+        self.forward.output_size() + self.backward.output_size()
+    }
+
+    /// Feeds forward a batch of sequences through both layers.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: Batch of sequences of shape( batch, time, input size )
+    /// * `mask`: Which steps of each sequence are real, of shape( batch, time )
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` of shape( batch, time, output size ) for
+    /// `ReturnMode::Sequences` or ( batch, 1, output size ) for `ReturnMode::Last`, holding
+    /// the output of the forward layer followed by the backward layer
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `input_array` or `mask` do not line up with the layers
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let reversed_mask = mask.map(reversed);
+        let forward = self.forward.states(input_array, mask)?;
+        let backward = self
+            .backward
+            .states(&reversed(input_array), reversed_mask.as_ref())?;
+
+        Ok(match self.return_mode {
+            ReturnMode::Sequences => zero_masked(
+                concatenate(Axis(2), &[forward.view(), reversed(&backward).view()]).unwrap(),
+                mask,
+            ),
+            ReturnMode::Last => concatenate(
+                Axis(2),
+                &[
+                    forward.slice(s![.., -1.., ..]),
+                    backward.slice(s![.., -1.., ..]),
+                ],
+            )
+            .unwrap(),
+        })
+    }
+
+    /// Backpropagates the gradient of the output of `pass_batch` through time in both layers,
+    /// taking one gradient descent step on their weights along the way.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_array`: The batch the layer was passed during the forward pass
+    /// * `mask`: The mask the layer was passed during the forward pass
+    /// * `output_gradient`: Gradient of the cost with respect to the output of `pass_batch`
+    /// * `learning_rate`: Size of the gradient descent step
+    ///
+    /// returns: `Result<Array3<f64>, DuckyError>` holding the gradient of the cost with respect
+    /// to `input_array`, computed before the weights are updated
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: the arrays do not line up with the layers
+    /// * `DuckyError::EmptyInput`: the sequences have no steps
+    /// * `DuckyError::NonFinite`: `input_array` contains a NaN or infinite value
+    pub fn back_pass_batch(
+        &self,
+        input_array: &Array3<f64>,
+        mask: Option<&Array2<bool>>,
+        output_gradient: &Array3<f64>,
+        learning_rate: f64,
+    ) -> Result<Array3<f64>, DuckyError> {
+        let (batch, time, _) = input_array.dim();
+        let split = self.forward.output_size();
+        check_shape(
+            &output_shape(self.return_mode, batch, time, self.output_size()),
+            output_gradient,
+        )?;
+
+        let (forward_gradient, backward_gradient) = match self.return_mode {
+            ReturnMode::Sequences => {
+                let output_gradient = zero_masked(output_gradient.clone(), mask);
+                (
+                    output_gradient.slice(s![.., .., ..split]).to_owned(),
+                    reversed(&output_gradient.slice(s![.., .., split..]).to_owned()),
+                )
+            }
+            ReturnMode::Last => {
+                let mut forward_gradient = Array3::zeros((batch, time, split));
+                let mut backward_gradient =
+                    Array3::zeros((batch, time, self.backward.output_size()));
+                forward_gradient
+                    .slice_mut(s![.., -1.., ..])
+                    .assign(&output_gradient.slice(s![.., .., ..split]));
+                backward_gradient
+                    .slice_mut(s![.., -1.., ..])
+                    .assign(&output_gradient.slice(s![.., .., split..]));
+                (forward_gradient, backward_gradient)
+            }
+        };
+
+        let reversed_mask = mask.map(reversed);
+        let input_gradient =
+            self.forward
+                .back_states(input_array, mask, &forward_gradient, learning_rate)?;
+        let backward_input_gradient = self.backward.back_states(
+            &reversed(input_array),
+            reversed_mask.as_ref(),
+            &backward_gradient,
+            learning_rate,
+        )?;
+
+        Ok(input_gradient + reversed(&backward_input_gradient))
+    }
+}
+
+impl<R: SequenceLayer> Layer1d for Bidirectional<R> {
+    fn pass(
+        &self,
+        input_array: Array1<f64>,
+        _mode: Mode,
+    ) -> Result<(Array1<f64>, Array1<f64>), DuckyError> {
+        let input_array = sequence(self.forward.input_size(), &input_array)?;
+        let output: Array1<f64> = self
+            .pass_batch(&input_array, None)?
+            .iter()
+            .cloned()
+            .collect();
+        Ok((output.clone(), output))
+    }
+
+    fn back_pass(
+        &self,
+        input_array: &Array1<f64>,
+        _weighted_input: &Array1<f64>,
+        output_gradient: &Array1<f64>,
+        learning_rate: f64,
+    ) -> Result<Array1<f64>, DuckyError> {
+        let input_array = sequence(self.forward.input_size(), input_array)?;
+        let shape = output_shape(
+            self.return_mode,
+            1,
+            input_array.shape()[1],
+            self.output_size(),
+        );
+
+        let input_gradient = self.back_pass_batch(
+            &input_array,
+            None,
+            &self::output_gradient(shape, output_gradient)?,
+            learning_rate,
+        )?;
+        Ok(input_gradient.iter().cloned().collect())
+    }
+
+    fn to_saved(&self) -> Result<SavedLayer, DuckyError> {
+        R::saved_bidirectional(self)
+    }
+}
+
+#[cfg(test)]
+mod recurrent_tests {
+    use super::*;
+    use crate::layers::Dense1d;
+    use crate::test_util::{assert_close, numerical_gradient, uneven};
+    use crate::train::train;
+    use crate::traits::Persist;
+    use ndarray::{arr1, arr2};
+
+    fn mask() -> Array2<bool> {
+        arr2(&[[true, true, true, true], [true, true, false, false]])
+    }
+
+    fn check_gradients<C: RecurrentCell>(return_mode: ReturnMode, mask: Option<&Array2<bool>>) {
+        let layer = Recurrent::<C>::new(2, 3, return_mode, Some(7));
+        let x = uneven((2, 4, 2));
+        let output_shape = layer.pass_batch(&x, mask).unwrap().raw_dim();
+        let output_gradient = Array3::from_shape_fn(output_shape, |(i, j, k)| {
+            ((i + 2 * j + 5 * k) as f64 * 1.3).cos()
+        });
+
+        let expected =
+            numerical_gradient(&x, &output_gradient, |x| layer.pass_batch(x, mask).unwrap());
+        let updated = layer.clone();
+        let found = updated
+            .back_pass_batch(&x, mask, &output_gradient, 1.)
+            .unwrap();
+        assert_close(&found, &expected);
+
+        let before = layer.weights();
+        let after = updated.weights();
+        let with = |weights: RecurrentWeights| {
+            Recurrent::<C>::from(weights, return_mode)
+                .unwrap()
+                .pass_batch(&x, mask)
+                .unwrap()
+        };
+
+        let input = numerical_gradient(&before.input, &output_gradient, |input| {
+            with(RecurrentWeights {
+                input: input.clone(),
+                ..before.clone()
+            })
+        });
+        let hidden = numerical_gradient(&before.hidden, &output_gradient, |hidden| {
+            with(RecurrentWeights {
+                hidden: hidden.clone(),
+                ..before.clone()
+            })
+        });
+        let bias = numerical_gradient(&before.bias, &output_gradient, |bias| {
+            with(RecurrentWeights {
+                bias: bias.clone(),
+                ..before.clone()
+            })
+        });
+        assert_close(&(&before.input - &after.input), &input);
+        assert_close(&(&before.hidden - &after.hidden), &hidden);
+        assert_close(&(&before.bias - &after.bias), &bias);
+    }
+
+    #[test]
+    fn simple_rnn_gradients() {
+        check_gradients::<SimpleRnnCell>(ReturnMode::Sequences, None);
+        check_gradients::<SimpleRnnCell>(ReturnMode::Last, Some(&mask()));
+    }
+
+    #[test]
+    fn lstm_gradients() {
+        check_gradients::<LstmCell>(ReturnMode::Sequences, Some(&mask()));
+        check_gradients::<LstmCell>(ReturnMode::Last, None);
+    }
+
+    #[test]
+    fn gru_gradients() {
+        check_gradients::<GruCell>(ReturnMode::Sequences, Some(&mask()));
+        check_gradients::<GruCell>(ReturnMode::Last, Some(&mask()));
+    }
+
+    #[test]
+    fn bidirectional_gradients() {
+        for return_mode in [ReturnMode::Sequences, ReturnMode::Last] {
+            let layer = Bidirectional::new(
+                LSTM::new(2, 3, ReturnMode::Last, Some(1)),
+                LSTM::new(2, 3, ReturnMode::Last, Some(2)),
+                return_mode,
+            )
+            .unwrap();
+            let x = uneven((2, 4, 2));
+            let mask = mask();
+            let output_gradient =
+                uneven(layer.pass_batch(&x, Some(&mask)).unwrap().dim()).mapv(|v| v + 0.5);
+
+            let expected = numerical_gradient(&x, &output_gradient, |x| {
+                layer.pass_batch(x, Some(&mask)).unwrap()
+            });
+            let found = layer
+                .back_pass_batch(&x, Some(&mask), &output_gradient, 0.)
+                .unwrap();
+            assert_close(&found, &expected);
+        }
+    }
+
+    #[test]
+    fn lstm_forget_bias() {
+        let layer = LSTM::new(2, 3, ReturnMode::Last, Some(3));
+        let bias = layer.weights().bias;
+
+        assert!(bias.slice(s![3..6]).iter().all(|&value| value >= 0.4));
+        assert!(bias.slice(s![..3]).iter().all(|&value| value.abs() <= 0.6));
+    }
+
+    #[test]
+    fn masked_steps_are_skipped() {
+        let padded = uneven((2, 4, 2));
+        let short = padded.slice(s![1..2, ..2, ..]).to_owned();
+        let mask = mask();
+
+        let last = GRU::new(2, 3, ReturnMode::Last, Some(4));
+        let padded_last = last.pass_batch(&padded, Some(&mask)).unwrap();
+        assert_close(
+            &padded_last.slice(s![1..2, .., ..]).to_owned(),
+            &last.pass_batch(&short, None).unwrap(),
+        );
+
+        let sequences = SimpleRNN::new(2, 3, ReturnMode::Sequences, Some(4));
+        let output = sequences.pass_batch(&padded, Some(&mask)).unwrap();
+        assert!(output
+            .slice(s![1, 2.., ..])
+            .iter()
+            .all(|&value| value == 0.));
+        assert!(output
+            .slice(s![0, 2.., ..])
+            .iter()
+            .all(|&value| value != 0.));
+
+        let bidirectional = Bidirectional::new(
+            GRU::new(2, 3, ReturnMode::Last, Some(5)),
+            GRU::new(2, 3, ReturnMode::Last, Some(6)),
+            ReturnMode::Last,
+        )
+        .unwrap();
+        let padded_last = bidirectional.pass_batch(&padded, Some(&mask)).unwrap();
+        assert_close(
+            &padded_last.slice(s![1..2, .., ..]).to_owned(),
+            &bidirectional.pass_batch(&short, None).unwrap(),
+        );
+    }
+
+    #[test]
+    fn masked_steps_get_no_gradient() {
+        let layer = LSTM::new(2, 3, ReturnMode::Sequences, Some(8));
+        let x = uneven((2, 4, 2));
+
+        let input_gradient = layer
+            .back_pass_batch(&x, Some(&mask()), &Array3::ones((2, 4, 3)), 0.1)
+            .unwrap();
+
+        assert!(input_gradient
+            .slice(s![1, 2.., ..])
+            .iter()
+            .all(|&value| value == 0.));
+    }
+
+    #[test]
+    fn recurrent_errors() {
+        let layer = SimpleRNN::new(2, 3, ReturnMode::Sequences, Some(9));
+
+        assert_eq!(
+            layer.pass_batch(&Array3::zeros((2, 4, 3)), None),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2, 4, 2],
+                found: vec![2, 4, 3],
+            })
+        );
+        assert_eq!(
+            layer.pass_batch(
+                &Array3::zeros((2, 4, 2)),
+                Some(&Array2::from_elem((2, 3), true))
+            ),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2, 4],
+                found: vec![2, 3],
+            })
+        );
+        assert_eq!(
+            layer.pass_batch(&Array3::zeros((2, 0, 2)), None),
+            Err(DuckyError::EmptyInput)
+        );
+        assert_eq!(
+            layer.pass_batch(&Array3::from_elem((1, 1, 2), f64::NAN), None),
+            Err(DuckyError::NonFinite)
+        );
+        assert_eq!(
+            layer.back_pass_batch(
+                &Array3::zeros((2, 4, 2)),
+                None,
+                &Array3::zeros((2, 1, 3)),
+                0.1
+            ),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2, 4, 3],
+                found: vec![2, 1, 3],
+            })
+        );
+        assert_eq!(
+            layer.pass(arr1(&[1., 2., 3.]), Mode::Eval),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![3],
+            })
+        );
+        assert!(matches!(
+            GRU::from(layer.weights(), ReturnMode::Last),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+        assert!(matches!(
+            Bidirectional::new(
+                layer.clone(),
+                SimpleRNN::new(3, 3, ReturnMode::Last, None),
+                ReturnMode::Last
+            ),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn recurrent_write_read() {
+        let layer = LSTM::new(2, 3, ReturnMode::Last, Some(10));
+        let mut buffer = Vec::new();
+        layer.write_to(&mut buffer).unwrap();
+
+        let loaded = LSTM::read_from(buffer.as_slice()).unwrap();
+        assert_eq!(loaded.weights(), layer.weights());
+        assert_eq!(loaded.return_mode(), ReturnMode::Last);
+    }
+
+    #[test]
+    fn recurrent_train() {
+        // The target is the last value of the sequence minus the first
+        let model: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(GRU::new(1, 4, ReturnMode::Last, Some(11))),
+            Box::new(Dense1d::from(
+                |x| x,
+                |x| x.map(|_| 1f64),
+                Array2::from_elem((1, 4), 0.1),
+                arr1(&[0.]),
+            )),
+        ];
+        let data = arr2(&[
+            [0., 0., 1., 1.],
+            [1., 0., 0., 0.],
+            [0.5, 0.5, 0.5, 0.5],
+            [-1., 0., 0., 1.],
+        ]);
+        let labels = arr2(&[[1.], [-1.], [0.], [2.]]);
+
+        let costs = train(
+            &model,
+            data.clone(),
+            labels.clone(),
+            data,
+            labels,
+            200,
+            0.05,
+        )
+        .unwrap();
+
+        assert!(costs.iter().all(|cost| cost.is_finite()));
+        assert!(costs[199] < costs[0] / 2.);
+    }
+}
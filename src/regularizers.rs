@@ -0,0 +1,220 @@
+use super::error::DuckyError;
+use ndarray::{Array, ArrayBase, Axis, Data, Dimension};
+use serde::{Deserialize, Serialize};
+
+/// Penalty on the size of a layer's parameters, added to the cost so training prefers
+/// smaller weights.
+///
+/// * `L1(l1)` - `l1` times the sum of the absolute values, which pushes weights to exactly zero.
+/// * `L2(l2)` - `l2` times the sum of the squares, which shrinks every weight.
+/// * `ElasticNet { l1, l2 }` - Both of the penalties above added together.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::regularizers::Regularizer;
+/// use ndarray::arr1;
+///
+/// let weights = arr1(&[1., -2.]);
+/// let regularizer = Regularizer::ElasticNet { l1: 0.1, l2: 0.5 };
+///
+/// assert_eq!(regularizer.penalty(&weights), 0.1 * 3. + 0.5 * 5.);
+/// assert_eq!(regularizer.gradient(&weights), arr1(&[0.1 + 1., -0.1 - 2.]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Regularizer {
+    L1(f64),
+    L2(f64),
+    ElasticNet { l1: f64, l2: f64 },
+}
+
+impl Regularizer {
+    fn coefficients(&self) -> (f64, f64) {
+        match *self {
+            Regularizer::L1(l1) => (l1, 0.),
+            Regularizer::L2(l2) => (0., l2),
+            Regularizer::ElasticNet { l1, l2 } => (l1, l2),
+        }
+    }
+
+    /// Checks the coefficients are finite and not negative.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: a coefficient is negative, NaN or infinite
+    pub fn check(&self) -> Result<(), DuckyError> {
+        let (l1, l2) = self.coefficients();
+        if [l1, l2]
+            .iter()
+            .any(|coefficient| !(coefficient.is_finite() && *coefficient >= 0.))
+        {
+            return Err(DuckyError::InvalidParameter(format!(
+                "regularization coefficients have to be finite and not negative, found {:?}",
+                self
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Amount the regularizer adds to the cost for `weights`.
+    pub fn penalty<S: Data<Elem = f64>, D: Dimension>(&self, weights: &ArrayBase<S, D>) -> f64 {
+        let (l1, l2) = self.coefficients();
+        weights.fold(0., |total, weight| {
+            total + l1 * weight.abs() + l2 * weight * weight
+        })
+    }
+
+    /// Gradient of `penalty` with respect to each weight. The gradient of the L1 penalty is
+    /// taken to be zero at zero.
+    pub fn gradient<S: Data<Elem = f64>, D: Dimension>(
+        &self,
+        weights: &ArrayBase<S, D>,
+    ) -> Array<f64, D> {
+        let (l1, l2) = self.coefficients();
+        weights.mapv(|weight| {
+            let sign = if weight == 0. { 0. } else { weight.signum() };
+            l1 * sign + 2. * l2 * weight
+        })
+    }
+}
+
+/// Limit on a layer's parameters, applied to them after every gradient descent step.
+///
+/// The norms are taken over the last axis, so for a `Dense1d` kernel of shape( output, input )
+/// they limit the weights going into each node, and for a bias they limit the whole bias.
+///
+/// * `MaxNorm(max)` - Scales down any weights whose L2 norm is above `max`.
+/// * `NonNegative` - Sets negative weights to zero.
+/// * `UnitNorm` - Scales the weights to have an L2 norm of one.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::regularizers::Constraint;
+/// use ndarray::arr2;
+///
+/// let mut weights = arr2(&[[6., 8.], [3., -4.]]);
+/// Constraint::MaxNorm(5.).apply(&mut weights);
+///
+/// assert_eq!(weights, arr2(&[[3., 4.], [3., -4.]]));
+///
+/// Constraint::NonNegative.apply(&mut weights);
+///
+/// assert_eq!(weights, arr2(&[[3., 4.], [3., 0.]]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Constraint {
+    MaxNorm(f64),
+    NonNegative,
+    UnitNorm,
+}
+
+impl Constraint {
+    /// Checks the maximum norm is positive.
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: the maximum norm is not positive or not finite
+    pub fn check(&self) -> Result<(), DuckyError> {
+        match *self {
+            Constraint::MaxNorm(max) if !(max > 0. && max.is_finite()) => Err(
+                DuckyError::InvalidParameter(format!("max norm has to be positive, found {}", max)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the constraint to `weights` in place.
+    pub fn apply<D: Dimension>(&self, weights: &mut Array<f64, D>) {
+        if weights.ndim() == 0 {
+            return;
+        }
+        let last = Axis(weights.ndim() - 1);
+
+        match *self {
+            Constraint::NonNegative => weights.mapv_inplace(|weight| weight.max(0.)),
+            Constraint::MaxNorm(max) => {
+                for mut lane in weights.lanes_mut(last) {
+                    let norm = lane.dot(&lane).sqrt();
+                    if norm > max {
+                        lane *= max / norm;
+                    }
+                }
+            }
+            Constraint::UnitNorm => {
+                for mut lane in weights.lanes_mut(last) {
+                    let norm = lane.dot(&lane).sqrt();
+                    // A lane of zeros has no direction to keep
+                    if norm > 0. {
+                        lane /= norm;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod regularizers_tests {
+    use super::*;
+    use ndarray::{arr1, arr2};
+
+    #[test]
+    fn penalties() {
+        let weights = arr2(&[[1., -2.], [0., 3.]]);
+
+        assert_eq!(Regularizer::L1(0.5).penalty(&weights), 3.);
+        assert_eq!(Regularizer::L2(0.5).penalty(&weights), 7.);
+        assert_eq!(
+            Regularizer::ElasticNet { l1: 0.5, l2: 0.5 }.penalty(&weights),
+            10.
+        );
+    }
+
+    #[test]
+    fn gradients() {
+        let weights = arr1(&[1., -2., 0.]);
+
+        assert_eq!(
+            Regularizer::L1(0.5).gradient(&weights),
+            arr1(&[0.5, -0.5, 0.])
+        );
+        assert_eq!(
+            Regularizer::L2(0.5).gradient(&weights),
+            arr1(&[1., -2., 0.])
+        );
+    }
+
+    #[test]
+    fn regularizer_check() {
+        assert!(Regularizer::L1(0.).check().is_ok());
+        assert!(Regularizer::L2(-0.1).check().is_err());
+        assert!(Regularizer::ElasticNet {
+            l1: 0.1,
+            l2: f64::NAN
+        }
+        .check()
+        .is_err());
+    }
+
+    #[test]
+    fn unit_norm() {
+        let mut weights = arr2(&[[3., 4.], [0., 0.]]);
+        Constraint::UnitNorm.apply(&mut weights);
+
+        assert_eq!(weights, arr2(&[[0.6, 0.8], [0., 0.]]));
+
+        let mut bias = arr1(&[0., 2.]);
+        Constraint::UnitNorm.apply(&mut bias);
+
+        assert_eq!(bias, arr1(&[0., 1.]));
+    }
+
+    #[test]
+    fn constraint_check() {
+        assert!(Constraint::MaxNorm(2.).check().is_ok());
+        assert!(Constraint::MaxNorm(0.).check().is_err());
+        assert!(Constraint::NonNegative.check().is_ok());
+    }
+}
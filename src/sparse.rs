@@ -0,0 +1,754 @@
+extern crate ndarray;
+
+use super::error::{check_finite, check_rows, DuckyError};
+use ndarray::{Array1, Array2, ArrayBase, ArrayView2, Data, Ix2};
+
+/// Row-wise access to the non-zero values of a feature matrix.
+///
+/// Lets models such as `StdNaiveBayes` take a dense `Vec<Vec<f64>>`, an ndarray `Array2`
+/// or `ArrayView2`, or a `CsrMatrix` without having to densify the sparse one first.
+pub trait FeatureMatrix {
+    /// Number of rows (samples) in the matrix.
+    fn n_rows(&self) -> usize;
+
+    /// Number of columns (features) in the matrix.
+    fn n_cols(&self) -> usize;
+
+    /// Iterates over the `(column, value)` pairs of every non-zero value in a row,
+    /// in increasing column order.
+    fn row_nonzeros(&self, row: usize) -> Box<dyn Iterator<Item = (usize, f64)> + '_>;
+
+    /// Checks that the matrix has at least one row and column and that every value is
+    /// finite and inside the matrix.
+    ///
+    /// returns: `Result<(), DuckyError>`
+    fn validate(&self) -> Result<(), DuckyError> {
+        if self.n_rows() == 0 || self.n_cols() == 0 {
+            return Err(DuckyError::EmptyInput);
+        }
+
+        for row in 0..self.n_rows() {
+            for (column, value) in self.row_nonzeros(row) {
+                if column >= self.n_cols() {
+                    return Err(DuckyError::ShapeMismatch {
+                        expected: vec![self.n_cols()],
+                        found: vec![column + 1],
+                    });
+                }
+                if !value.is_finite() {
+                    return Err(DuckyError::NonFinite);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the matrix into a dense `Array2<f64>`.
+    fn to_array2(&self) -> Array2<f64> {
+        let mut dense = Array2::zeros((self.n_rows(), self.n_cols()));
+        for row in 0..self.n_rows() {
+            for (column, value) in self.row_nonzeros(row) {
+                dense[[row, column]] = value;
+            }
+        }
+        dense
+    }
+
+    /// Matrix-matrix product with a dense matrix of shape( columns, k ), only touching the
+    /// non-zero values of this matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `matrix`: Has to have as many rows as this matrix has columns else will panic
+    ///
+    /// returns: `Array2<f64>` of shape( rows, k )
+    fn dot_dense(&self, matrix: &ArrayView2<f64>) -> Array2<f64> {
+        assert_eq!(
+            self.n_cols(),
+            matrix.nrows(),
+            "Matrix has {} columns, other matrix has {} rows",
+            self.n_cols(),
+            matrix.nrows()
+        );
+
+        let mut product = Array2::zeros((self.n_rows(), matrix.ncols()));
+        for (row, mut product_row) in product.rows_mut().into_iter().enumerate() {
+            for (column, value) in self.row_nonzeros(row) {
+                product_row.scaled_add(value, &matrix.row(column));
+            }
+        }
+        product
+    }
+}
+
+impl<S: Data<Elem = f64>> FeatureMatrix for ArrayBase<S, Ix2> {
+    fn n_rows(&self) -> usize {
+        self.nrows()
+    }
+
+    fn n_cols(&self) -> usize {
+        self.ncols()
+    }
+
+    fn row_nonzeros(&self, row: usize) -> Box<dyn Iterator<Item = (usize, f64)> + '_> {
+        Box::new(
+            self.row(row)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, value)| **value != 0.0)
+                .map(|(column, value)| (column, *value)),
+        )
+    }
+
+    fn validate(&self) -> Result<(), DuckyError> {
+        if self.is_empty() {
+            return Err(DuckyError::EmptyInput);
+        }
+        check_finite(self)
+    }
+
+    fn to_array2(&self) -> Array2<f64> {
+        self.to_owned()
+    }
+
+    fn dot_dense(&self, matrix: &ArrayView2<f64>) -> Array2<f64> {
+        self.dot(matrix)
+    }
+}
+
+impl FeatureMatrix for [Vec<f64>] {
+    fn n_rows(&self) -> usize {
+        self.len()
+    }
+
+    fn n_cols(&self) -> usize {
+        self.first().map_or(0, |row| row.len())
+    }
+
+    fn row_nonzeros(&self, row: usize) -> Box<dyn Iterator<Item = (usize, f64)> + '_> {
+        Box::new(
+            self[row]
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| **value != 0.0)
+                .map(|(column, value)| (column, *value)),
+        )
+    }
+
+    fn validate(&self) -> Result<(), DuckyError> {
+        check_rows(self, self.n_cols())
+    }
+}
+
+impl FeatureMatrix for Vec<Vec<f64>> {
+    fn n_rows(&self) -> usize {
+        self.as_slice().n_rows()
+    }
+
+    fn n_cols(&self) -> usize {
+        self.as_slice().n_cols()
+    }
+
+    fn row_nonzeros(&self, row: usize) -> Box<dyn Iterator<Item = (usize, f64)> + '_> {
+        self.as_slice().row_nonzeros(row)
+    }
+
+    fn validate(&self) -> Result<(), DuckyError> {
+        self.as_slice().validate()
+    }
+}
+
+impl<const N: usize> FeatureMatrix for [Vec<f64>; N] {
+    fn n_rows(&self) -> usize {
+        self.as_slice().n_rows()
+    }
+
+    fn n_cols(&self) -> usize {
+        self.as_slice().n_cols()
+    }
+
+    fn row_nonzeros(&self, row: usize) -> Box<dyn Iterator<Item = (usize, f64)> + '_> {
+        self.as_slice().row_nonzeros(row)
+    }
+
+    fn validate(&self) -> Result<(), DuckyError> {
+        self.as_slice().validate()
+    }
+}
+
+impl FeatureMatrix for CsrMatrix {
+    fn n_rows(&self) -> usize {
+        self.n_rows()
+    }
+
+    fn n_cols(&self) -> usize {
+        self.n_cols()
+    }
+
+    fn row_nonzeros(&self, row: usize) -> Box<dyn Iterator<Item = (usize, f64)> + '_> {
+        Box::new(self.row(row).iter())
+    }
+
+    fn dot_dense(&self, matrix: &ArrayView2<f64>) -> Array2<f64> {
+        CsrMatrix::dot_dense(self, matrix)
+    }
+}
+
+/// Compressed sparse row (CSR) matrix of `f64` values.
+///
+/// Only the non-zero values are stored, so a matrix of token counts over a large
+/// vocabulary takes memory in proportion to the number of words in the documents
+/// rather than `documents x vocabulary`.
+///
+/// # Fields
+/// `indptr`: Row `i` is stored in `indices[indptr[i]..indptr[i + 1]]` and
+///     `data[indptr[i]..indptr[i + 1]]`.
+///
+/// `indices`: Column index of every stored value, increasing within each row.
+///
+/// `data`: Every stored value.
+///
+/// `n_cols`: Number of columns in the matrix.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::sparse::CsrMatrix;
+/// use ndarray::arr2;
+///
+/// let matrix = CsrMatrix::from_dense(&vec![
+///     vec![1.0, 0.0, 2.0],
+///     vec![0.0, 0.0, 3.0],
+/// ]);
+///
+/// assert_eq!(matrix.nnz(), 3);
+/// assert_eq!(matrix.to_dense(), arr2(&[[1.0, 0.0, 2.0], [0.0, 0.0, 3.0]]));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrMatrix {
+    indptr: Vec<usize>,
+    indices: Vec<usize>,
+    data: Vec<f64>,
+    n_cols: usize,
+}
+
+/// A borrowed row of a `CsrMatrix`.
+#[derive(Debug, Clone, Copy)]
+pub struct SparseRow<'a> {
+    pub indices: &'a [usize],
+    pub data: &'a [f64],
+}
+
+impl<'a> SparseRow<'a> {
+    /// Iterates over the `(column, value)` pairs stored in the row.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, f64)> + 'a {
+        self.indices.iter().cloned().zip(self.data.iter().cloned())
+    }
+
+    /// Dot product of the row with a dense vector.
+    ///
+    /// # Arguments
+    ///
+    /// * `vector`: Has to be at least as long as the number of columns of the matrix
+    ///
+    /// returns: `f64`
+    pub fn dot(&self, vector: &Array1<f64>) -> f64 {
+        self.iter()
+            .map(|(column, value)| value * vector[column])
+            .sum()
+    }
+}
+
+impl CsrMatrix {
+    /// Creates an empty matrix with no rows and `n_cols` columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_cols`: Number of columns of the matrix
+    ///
+    /// returns: `CsrMatrix`
+    pub fn new(n_cols: usize) -> Self {
+        Self {
+            indptr: vec![0],
+            indices: Vec::new(),
+            data: Vec::new(),
+            n_cols,
+        }
+    }
+
+    /// Creates a matrix from its raw CSR parts.
+    ///
+    /// # Arguments
+    ///
+    /// * `indptr`: Offsets of each row into `indices` and `data`, starting at 0
+    /// * `indices`: Column index of every stored value, strictly increasing within each row
+    /// * `data`: Every stored value
+    /// * `n_cols`: Number of columns of the matrix
+    ///
+    /// returns: `Result<CsrMatrix, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `indptr` does not start at 0 or decreases, or the
+    ///   column indices of a row are not sorted and unique
+    /// * `DuckyError::ShapeMismatch`: `indices`, `data` and the end of `indptr` differ in
+    ///   length, or a column index is out of bounds
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::error::DuckyError;
+    /// use ducky_learn::sparse::CsrMatrix;
+    ///
+    /// let matrix = CsrMatrix::from_parts(vec![0, 2, 3], vec![0, 2, 1], vec![1., 2., 3.], 3)
+    ///     .unwrap();
+    /// assert_eq!(matrix.to_vec(), vec![vec![1., 0., 2.], vec![0., 3., 0.]]);
+    ///
+    /// let unsorted = CsrMatrix::from_parts(vec![0, 2], vec![2, 0], vec![1., 2.], 3);
+    /// assert!(matches!(unsorted, Err(DuckyError::InvalidParameter(_))));
+    /// ```
+    pub fn from_parts(
+        indptr: Vec<usize>,
+        indices: Vec<usize>,
+        data: Vec<f64>,
+        n_cols: usize,
+    ) -> Result<Self, DuckyError> {
+        if indptr.first() != Some(&0) {
+            return Err(DuckyError::InvalidParameter(
+                "indptr has to start at 0".to_string(),
+            ));
+        }
+        if indptr.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(DuckyError::InvalidParameter(
+                "indptr has to be non decreasing".to_string(),
+            ));
+        }
+        if indices.len() != data.len() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![indices.len()],
+                found: vec![data.len()],
+            });
+        }
+        if *indptr.last().unwrap() != data.len() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![data.len()],
+                found: vec![*indptr.last().unwrap()],
+            });
+        }
+        if let Some(&column) = indices.iter().find(|&&column| column >= n_cols) {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![n_cols],
+                found: vec![column + 1],
+            });
+        }
+        for row in indptr.windows(2) {
+            if indices[row[0]..row[1]]
+                .windows(2)
+                .any(|pair| pair[0] >= pair[1])
+            {
+                return Err(DuckyError::InvalidParameter(
+                    "column indices of a row have to be sorted and unique".to_string(),
+                ));
+            }
+        }
+
+        Ok(Self {
+            indptr,
+            indices,
+            data,
+            n_cols,
+        })
+    }
+
+    /// Creates a matrix holding the non-zero values of any `FeatureMatrix`,
+    /// such as a dense `Vec<Vec<f64>>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `matrix`: Matrix to compress
+    ///
+    /// returns: `CsrMatrix`
+    pub fn from_dense<M: FeatureMatrix + ?Sized>(matrix: &M) -> Self {
+        let mut csr = Self::new(matrix.n_cols());
+        for row in 0..matrix.n_rows() {
+            csr.push_row(matrix.row_nonzeros(row));
+        }
+        csr
+    }
+
+    /// Appends a row to the bottom of the matrix. Zeros are dropped and entries are
+    /// sorted by column, entries sharing a column are summed.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries`: `(column, value)` pairs of the new row
+    ///
+    /// # Panics
+    ///
+    /// Panics if a column is out of bounds.
+    pub fn push_row<I: IntoIterator<Item = (usize, f64)>>(&mut self, entries: I) {
+        let mut entries: Vec<(usize, f64)> = entries.into_iter().collect();
+        entries.sort_by_key(|(column, _)| *column);
+
+        let row_start = self.indices.len();
+        for (column, value) in entries {
+            assert!(
+                column < self.n_cols,
+                "Column {} is out of bounds for a matrix with {} columns",
+                column,
+                self.n_cols
+            );
+
+            if self.indices.len() > row_start && *self.indices.last().unwrap() == column {
+                *self.data.last_mut().unwrap() += value;
+            } else {
+                self.indices.push(column);
+                self.data.push(value);
+            }
+        }
+
+        // Drop explicit zeros, including ones created by summing duplicates
+        let mut keep = row_start;
+        for read in row_start..self.indices.len() {
+            if self.data[read] != 0.0 {
+                self.indices[keep] = self.indices[read];
+                self.data[keep] = self.data[read];
+                keep += 1;
+            }
+        }
+        self.indices.truncate(keep);
+        self.data.truncate(keep);
+
+        self.indptr.push(self.indices.len());
+    }
+
+    /// Number of rows of the matrix.
+    pub fn n_rows(&self) -> usize {
+        self.indptr.len() - 1
+    }
+
+    /// Number of columns of the matrix.
+    pub fn n_cols(&self) -> usize {
+        self.n_cols
+    }
+
+    /// `(rows, columns)` of the matrix.
+    pub fn shape(&self) -> (usize, usize) {
+        (self.n_rows(), self.n_cols)
+    }
+
+    /// Number of stored (non-zero) values.
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Offsets of each row into `indices` and `data`.
+    pub fn indptr(&self) -> &[usize] {
+        &self.indptr
+    }
+
+    /// Column index of every stored value.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Every stored value.
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+
+    /// Borrows a single row of the matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `row`: Index of the row, will panic if out of bounds
+    ///
+    /// returns: `SparseRow`
+    pub fn row(&self, row: usize) -> SparseRow<'_> {
+        let (start, end) = (self.indptr[row], self.indptr[row + 1]);
+        SparseRow {
+            indices: &self.indices[start..end],
+            data: &self.data[start..end],
+        }
+    }
+
+    /// Iterates over every row of the matrix.
+    pub fn rows(&self) -> impl Iterator<Item = SparseRow<'_>> {
+        (0..self.n_rows()).map(move |row| self.row(row))
+    }
+
+    /// Matrix-vector product.
+    ///
+    /// # Arguments
+    ///
+    /// * `vector`: Has to be the same length as the number of columns else will panic
+    ///
+    /// returns: `Array1<f64>` with one value per row
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::sparse::CsrMatrix;
+    /// use ndarray::arr1;
+    ///
+    /// let matrix = CsrMatrix::from_dense(&vec![vec![1.0, 0.0], vec![0.0, 2.0]]);
+    /// assert_eq!(matrix.dot(&arr1(&[3.0, 4.0])), arr1(&[3.0, 8.0]));
+    /// ```
+    pub fn dot(&self, vector: &Array1<f64>) -> Array1<f64> {
+        assert_eq!(
+            self.n_cols,
+            vector.len(),
+            "Matrix has {} columns, vector has length {}",
+            self.n_cols,
+            vector.len()
+        );
+
+        self.rows().map(|row| row.dot(vector)).collect()
+    }
+
+    /// Matrix-matrix product with a dense matrix of shape( columns, k ).
+    ///
+    /// # Arguments
+    ///
+    /// * `matrix`: Has to have as many rows as this matrix has columns else will panic
+    ///
+    /// returns: `Array2<f64>` of shape( rows, k )
+    pub fn dot_dense<S: Data<Elem = f64>>(&self, matrix: &ArrayBase<S, Ix2>) -> Array2<f64> {
+        assert_eq!(
+            self.n_cols,
+            matrix.nrows(),
+            "Matrix has {} columns, other matrix has {} rows",
+            self.n_cols,
+            matrix.nrows()
+        );
+
+        let mut product = Array2::zeros((self.n_rows(), matrix.ncols()));
+        for (mut product_row, row) in product.rows_mut().into_iter().zip(self.rows()) {
+            for (column, value) in row.iter() {
+                product_row.scaled_add(value, &matrix.row(column));
+            }
+        }
+        product
+    }
+
+    /// Converts the matrix into a dense `Array2<f64>`.
+    pub fn to_dense(&self) -> Array2<f64> {
+        let mut dense = Array2::zeros(self.shape());
+        for (i, row) in self.rows().enumerate() {
+            for (column, value) in row.iter() {
+                dense[[i, column]] = value;
+            }
+        }
+        dense
+    }
+
+    /// Converts the matrix into a dense `Vec<Vec<f64>>`.
+    pub fn to_vec(&self) -> Vec<Vec<f64>> {
+        self.rows()
+            .map(|row| {
+                let mut dense_row = vec![0.0; self.n_cols];
+                for (column, value) in row.iter() {
+                    dense_row[column] = value;
+                }
+                dense_row
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod sparse_tests {
+    use super::*;
+    use ndarray::{arr1, arr2};
+
+    fn example_matrix() -> CsrMatrix {
+        CsrMatrix::from_dense(&vec![
+            vec![1.0, 0.0, 2.0, 0.0],
+            vec![0.0, 0.0, 0.0, 0.0],
+            vec![0.0, 3.0, 0.0, 4.0],
+        ])
+    }
+
+    #[test]
+    fn from_dense() {
+        let matrix = example_matrix();
+
+        assert_eq!(matrix.shape(), (3, 4));
+        assert_eq!(matrix.indptr(), &[0, 2, 2, 4]);
+        assert_eq!(matrix.indices(), &[0, 2, 1, 3]);
+        assert_eq!(matrix.data(), &[1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn from_parts() {
+        let matrix =
+            CsrMatrix::from_parts(vec![0, 2, 2, 4], vec![0, 2, 1, 3], vec![1., 2., 3., 4.], 4)
+                .unwrap();
+
+        assert_eq!(matrix, example_matrix());
+    }
+
+    #[test]
+    fn from_parts_errors() {
+        let parts = |indptr: &[usize], indices: &[usize], data: &[f64]| {
+            CsrMatrix::from_parts(indptr.to_vec(), indices.to_vec(), data.to_vec(), 3)
+        };
+
+        assert!(matches!(
+            parts(&[], &[], &[]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            parts(&[1, 1], &[0], &[1.]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            parts(&[0, 2, 1], &[0, 1], &[1., 2.]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert_eq!(
+            parts(&[0, 1], &[0], &[1., 2.]),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1],
+                found: vec![2]
+            })
+        );
+        assert_eq!(
+            parts(&[0, 1], &[0, 1], &[1., 2.]),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![2],
+                found: vec![1]
+            })
+        );
+        assert_eq!(
+            parts(&[0, 1], &[3], &[1.]),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![3],
+                found: vec![4]
+            })
+        );
+        // Unsorted and repeated columns within a row
+        assert!(matches!(
+            parts(&[0, 2], &[2, 0], &[1., 2.]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            parts(&[0, 2], &[1, 1], &[1., 2.]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        // Columns only have to increase within a row
+        assert!(parts(&[0, 1, 2], &[2, 0], &[1., 2.]).is_ok());
+    }
+
+    #[test]
+    fn push_row_sorts_and_sums() {
+        let mut matrix = CsrMatrix::new(5);
+        matrix.push_row(vec![
+            (4, 1.0),
+            (1, 2.0),
+            (4, 1.0),
+            (0, 0.0),
+            (2, 1.0),
+            (2, -1.0),
+        ]);
+
+        assert_eq!(matrix.row(0).indices, &[1, 4]);
+        assert_eq!(matrix.row(0).data, &[2.0, 2.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn push_row_out_of_bounds() {
+        let mut matrix = CsrMatrix::new(2);
+        matrix.push_row(vec![(2, 1.0)]);
+    }
+
+    #[test]
+    fn to_dense_round_trip() {
+        let dense = vec![
+            vec![1.0, 0.0, 2.0, 0.0],
+            vec![0.0, 0.0, 0.0, 0.0],
+            vec![0.0, 3.0, 0.0, 4.0],
+        ];
+        let matrix = CsrMatrix::from_dense(&dense);
+
+        assert_eq!(matrix.to_vec(), dense);
+        assert_eq!(
+            matrix.to_dense(),
+            arr2(&[[1., 0., 2., 0.], [0., 0., 0., 0.], [0., 3., 0., 4.]])
+        );
+    }
+
+    #[test]
+    fn dot_vector() {
+        let matrix = example_matrix();
+
+        assert_eq!(matrix.dot(&arr1(&[1., 1., 1., 1.])), arr1(&[3., 0., 7.]));
+        assert_eq!(matrix.row(2).dot(&arr1(&[0., 2., 0., 0.5])), 8.);
+    }
+
+    #[test]
+    fn dot_dense_matches_ndarray() {
+        let matrix = example_matrix();
+        let other = arr2(&[[1., 2.], [3., 4.], [5., 6.], [7., 8.]]);
+
+        assert_eq!(matrix.dot_dense(&other), matrix.to_dense().dot(&other));
+    }
+
+    #[test]
+    fn feature_matrix_dot_dense() {
+        let matrix = example_matrix();
+        let dense = matrix.to_dense();
+        let rows = matrix.to_vec();
+        let other = arr2(&[[1., 2.], [3., 4.], [5., 6.], [7., 8.]]);
+        let expected = dense.dot(&other);
+
+        assert_eq!(FeatureMatrix::dot_dense(&matrix, &other.view()), expected);
+        assert_eq!(FeatureMatrix::dot_dense(&dense, &other.view()), expected);
+        assert_eq!(rows.dot_dense(&other.view()), expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dot_wrong_size() {
+        example_matrix().dot(&arr1(&[1., 1.]));
+    }
+
+    #[test]
+    fn feature_matrix_rows() {
+        let dense = vec![vec![0.0, 5.0, 0.0, 6.0]];
+        let matrix = CsrMatrix::from_dense(&dense);
+
+        let dense_nonzeros: Vec<(usize, f64)> = dense.row_nonzeros(0).collect();
+        let sparse_nonzeros: Vec<(usize, f64)> = matrix.row_nonzeros(0).collect();
+
+        assert_eq!(dense_nonzeros, vec![(1, 5.0), (3, 6.0)]);
+        assert_eq!(sparse_nonzeros, dense_nonzeros);
+        assert_eq!(FeatureMatrix::n_cols(&dense), 4);
+    }
+
+    #[test]
+    fn feature_matrix_ndarray() {
+        let dense = arr2(&[[0.0, 5.0], [6.0, 0.0]]);
+
+        let nonzeros: Vec<(usize, f64)> = dense.view().row_nonzeros(1).collect();
+
+        assert_eq!(nonzeros, vec![(0, 6.0)]);
+        assert_eq!(CsrMatrix::from_dense(&dense.view()).to_dense(), dense);
+        assert_eq!(vec![vec![0.0, 5.0], vec![6.0, 0.0]].to_array2(), dense);
+        assert_eq!(arr2(&[[f64::NAN]]).validate(), Err(DuckyError::NonFinite));
+    }
+
+    #[test]
+    fn feature_matrix_validate() {
+        let ragged = vec![vec![1.0, 2.0], vec![1.0]];
+        let empty: Vec<Vec<f64>> = vec![];
+        let nan = CsrMatrix::from_dense(&vec![vec![0.0, f64::NAN]]);
+
+        assert_eq!(example_matrix().validate(), Ok(()));
+        assert!(ragged.validate().is_err());
+        assert_eq!(empty.validate(), Err(DuckyError::EmptyInput));
+        assert_eq!(nan.validate(), Err(DuckyError::NonFinite));
+    }
+}
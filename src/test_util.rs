@@ -0,0 +1,39 @@
+use ndarray::prelude::*;
+use ndarray::ShapeBuilder;
+
+/// Array of the given shape filled with distinct, uneven values.
+pub(crate) fn uneven<Sh: ShapeBuilder>(shape: Sh) -> Array<f64, Sh::Dim> {
+    let mut index = 0f64;
+    Array::from_shape_simple_fn(shape, || {
+        index += 1.;
+        (index * 0.37).sin() * 2.
+    })
+}
+
+/// Gradient of `sum(f(x) * weights)` with respect to `x`, by central differences.
+pub(crate) fn numerical_gradient<D: Dimension, E: Dimension>(
+    x: &Array<f64, D>,
+    weights: &Array<f64, E>,
+    f: impl Fn(&Array<f64, D>) -> Array<f64, E>,
+) -> Array<f64, D> {
+    let step = 1e-6;
+    let mut gradient = Array::<f64, D>::zeros(x.raw_dim());
+
+    for (index, value) in gradient.iter_mut().enumerate() {
+        let mut above = x.clone();
+        let mut below = x.clone();
+        above.as_slice_mut().unwrap()[index] += step;
+        below.as_slice_mut().unwrap()[index] -= step;
+        *value = ((f(&above) * weights).sum() - (f(&below) * weights).sum()) / (2. * step);
+    }
+
+    gradient
+}
+
+/// Asserts that two arrays have the same shape and values within 1e-6 of each other.
+pub(crate) fn assert_close<D: Dimension>(found: &Array<f64, D>, expected: &Array<f64, D>) {
+    assert_eq!(found.shape(), expected.shape());
+    for (found, expected) in found.iter().zip(expected) {
+        assert!((found - expected).abs() < 1e-6, "{} != {}", found, expected);
+    }
+}
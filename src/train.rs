@@ -1,21 +1,235 @@
+use super::cost::{deriv_mean_squared_error, mean_squared_error};
+use super::error::{check_targets, DuckyError};
 use super::layers::*;
-use ndarray::{arr1, Array1, Array2};
+use super::sparse::FeatureMatrix;
+use super::traits;
+use super::util::{unique_labels, Fit, Unfit};
+use ndarray::{arr1, Array1, Array2, ArrayBase, ArrayView2, Axis, Data, Ix2};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::iter::zip;
 
+/// Trains the model with stochastic gradient descent on the mean squared error, one row of
+/// the training data at a time, and scores it on the test data after every epoch.
+///
+/// # Arguments
+///
+/// * `model`: Layers of the model, from input to output
+/// * `train_data`: Training data of shape( samples, features )
+/// * `train_lbl`: Target outputs of the training data of shape( samples, outputs )
+/// * `test_data`: Test data of shape( samples, features )
+/// * `test_lbl`: Target outputs of the test data of shape( samples, outputs )
+/// * `epochs`: Number of passes over the training data
+/// * `learning_rate`: Size of each gradient descent step
+///
+/// returns: `Result<Vec<f64>, DuckyError>` holding the mean squared error on the test data,
+/// plus the penalties of any regularized layers, after each epoch
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: the data and labels do not line up with each other or the model
+/// * `DuckyError::NonFinite`: the data contains a NaN or infinite value
+/// * `DuckyError::InvalidParameter`: `learning_rate` is not positive
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::layers::*;
+/// use ducky_learn::train::train;
+/// use ndarray::{arr1, arr2};
+///
+/// let model = vec![Dense1d::from(|x| x, |x| x.map(|_| 1f64), arr2(&[[0.]]), arr1(&[0.]))];
+/// let data = arr2(&[[1.], [2.]]);
+/// let labels = arr2(&[[2.], [4.]]);
+///
+/// let costs = train(&model, data.clone(), labels.clone(), data, labels, 20, 0.05).unwrap();
+///
+/// assert!(costs.last().unwrap() < &costs[0]);
+/// ```
+///
+/// See `train_batches` to take each step on a batch of rows instead.
 pub fn train<L>(
-    model: &Vec<L>,
+    model: &[L],
     train_data: Array2<f64>,
     train_lbl: Array2<f64>,
     test_data: Array2<f64>,
     test_lbl: Array2<f64>,
-) where
+    epochs: usize,
+    learning_rate: f64,
+) -> Result<Vec<f64>, DuckyError>
+where
+    L: Layer1d,
+{
+    train_batches(
+        model,
+        train_data,
+        train_lbl,
+        test_data,
+        test_lbl,
+        epochs,
+        1,
+        learning_rate,
+    )
+}
+
+/// Trains the model with mini-batch gradient descent on the mean squared error and scores it
+/// on the test data after every epoch.
+///
+/// Batches of more than one row go through `Layer1d::pass_batch` and
+/// `Layer1d::back_pass_batch`, so `BatchNorm1d` normalizes them with their own mean and
+/// variance. A `batch_size` of one trains one row at a time with `pass` and `back_pass`, as
+/// `train` does.
+///
+/// # Arguments
+///
+/// * `model`: Layers of the model, from input to output
+/// * `train_data`: Training data of shape( samples, features )
+/// * `train_lbl`: Target outputs of the training data of shape( samples, outputs )
+/// * `test_data`: Test data of shape( samples, features )
+/// * `test_lbl`: Target outputs of the test data of shape( samples, outputs )
+/// * `epochs`: Number of passes over the training data
+/// * `batch_size`: Number of rows in each gradient descent step, the last batch of an epoch
+///   may be smaller
+/// * `learning_rate`: Size of each gradient descent step
+///
+/// returns: `Result<Vec<f64>, DuckyError>` holding the mean squared error on the test data,
+/// plus the penalties of any regularized layers, after each epoch
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: the data and labels do not line up with each other or the model
+/// * `DuckyError::NonFinite`: the data contains a NaN or infinite value
+/// * `DuckyError::InvalidParameter`: `batch_size` is zero or `learning_rate` is not positive
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::layers::*;
+/// use ducky_learn::train::train_batches;
+/// use ndarray::{arr1, arr2};
+///
+/// let model: Vec<Box<dyn Layer1d>> = vec![
+///     Box::new(BatchNorm1d::new(1, 0.1, 1e-5).unwrap()),
+///     Box::new(Dense1d::from(|x| x, |x| x.map(|_| 1f64), arr2(&[[0.]]), arr1(&[0.]))),
+/// ];
+/// let data = arr2(&[[1.], [2.], [3.], [4.]]);
+/// let labels = arr2(&[[2.], [4.], [6.], [8.]]);
+///
+/// let costs = train_batches(&model, data.clone(), labels.clone(), data, labels, 20, 2, 0.1)
+///     .unwrap();
+///
+/// assert!(costs.last().unwrap() < &costs[0]);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn train_batches<L>(
+    model: &[L],
+    train_data: Array2<f64>,
+    train_lbl: Array2<f64>,
+    test_data: Array2<f64>,
+    test_lbl: Array2<f64>,
+    epochs: usize,
+    batch_size: usize,
+    learning_rate: f64,
+) -> Result<Vec<f64>, DuckyError>
+where
     L: Layer1d,
 {
-    todo!()
+    check_targets(test_data.nrows(), test_lbl.nrows())?;
+
+    let mut costs = Vec::with_capacity(epochs);
+    for _ in 0..epochs {
+        train_epoch(
+            model,
+            &train_data.view(),
+            &train_lbl.view(),
+            batch_size,
+            learning_rate,
+        )?;
+
+        let mut cost = 0.0;
+        for (data, target) in zip(test_data.rows(), test_lbl.rows()) {
+            let (_, activation_vec) = forward_pass(model, data.to_owned(), Mode::Eval)?;
+            let output = activation_vec
+                .last()
+                .cloned()
+                .unwrap_or_else(|| data.to_owned());
+            check_targets(target.len(), output.len())?;
+            cost += mean_squared_error(target.to_owned(), output)
+                .mean()
+                .unwrap_or(0.0);
+        }
+        let penalty: f64 = model.iter().map(|layer| layer.penalty()).sum();
+        costs.push(cost / test_data.nrows().max(1) as f64 + penalty);
+    }
+
+    Ok(costs)
+}
+
+/// Runs one epoch of gradient descent over `data`, a row at a time when `batch_size` is one
+/// and a batch at a time otherwise.
+fn train_epoch<L: Layer1d>(
+    model: &[L],
+    data: &ArrayView2<f64>,
+    labels: &ArrayView2<f64>,
+    batch_size: usize,
+    learning_rate: f64,
+) -> Result<(), DuckyError> {
+    check_targets(data.nrows(), labels.nrows())?;
+    if batch_size == 0 {
+        return Err(DuckyError::InvalidParameter(
+            "batch_size has to be positive".to_string(),
+        ));
+    }
+    if !(learning_rate > 0.0 && learning_rate.is_finite()) {
+        return Err(DuckyError::InvalidParameter(format!(
+            "learning_rate has to be positive, found {}",
+            learning_rate
+        )));
+    }
+
+    if batch_size > 1 {
+        for (batch, targets) in zip(
+            data.axis_chunks_iter(Axis(0), batch_size),
+            labels.axis_chunks_iter(Axis(0), batch_size),
+        ) {
+            let batch = batch.to_owned();
+            let (weighted_batches, activation_batches) =
+                forward_pass_batch(model, &batch, Mode::Train)?;
+            back_propagation_batch(
+                model,
+                &batch,
+                weighted_batches,
+                activation_batches,
+                &targets.to_owned(),
+                learning_rate,
+            )?;
+        }
+        return Ok(());
+    }
+
+    for (row, target) in zip(data.rows(), labels.rows()) {
+        let (weights_bias_vec, activation_vec) = forward_pass(model, row.to_owned(), Mode::Train)?;
+        back_propagation(
+            model,
+            row.to_owned(),
+            weights_bias_vec,
+            activation_vec,
+            target.to_owned(),
+            learning_rate,
+        )?;
+    }
+
+    Ok(())
 }
 
 //noinspection RsBorrowChecker For some reason it says that the item is moved eventhough it isn't
-pub fn forward_pass<L>(model: &Vec<L>, data: Array1<f64>) -> (Vec<Array1<f64>>, Vec<Array1<f64>>)
+#[allow(clippy::type_complexity)]
+pub fn forward_pass<L>(
+    model: &[L],
+    data: Array1<f64>,
+    mode: Mode,
+) -> Result<(Vec<Array1<f64>>, Vec<Array1<f64>>), DuckyError>
 where
     L: Layer1d,
 {
@@ -26,32 +240,409 @@ where
     let mut weight_pass;
 
     for layer in model.iter() {
-        (weight_pass, activation_pass) = layer.pass(activation_pass.clone());
+        (weight_pass, activation_pass) = layer.pass(activation_pass.clone(), mode)?;
 
         weights_bias_vec.push(weight_pass);
         activation_vec.push(activation_pass.clone());
     }
 
-    (weights_bias_vec, activation_vec)
+    Ok((weights_bias_vec, activation_vec))
 }
 
+/// Propagates the gradient of the mean squared error between the output of the model and
+/// `target_out` back through the model, taking one gradient descent step on every layer.
+///
+/// # Arguments
+///
+/// * `model`: Layers of the model, from input to output
+/// * `data`: The input the model was passed
+/// * `weights_bias_vec`: Weighted inputs of each layer, as returned by `forward_pass`
+/// * `activation_vec`: Outputs of each layer, as returned by `forward_pass`
+/// * `target_out`: The output the model should have returned
+/// * `learning_rate`: Size of the gradient descent step
+///
+/// returns: `Result<Array1<f64>, DuckyError>` holding the gradient of the cost with respect to
+/// `data`, so the model can be stacked on top of other layers such as those in `conv`
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: the forward pass or `target_out` do not line up with the model
 pub fn back_propagation<L>(
-    model: &Vec<L>,
+    model: &[L],
+    data: Array1<f64>,
     weights_bias_vec: Vec<Array1<f64>>,
     activation_vec: Vec<Array1<f64>>,
     target_out: Array1<f64>,
-) where
+    learning_rate: f64,
+) -> Result<Array1<f64>, DuckyError>
+where
+    L: Layer1d,
+{
+    check_targets(model.len(), weights_bias_vec.len())?;
+    check_targets(model.len(), activation_vec.len())?;
+    let output = activation_vec
+        .last()
+        .cloned()
+        .unwrap_or_else(|| data.clone());
+    check_targets(output.len(), target_out.len())?;
+
+    let mut gradient = deriv_mean_squared_error(target_out, output);
+    for (index, layer) in model.iter().enumerate().rev() {
+        let input_array = match index {
+            0 => &data,
+            _ => &activation_vec[index - 1],
+        };
+        gradient = layer.back_pass(
+            input_array,
+            &weights_bias_vec[index],
+            &gradient,
+            learning_rate,
+        )?;
+    }
+
+    Ok(gradient)
+}
+
+/// Feeds forward a batch of rows through every layer of the model with
+/// `Layer1d::pass_batch`.
+///
+/// # Arguments
+///
+/// * `model`: Layers of the model, from input to output
+/// * `data`: Batch of shape( samples, features )
+/// * `mode`: Whether the model is being trained or used for inference
+///
+/// returns: `Result<(Vec<Array2<f64>>, Vec<Array2<f64>>), DuckyError>` holding the weighted
+/// inputs and the outputs of each layer, one row per sample
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: `data` or a layer does not line up with the next layer
+/// * `DuckyError::NonFinite`: `data` contains a NaN or infinite value
+#[allow(clippy::type_complexity)]
+pub fn forward_pass_batch<L>(
+    model: &[L],
+    data: &Array2<f64>,
+    mode: Mode,
+) -> Result<(Vec<Array2<f64>>, Vec<Array2<f64>>), DuckyError>
+where
     L: Layer1d,
 {
-    todo!()
+    let mut weighted_batches = Vec::with_capacity(model.len());
+    let mut activation_batches: Vec<Array2<f64>> = Vec::with_capacity(model.len());
+
+    for layer in model.iter() {
+        let (weighted_batch, activation_batch) =
+            layer.pass_batch(activation_batches.last().unwrap_or(data), mode)?;
+
+        weighted_batches.push(weighted_batch);
+        activation_batches.push(activation_batch);
+    }
+
+    Ok((weighted_batches, activation_batches))
+}
+
+/// Propagates the gradient of the mean squared error, averaged over the rows of the batch,
+/// back through the model with `Layer1d::back_pass_batch`, taking a gradient descent step on
+/// every layer.
+///
+/// # Arguments
+///
+/// * `model`: Layers of the model, from input to output
+/// * `data`: The batch the model was passed
+/// * `weighted_batches`: Weighted inputs of each layer, as returned by `forward_pass_batch`
+/// * `activation_batches`: Outputs of each layer, as returned by `forward_pass_batch`
+/// * `target_out`: The outputs the model should have returned, one row per sample
+/// * `learning_rate`: Size of the gradient descent step
+///
+/// returns: `Result<Array2<f64>, DuckyError>` holding the gradient of the cost with respect to
+/// `data`
+///
+/// # Errors
+///
+/// * `DuckyError::ShapeMismatch`: the forward pass or `target_out` do not line up with the model
+pub fn back_propagation_batch<L>(
+    model: &[L],
+    data: &Array2<f64>,
+    weighted_batches: Vec<Array2<f64>>,
+    activation_batches: Vec<Array2<f64>>,
+    target_out: &Array2<f64>,
+    learning_rate: f64,
+) -> Result<Array2<f64>, DuckyError>
+where
+    L: Layer1d,
+{
+    check_targets(model.len(), weighted_batches.len())?;
+    check_targets(model.len(), activation_batches.len())?;
+    let output = activation_batches.last().unwrap_or(data);
+    if output.shape() != target_out.shape() {
+        return Err(DuckyError::ShapeMismatch {
+            expected: output.shape().to_vec(),
+            found: target_out.shape().to_vec(),
+        });
+    }
+
+    let mut gradient = Array2::zeros(output.raw_dim());
+    for ((mut gradient_row, output_row), target_row) in gradient
+        .rows_mut()
+        .into_iter()
+        .zip(output.rows())
+        .zip(target_out.rows())
+    {
+        gradient_row.assign(&deriv_mean_squared_error(
+            target_row.to_owned(),
+            output_row.to_owned(),
+        ));
+    }
+    gradient /= data.nrows().max(1) as f64;
+
+    for (index, layer) in model.iter().enumerate().rev() {
+        let input_batch = match index {
+            0 => data,
+            _ => &activation_batches[index - 1],
+        };
+        gradient = layer.back_pass_batch(
+            input_batch,
+            &weighted_batches[index],
+            &gradient,
+            learning_rate,
+        )?;
+    }
+
+    Ok(gradient)
+}
+
+/// Classifier built from a stack of layers and trained with `train_batches`-style gradient
+/// descent on one-hot encoded labels, one row at a time unless `with_batch_size` sets a larger
+/// batch. The predicted class is the output node with the highest activation, so the last
+/// layer needs one node per class.
+///
+/// The layers are `Dense1d` by default. Use `Box<dyn Layer1d>` to mix in other layers such as
+/// `Dropout`, which is only active while fitting, or `BatchNorm1d` and `LayerNorm`.
+///
+/// A fitted classifier can be saved with `traits::Persist` when its `Dense1d` layers have
+/// named activations, the state of every layer included.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::layers::*;
+/// use ducky_learn::train::DenseClassifier;
+/// use ndarray::{arr1, arr2};
+///
+/// let layers = vec![Dense1d::from(
+///     |x| x,
+///     |x| x.map(|_| 1f64),
+///     arr2(&[[0., 0.], [0., 0.]]),
+///     arr1(&[0., 0.]),
+/// )];
+/// let x = arr2(&[[1., 0.], [0., 1.]]);
+/// let y = vec!["left", "right"];
+///
+/// let model = DenseClassifier::new(layers, 50, 0.1).fit(&x, &y).unwrap();
+///
+/// assert_eq!(model.predict(&x).unwrap(), y);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DenseClassifier<State = Unfit, L = String, Layer = Dense1d> {
+    pub layers: Vec<Layer>,
+    pub epochs: usize,
+    pub learning_rate: f64,
+    pub batch_size: usize,
+    pub classes: Vec<L>,
+    state: std::marker::PhantomData<State>,
+}
+
+impl<Layer: Layer1d> DenseClassifier<Unfit, String, Layer> {
+    /// Creates a new, untrained classifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `layers`: Layers of the network, from input to output
+    /// * `epochs`: Number of passes over the training data
+    /// * `learning_rate`: Size of each gradient descent step
+    ///
+    /// returns: `DenseClassifier<Unfit, String, Layer>`
+    pub fn new(layers: Vec<Layer>, epochs: usize, learning_rate: f64) -> Self {
+        DenseClassifier {
+            layers,
+            epochs,
+            learning_rate,
+            batch_size: 1,
+            classes: Vec::new(),
+            state: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the number of rows in each gradient descent step. Batches of more than one row are
+    /// passed through the layers together, so `BatchNorm1d` trains on batch statistics.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_size`: Number of rows in each step, one by default
+    ///
+    /// returns: `Result<DenseClassifier<Unfit, String, Layer>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::InvalidParameter`: `batch_size` is zero
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ducky_learn::layers::*;
+    /// use ducky_learn::train::DenseClassifier;
+    /// use ndarray::{arr1, arr2};
+    ///
+    /// let layers: Vec<Box<dyn Layer1d>> = vec![
+    ///     Box::new(BatchNorm1d::new(2, 0.1, 1e-5).unwrap()),
+    ///     Box::new(Dense1d::from(|x| x, |x| x.map(|_| 1f64), arr2(&[[0., 0.], [0., 0.]]), arr1(&[0., 0.]))),
+    /// ];
+    /// let x = arr2(&[[1., 0.], [0., 1.], [2., 0.], [0., 2.]]);
+    /// let y = vec!["left", "right", "left", "right"];
+    ///
+    /// let model = DenseClassifier::new(layers, 50, 0.1)
+    ///     .with_batch_size(2)
+    ///     .unwrap()
+    ///     .fit(&x, &y)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(model.predict(&x).unwrap(), y);
+    /// ```
+    pub fn with_batch_size(mut self, batch_size: usize) -> Result<Self, DuckyError> {
+        if batch_size == 0 {
+            return Err(DuckyError::InvalidParameter(
+                "batch_size has to be positive".to_string(),
+            ));
+        }
+        self.batch_size = batch_size;
+
+        Ok(self)
+    }
+
+    /// Trains the network on the data, one-hot encoding the labels in the order they first
+    /// appear.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Training data of shape( samples, features )
+    /// * `y`: Label of each sample
+    ///
+    /// returns: `Result<DenseClassifier<Fit, L, Layer>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::EmptyInput`: `x` has no rows
+    /// * `DuckyError::ShapeMismatch`: `y` does not line up with `x`, or the network does not
+    ///   line up with the features or the number of classes
+    /// * `DuckyError::NonFinite`: `x` contains a NaN or infinite value
+    /// * `DuckyError::InvalidParameter`: `learning_rate` is not positive
+    pub fn fit<S: Data<Elem = f64>, L: Hash + Eq + Clone>(
+        self,
+        x: &ArrayBase<S, Ix2>,
+        y: &[L],
+    ) -> Result<DenseClassifier<Fit, L, Layer>, DuckyError> {
+        x.validate()?;
+        check_targets(x.nrows(), y.len())?;
+
+        let classes = unique_labels(y);
+        let class_index: HashMap<&L, usize> = classes
+            .iter()
+            .enumerate()
+            .map(|(index, class)| (class, index))
+            .collect();
+        let mut targets = Array2::zeros((y.len(), classes.len()));
+        for (row, label) in y.iter().enumerate() {
+            targets[[row, class_index[label]]] = 1.0;
+        }
+
+        for _ in 0..self.epochs {
+            train_epoch(
+                &self.layers,
+                &x.view(),
+                &targets.view(),
+                self.batch_size,
+                self.learning_rate,
+            )?;
+        }
+
+        Ok(DenseClassifier {
+            layers: self.layers,
+            epochs: self.epochs,
+            learning_rate: self.learning_rate,
+            batch_size: self.batch_size,
+            classes,
+            state: std::marker::PhantomData,
+        })
+    }
+}
+
+impl<L: Clone, Layer: Layer1d> DenseClassifier<Fit, L, Layer> {
+    /// Predicts the class of every row of `x`.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Data of shape( samples, features )
+    ///
+    /// returns: `Result<Vec<L>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::ShapeMismatch`: `x` does not line up with the network
+    /// * `DuckyError::NonFinite`: `x` contains a NaN or infinite value
+    pub fn predict<S: Data<Elem = f64>>(
+        &self,
+        x: &ArrayBase<S, Ix2>,
+    ) -> Result<Vec<L>, DuckyError> {
+        x.rows()
+            .into_iter()
+            .map(|row| {
+                let (_, activation_vec) = forward_pass(&self.layers, row.to_owned(), Mode::Eval)?;
+                let output = activation_vec
+                    .last()
+                    .cloned()
+                    .unwrap_or_else(|| row.to_owned());
+                check_targets(self.classes.len(), output.len())?;
+                let best = output.iter().enumerate().fold(0, |best, (index, value)| {
+                    if *value > output[best] {
+                        index
+                    } else {
+                        best
+                    }
+                });
+                Ok(self.classes[best].clone())
+            })
+            .collect()
+    }
+}
+
+impl<S: Data<Elem = f64>, L: Hash + Eq + Clone, Layer: Layer1d> traits::Fit<ArrayBase<S, Ix2>, [L]>
+    for DenseClassifier<Unfit, String, Layer>
+{
+    type Fitted = DenseClassifier<Fit, L, Layer>;
+
+    fn fit(self, x: &ArrayBase<S, Ix2>, y: &[L]) -> Result<Self::Fitted, DuckyError> {
+        DenseClassifier::fit(self, x, y)
+    }
+}
+
+impl<S: Data<Elem = f64>, L: Clone, Layer: Layer1d> traits::Predict<ArrayBase<S, Ix2>>
+    for DenseClassifier<Fit, L, Layer>
+{
+    type Output = Vec<L>;
+
+    fn predict(&self, x: &ArrayBase<S, Ix2>) -> Result<Vec<L>, DuckyError> {
+        DenseClassifier::predict(self, x)
+    }
 }
 
 #[cfg(test)]
 mod train_tests {
     use super::*;
-    use crate::layers::*;
     use crate::activations::*;
-    use ndarray::arr1;
+    use crate::layers::*;
+    use crate::regularizers::Regularizer;
+    use ndarray::{arr1, arr2};
 
     #[test]
     fn forwards_pass_1() {
@@ -61,7 +652,8 @@ mod train_tests {
             Dense1d::new(5, 10, softmax_1d, deriv_relu_1d),
         ];
 
-        let (weights_bias_vec, activation_vec) = forward_pass(&model, arr1(&[1.]));
+        let (weights_bias_vec, activation_vec) =
+            forward_pass(&model, arr1(&[1.]), Mode::Train).unwrap();
 
         assert_eq!(weights_bias_vec.len(), 3);
         assert_eq!(activation_vec.len(), 3)
@@ -76,7 +668,7 @@ mod train_tests {
         ];
 
         let (weights_bias_vec, activation_vec) =
-            forward_pass(&model, arr1(&[1., 2., 0.2, 1., 0.32]));
+            forward_pass(&model, arr1(&[1., 2., 0.2, 1., 0.32]), Mode::Train).unwrap();
 
         assert_eq!(weights_bias_vec.first().unwrap().shape(), [5]);
         assert_eq!(activation_vec.first().unwrap().shape(), [5])
@@ -91,14 +683,345 @@ mod train_tests {
         ];
 
         let (weights_bias_vec, activation_vec) =
-            forward_pass(&model, arr1(&[1., 2., 0.2, 1., 0.32]));
+            forward_pass(&model, arr1(&[1., 2., 0.2, 1., 0.32]), Mode::Train).unwrap();
 
         assert_eq!(weights_bias_vec.last().unwrap().shape(), [10]);
         assert_eq!(activation_vec.last().unwrap().shape(), [10])
     }
 
+    fn identity_layer(weights: Array2<f64>, bias: Array1<f64>) -> Dense1d {
+        Dense1d::from(|x| x, |x| x.map(|_| 1f64), weights, bias)
+    }
+
+    #[test]
+    fn back_propagation_1() {
+        let model = vec![
+            identity_layer(arr2(&[[1., 1.]]), arr1(&[0.])),
+            identity_layer(arr2(&[[2.]]), arr1(&[0.])),
+        ];
+        let data = arr1(&[1., 1.]);
+        let (weights_bias_vec, activation_vec) =
+            forward_pass(&model, data.clone(), Mode::Train).unwrap();
+
+        // Output 4, so the cost gradient is 2 * (4 - 3) = 2
+        let input_gradient = back_propagation(
+            &model,
+            data.clone(),
+            weights_bias_vec,
+            activation_vec,
+            arr1(&[3.]),
+            0.1,
+        )
+        .unwrap();
+
+        assert_eq!(input_gradient, arr1(&[4., 4.]));
+
+        // Second layer: w = 2 - 0.1 * 2 * 2, b = -0.1 * 2
+        // First layer sees gradient 2 * 2 = 4: w = 1 - 0.1 * 4, b = -0.1 * 4
+        let (_, activation_vec) = forward_pass(&model, data, Mode::Train).unwrap();
+        let hidden = 0.6 + 0.6 - 0.4;
+        assert!((activation_vec[1][0] - (hidden * 1.6 - 0.2)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn back_propagation_diff_size() {
+        let model = vec![identity_layer(arr2(&[[1.]]), arr1(&[0.]))];
+        let (weights_bias_vec, activation_vec) =
+            forward_pass(&model, arr1(&[1.]), Mode::Train).unwrap();
+
+        assert_eq!(
+            back_propagation(
+                &model,
+                arr1(&[1.]),
+                weights_bias_vec,
+                activation_vec,
+                arr1(&[1., 2.]),
+                0.1
+            ),
+            Err(DuckyError::ShapeMismatch {
+                expected: vec![1],
+                found: vec![2]
+            })
+        );
+    }
+
+    #[test]
+    fn train_reduces_cost() {
+        let model = vec![
+            Dense1d::from(
+                relu_1d,
+                deriv_relu_1d,
+                arr2(&[[0.5, 0.1], [0.2, 0.4]]),
+                arr1(&[0.1, 0.1]),
+            ),
+            identity_layer(arr2(&[[0.3, 0.3]]), arr1(&[0.])),
+        ];
+        let data = arr2(&[[0., 1.], [1., 0.], [1., 1.], [0., 0.]]);
+        let labels = arr2(&[[1.], [1.], [2.], [0.]]);
+
+        let costs = train(
+            &model,
+            data.clone(),
+            labels.clone(),
+            data,
+            labels,
+            100,
+            0.05,
+        )
+        .unwrap();
+
+        assert_eq!(costs.len(), 100);
+        assert!(costs[99] < costs[0]);
+        assert!(costs[99] < 0.05);
+    }
+
+    #[test]
+    fn train_invalid_learning_rate() {
+        let model = vec![identity_layer(arr2(&[[1.]]), arr1(&[0.]))];
+        let data = arr2(&[[1.]]);
+
+        assert!(matches!(
+            train(
+                &model,
+                data.clone(),
+                data.clone(),
+                data.clone(),
+                data,
+                1,
+                0.0
+            ),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn dense_classifier_fit_predict() {
+        let layers = vec![
+            Dense1d::from(
+                relu_1d,
+                deriv_relu_1d,
+                arr2(&[[0.5, -0.2], [-0.3, 0.6], [0.2, 0.2]]),
+                arr1(&[0.1, 0.1, 0.1]),
+            ),
+            identity_layer(arr2(&[[0.1, -0.1, 0.2], [-0.2, 0.1, 0.1]]), arr1(&[0., 0.])),
+        ];
+        let x = arr2(&[[2., 0.], [1.8, 0.3], [0., 2.], [0.2, 1.7]]);
+        let y = vec![1u8, 1, 7, 7];
+
+        let model = DenseClassifier::new(layers, 200, 0.05).fit(&x, &y).unwrap();
+
+        assert_eq!(model.classes, vec![1, 7]);
+        assert_eq!(model.predict(&x).unwrap(), y);
+    }
+
+    #[test]
+    fn dense_classifier_with_dropout() {
+        let layers: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(Dense1d::from(
+                relu_1d,
+                deriv_relu_1d,
+                arr2(&[[0.5, -0.2], [-0.3, 0.6], [0.2, 0.2]]),
+                arr1(&[0.1, 0.1, 0.1]),
+            )),
+            Box::new(Dropout::new(0.2, Some(42)).unwrap()),
+            Box::new(identity_layer(
+                arr2(&[[0.1, -0.1, 0.2], [-0.2, 0.1, 0.1]]),
+                arr1(&[0., 0.]),
+            )),
+        ];
+        let x = arr2(&[[2., 0.], [1.8, 0.3], [0., 2.], [0.2, 1.7]]);
+        let y = vec!["a", "a", "b", "b"];
+
+        let model = DenseClassifier::new(layers, 300, 0.05).fit(&x, &y).unwrap();
+
+        // Dropout is switched off when predicting, so predictions are repeatable
+        assert_eq!(model.predict(&x).unwrap(), y);
+        assert_eq!(model.predict(&x).unwrap(), model.predict(&x).unwrap());
+    }
+
+    #[test]
+    fn classifier_write_read() {
+        use crate::activations::Activation;
+        use crate::traits::Persist;
+
+        let layers: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(Dense1d::new_named(2, 3, Activation::Relu, Some(1))),
+            Box::new(BatchNorm1d::new(3, 0.1, 1e-5).unwrap()),
+            Box::new(Dense1d::new_named(3, 2, Activation::Identity, Some(2))),
+        ];
+        let x = arr2(&[[2., 0.], [1.8, 0.3], [0., 2.], [0.2, 1.7]]);
+        let y = vec![
+            "a".to_string(),
+            "a".to_string(),
+            "b".to_string(),
+            "b".to_string(),
+        ];
+        let model = DenseClassifier::new(layers, 20, 0.05).fit(&x, &y).unwrap();
+
+        let mut saved = Vec::new();
+        model.write_to(&mut saved).unwrap();
+        let loaded =
+            DenseClassifier::<Fit, String, Box<dyn Layer1d>>::read_from(saved.as_slice()).unwrap();
+
+        assert_eq!(loaded.classes, model.classes);
+        assert_eq!(loaded.predict(&x).unwrap(), model.predict(&x).unwrap());
+        for row in x.rows() {
+            assert_eq!(
+                forward_pass(&loaded.layers, row.to_owned(), Mode::Eval).unwrap(),
+                forward_pass(&model.layers, row.to_owned(), Mode::Eval).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn train_with_normalization() {
+        let model: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(Dense1d::from(
+                relu_1d,
+                deriv_relu_1d,
+                arr2(&[[0.5, 0.1], [0.2, 0.4], [-0.3, 0.6]]),
+                arr1(&[0.1, 0.1, 0.1]),
+            )),
+            Box::new(BatchNorm1d::new(3, 0.1, 1e-5).unwrap()),
+            Box::new(identity_layer(
+                arr2(&[[0.3, 0.3, 0.1], [0.1, -0.2, 0.4]]),
+                arr1(&[0., 0.]),
+            )),
+            Box::new(LayerNorm::new(2, 1e-5).unwrap()),
+            Box::new(identity_layer(arr2(&[[0.5, -0.5]]), arr1(&[0.]))),
+        ];
+        let data = arr2(&[[0., 1.], [1., 0.], [1., 1.], [0., 0.]]);
+        let labels = arr2(&[[1.], [1.], [2.], [0.]]);
+
+        let costs = train(
+            &model,
+            data.clone(),
+            labels.clone(),
+            data,
+            labels,
+            200,
+            0.02,
+        )
+        .unwrap();
+
+        assert!(costs.iter().all(|cost| cost.is_finite()));
+        assert!(costs[199] < costs[0]);
+    }
+
+    #[test]
+    fn train_batches_normalizes_batches() {
+        let model: Vec<Box<dyn Layer1d>> = vec![
+            Box::new(BatchNorm1d::new(1, 1., 1e-5).unwrap()),
+            Box::new(identity_layer(arr2(&[[0.5]]), arr1(&[0.]))),
+        ];
+        let data = arr2(&[[1.], [2.], [3.], [6.]]);
+        let labels = arr2(&[[0.], [1.], [2.], [3.]]);
+
+        let costs = train_batches(
+            &model,
+            data.clone(),
+            labels.clone(),
+            data.clone(),
+            labels.clone(),
+            1,
+            4,
+            0.1,
+        )
+        .unwrap();
+
+        // With a momentum of one the running statistics are those of the only batch
+        let (weights_bias_vec, _) = forward_pass(&model, arr1(&[3.]), Mode::Eval).unwrap();
+        assert!(weights_bias_vec[0][0].abs() < 1e-9);
+        assert!(costs[0].is_finite());
+        assert!(matches!(
+            train_batches(
+                &model,
+                data.clone(),
+                labels.clone(),
+                data,
+                labels,
+                1,
+                0,
+                0.1
+            ),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn dense_classifier_batch_size() {
+        let layers = vec![identity_layer(arr2(&[[0., 0.], [0., 0.]]), arr1(&[0., 0.]))];
+        let x = arr2(&[[1., 0.], [0., 1.], [2., 0.], [0., 2.]]);
+        let y = vec!["left", "right", "left", "right"];
+
+        let model = DenseClassifier::new(layers.clone(), 50, 0.1)
+            .with_batch_size(3)
+            .unwrap()
+            .fit(&x, &y)
+            .unwrap();
+
+        assert_eq!(model.batch_size, 3);
+        assert_eq!(model.predict(&x).unwrap(), y);
+        assert!(matches!(
+            DenseClassifier::new(layers, 50, 0.1).with_batch_size(0),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn train_auto_dense() {
+        let model = vec![
+            Dense1d::new_auto(2, 4, |z| z.tanh(), Some(1)),
+            Dense1d::new_auto(4, 1, |z| z.sigmoid(), Some(2)),
+        ];
+        let data = arr2(&[[0., 1.], [1., 0.], [1., 1.], [0., 0.]]);
+        let labels = arr2(&[[1.], [1.], [0.], [0.]]);
+
+        let costs = train(&model, data.clone(), labels.clone(), data, labels, 500, 0.5).unwrap();
+
+        assert!(costs.iter().all(|cost| cost.is_finite()));
+        assert!(costs[499] < costs[0] / 2.);
+    }
+
+    #[test]
+    fn train_regularized() {
+        let model = vec![identity_layer(arr2(&[[0., 0.]]), arr1(&[0.]))
+            .with_regularizers(Some(Regularizer::L2(0.5)), None)
+            .unwrap()];
+        let data = arr2(&[[1., 0.], [0., 1.]]);
+        let labels = arr2(&[[2.], [2.]]);
+
+        let costs = train(
+            &model,
+            data.clone(),
+            labels.clone(),
+            data,
+            labels,
+            200,
+            0.05,
+        )
+        .unwrap();
+
+        // The penalty keeps the weights from reaching the targets, and is part of the cost
+        let weights = model[0].weights();
+        let bias = model[0].bias()[0];
+        let error = (2. - weights[[0, 0]] - bias).powi(2) + (2. - weights[[0, 1]] - bias).powi(2);
+        assert!(weights[[0, 0]] < 2. - bias);
+        assert!((costs[199] - error / 2. - model[0].penalty()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dense_classifier_wrong_output_size() {
+        let layers = vec![identity_layer(arr2(&[[1., 1.]]), arr1(&[0.]))];
+        let x = arr2(&[[1., 0.], [0., 1.]]);
+
+        assert!(matches!(
+            DenseClassifier::new(layers, 1, 0.1).fit(&x, &["a", "b"]),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
     #[test]
-    #[should_panic]
     fn forwards_pass_4() {
         let model = vec![
             Dense1d::new(5, 3, relu_1d, deriv_relu_1d),
@@ -106,7 +1029,9 @@ mod train_tests {
             Dense1d::new(5, 10, softmax_1d, deriv_relu_1d),
         ];
 
-        let (weights_bias_vec, activation_vec) =
-            forward_pass(&model, arr1(&[1., 2., 0.2, 1., 0.32]));
+        assert!(matches!(
+            forward_pass(&model, arr1(&[1., 2., 0.2, 1., 0.32]), Mode::Train),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
     }
 }
@@ -0,0 +1,352 @@
+use super::error::DuckyError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Trait for anything that learns its parameters from data.
+///
+/// Models following the `Unfit`/`Fit` typestate pattern from `util` implement this on the
+/// `Unfit` state and return the `Fit` state, so a model can only be used for predictions once
+/// it has been fit. Transformers without a typestate, such as `CountVectorizer`, return
+/// themselves. Unsupervised models take `()` as their targets.
+///
+/// # Type parameters
+/// - `X`: The training data.
+/// - `Y`: The target values.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::error::DuckyError;
+/// use ducky_learn::naive_bayes::StdNaiveBayes;
+/// use ducky_learn::traits::{Fit, Predict};
+///
+/// fn fit_predict<M, X: ?Sized>(model: M, x: &X, y: &[String]) -> Result<Vec<String>, DuckyError>
+/// where
+///     M: Fit<X, [String]>,
+///     M::Fitted: Predict<X, Output = Vec<String>>,
+/// {
+///     model.fit(x, y)?.predict(x)
+/// }
+///
+/// let x = vec![vec![3.0, 0.0], vec![0.0, 3.0]];
+/// let y = vec!["class1".to_string(), "class2".to_string()];
+///
+/// assert_eq!(fit_predict(StdNaiveBayes::new(1.0), &x, &y), Ok(y));
+/// ```
+pub trait Fit<X: ?Sized, Y: ?Sized> {
+    /// The fitted version of the model.
+    type Fitted;
+
+    /// Fits the model to the training data.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The training data
+    /// * `y`: The target values
+    ///
+    /// returns: `Result<Self::Fitted, DuckyError>` holding the fitted model
+    ///
+    /// # Errors
+    ///
+    /// Any `DuckyError` from data the model can not be fit on.
+    fn fit(self, x: &X, y: &Y) -> Result<Self::Fitted, DuckyError>;
+}
+
+/// Trait for fitted models that predict a target for every row of some data.
+///
+/// # Type parameters
+/// - `X`: The data to predict target values for.
+pub trait Predict<X: ?Sized> {
+    /// The predicted target values.
+    type Output;
+
+    /// Predicts the target values for the given data.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The data to predict target values for
+    ///
+    /// returns: `Result<Self::Output, DuckyError>` holding the predicted target values
+    ///
+    /// # Errors
+    ///
+    /// Any `DuckyError` from data the model can not predict on.
+    fn predict(&self, x: &X) -> Result<Self::Output, DuckyError>;
+}
+
+/// Trait for fitted classifiers that predict the probability of each class.
+///
+/// # Type parameters
+/// - `X`: The data to predict probabilities for.
+pub trait PredictProba<X: ?Sized> {
+    /// The predicted probabilities.
+    type Output;
+
+    /// Predicts the probability of each class for the given data.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The data to predict probabilities for
+    ///
+    /// returns: `Result<Self::Output, DuckyError>` holding the predicted probabilities
+    ///
+    /// # Errors
+    ///
+    /// Any `DuckyError` from data the model can not predict on.
+    fn predict_proba(&self, x: &X) -> Result<Self::Output, DuckyError>;
+}
+
+/// Trait for fitted transformers that map data into a new representation.
+///
+/// # Type parameters
+/// - `X`: The data to transform.
+pub trait Transform<X: ?Sized> {
+    /// The transformed data.
+    type Output;
+
+    /// Transforms the given data.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: The data to transform
+    ///
+    /// returns: `Result<Self::Output, DuckyError>` holding the transformed data
+    ///
+    /// # Errors
+    ///
+    /// Any `DuckyError` from data the transformer can not transform.
+    fn transform(&self, x: &X) -> Result<Self::Output, DuckyError>;
+}
+
+/// Trait for models that can be saved and loaded.
+///
+/// Implemented for every model, transformer and `Pipeline` that can be serialized with serde,
+/// so a fitted model can be saved once and loaded later without refitting. Models are stored
+/// in bincode's compact binary format, which keeps every `f64` exactly, NaN included.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::naive_bayes::StdNaiveBayes;
+/// use ducky_learn::traits::Persist;
+/// use ducky_learn::util::Fit;
+///
+/// let x = vec![vec![3.0, 0.0], vec![0.0, 3.0]];
+/// let y = vec!["class1".to_string(), "class2".to_string()];
+/// let model = StdNaiveBayes::new(1.0).fit(&x, &y).unwrap();
+///
+/// let mut saved = Vec::new();
+/// model.write_to(&mut saved).unwrap();
+/// let loaded = StdNaiveBayes::<Fit, String>::read_from(saved.as_slice()).unwrap();
+///
+/// assert_eq!(loaded.predict(&x).unwrap(), y);
+/// ```
+pub trait Persist: Sized {
+    /// Writes the model in bincode's binary format.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: Where to write the model to
+    ///
+    /// returns: `Result<(), DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::Persistence`: the model can not be serialized or written
+    fn write_to<W: Write>(&self, writer: W) -> Result<(), DuckyError>;
+
+    /// Reads a model written by `write_to`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader`: Where to read the model from
+    ///
+    /// returns: `Result<Self, DuckyError>` holding the model
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::Persistence`: the model can not be read or is not a valid model of this
+    ///   type
+    fn read_from<R: Read>(reader: R) -> Result<Self, DuckyError>;
+
+    /// Saves the model to a file, replacing the file if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Path of the file to save to
+    ///
+    /// returns: `Result<(), DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::Persistence`: the model can not be serialized or the file can not be
+    ///   written
+    fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), DuckyError> {
+        let file =
+            File::create(path).map_err(|error| DuckyError::Persistence(error.to_string()))?;
+        let mut writer = BufWriter::new(file);
+        self.write_to(&mut writer)?;
+        writer
+            .flush()
+            .map_err(|error| DuckyError::Persistence(error.to_string()))
+    }
+
+    /// Loads a model saved with `save`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Path of the file to load from
+    ///
+    /// returns: `Result<Self, DuckyError>` holding the model
+    ///
+    /// # Errors
+    ///
+    /// * `DuckyError::Persistence`: the file can not be read or does not hold a valid model of
+    ///   this type
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self, DuckyError> {
+        let file = File::open(path).map_err(|error| DuckyError::Persistence(error.to_string()))?;
+        Self::read_from(BufReader::new(file))
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Persist for T {
+    fn write_to<W: Write>(&self, writer: W) -> Result<(), DuckyError> {
+        bincode::serialize_into(writer, self)
+            .map_err(|error| DuckyError::Persistence(error.to_string()))
+    }
+
+    fn read_from<R: Read>(reader: R) -> Result<Self, DuckyError> {
+        bincode::deserialize_from(reader)
+            .map_err(|error| DuckyError::Persistence(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod traits_tests {
+    use super::*;
+    use crate::feature_extraction::{CountVectorizer, HashingVectorizer, TfidfVectorizer};
+    use crate::naive_bayes::{GaussianNaiveBayes, StdNaiveBayes};
+
+    fn text_pipeline<V, M>(
+        vectorizer: V,
+        model: M,
+        documents: &[String],
+        y: &[String],
+    ) -> Vec<String>
+    where
+        V: Fit<[String], ()>,
+        V::Fitted: Transform<[String]>,
+        M: Fit<<V::Fitted as Transform<[String]>>::Output, [String]>,
+        M::Fitted: Predict<<V::Fitted as Transform<[String]>>::Output, Output = Vec<String>>,
+    {
+        let vectorizer = vectorizer.fit(documents, &()).unwrap();
+        let x = vectorizer.transform(documents).unwrap();
+        model.fit(&x, y).unwrap().predict(&x).unwrap()
+    }
+
+    fn documents() -> (Vec<String>, Vec<String>) {
+        let documents = vec![
+            "duck duck goose".to_string(),
+            "duck duck duck".to_string(),
+            "goose goose goose".to_string(),
+            "goose goose duck".to_string(),
+        ];
+        let y = vec![
+            "duck".to_string(),
+            "duck".to_string(),
+            "goose".to_string(),
+            "goose".to_string(),
+        ];
+        (documents, y)
+    }
+
+    #[test]
+    fn generic_pipeline_std() {
+        let (documents, y) = documents();
+
+        assert_eq!(
+            text_pipeline(
+                CountVectorizer::new(),
+                StdNaiveBayes::new(1.0),
+                &documents,
+                &y
+            ),
+            y
+        );
+        assert_eq!(
+            text_pipeline(
+                TfidfVectorizer::default(),
+                StdNaiveBayes::new(1.0),
+                &documents,
+                &y
+            ),
+            y
+        );
+        assert_eq!(
+            text_pipeline(
+                HashingVectorizer::new(64, false, None).unwrap(),
+                StdNaiveBayes::new(1.0),
+                &documents,
+                &y
+            ),
+            y
+        );
+    }
+
+    #[test]
+    fn generic_pipeline_gaussian() {
+        let (documents, y) = documents();
+
+        assert_eq!(
+            text_pipeline(
+                CountVectorizer::new(),
+                GaussianNaiveBayes::new(),
+                &documents,
+                &y
+            ),
+            y
+        );
+    }
+
+    #[test]
+    fn generic_predict_proba() {
+        fn row_sums<M: PredictProba<[Vec<f64>], Output = Vec<Vec<f64>>>>(
+            model: &M,
+            x: &[Vec<f64>],
+        ) -> Vec<f64> {
+            model
+                .predict_proba(x)
+                .unwrap()
+                .iter()
+                .map(|row| row.iter().sum())
+                .collect()
+        }
+
+        let x = vec![
+            vec![2.0, 1.0],
+            vec![3.0, 2.5],
+            vec![2.5, 1.5],
+            vec![4.0, 3.0],
+        ];
+        let y = vec![
+            "class1".to_string(),
+            "class1".to_string(),
+            "class2".to_string(),
+            "class2".to_string(),
+        ];
+
+        let std_model = Fit::fit(StdNaiveBayes::new(1.0), x.as_slice(), y.as_slice()).unwrap();
+        let gaussian_model =
+            Fit::fit(GaussianNaiveBayes::new(), x.as_slice(), y.as_slice()).unwrap();
+
+        for sum in row_sums(&std_model, &x)
+            .into_iter()
+            .chain(row_sums(&gaussian_model, &x))
+        {
+            assert!((sum - 1.0).abs() < 1e-9);
+        }
+    }
+}
@@ -1,16 +1,37 @@
 extern crate ndarray;
 
+use super::error::{check_finite, DuckyError};
+use super::traits;
 use ndarray::Array2;
-use std::error::Error;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
 
 /// Marker struct indicating a model that has not been fit.
+#[derive(Debug, Clone, Copy)]
 pub struct Unfit;
 
 /// Marker struct indicating a model that has been fit.
+#[derive(Debug, Clone, Copy)]
 pub struct Fit;
 
+/// Random number generator seeded with `seed`, or from the operating system if there is none.
+pub(crate) fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
 /// Generates a one-hot encoding for a vector of integers.
 ///
+/// The width of the encoding is one more than the largest integer in `input_array`, so
+/// separate batches can come out with different widths. Use a fitted `OneHotEncoder` when
+/// they have to line up.
+///
 /// # Arguments
 ///
 /// * `input_array`: List of integers to be encoded. Each integer should be less than or equal to the maximum integer in the array.
@@ -19,7 +40,7 @@ pub struct Fit;
 ///
 /// # Errors
 ///
-/// Returns an error if the `input_array` is empty.
+/// Returns `DuckyError::EmptyInput` if the `input_array` is empty.
 ///
 /// # Examples
 ///
@@ -36,13 +57,11 @@ pub struct Fit;
 ///
 /// assert_eq!(one_hot_encoding_vec(input_array).unwrap(), output_array);
 /// ```
-pub fn one_hot_encoding_vec<T: AsRef<[usize]>>(
-    input_array: T,
-) -> Result<Array2<f64>, Box<dyn Error>> {
+pub fn one_hot_encoding_vec<T: AsRef<[usize]>>(input_array: T) -> Result<Array2<f64>, DuckyError> {
     let input_array = input_array.as_ref();
     let max_val = match input_array.iter().max() {
         Some(&max) => max + 1,
-        None => return Err("Empty input array".into()),
+        None => return Err(DuckyError::EmptyInput),
     };
 
     let mut encoding_array: Vec<Vec<f64>> = Vec::with_capacity(input_array.len());
@@ -57,7 +76,464 @@ pub fn one_hot_encoding_vec<T: AsRef<[usize]>>(
     let n_row = input_array.len();
     let n_col = max_val;
 
-    Array2::from_shape_vec((n_row, n_col), data).map_err(|err| err.into())
+    Ok(Array2::from_shape_vec((n_row, n_col), data).unwrap())
+}
+
+/// Unique labels of `y`, in order of first appearance.
+pub(crate) fn unique_labels<L: Hash + Eq + Clone>(y: &[L]) -> Vec<L> {
+    let mut seen: HashSet<&L> = HashSet::new();
+    y.iter()
+        .filter(|label| seen.insert(*label))
+        .cloned()
+        .collect()
+}
+
+/// Index of every label in `labels`, the first one wins duplicates.
+fn label_index<L: Hash + Eq + Clone>(labels: &[L]) -> HashMap<L, usize> {
+    let mut index = HashMap::with_capacity(labels.len());
+    for (i, label) in labels.iter().enumerate() {
+        index.entry(label.clone()).or_insert(i);
+    }
+    index
+}
+
+/// Encodes labels of any hashable type as integers `0..n_classes`.
+///
+/// # Fields
+/// - `classes`: The unique labels seen during fit, in order of first appearance. A label is
+///   encoded as its index in this list.
+///
+/// # Type parameters
+/// - `State`: Indicates whether the encoder has been fit. Can either be `Fit` or `Unfit`.
+/// - `L`: The type of the labels, anything `Hash + Eq + Clone`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::util::LabelEncoder;
+///
+/// let y = vec!["cat", "dog", "cat", "bird"];
+///
+/// let encoder = LabelEncoder::new().fit(&y).unwrap();
+///
+/// assert_eq!(encoder.classes, vec!["cat", "dog", "bird"]);
+/// assert_eq!(encoder.transform(&["bird", "cat"]).unwrap(), vec![2, 0]);
+/// assert_eq!(encoder.inverse_transform(&[1, 0]).unwrap(), vec!["dog", "cat"]);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "L: Deserialize<'de> + Hash + Eq"))]
+pub struct LabelEncoder<State = Unfit, L = String> {
+    pub classes: Vec<L>,
+
+    class_index: HashMap<L, usize>,
+    state: std::marker::PhantomData<State>,
+}
+
+impl<L> Default for LabelEncoder<Unfit, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L> LabelEncoder<Unfit, L> {
+    /// Creates a new, unfitted `LabelEncoder`.
+    pub fn new() -> Self {
+        Self {
+            classes: Vec::new(),
+            class_index: HashMap::new(),
+            state: Default::default(),
+        }
+    }
+}
+
+impl<L: Hash + Eq + Clone + Debug> LabelEncoder<Unfit, L> {
+    /// Learns the unique labels of `y`.
+    ///
+    /// # Arguments
+    ///
+    /// * `y`: Labels to learn the classes from
+    ///
+    /// returns: `Result<LabelEncoder<Fit, L>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Returns `DuckyError::EmptyInput` if `y` is empty.
+    pub fn fit(self, y: &[L]) -> Result<LabelEncoder<Fit, L>, DuckyError> {
+        if y.is_empty() {
+            return Err(DuckyError::EmptyInput);
+        }
+
+        let classes = unique_labels(y);
+        Ok(LabelEncoder {
+            class_index: label_index(&classes),
+            classes,
+            state: std::marker::PhantomData::<Fit>,
+        })
+    }
+
+    /// Learns the unique labels of `y` and encodes them.
+    ///
+    /// # Arguments
+    ///
+    /// * `y`: Labels to learn the classes from and encode
+    ///
+    /// returns: `Result<(LabelEncoder<Fit, L>, Vec<usize>), DuckyError>`
+    pub fn fit_transform(self, y: &[L]) -> Result<(LabelEncoder<Fit, L>, Vec<usize>), DuckyError> {
+        let encoder = self.fit(y)?;
+        let encoded = encoder.transform(y)?;
+        Ok((encoder, encoded))
+    }
+}
+
+impl<L: Hash + Eq + Clone + Debug> LabelEncoder<Fit, L> {
+    /// Encodes each label as its index in `classes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `y`: Labels to encode
+    ///
+    /// returns: `Result<Vec<usize>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Returns `DuckyError::UnknownClass` if a label was not seen during fit.
+    pub fn transform(&self, y: &[L]) -> Result<Vec<usize>, DuckyError> {
+        y.iter()
+            .map(|label| {
+                self.class_index
+                    .get(label)
+                    .copied()
+                    .ok_or_else(|| DuckyError::UnknownClass(format!("{:?}", label)))
+            })
+            .collect()
+    }
+
+    /// Turns encoded labels back into the original labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoded`: Indices into `classes`
+    ///
+    /// returns: `Result<Vec<L>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Returns `DuckyError::UnknownClass` if an index is not less than the number of classes.
+    pub fn inverse_transform(&self, encoded: &[usize]) -> Result<Vec<L>, DuckyError> {
+        encoded
+            .iter()
+            .map(|&index| {
+                self.classes
+                    .get(index)
+                    .cloned()
+                    .ok_or_else(|| DuckyError::UnknownClass(index.to_string()))
+            })
+            .collect()
+    }
+}
+
+impl<L: Hash + Eq + Clone + Debug> traits::Fit<[L], ()> for LabelEncoder<Unfit, L> {
+    type Fitted = LabelEncoder<Fit, L>;
+
+    fn fit(self, y: &[L], _: &()) -> Result<LabelEncoder<Fit, L>, DuckyError> {
+        self.fit(y)
+    }
+}
+
+impl<L: Hash + Eq + Clone + Debug> traits::Transform<[L]> for LabelEncoder<Fit, L> {
+    type Output = Vec<usize>;
+
+    fn transform(&self, y: &[L]) -> Result<Vec<usize>, DuckyError> {
+        self.transform(y)
+    }
+}
+
+/// What a `OneHotEncoder` does with a category it did not see during fit.
+///
+/// # Variants
+/// - `Error`: Return `DuckyError::UnknownClass`.
+/// - `Ignore`: Encode the category as a row of zeros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandleUnknown {
+    Error,
+    Ignore,
+}
+
+/// Encodes categories of any hashable type as one-hot rows of an `Array2<f64>`.
+///
+/// Unlike `one_hot_encoding_vec`, the width of the encoding is fixed by the categories learned
+/// during fit (or given up front), so train and test batches always line up.
+///
+/// # Fields
+/// - `categories`: The categories, column `i` of the encoding is `categories[i]`. Learned during
+///   fit in order of first appearance unless given with `with_categories`.
+/// - `handle_unknown`: What to do with categories not in `categories`.
+/// - `drop_first`: Drops the column of the first category, which is then encoded as a row of zeros.
+///   It cannot be combined with `HandleUnknown::Ignore`, as unknown categories would then be
+///   encoded the same as the first category.
+///
+/// # Type parameters
+/// - `State`: Indicates whether the encoder has been fit. Can either be `Fit` or `Unfit`.
+/// - `L`: The type of the categories, anything `Hash + Eq + Clone`.
+///
+/// # Examples
+///
+/// ```
+/// use ducky_learn::util::{HandleUnknown, OneHotEncoder};
+/// use ndarray::array;
+///
+/// let encoder = OneHotEncoder::new(HandleUnknown::Ignore, false)
+///     .unwrap()
+///     .fit(&["red", "green", "blue"])
+///     .unwrap();
+///
+/// let encoded = encoder.transform(&["blue", "red", "purple"]).unwrap();
+///
+/// assert_eq!(encoded, array![[0., 0., 1.], [1., 0., 0.], [0., 0., 0.]]);
+/// assert_eq!(
+///     encoder.inverse_transform(&encoded.slice(ndarray::s![..2, ..]).to_owned()).unwrap(),
+///     vec!["blue", "red"]
+/// );
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "L: Deserialize<'de> + Hash + Eq"))]
+pub struct OneHotEncoder<State = Unfit, L = String> {
+    pub categories: Vec<L>,
+    pub handle_unknown: HandleUnknown,
+    pub drop_first: bool,
+
+    category_index: HashMap<L, usize>,
+    state: std::marker::PhantomData<State>,
+}
+
+impl<L> Default for OneHotEncoder<Unfit, L> {
+    fn default() -> Self {
+        Self {
+            categories: Vec::new(),
+            handle_unknown: HandleUnknown::Error,
+            drop_first: false,
+            category_index: HashMap::new(),
+            state: Default::default(),
+        }
+    }
+}
+
+/// Checks that `drop_first` and `handle_unknown` do not both encode categories as a row of
+/// zeros, which `inverse_transform` could not tell apart.
+fn check_drop_first(handle_unknown: HandleUnknown, drop_first: bool) -> Result<(), DuckyError> {
+    if drop_first && handle_unknown == HandleUnknown::Ignore {
+        return Err(DuckyError::InvalidParameter(
+            "drop_first cannot be combined with HandleUnknown::Ignore".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+impl<L> OneHotEncoder<Unfit, L> {
+    /// Creates a new, unfitted `OneHotEncoder` that learns its categories during fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle_unknown`: What to do with categories not seen during fit
+    /// * `drop_first`: Whether to drop the column of the first category
+    ///
+    /// returns: `Result<OneHotEncoder<Unfit, L>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Returns `DuckyError::InvalidParameter` if `drop_first` is combined with
+    /// `HandleUnknown::Ignore`.
+    pub fn new(handle_unknown: HandleUnknown, drop_first: bool) -> Result<Self, DuckyError> {
+        Self::with_categories(Vec::new(), handle_unknown, drop_first)
+    }
+
+    /// Creates a new, unfitted `OneHotEncoder` with a fixed list of categories, which fit
+    /// keeps instead of learning them from the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `categories`: Unique categories, in column order, checked for duplicates by fit
+    /// * `handle_unknown`: What to do with categories not in `categories`
+    /// * `drop_first`: Whether to drop the column of the first category
+    ///
+    /// returns: `Result<OneHotEncoder<Unfit, L>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Returns `DuckyError::InvalidParameter` if `drop_first` is combined with
+    /// `HandleUnknown::Ignore`.
+    pub fn with_categories(
+        categories: Vec<L>,
+        handle_unknown: HandleUnknown,
+        drop_first: bool,
+    ) -> Result<Self, DuckyError> {
+        check_drop_first(handle_unknown, drop_first)?;
+
+        Ok(Self {
+            categories,
+            handle_unknown,
+            drop_first,
+            category_index: HashMap::new(),
+            state: Default::default(),
+        })
+    }
+}
+
+impl<L: Hash + Eq + Clone + Debug> OneHotEncoder<Unfit, L> {
+    /// Learns the categories of `y`, or keeps the fixed categories if some were given.
+    ///
+    /// # Arguments
+    ///
+    /// * `y`: Categories to learn from
+    ///
+    /// returns: `Result<OneHotEncoder<Fit, L>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// Returns `DuckyError::EmptyInput` if there are no categories to learn from and
+    /// `DuckyError::InvalidParameter` if the fixed categories contain a duplicate or
+    /// `drop_first` was set to be combined with `HandleUnknown::Ignore`.
+    pub fn fit(self, y: &[L]) -> Result<OneHotEncoder<Fit, L>, DuckyError> {
+        check_drop_first(self.handle_unknown, self.drop_first)?;
+        let categories = if self.categories.is_empty() {
+            unique_labels(y)
+        } else {
+            self.categories
+        };
+        if categories.is_empty() {
+            return Err(DuckyError::EmptyInput);
+        }
+
+        let category_index = label_index(&categories);
+        if category_index.len() != categories.len() {
+            return Err(DuckyError::InvalidParameter(format!(
+                "categories {:?} contain a duplicate",
+                categories
+            )));
+        }
+
+        Ok(OneHotEncoder {
+            category_index,
+            categories,
+            handle_unknown: self.handle_unknown,
+            drop_first: self.drop_first,
+            state: std::marker::PhantomData::<Fit>,
+        })
+    }
+
+    /// Learns the categories of `y` and encodes them.
+    ///
+    /// # Arguments
+    ///
+    /// * `y`: Categories to learn from and encode
+    ///
+    /// returns: `Result<(OneHotEncoder<Fit, L>, Array2<f64>), DuckyError>`
+    pub fn fit_transform(
+        self,
+        y: &[L],
+    ) -> Result<(OneHotEncoder<Fit, L>, Array2<f64>), DuckyError> {
+        let encoder = self.fit(y)?;
+        let encoded = encoder.transform(y)?;
+        Ok((encoder, encoded))
+    }
+}
+
+impl<L: Hash + Eq + Clone + Debug> OneHotEncoder<Fit, L> {
+    /// Number of columns of the encoding.
+    pub fn n_columns(&self) -> usize {
+        self.categories.len() - self.drop_first as usize
+    }
+
+    /// Encodes every category as a one-hot row.
+    ///
+    /// # Arguments
+    ///
+    /// * `y`: Categories to encode
+    ///
+    /// returns: `Result<Array2<f64>, DuckyError>` of shape( y.len(), n_columns )
+    ///
+    /// # Errors
+    ///
+    /// Returns `DuckyError::UnknownClass` if a category was not seen during fit and
+    /// `handle_unknown` is `HandleUnknown::Error`.
+    pub fn transform(&self, y: &[L]) -> Result<Array2<f64>, DuckyError> {
+        let offset = self.drop_first as usize;
+        let mut encoded = Array2::zeros((y.len(), self.n_columns()));
+
+        for (row, category) in y.iter().enumerate() {
+            match self.category_index.get(category) {
+                Some(&column) if column >= offset => encoded[[row, column - offset]] = 1.,
+                Some(_) => {}
+                None if self.handle_unknown == HandleUnknown::Ignore => {}
+                None => return Err(DuckyError::UnknownClass(format!("{:?}", category))),
+            }
+        }
+
+        Ok(encoded)
+    }
+
+    /// Turns one-hot rows back into categories, taking the largest column of each row so
+    /// that predicted class probabilities can be decoded too.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`: Encoded rows of shape( rows, n_columns )
+    ///
+    /// returns: `Result<Vec<L>, DuckyError>`
+    ///
+    /// # Errors
+    ///
+    /// - `DuckyError::ShapeMismatch` if `x` does not have `n_columns` columns.
+    /// - `DuckyError::NonFinite` if `x` contains a NaN or infinite value.
+    /// - `DuckyError::UnknownClass` if a row has no positive value and `drop_first` is false,
+    ///   as happens to unknown categories encoded with `HandleUnknown::Ignore`. With
+    ///   `drop_first` such a row is the first category.
+    pub fn inverse_transform(&self, x: &Array2<f64>) -> Result<Vec<L>, DuckyError> {
+        if x.ncols() != self.n_columns() {
+            return Err(DuckyError::ShapeMismatch {
+                expected: vec![x.nrows(), self.n_columns()],
+                found: x.shape().to_vec(),
+            });
+        }
+        check_finite(x)?;
+
+        x.rows()
+            .into_iter()
+            .enumerate()
+            .map(|(row_index, row)| {
+                let (mut max_column, mut max_value) = (None, 0.);
+                for (column, &value) in row.iter().enumerate() {
+                    if value > max_value {
+                        max_column = Some(column);
+                        max_value = value;
+                    }
+                }
+
+                match max_column {
+                    Some(column) => Ok(self.categories[column + self.drop_first as usize].clone()),
+                    None if self.drop_first => Ok(self.categories[0].clone()),
+                    None => Err(DuckyError::UnknownClass(format!("row {}", row_index))),
+                }
+            })
+            .collect()
+    }
+}
+
+impl<L: Hash + Eq + Clone + Debug> traits::Fit<[L], ()> for OneHotEncoder<Unfit, L> {
+    type Fitted = OneHotEncoder<Fit, L>;
+
+    fn fit(self, y: &[L], _: &()) -> Result<OneHotEncoder<Fit, L>, DuckyError> {
+        self.fit(y)
+    }
+}
+
+impl<L: Hash + Eq + Clone + Debug> traits::Transform<[L]> for OneHotEncoder<Fit, L> {
+    type Output = Array2<f64>;
+
+    fn transform(&self, y: &[L]) -> Result<Array2<f64>, DuckyError> {
+        self.transform(y)
+    }
 }
 
 #[cfg(test)]
@@ -90,4 +566,138 @@ mod util_tests {
         ];
         assert_eq!(one_hot_encoding_vec(input).unwrap(), expected);
     }
+    #[test]
+    fn label_encoder_round_trip() {
+        let y = vec![3usize, 7, 3, 1];
+
+        let (encoder, encoded) = LabelEncoder::new().fit_transform(&y).unwrap();
+
+        assert_eq!(encoder.classes, vec![3, 7, 1]);
+        assert_eq!(encoded, vec![0, 1, 0, 2]);
+        assert_eq!(encoder.inverse_transform(&encoded).unwrap(), y);
+    }
+
+    #[test]
+    fn label_encoder_errors() {
+        let empty: Vec<String> = vec![];
+        assert_eq!(
+            LabelEncoder::new().fit(&empty).unwrap_err(),
+            DuckyError::EmptyInput
+        );
+
+        let encoder = LabelEncoder::new()
+            .fit(&["a".to_string(), "b".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            encoder.transform(&["c".to_string()]).unwrap_err(),
+            DuckyError::UnknownClass("\"c\"".to_string())
+        );
+        assert_eq!(
+            encoder.inverse_transform(&[2]).unwrap_err(),
+            DuckyError::UnknownClass("2".to_string())
+        );
+    }
+
+    #[test]
+    fn one_hot_encoder_fixed_width() {
+        let encoder = OneHotEncoder::new(HandleUnknown::Error, false)
+            .unwrap()
+            .fit(&[2, 0, 1])
+            .unwrap();
+
+        // A batch holding only some of the categories keeps the full width
+        assert_eq!(encoder.transform(&[0]).unwrap(), array![[0., 1., 0.]]);
+        assert_eq!(
+            encoder.transform(&[5]).unwrap_err(),
+            DuckyError::UnknownClass("5".to_string())
+        );
+    }
+
+    #[test]
+    fn one_hot_encoder_fixed_categories() {
+        let encoder = OneHotEncoder::with_categories(
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            HandleUnknown::Ignore,
+            false,
+        )
+        .unwrap()
+        .fit(&[])
+        .unwrap();
+
+        assert_eq!(
+            encoder
+                .transform(&["c".to_string(), "z".to_string()])
+                .unwrap(),
+            array![[0., 0., 1.], [0., 0., 0.]]
+        );
+        assert_eq!(
+            encoder
+                .inverse_transform(&array![[0.1, 0.7, 0.2], [0., 0., 0.]])
+                .unwrap_err(),
+            DuckyError::UnknownClass("row 1".to_string())
+        );
+        assert_eq!(
+            encoder.inverse_transform(&array![[0.1, 0.7, 0.2]]).unwrap(),
+            vec!["b".to_string()]
+        );
+    }
+
+    #[test]
+    fn one_hot_encoder_duplicate_categories() {
+        let encoder =
+            OneHotEncoder::with_categories(vec![1, 2, 1], HandleUnknown::Error, false).unwrap();
+
+        assert!(matches!(
+            encoder.fit(&[1, 2]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn one_hot_encoder_drop_first() {
+        let y = vec!["x", "y", "z", "x"];
+
+        let (encoder, encoded) = OneHotEncoder::new(HandleUnknown::Error, true)
+            .unwrap()
+            .fit_transform(&y)
+            .unwrap();
+
+        assert_eq!(encoder.n_columns(), 2);
+        assert_eq!(encoded, array![[0., 0.], [1., 0.], [0., 1.], [0., 0.]]);
+        assert_eq!(encoder.inverse_transform(&encoded).unwrap(), y);
+        assert!(matches!(
+            encoder.inverse_transform(&array![[1., 0., 0.]]),
+            Err(DuckyError::ShapeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn one_hot_encoder_drop_first_ignore() {
+        // An unknown category would be encoded the same as the dropped first one
+        assert!(matches!(
+            OneHotEncoder::<Unfit, u8>::new(HandleUnknown::Ignore, true),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            OneHotEncoder::with_categories(vec![1, 2], HandleUnknown::Ignore, true),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+
+        let mut encoder = OneHotEncoder::new(HandleUnknown::Ignore, false).unwrap();
+        encoder.drop_first = true;
+        assert!(matches!(
+            encoder.fit(&[1, 2]),
+            Err(DuckyError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn one_hot_encoder_empty() {
+        let empty: Vec<u8> = vec![];
+        assert_eq!(
+            OneHotEncoder::default().fit(&empty).unwrap_err(),
+            DuckyError::EmptyInput
+        );
+    }
 }